@@ -1,21 +1,28 @@
 #![allow(unused_results)]
 
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
 use message_board::cryptography::UserAeadKey;
 use message_board::*;
 use ratatui::layout::{Constraint, Layout};
-use ratatui::style::Stylize;
+use ratatui::style::{Color, Stylize};
 use ratatui::widgets::{Clear};
 use std::io::Write;
 use ratatui::{
-    text::{Line, Text},
+    text::{Line, Span, Text},
     widgets::{Block, Paragraph, Widget},
-    layout::Rect,
+    layout::{Position, Rect},
     buffer::Buffer,
 };
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::time::Duration;
 use message_board::utils::*;
+use message_board::tree_preview::TreePreview;
+use message_board::perms_draft::PermsDraft;
+#[cfg(feature = "markdown")]
+use message_board::markdown::*;
 use message_board::internal_error;
 
 const ENTRY_VARIANTS: [EntryVariant; 2] = [
@@ -30,28 +37,87 @@ const DEFAULT_BASES: [DefaultBase; 3] = [
 ];
 
     
+/// extended off of the user home
+/// 
+/// keys:
+///     address: the message board's IP address / host name,
+///     port (optional): defaults to `PORT`,
+///     board (optional): which of the server's boards to use, defaults to `MAIN_BOARD`, 
+///         the user must be one of that board's as each board has its own users,
+///     user_id, user_aead: "None" to make a new user, the id can be an integer or a hex string,
+///     guest (optional): whether to browse read only as a guest rather than as the user, see `ANONYMOUS_USER_ID`, 
+///         defaults to false, also set by the `--guest` argument,
+///     display_name (optional): shown to others in place of the user's id, set on the board when connecting, 
+///         at most `MAX_DISPLAY_NAME_SIZE` bytes,
+///     entry_cache_size, tls, tls_ca, path (optional),
+///     ping_interval (optional): seconds idle before pinging the board to keep the connection open, 
+///         defaults to `DEFAULT_PING_INTERVAL_SECS`, 0 to never ping,
+///     max_response_size (optional): bytes, larger responses are refused, defaults to `DEFAULT_MAX_RESPONSE_SIZE`,
+///     nodelay (optional): whether to send requests without waiting to batch them (disables Nagle's algorithm), defaults to true,
+///     allow_empty_messages (optional): whether messages with nothing but whitespace can be written, defaults to false,
+///     connect_timeout (optional): seconds to wait on each attempt to connect, defaults to `DEFAULT_CONNECT_TIMEOUT_SECS`,
+///     connect_retries (optional): failed attempts to connect retried before giving up, defaults to `DEFAULT_CONNECT_RETRIES`,
+///     retry_delay (optional): seconds before the first retry, doubling after each up to `MAX_RETRY_DELAY_SECS`, 
+///         defaults to `DEFAULT_RETRY_DELAY_SECS`,
+///     [keys] (optional): action = key or [keys], see `Keymap`, ie. `up = ["k", "Up"]`
+///     [colors] (optional): entry type = color, see `EntryColors`, ie. `access_group = "cyan"`
 const RC_FILE: &str = ".config/message_board/client_rc.toml";
+/// entries written while the board couldn't be reached, extended off of the user home
+const OUTBOX_FILE: &str = ".config/message_board/outbox";
+/// when each entry was last seen, to mark the children with activity since, extended off of the user home
+const LAST_SEEN_FILE: &str = ".config/message_board/last_seen";
+/// how many navigations can be undone
+const NAVIGATION_HISTORY_LEN: usize = 64;
+const DEFAULT_ENTRY_CACHE_SIZE: usize = 128;
+/// under the usual NAT / firewall idle timeouts
+const DEFAULT_PING_INTERVAL_SECS: u64 = 30;
+/// well over the largest entry the board accepts by default
+const DEFAULT_MAX_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_CONNECT_RETRIES: u32 = 4;
+const DEFAULT_RETRY_DELAY_SECS: u64 = 1;
+const MAX_RETRY_DELAY_SECS: u64 = 30;
+/// the most display names looked up when showing a single entry, ie. one with long perm lists
+const MAX_NAME_LOOKUPS: usize = 32;
+/// perm lists longer than this are shown as a count until opened
+const MAX_SHOWN_PERM_IDS: usize = 64;
     
 mod client_libs;
 use client_libs::utils::*;
 use client_libs::base_widgets::*;
+use client_libs::cli::{Command, USAGE};
 
 
 struct Config {
     user_id: Option<UserId>,
     user_aead: Option<UserAeadKey>,
+    guest: bool,
+    display_name: Option<String>,
     server_address: String,
+    server_port: u16,
+    board: String,
+    entry_cache_size: usize,
+    tls: bool,
+    tls_ca: Option<String>,
+    path: Vec<EntryId>, // where the client was last, below the root
+    ping_interval_secs: u64, // 0 to never ping
+    max_response_size: usize,
+    nodelay: bool,
+    allow_empty_messages: bool,
+    connect_timeout_secs: u64,
+    connect_retries: u32,
+    retry_delay_secs: u64,
+    keymap: Keymap,
+    entry_colors: EntryColors,
 }
 
 impl Config {
-    fn from_toml(config_toml: &toml::Table) -> Self {
-        let user_id_val = &config_toml["user_id"];
-        let user_id = match user_id_val {
-            toml::Value::Integer(id) => Some((*id as u64).into()), //scuff
-            toml::Value::String(str) if str == "None" => None,
-            _ => {panic!("The client RC file was misformatted")}
-        };
-        let toml::Value::String(user_aead_hex) = &config_toml["user_aead"] else {panic!("The client RC file was misformatted")};
+    fn from_toml(config_toml: &toml_edit::DocumentMut) -> Self {
+        if let Err(key) = require_config_keys(config_toml, &["address", "user_id", "user_aead"]) {
+            panic!("The client RC file is missing the \"{}\" key", key)
+        }
+        let user_id = parse_user_id(&config_toml["user_id"]).unwrap_or_else(|err| panic!("\"user_id\" is misformatted: {}", err)).map(UserId::from);
+        let Some(user_aead_hex) = config_toml["user_aead"].as_str() else {panic!("\"user_aead\" should be a hex string or \"None\"")};
         let user_aead;
         if user_aead_hex == "None" {
             user_aead = None;
@@ -61,47 +127,215 @@ impl Config {
                 .flatten()
                 .expect("The client RC file was misformatted"));
         }
-        let toml::Value::String(server_address) = &config_toml["address"] else {panic!("The client RC file was misformatted")};
+        let guest = match config_toml.get("guest") {
+            Some(guest) => guest.as_bool().expect("\"guest\" should be a boolean"),
+            None => false,
+        };
+        let display_name = config_toml.get("display_name").map(|name| {
+            let name = name.as_str().expect("\"display_name\" should be a string");
+            if name.len() > MAX_DISPLAY_NAME_SIZE {panic!("\"display_name\" should be at most {} bytes", MAX_DISPLAY_NAME_SIZE)}
+            name.to_string()
+        });
+        let board = match config_toml.get("board") {
+            Some(board) => {
+                let board = board.as_str().expect("\"board\" should be a string of the board's name");
+                if validate_board_name(board).is_err() {panic!("\"board\" ({}) isn't a valid board name", board)}
+                board.to_string()
+            }
+            None => String::from(MAIN_BOARD),
+        };
+        let Some(server_address) = config_toml["address"].as_str() else {panic!("\"address\" should be a string of the message board's address")};
+        let server_port = match config_toml.get("port") {
+            Some(port) => port.as_integer().and_then(|port| u16::try_from(port).ok()).expect("\"port\" should be an integer from 0 to 65535"),
+            None => PORT,
+        };
+        let entry_cache_size = match config_toml.get("entry_cache_size") {
+            Some(size) => match size.as_integer() {
+                Some(size) if size >= 0 => size as usize,
+                _ => panic!("\"entry_cache_size\" should be a non-negative integer"),
+            },
+            None => DEFAULT_ENTRY_CACHE_SIZE,
+        };
+        let tls = match config_toml.get("tls") {
+            Some(tls) => tls.as_bool().expect("\"tls\" should be a boolean"),
+            None => false,
+        };
+        let tls_ca = config_toml.get("tls_ca").map(|path| path.as_str().expect("\"tls_ca\" should be a string of the path to a pem CA certificate").to_string());
+        let path = match config_toml.get("path") {
+            Some(path) => path.as_array().expect("\"path\" should be an array of hex entry ids").iter().map(|entry_id| {
+                entry_id.as_str().and_then(|hex| u64::from_str_radix(hex, 16).ok()).expect("\"path\" should be an array of hex entry ids").into()
+            }).collect(),
+            None => Vec::new(),
+        };
+        let ping_interval_secs = match config_toml.get("ping_interval") {
+            Some(secs) => match secs.as_integer() {
+                Some(secs) if secs >= 0 => secs as u64,
+                _ => panic!("\"ping_interval\" should be a non-negative integer"),
+            },
+            None => DEFAULT_PING_INTERVAL_SECS,
+        };
+        let max_response_size = match config_toml.get("max_response_size") {
+            Some(size) => match size.as_integer() {
+                Some(size) if size > 0 => size as usize,
+                _ => panic!("\"max_response_size\" should be a positive integer"),
+            },
+            None => DEFAULT_MAX_RESPONSE_SIZE,
+        };
+        let nodelay = match config_toml.get("nodelay") {
+            Some(nodelay) => nodelay.as_bool().expect("\"nodelay\" should be a boolean"),
+            None => true,
+        };
+        let allow_empty_messages = match config_toml.get("allow_empty_messages") {
+            Some(allow) => allow.as_bool().expect("\"allow_empty_messages\" should be a boolean"),
+            None => false,
+        };
+        let connect_timeout_secs = match config_toml.get("connect_timeout") {
+            Some(secs) => match secs.as_integer() {
+                Some(secs) if secs > 0 => secs as u64,
+                _ => panic!("\"connect_timeout\" should be a positive integer"),
+            },
+            None => DEFAULT_CONNECT_TIMEOUT_SECS,
+        };
+        let connect_retries = match config_toml.get("connect_retries") {
+            Some(retries) => retries.as_integer().and_then(|retries| u32::try_from(retries).ok()).expect("\"connect_retries\" should be a non-negative integer"),
+            None => DEFAULT_CONNECT_RETRIES,
+        };
+        let retry_delay_secs = match config_toml.get("retry_delay") {
+            Some(secs) => match secs.as_integer() {
+                Some(secs) if secs >= 0 => secs as u64,
+                _ => panic!("\"retry_delay\" should be a non-negative integer"),
+            },
+            None => DEFAULT_RETRY_DELAY_SECS,
+        };
+        let keymap = match config_toml.get("keys") {
+            Some(keys) => {
+                let keys = keys.as_table_like().expect("\"keys\" should be a table of actions to keys");
+                Keymap::from_toml(keys).unwrap_or_else(|e| panic!("Bad key binding in the client RC file: {}", e))
+            }
+            None => Keymap::default(),
+        };
+        let entry_colors = match config_toml.get("colors") {
+            Some(colors) => {
+                let colors = colors.as_table_like().expect("\"colors\" should be a table of entry types to colors");
+                EntryColors::from_toml(colors).unwrap_or_else(|e| panic!("Bad color in the client RC file: {}", e))
+            }
+            None => EntryColors::default(),
+        };
         Config { 
             user_id, 
             user_aead: user_aead,
-            server_address: server_address.clone(),
+            guest,
+            display_name,
+            server_address: server_address.to_string(),
+            server_port,
+            board,
+            entry_cache_size,
+            tls,
+            tls_ca,
+            path,
+            ping_interval_secs,
+            max_response_size,
+            nodelay,
+            allow_empty_messages,
+            connect_timeout_secs,
+            connect_retries,
+            retry_delay_secs,
+            keymap,
+            entry_colors,
         }
     }
 
-    fn into_toml(self) -> toml::Table {
-        let mut config_toml = toml::Table::new();
-        let user_id = match self.user_id {
-            Some(id) => toml::Value::Integer(u64::from(id) as i64),
-            None => toml::Value::String(String::from("None")),
+    /// writes the config back into the document it was read from, leaving everything else as is
+    fn write_toml(self, config_toml: &mut toml_edit::DocumentMut) {
+        match self.user_id {
+            Some(id) => set_config_value(config_toml, "user_id", u64::from(id) as i64),
+            None => set_config_value(config_toml, "user_id", "None"),
         };
-        config_toml.insert(String::from("user_id"), user_id);
-        let user_aead = match self.user_aead {
-            Some(aead) => toml::Value::String(write_long_hex_string(&aead.into_data().unwrap())),
-            None => toml::Value::String(String::from("None")),
+        match self.user_aead {
+            Some(aead) => set_config_value(config_toml, "user_aead", write_long_hex_string(&aead.into_data().expect("A user aead key should always encode"))),
+            None => set_config_value(config_toml, "user_aead", "None"),
         };
-        config_toml.insert(String::from("user_aead"), user_aead);
-        config_toml.insert(String::from("address"), toml::Value::String(self.server_address));
-        config_toml
+        if config_toml.contains_key("guest") || self.guest {
+            set_config_value(config_toml, "guest", self.guest);
+        }
+        if let Some(display_name) = self.display_name {
+            set_config_value(config_toml, "display_name", display_name);
+        }
+        set_config_value(config_toml, "address", self.server_address);
+        if config_toml.contains_key("port") || self.server_port != PORT {
+            set_config_value(config_toml, "port", self.server_port as i64);
+        }
+        if config_toml.contains_key("board") || self.board != MAIN_BOARD {
+            set_config_value(config_toml, "board", self.board);
+        }
+        if config_toml.contains_key("entry_cache_size") || self.entry_cache_size != DEFAULT_ENTRY_CACHE_SIZE {
+            set_config_value(config_toml, "entry_cache_size", self.entry_cache_size as i64);
+        }
+        if config_toml.contains_key("tls") || self.tls {
+            set_config_value(config_toml, "tls", self.tls);
+        }
+        if let Some(tls_ca) = self.tls_ca {
+            set_config_value(config_toml, "tls_ca", tls_ca);
+        }
+        if config_toml.contains_key("ping_interval") || self.ping_interval_secs != DEFAULT_PING_INTERVAL_SECS {
+            set_config_value(config_toml, "ping_interval", self.ping_interval_secs as i64);
+        }
+        if config_toml.contains_key("max_response_size") || self.max_response_size != DEFAULT_MAX_RESPONSE_SIZE {
+            set_config_value(config_toml, "max_response_size", self.max_response_size as i64);
+        }
+        if config_toml.contains_key("nodelay") || !self.nodelay {
+            set_config_value(config_toml, "nodelay", self.nodelay);
+        }
+        if config_toml.contains_key("allow_empty_messages") || self.allow_empty_messages {
+            set_config_value(config_toml, "allow_empty_messages", self.allow_empty_messages);
+        }
+        if config_toml.contains_key("connect_timeout") || self.connect_timeout_secs != DEFAULT_CONNECT_TIMEOUT_SECS {
+            set_config_value(config_toml, "connect_timeout", self.connect_timeout_secs as i64);
+        }
+        if config_toml.contains_key("connect_retries") || self.connect_retries != DEFAULT_CONNECT_RETRIES {
+            set_config_value(config_toml, "connect_retries", self.connect_retries as i64);
+        }
+        if config_toml.contains_key("retry_delay") || self.retry_delay_secs != DEFAULT_RETRY_DELAY_SECS {
+            set_config_value(config_toml, "retry_delay", self.retry_delay_secs as i64);
+        }
+        if config_toml.contains_key("path") || !self.path.is_empty() {
+            set_config_value(config_toml, "path", self.path.iter().map(|entry_id| format!("{:016X}", **entry_id)).collect::<toml_edit::Array>());
+        }
     }
 }
 
+fn read_rc_config(real_rc_config: &std::path::Path) -> std::io::Result<toml_edit::DocumentMut> {
+    std::fs::read_to_string(real_rc_config).map(|str| read_config(&str).expect("The Client Rc was misformatted"))
+}
+
 fn edit_config<F: FnOnce(&mut Config)>(f: F) {
     let user_home = std::env::home_dir().unwrap();
     let mut real_rc_config = user_home.clone();
     real_rc_config.push(RC_FILE);
-    let mut config = Config::from_toml(&std::fs::read_to_string(&real_rc_config)
-        .map(|str| str.parse::<toml::Table>().expect("The Server Rc was misformatted")).unwrap());
+    let mut config_toml = read_rc_config(&real_rc_config).unwrap();
+    let mut config = Config::from_toml(&config_toml);
     f(&mut config);
-    let _ = std::fs::write(&real_rc_config, &config.into_toml().to_string());
+    config.write_toml(&mut config_toml);
+    let _ = std::fs::write(&real_rc_config, &config_toml.to_string());
+}
+
+fn outbox_path() -> std::path::PathBuf {
+    let mut path = std::env::home_dir().unwrap();
+    path.push(OUTBOX_FILE);
+    path
+}
+
+fn last_seen_path() -> std::path::PathBuf {
+    let mut path = std::env::home_dir().unwrap();
+    path.push(LAST_SEEN_FILE);
+    path
 }
 
 fn get_config() -> Config {
     let user_home = std::env::home_dir().unwrap();
     let mut real_rc_config = user_home.clone();
     real_rc_config.push(RC_FILE);
-    Config::from_toml(&std::fs::read_to_string(&real_rc_config)
-        .map(|str| str.parse::<toml::Table>().expect("The Server Rc was misformatted")).unwrap())
+    Config::from_toml(&read_rc_config(&real_rc_config).unwrap())
 }
 
 fn validate_config() {
@@ -111,7 +345,7 @@ fn validate_config() {
     let mut stdin = std::io::stdin();
     let mut stdout = std::io::stdout();
     let mut input_buffer = String::new();
-    let mut rc_config_result = std::fs::read_to_string(&real_rc_config).map(|str| str.parse::<toml::Table>().expect("The Client Rc was misformatted"));
+    let mut rc_config_result = read_rc_config(&real_rc_config);
     if let Err(ref e) = rc_config_result {
         match e.kind() {
             std::io::ErrorKind::NotFound => {
@@ -120,14 +354,14 @@ fn validate_config() {
                 input_buffer.clear();
                 let create = stdin_y_n(&mut stdin, &mut input_buffer);
                 if create {
-                    let mut config = toml::Table::new();
+                    let mut config = toml_edit::DocumentMut::new();
                     print!("Please enter the message board's address: ");
                     let _ = stdout.flush();
                     let mut server_address = String::new();
                     let _ = stdin.read_line(&mut server_address);
-                    config.insert("address".to_string(), toml::Value::String(server_address.trim().to_string()));
-                    config.insert("user_id".to_string(), toml::Value::String("None".to_string()));
-                    config.insert("user_aead".to_string(), toml::Value::String("None".to_string()));
+                    set_config_value(&mut config, "address", server_address.trim());
+                    set_config_value(&mut config, "user_id", "None");
+                    set_config_value(&mut config, "user_aead", "None");
 
                     let mut parent = real_rc_config.clone();
                     parent.pop();
@@ -142,18 +376,37 @@ fn validate_config() {
         }
     }
     let rc_config = rc_config_result.unwrap();
-    // FIXME: doesn't make sure the config contains all the keys
     let _ = Config::from_toml(&rc_config);
 }
 
 
 
 
+/// the users the viewer shows for the entry, its author then any listed in its perms
+fn shown_user_ids(entry: &Entry) -> Vec<UserId> {
+    let mut user_ids = vec![entry.header_data.author_id];
+    if let EntryData::AccessGroup { write_perms, read_perms, .. } = &entry.entry_data {
+        for perms in [write_perms, read_perms] {
+            user_ids.extend(perms.iter_whitelist().chain(perms.iter_blacklist()));
+        }
+    }
+    user_ids
+}
+
+/// the lengths of the group's perm lists, which entries are gotten without (see `MessageBoardConnection::get_full_entry`), 
+/// None if the entry isn't a group or they couldn't be gotten
+fn group_perms_summary(board: &mut MessageBoardConnection, entry_id: EntryId, entry: &Entry) -> Option<(PermsSummary, PermsSummary)> {
+    let EntryData::AccessGroup { .. } = entry.entry_data else {return None};
+    board.perms_summary(entry_id).ok()
+}
+
 fn extract_name(entry_id: EntryId, entry: &Entry) -> String {
     #[allow(unreachable_patterns)]
     match &entry.entry_data {
         EntryData::AccessGroup { name, write_perms: _, read_perms: _ } => name.clone(),
         EntryData::Message { timestamp: _, message: _ } => entry_id.to_string(),
+        EntryData::Reaction { emoji, target_id: _ } => emoji.clone(),
+        EntryData::Attachment { filename, .. } => filename.clone(),
         _ => entry_id.to_string(),
     }
 }
@@ -161,16 +414,24 @@ fn extract_name(entry_id: EntryId, entry: &Entry) -> String {
 
 #[derive(Debug)]
 struct PathManager {
-    path: Vec<(EntryId, String)>
+    root_id: EntryId, // as given by the board, see `MessageBoardConnection::get_root`
+    path: Vec<(EntryId, String)>,
+    inner_area: Cell<Rect>, // where the path was last rendered, for mapping clicks
 }
 
 impl PathManager {
-    fn new() -> Self {
+    fn new(root_id: EntryId) -> Self {
         Self {
+            root_id,
             path: Vec::new(),
+            inner_area: Cell::new(Rect::default()),
         }
     }
 
+    fn root_id(&self) -> EntryId {
+        self.root_id
+    }
+
     fn is_init(&self) -> bool {
         self.path.len() > 0
     }
@@ -190,11 +451,12 @@ impl PathManager {
     }
 
     fn push(&mut self, entry_id: EntryId, entry: &Entry) -> Result<(), DataError> {
-        let HeaderData { version: _, parent_id, children_ids: _, author_id: _ } = &entry.header_data;
+        let HeaderData { version: _, parent_id, children_ids: _, author_id: _, last_activity: _, pinned: _, deleted: _ } = &entry.header_data;
         if self.path.len() > 0 {
             if *parent_id != self.peek().unwrap().0 {return Err(DataError::NonChild)}
         } else {
-            if (**parent_id != ROOT_ENTRY_ID) | (*entry_id != ROOT_ENTRY_ID) {return Err(DataError::MalformedRoot)}
+            if entry_id != self.root_id {return Err(DataError::MalformedRoot)}
+            entry.validate_root()?;
         }
 
         let name = extract_name(entry_id, entry);
@@ -202,9 +464,61 @@ impl PathManager {
         Ok(())
     }
 
+    /// pops back to `entry_id`, leaving the path as is and returning false if it isn't in the path
+    /// 
+    /// like `pop`, this never removes the root
+    fn truncate_to(&mut self, entry_id: EntryId) -> bool {
+        let Some(idx) = self.path.iter().position(|x| x.0 == entry_id) else {return false};
+        self.path.truncate(idx + 1);
+        true
+    }
+
     fn len(&self) -> usize {
         self.path.len()
     }
+
+    /// how many entries deep the path is below the root
+    fn depth(&self) -> usize {
+        self.path.len().saturating_sub(1)
+    }
+
+    /// the segments shown in `width`, None being the "…" in place of those elided (see `elide_path`)
+    fn shown_segments(&self, width: u16) -> Vec<Option<&(EntryId, String)>> {
+        let widths: Vec<usize> = self.path.iter().map(|(_, name)| Span::raw(name).width()).collect();
+        let mut segments = Vec::new();
+        let mut last_idx = None;
+        for idx in elide_path(&widths, width as usize) {
+            if last_idx.is_some_and(|last_idx| idx > last_idx + 1) {segments.push(None)}
+            segments.push(Some(&self.path[idx]));
+            last_idx = Some(idx);
+        }
+        segments
+    }
+
+    /// the entry whose segment was last rendered at the position
+    fn entry_at(&self, column: u16, row: u16) -> Option<EntryId> {
+        let inner_area = self.inner_area.get();
+        if !inner_area.contains(Position::new(column, row)) {return None}
+        let mut segment_start = inner_area.x;
+        for (idx, segment) in self.shown_segments(inner_area.width).into_iter().enumerate() {
+            if idx > 0 {
+                segment_start += 1; // the "/"
+            }
+            let Some((entry_id, name)) = segment else {
+                segment_start += 1; // the "…"
+                continue
+            };
+            let segment_end = segment_start.saturating_add(Span::raw(name).width() as u16);
+            if (segment_start..segment_end).contains(&column) {return Some(*entry_id)}
+            segment_start = segment_end;
+        }
+        None
+    }
+
+    /// the ids of the path below the root
+    fn entry_ids(&self) -> Vec<EntryId> {
+        self.path.iter().skip(1).map(|x| x.0).collect()
+    }
 }
 
 impl Widget for &PathManager {
@@ -212,13 +526,18 @@ impl Widget for &PathManager {
         Clear.render(area, buf);
 
         let block = Block::bordered()
-            .title(" Path ");
+            .title(format!(" Path ({}) ", self.depth()));
+        self.inner_area.set(block.inner(area));
 
         let mut path = Line::default();
-        path.push_span(&self.path[0].1);
-        for (_id, name) in &self.path[1..] {
-            path.push_span("/".bold());
-            path.push_span(name);
+        for (idx, segment) in self.shown_segments(block.inner(area).width).into_iter().enumerate() {
+            if idx > 0 {
+                path.push_span("/".bold());
+            }
+            match segment {
+                Some((_id, name)) => path.push_span(name.as_str()),
+                None => path.push_span("…".bold()),
+            }
         }
 
         Paragraph::new(path)
@@ -229,27 +548,201 @@ impl Widget for &PathManager {
 
 
 #[derive(Debug)]
-struct Navigator(ScrollContainer<(EntryId, String)>);
+struct Navigator(ScrollContainer<(EntryId, String)>, ChildSorting, Option<TreePreview>); // the preview, when shown in place of the plain children
+
+/// the children in their inserted order and what they are sorted by, pinned children always come first
+#[derive(Debug, Default)]
+struct ChildSorting {
+    order: ChildOrder,
+    children: Vec<EntryId>,
+    timestamps: HashMap<EntryId, Option<u64>>, // None if not a message, kept so changing the order doesn't refetch
+    pinned: HashSet<EntryId>, // filled in along with `timestamps`
+    entry_types: HashMap<EntryId, u8>, // to color the children by, also filled in along with `timestamps`
+    reactions: HashMap<EntryId, String>, // the emoji of the children which are reactions, also filled in along with `timestamps`
+    deleted: HashSet<EntryId>, // tombstones, shown grayed out, also filled in along with `timestamps`
+    unseen: HashSet<EntryId>, // the children with activity since the entry was last seen, marked with a `*`
+    unseen_of: Option<EntryId>, // the entry `unseen` was worked out for, so reloading it keeps the marks
+}
 
 impl Navigator {
-    fn replace_items(&mut self, items: &[EntryId]) {
-        self.0.replace_items(items.iter().copied().map(|x| (x, x.to_string())).collect())
+    fn new() -> Self {
+        Self(ScrollContainer::new(Vec::new()), ChildSorting::default(), None)
+    }
+
+    /// `get_entry` is only used to sort by, for children not seen before
+    fn replace_items(&mut self, items: &[EntryId], get_entry: impl FnMut(EntryId) -> Result<Entry, DataError>) {
+        self.1.children = items.to_vec();
+        let items = self.shown_items(get_entry);
+        self.0.replace_items(items)
+    }
+
+    /// moves on to the next order, keeping the cursor on the same child
+    fn cycle_order(&mut self, get_entry: impl FnMut(EntryId) -> Result<Entry, DataError>) {
+        self.1.order = self.1.order.next();
+        self.resort(get_entry);
+    }
+
+    /// the selected child, if the navigator is focused
+    fn selected(&self) -> Option<EntryId> {
+        self.0.selection().map(|x| x.1.0)
+    }
+
+    fn is_pinned(&self, child_id: EntryId) -> bool {
+        self.1.pinned.contains(&child_id)
+    }
+
+    /// whether the entry is one of the children, rather than further down in the tree preview
+    fn is_child(&self, entry_id: EntryId) -> bool {
+        self.1.children.contains(&entry_id)
+    }
+
+    /// shows the tree preview in place of the children and vice versa
+    fn toggle_tree(&mut self, get_entry: impl FnMut(EntryId) -> Result<Entry, DataError>) {
+        self.2 = match self.2.take() {
+            Some(_) => None,
+            None => Some(TreePreview::new()),
+        };
+        self.resort(get_entry);
+    }
+
+    /// expands / collapses the selected child in the tree preview, getting the children of those newly expanded in one request,
+    /// false if there was nothing to expand
+    fn toggle_selected(&mut self, board: &mut MessageBoardConnection) -> bool {
+        let Some(selected) = self.selected() else {return false};
+        let Some(tree) = &mut self.2 else {return false};
+        if !tree.toggle(selected) {return false}
+        let to_fetch = tree.to_fetch();
+        // failed prefetches only mean the entries are gotten one by one
+        let _ = board.prefetch_entries(&to_fetch);
+        let mut all_grandchildren = Vec::new();
+        for child_id in to_fetch {
+            // a child which can't be read shows as having none
+            let grandchildren = board.get_entry(child_id).map(|entry| entry.header_data.children_ids).unwrap_or_default();
+            all_grandchildren.extend_from_slice(&grandchildren);
+            tree.set_grandchildren(child_id, grandchildren);
+        }
+        // only to color them by
+        let _ = board.prefetch_entries(&all_grandchildren);
+        for grandchild_id in all_grandchildren {
+            if let Ok(entry) = board.get_entry(grandchild_id) {
+                self.1.entry_types.insert(grandchild_id, entry.entry_data.get_discriminant());
+            }
+        }
+        self.resort(|entry_id| board.get_entry(entry_id));
+        true
+    }
+
+    /// marks the children with activity since the entry was last seen, unless they already are for this entry
+    fn update_unseen(&mut self, entry_id: EntryId, entry: &Entry, board: &mut MessageBoardConnection) {
+        if self.1.unseen_of == Some(entry_id) {return}
+        // failing to get them only loses the marks
+        let unseen = board.unseen_children(entry_id, entry.header_data.last_activity).unwrap_or_default();
+        self.1.unseen = unseen.into_iter().collect();
+        self.1.unseen_of = Some(entry_id);
+    }
+
+    /// the reactions among the children, see `count_reactions`
+    fn reaction_counts(&self) -> Vec<(String, usize)> {
+        count_reactions(self.1.children.iter().filter_map(|child_id| self.1.reactions.get(child_id)).map(String::as_str))
+    }
+
+    /// refetches what the child is sorted by, ie. after it was pinned, keeping the cursor on the same child
+    fn refresh_child(&mut self, child_id: EntryId, get_entry: impl FnMut(EntryId) -> Result<Entry, DataError>) {
+        self.1.timestamps.remove(&child_id);
+        self.1.pinned.remove(&child_id);
+        self.1.reactions.remove(&child_id);
+        self.1.entry_types.remove(&child_id);
+        self.1.deleted.remove(&child_id);
+        self.resort(get_entry);
+    }
+
+    fn resort(&mut self, get_entry: impl FnMut(EntryId) -> Result<Entry, DataError>) {
+        let selected = self.selected();
+        let items = self.shown_items(get_entry);
+        self.0.replace_items(items);
+        if let Some(selected) = selected {
+            self.0.cursor_pos = self.0.items.iter().position(|x| x.0 == selected);
+        }
+    }
+
+    /// the sorted children, or the rows of the tree preview when it is shown
+    fn shown_items(&mut self, get_entry: impl FnMut(EntryId) -> Result<Entry, DataError>) -> Vec<(EntryId, String)> {
+        let children = self.sorted_children(get_entry);
+        let Some(tree) = &mut self.2 else {
+            return children.into_iter().map(|x| (x, x.to_string())).collect()
+        };
+        tree.set_children(children);
+        tree.rows().into_iter().map(|(depth, entry_id)| {
+            let marker = match depth {
+                1 if tree.is_expanded(entry_id) => "▾ ",
+                1 => "▸ ",
+                _ => "    ",
+            };
+            (entry_id, format!("{}{}", marker, entry_id))
+        }).collect()
+    }
+
+    fn sorted_children(&mut self, mut get_entry: impl FnMut(EntryId) -> Result<Entry, DataError>) -> Vec<EntryId> {
+        let mut children = self.1.children.clone();
+        for child_id in &children {
+            if self.1.timestamps.contains_key(child_id) {continue}
+            // a child which can't be read is sorted with the unpinned non messages rather than failing the list
+            if let Ok(entry) = get_entry(*child_id) {
+                self.1.entry_types.insert(*child_id, entry.entry_data.get_discriminant());
+                let deleted = entry.header_data.deleted;
+                if deleted {self.1.deleted.insert(*child_id);}
+                let timestamp = match entry.entry_data {
+                    EntryData::Message { timestamp, .. } => Some(timestamp),
+                    EntryData::AccessGroup { .. } | EntryData::Attachment { .. } => None,
+                    // a deleted reaction isn't counted
+                    EntryData::Reaction { emoji, .. } => {
+                        if !deleted {self.1.reactions.insert(*child_id, emoji);}
+                        None
+                    }
+                };
+                self.1.timestamps.insert(*child_id, timestamp);
+                if entry.header_data.pinned {self.1.pinned.insert(*child_id);}
+            }
+        }
+        let timestamps = &self.1.timestamps;
+        self.1.order.sort(&mut children, |child_id| timestamps.get(child_id).copied().flatten());
+        // stable, so each half keeps the order
+        children.sort_by_key(|child_id| !self.1.pinned.contains(child_id));
+        children
     }
 }
 
 impl InputWidget for Navigator {
     fn render(&self, area: Rect, buf: &mut Buffer) -> Rect {
-        self.0.base_render(area, buf, " Children ", |x| x.1.clone())
+        let name = if self.2.is_some() {"Tree"} else {"Children"};
+        let title = match self.1.order {
+            ChildOrder::Inserted => format!(" {} ", name),
+            order => format!(" {} ({}) ", name, order.name()),
+        };
+        self.0.base_render(area, buf, title, |x| {
+            // styled on the span, the line's own style is the cursor's
+            let text = if self.1.unseen.contains(&x.0) {format!("* {}", x.1)} else {x.1.clone()};
+            // children which couldn't be read are left uncolored
+            let color = match self.1.entry_types.get(&x.0) {
+                _ if self.1.deleted.contains(&x.0) => Color::DarkGray,
+                Some(entry_type) => entry_colors().of(*entry_type),
+                None => Color::Reset,
+            };
+            let span = Span::from(text).fg(color);
+            if self.is_pinned(x.0) {Line::from(span.bold())} else {Line::from(span)}
+        })
     }
 
     fn handle_event(&mut self, event: Event) -> Option<StateChange> {
         if let Some(event) = self.0.base_handle_event(event.clone()) {
             return Some(event)
         } else {
+            // double clicking opens the child, same as Enter
+            if self.0.take_double_click() {return Some(StateChange::Pop)}
             if let Event::Key(key_event) = event {
                 if !key_event.is_press() {return None}
-                match key_event.code {
-                    KeyCode::Enter => {
+                match key_event {
+                    _ if keymap().matches(Action::Open, &key_event) => {
                         return Some(StateChange::Pop)
                     }
                     _ => {}
@@ -270,6 +763,249 @@ impl InputWidget for Navigator {
     }
 }
 
+/// popup previewing an entry before it is written
+#[derive(Debug)]
+struct ConfirmEntry {
+    entry: Entry,
+    confirmed: bool,
+}
+
+impl ConfirmEntry {
+    fn new(entry: Entry) -> Self {
+        Self { entry, confirmed: false }
+    }
+}
+
+impl InputWidget for ConfirmEntry {
+    fn render(&self, area: Rect, buf: &mut Buffer) -> Rect {
+        let mut layout = Layout::horizontal([Constraint::Fill(1), Constraint::Percentage(50), Constraint::Fill(1)]).split(area);
+        layout = Layout::vertical([Constraint::Fill(1), Constraint::Percentage(50), Constraint::Fill(1)]).split(layout[1]);
+        let confirm_popup_area = layout[1];
+
+        let block = Block::bordered()
+            .title(" Write this entry? ")
+            .title_bottom(Line::from(" (y)es / (n)o ").centered());
+
+        let text = match &self.entry.entry_data {
+            EntryData::Message { message, .. } => Text::from(message.as_str()),
+            EntryData::AccessGroup { name, write_perms, read_perms } => Text::from(vec![
+                Line::from(format!("Access Group: {}", name)),
+                Line::from(format!("Write: {}", write_perms.get_default_base())),
+                Line::from(format!("Read: {}", read_perms.get_default_base())),
+            ]),
+            EntryData::Reaction { emoji, target_id } => Text::from(format!("Reaction {} to {}", emoji, target_id)),
+            EntryData::Attachment { filename, mime, data } => Text::from(format!("Attachment {} ({}, {} bytes)", filename, mime, data.len())),
+        };
+
+        Clear.render(confirm_popup_area, buf);
+        Paragraph::new(text).block(block).render(confirm_popup_area, buf);
+        confirm_popup_area
+    }
+
+    fn handle_event(&mut self, event: Event) -> Option<StateChange> {
+        let Event::Key(key_event) = event else {return None};
+        if !key_event.is_press() {return None}
+        match key_event.code {
+            KeyCode::Char('y') => {
+                self.confirmed = true;
+                Some(StateChange::Pop)
+            }
+            KeyCode::Char('n') => Some(StateChange::Pop),
+            _ => None
+        }
+    }
+
+    fn consume_child(&mut self, child: ClientState) -> Option<StateChange> {
+        if let ClientState::Error(_) = child {} else {
+            eprintln!("unexpected child of ConfirmEntry")
+        }
+        None
+    }
+}
+
+/// popup asking before an entry is deleted
+#[derive(Debug)]
+struct ConfirmDelete {
+    entry_id: EntryId,
+    confirmed: bool,
+}
+
+impl ConfirmDelete {
+    fn new(entry_id: EntryId) -> Self {
+        Self { entry_id, confirmed: false }
+    }
+}
+
+impl InputWidget for ConfirmDelete {
+    fn render(&self, area: Rect, buf: &mut Buffer) -> Rect {
+        let mut layout = Layout::horizontal([Constraint::Fill(1), Constraint::Percentage(50), Constraint::Fill(1)]).split(area);
+        layout = Layout::vertical([Constraint::Fill(1), Constraint::Length(3), Constraint::Fill(1)]).split(layout[1]);
+        let confirm_popup_area = layout[1];
+
+        let block = Block::bordered()
+            .title(" Delete this entry? ")
+            .title_bottom(Line::from(" (y)es / (n)o ").centered());
+
+        Clear.render(confirm_popup_area, buf);
+        Paragraph::new(Line::from(self.entry_id.to_string()).centered()).block(block).render(confirm_popup_area, buf);
+        confirm_popup_area
+    }
+
+    fn handle_event(&mut self, event: Event) -> Option<StateChange> {
+        let Event::Key(key_event) = event else {return None};
+        if !key_event.is_press() {return None}
+        match key_event.code {
+            KeyCode::Char('y') => {
+                self.confirmed = true;
+                Some(StateChange::Pop)
+            }
+            KeyCode::Char('n') => Some(StateChange::Pop),
+            _ => None
+        }
+    }
+
+    fn consume_child(&mut self, child: ClientState) -> Option<StateChange> {
+        if let ClientState::Error(_) = child {} else {
+            eprintln!("unexpected child of ConfirmDelete")
+        }
+        None
+    }
+}
+
+/// popup taking the hex id of an entry to jump straight to
+#[derive(Debug)]
+struct JumpInput {
+    text: TextEntry,
+    confirmed: bool,
+}
+
+impl JumpInput {
+    fn new() -> Self {
+        Self { text: TextEntry::new(16), confirmed: false }
+    }
+
+    fn entry_id(&self) -> Result<EntryId, DataError> {
+        let hex = self.text.text.iter().collect::<String>();
+        u64::from_str_radix(hex.trim(), 16).map(EntryId::from).map_err(|_| DataError::NotHex)
+    }
+}
+
+impl InputWidget for JumpInput {
+    fn render(&self, area: Rect, buf: &mut Buffer) -> Rect {
+        let mut layout = Layout::horizontal([Constraint::Fill(1), Constraint::Percentage(50), Constraint::Fill(1)]).split(area);
+        layout = Layout::vertical([Constraint::Fill(1), Constraint::Length(3), Constraint::Fill(1)]).split(layout[1]);
+        let jump_popup_area = layout[1];
+
+        let block = Block::bordered()
+            .title(" Jump to id ")
+            .title_bottom(Line::from(" Enter to jump / Esc to cancel ").centered());
+
+        Clear.render(jump_popup_area, buf);
+        let text_area = block.inner(jump_popup_area);
+        block.render(jump_popup_area, buf);
+        self.text.render(text_area, buf);
+        jump_popup_area
+    }
+
+    fn handle_event(&mut self, event: Event) -> Option<StateChange> {
+        if let Event::Key(key_event) = &event {
+            if !key_event.is_press() {return None}
+            match key_event.code {
+                KeyCode::Enter => {
+                    self.confirmed = true;
+                    return Some(StateChange::Pop)
+                }
+                KeyCode::Esc => return Some(StateChange::Pop),
+                _ => {}
+            }
+        }
+        self.text.handle_event(event).map(|_| StateChange::Blank)
+    }
+
+    fn consume_child(&mut self, child: ClientState) -> Option<StateChange> {
+        if let ClientState::Error(_) = child {} else {
+            eprintln!("unexpected child of JumpInput")
+        }
+        None
+    }
+}
+
+/// popup showing the encoded bytes of an entry, for debugging the format
+#[derive(Debug)]
+struct RawView {
+    lines: Vec<String>, // see `hex_dump`
+    len: usize,
+    scroll: usize,
+    height: Cell<usize>, // lines shown when last rendered, for clamping the scroll
+}
+
+impl RawView {
+    fn new(entry: &Entry) -> Result<Self, DataError> {
+        let data = entry.into_data()?;
+        Ok(Self { lines: hex_dump(&data), len: data.len(), scroll: 0, height: Cell::new(0) })
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.height.get())
+    }
+}
+
+impl InputWidget for RawView {
+    fn render(&self, area: Rect, buf: &mut Buffer) -> Rect {
+        let mut layout = Layout::horizontal([Constraint::Fill(1), Constraint::Length(80), Constraint::Fill(1)]).split(area);
+        layout = Layout::vertical([Constraint::Fill(1), Constraint::Percentage(80), Constraint::Fill(1)]).split(layout[1]);
+        let raw_popup_area = layout[1];
+
+        let block = Block::bordered()
+            .title(format!(" Raw Entry ({} bytes) ", self.len))
+            .title_bottom(Line::from(format!(" {}/{} ", (self.scroll + 1).min(self.lines.len()), self.lines.len())).right_aligned());
+        self.height.set(block.inner(raw_popup_area).height as usize);
+
+        let text = Text::from_iter(self.lines.iter().map(|line| Line::from(line.as_str())));
+        Clear.render(raw_popup_area, buf);
+        Paragraph::new(text)
+            .block(block)
+            .scroll((self.scroll.min(u16::MAX as usize) as u16, 0))
+            .render(raw_popup_area, buf);
+        raw_popup_area
+    }
+
+    fn handle_event(&mut self, event: Event) -> Option<StateChange> {
+        match event {
+            Event::Key(key_event) => {
+                if !key_event.is_press() {return None}
+                match key_event {
+                    _ if keymap().matches(Action::Raw, &key_event) => return Some(StateChange::Pop),
+                    _ if down!(&key_event) => self.scroll += 1,
+                    _ if up!(&key_event) => self.scroll = self.scroll.saturating_sub(1),
+                    _ => match key_event.code {
+                        KeyCode::PageDown => self.scroll += self.height.get().max(1),
+                        KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(self.height.get().max(1)),
+                        KeyCode::Home => self.scroll = 0,
+                        KeyCode::End => self.scroll = self.max_scroll(),
+                        _ => return None,
+                    }
+                }
+            }
+            Event::Mouse(mouse_event) => match mouse_event.kind {
+                MouseEventKind::ScrollDown => self.scroll += 1,
+                MouseEventKind::ScrollUp => self.scroll = self.scroll.saturating_sub(1),
+                _ => return None,
+            }
+            _ => return None,
+        }
+        self.scroll = self.scroll.min(self.max_scroll());
+        Some(StateChange::Blank)
+    }
+
+    fn consume_child(&mut self, child: ClientState) -> Option<StateChange> {
+        if let ClientState::Error(_) = child {} else {
+            eprintln!("unexpected child of RawView")
+        }
+        None
+    }
+}
+
 #[derive(Debug)]
 struct EntryVariantSelector{
     selector: ScrollContainer<EntryVariant>,
@@ -304,8 +1040,8 @@ impl InputWidget for EntryVariantSelector {
         } else {
             if let Event::Key(key_event) = event {
                 if !key_event.is_press() {return None}
-                match key_event.code {
-                    KeyCode::Enter => {
+                match key_event {
+                    _ if keymap().matches(Action::Open, &key_event) => {
                         self.was_selected = true;
                         return Some(StateChange::Pop)
                     }
@@ -367,8 +1103,8 @@ impl InputWidget for DefaultBaseSelector {
         } else {
             if let Event::Key(key_event) = event {
                 if !key_event.is_press() {return None}
-                match key_event.code {
-                    KeyCode::Enter => {
+                match key_event {
+                    _ if keymap().matches(Action::Open, &key_event) => {
                         self.was_selected = true;
                         return Some(StateChange::Pop)
                     }
@@ -451,12 +1187,12 @@ impl InputWidget for IdList {
                         return Some(StateChange::Push(ClientState::TextEntry(text_entry)));
                     }
                     let Ok(new_id) = u64::from_str_radix(text, 16) else {
-                        return Some(StateChange::Push(ClientState::Error(vec![internal_error!()])));
+                        return Some(StateChange::Push(ClientState::Error(vec![internal_error!().into()])));
                     };
                     let new_id = Some(new_id.into());
                     if !id_list.container.items.contains(&new_id) {
                         let Some((_, new_id_slot)) = id_list.container.selection_mut() else {
-                            return Some(StateChange::Push(ClientState::Error(vec![internal_error!()])));
+                            return Some(StateChange::Push(ClientState::Error(vec![internal_error!().into()])));
                         };
                         *new_id_slot = new_id;
                     }
@@ -477,6 +1213,122 @@ impl InputWidget for IdList {
 }
 
 
+/// edits the ids of a new access group's perms, typing hex adds to the focused list and the selected id can be removed
+#[derive(Debug)]
+struct PermsEditor {
+    title: &'static str,
+    draft: PermsDraft,
+    error: Option<DataError>, // why the last id typed wasn't added, cleared when typing
+    is_focused: bool,
+}
+
+impl PermsEditor {
+    fn new(title: &'static str, base: DefaultBase) -> Self {
+        Self {
+            title,
+            draft: PermsDraft::new(DefaultedIdSet::empty_from_base(base)),
+            error: None,
+            is_focused: false,
+        }
+    }
+}
+
+impl InputWidget for PermsEditor {
+    fn render(&self, area: Rect, buf: &mut Buffer) -> Rect {
+        let block = Block::bordered().title(self.title);
+        let inner_area = block.inner(area);
+        Clear.render(inner_area, buf);
+        let areas = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ]).split(inner_area);
+
+        // a White base has no whitelist and a Black base no blacklist
+        let perms = self.draft.perms();
+        let mut lists = Vec::new();
+        if let DefaultedIdSet::Inherit { .. } | DefaultedIdSet::Black { .. } = perms {lists.push((" Whitelisted ", false, perms.iter_whitelist()))}
+        if let DefaultedIdSet::Inherit { .. } | DefaultedIdSet::White { .. } = perms {lists.push((" Blacklisted ", true, perms.iter_blacklist()))}
+        let list_areas = Layout::horizontal(vec![Constraint::Fill(1); lists.len()]).split(areas[0]);
+        for ((title, is_blacklist, ids), list_area) in lists.into_iter().zip(list_areas.iter()) {
+            let is_focused_list = self.is_focused && is_blacklist == self.draft.blacklist_focused();
+            let mut text = Text::default();
+            for (idx, id) in ids.enumerate() {
+                if is_focused_list && self.draft.selected() == Some(idx) {
+                    text.push_line(id.to_string().reversed());
+                } else {
+                    text.push_line(id.to_string());
+                }
+            }
+            let list_block = if is_focused_list {Block::bordered().title(title.bold())} else {Block::bordered().title(title)};
+            Paragraph::new(text).block(list_block).render(*list_area, buf);
+        }
+
+        let mut input_line = Line::from(" Add id: ");
+        input_line.push_span(self.draft.input().to_string());
+        if self.is_focused {input_line.push_span(' '.reversed())}
+        input_line.render(areas[1], buf);
+        let hint_line = match &self.error {
+            Some(DataError::NotHex) => Line::from(" Type the id in hex ".red()),
+            Some(DataError::AlreadyExists) => Line::from(" Already listed ".red()),
+            Some(e) => Line::from(format!(" {} ", e).red()),
+            None => Line::from(" Enter: add, Delete: remove selected, Tab: switch list ".dark_gray()),
+        };
+        hint_line.render(areas[2], buf);
+        block.render(area, buf);
+        inner_area
+    }
+
+    fn handle_event(&mut self, event: Event) -> Option<StateChange> {
+        let Event::Key(key_event) = event else {return None};
+        if !key_event.is_press() {return None}
+        match key_event.code {
+            // typed hex is the id being entered, before any keybind on the same key
+            KeyCode::Char(c) if c.is_ascii_hexdigit() => {
+                self.error = None;
+                self.draft.push_char(c);
+                return Some(StateChange::Blank)
+            }
+            KeyCode::Backspace if !self.draft.input().is_empty() => {
+                self.error = None;
+                self.draft.pop_char();
+                return Some(StateChange::Blank)
+            }
+            KeyCode::Backspace | KeyCode::Delete => {
+                self.draft.remove_selected();
+                return Some(StateChange::Blank)
+            }
+            KeyCode::Tab => {
+                self.draft.toggle_list();
+                return Some(StateChange::Blank)
+            }
+            _ => {}
+        }
+        match key_event {
+            // with nothing typed, done with this set of perms
+            _ if keymap().matches(Action::Open, &key_event) && self.draft.input().is_empty() => Some(StateChange::Pop),
+            _ if keymap().matches(Action::Open, &key_event) => {
+                self.error = self.draft.add_input().err();
+                Some(StateChange::Blank)
+            }
+            _ if keymap().matches(Action::Up, &key_event) => {self.draft.select_up(); Some(StateChange::Blank)}
+            _ if keymap().matches(Action::Down, &key_event) => {self.draft.select_down(); Some(StateChange::Blank)}
+            _ if keymap().matches(Action::Left, &key_event) => Some(StateChange::MoveLeft),
+            _ if keymap().matches(Action::Right, &key_event) => Some(StateChange::MoveRight),
+            _ => None
+        }
+    }
+
+    fn focus(&mut self) {self.is_focused = true}
+    fn unfocus(&mut self) {self.is_focused = false}
+
+    fn consume_child(&mut self, child: ClientState) -> Option<StateChange> {
+        if let ClientState::Error(_) = child {} else {
+            eprintln!("unexpected child of PermsEditor")
+        }
+        None
+    }
+}
 
 
 #[derive(Debug)]
@@ -487,6 +1339,20 @@ struct EntryViewer {
     y_select: usize,
     x_size: usize,
     y_size: usize,
+    // only the rows in view are handed to the `Paragraph`, long messages are slow to lay out whole
+    content_scroll: usize, // in wrapped rows
+    content_rows: RefCell<Option<(usize, Vec<Range<usize>>)>>, // the message wrapped at the width it was last rendered at
+    content_height: Cell<usize>, // rows shown when last rendered
+    markdown: bool, // whether messages are rendered as markdown, only kept for the session
+    #[cfg(feature = "markdown")]
+    styled_rows: RefCell<Option<(usize, Option<Vec<StyledLine>>)>>, // like `content_rows`, None inside if the markdown didn't parse
+    reactions: Vec<(String, usize)>, // counts of the reactions to the entry, shown in the footer
+    view_count: Option<u64>, // how many users have read the entry, None if it couldn't be marked read
+    subtree_count: Option<u64>, // how many entries are below the entry, None if it couldn't be counted
+    user_labels: HashMap<UserId, String>, // how the users shown are labelled, see `user_label`, those missing are shown by id
+    expanded_lists: HashSet<usize>, // the perm lists (by x) over `MAX_SHOWN_PERM_IDS` which have been opened
+    perms_summary: Option<(PermsSummary, PermsSummary)>, // the group's (write, read) list lengths, only kept while the entry is without its ids
+    full_entry_wanted: bool, // a list was opened while the entry was without its ids, see `take_full_entry_wanted`
 
     is_focused: bool,
     viewer_user_id: UserId,
@@ -501,6 +1367,19 @@ impl EntryViewer {
             y_select: 0,
             x_size: 0,
             y_size: 0,
+            content_scroll: 0,
+            content_rows: RefCell::new(None),
+            content_height: Cell::new(0),
+            markdown: false,
+            #[cfg(feature = "markdown")]
+            styled_rows: RefCell::new(None),
+            reactions: Vec::new(),
+            view_count: None,
+            subtree_count: None,
+            user_labels: HashMap::new(),
+            expanded_lists: HashSet::new(),
+            perms_summary: None,
+            full_entry_wanted: false,
 
             is_focused: false,
             viewer_user_id: user_id,
@@ -512,7 +1391,7 @@ impl EntryViewer {
         let old_x_size = self.x_size;
         let old_y_size = self.y_size;
         match &entry.entry_data {
-            EntryData::Message { .. } => {
+            EntryData::Message { .. } | EntryData::Reaction { .. } | EntryData::Attachment { .. } => {
                 self.x_size = 1;
                 self.y_size = 1;
             }
@@ -535,6 +1414,13 @@ impl EntryViewer {
             self.y_select /= old_y_size;
         }
 
+        self.content_rows.replace(None);
+        #[cfg(feature = "markdown")]
+        self.styled_rows.replace(None);
+        self.expanded_lists.clear();
+        self.perms_summary = None;
+        self.full_entry_wanted = false;
+
         let out = if self.has_mutated {
             self.take_entry().0
         } else {
@@ -555,6 +1441,105 @@ impl EntryViewer {
         &self.entry
     }
 
+    /// swaps in the entry gotten with its perm lists, keeping which lists are open
+    fn set_full_entry(&mut self, entry: Entry) {
+        // only groups missing their ids are swapped, and those can't have been changed
+        if self.perms_summary.is_none() {return}
+        self.entry = Some(entry);
+        self.perms_summary = None;
+    }
+
+    /// the lengths of the group's perm lists, shown in their place until one is opened, 
+    /// not kept if the entry already has all of the ids
+    fn set_perms_summary(&mut self, perms_summary: Option<(PermsSummary, PermsSummary)>) {
+        self.perms_summary = perms_summary.filter(|(write, read)| match &self.entry {
+            Some(Entry { entry_data: EntryData::AccessGroup { write_perms, read_perms, .. }, .. }) => {
+                PermsSummary::new(write_perms).ok() != Some(*write) || PermsSummary::new(read_perms).ok() != Some(*read)
+            }
+            _ => false,
+        });
+    }
+
+    /// whether a list was opened while the entry was without its ids, for the entry to be gotten with them (see `set_full_entry`)
+    fn take_full_entry_wanted(&mut self) -> bool {
+        std::mem::take(&mut self.full_entry_wanted)
+    }
+
+    fn set_reactions(&mut self, reactions: Vec<(String, usize)>) {
+        self.reactions = reactions;
+    }
+
+    fn set_view_count(&mut self, view_count: Option<u64>) {
+        self.view_count = view_count;
+    }
+
+    fn set_subtree_count(&mut self, subtree_count: Option<u64>) {
+        self.subtree_count = subtree_count;
+    }
+
+    fn set_user_labels(&mut self, user_labels: HashMap<UserId, String>) {
+        self.user_labels = user_labels;
+    }
+
+    fn user_label(&self, user_id: UserId) -> String {
+        self.user_labels.get(&user_id).cloned().unwrap_or_else(|| user_id.to_string())
+    }
+
+    fn scroll_to_top(&mut self) {
+        self.content_scroll = 0;
+    }
+
+    /// how many rows the message wrapped to when last rendered
+    fn content_row_count(&self) -> usize {
+        #[cfg(feature = "markdown")]
+        if self.markdown {
+            if let Some((_, Some(rows))) = self.styled_rows.borrow().as_ref() {return rows.len()}
+        }
+        self.content_rows.borrow().as_ref().map_or(0, |(_, rows)| rows.len())
+    }
+
+    /// switches between rendering messages as markdown and as plain text, 
+    /// false if the client was built without the `markdown` feature
+    fn toggle_markdown(&mut self) -> bool {
+        if cfg!(not(feature = "markdown")) {return false}
+        self.markdown = !self.markdown;
+        self.content_scroll = 0;
+        true
+    }
+
+    /// renders the message as markdown, giving the row count and the first row shown,
+    /// None if markdown is off or the message didn't parse, for it to be rendered as plain text
+    #[cfg(feature = "markdown")]
+    fn render_markdown(&self, message: &str, area: Rect, buf: &mut Buffer) -> Option<(usize, usize)> {
+        if !self.markdown {return None}
+        let width = area.width as usize;
+        let mut styled_rows = self.styled_rows.borrow_mut();
+        if styled_rows.as_ref().is_none_or(|(rows_width, _)| *rows_width != width) {
+            let rows = styled_lines(message).map(|lines| lines.iter().flat_map(|line| {
+                // a blank line still takes up a row
+                if line.is_empty() {vec![Vec::new()]} else {wrap_styled_line(line, width)}
+            }).collect());
+            *styled_rows = Some((width, rows));
+        }
+        let rows = styled_rows.as_ref().unwrap().1.as_ref()?;
+        let window = line_window(rows.len(), self.content_scroll, area.height as usize);
+        let text = Text::from_iter(rows[window.clone()].iter().map(|row| Line::from_iter(row.iter().map(|(text, style)| {
+            let mut span = Span::raw(text.as_str());
+            if style.bold || style.heading {span = span.bold()}
+            if style.heading {span = span.underlined()}
+            if style.italic {span = span.italic()}
+            if style.code {span = span.fg(Color::Yellow)}
+            span
+        }))));
+        Paragraph::new(text).render(area, buf);
+        Some((rows.len(), window.start))
+    }
+
+    #[cfg(not(feature = "markdown"))]
+    fn render_markdown(&self, _message: &str, _area: Rect, _buf: &mut Buffer) -> Option<(usize, usize)> {
+        None
+    }
+
     fn as_entry_mut(&mut self) -> &mut Option<Entry> {
         if let Some(_) = self.entry {self.has_mutated |= true;}
         &mut self.entry
@@ -564,7 +1549,7 @@ impl EntryViewer {
 impl InputWidget for EntryViewer {
     fn render(&self, area: Rect, buf: &mut Buffer) -> Rect {
         Clear.render(area, buf);
-        let block = Block::bordered();
+        let mut block = Block::bordered();
         let inner_area = block.inner(area);
         let mut title = Line::default();
         let sub_area = match &self.entry {
@@ -572,19 +1557,36 @@ impl InputWidget for EntryViewer {
                 match &entry.entry_data {
                     EntryData::Message { timestamp, message } => {
                         title.push_span(" Message by ");
-                        title.push_span(entry.header_data.author_id.to_string());
+                        title.push_span(self.user_label(entry.header_data.author_id));
                         title.push_span(", written/editted ");
                         title.push_span(chrono::DateTime::from_timestamp_secs(*timestamp as i64).unwrap().to_string());
                         title.push_span(" ");
 
-                        Paragraph::new(message as &str).render(inner_area, buf);
+                        let (width, height) = (inner_area.width as usize, inner_area.height as usize);
+                        self.content_height.set(height);
+                        let (row_count, first_row) = match self.render_markdown(message, inner_area, buf) {
+                            Some(rendered) => rendered,
+                            None => {
+                                let mut content_rows = self.content_rows.borrow_mut();
+                                if content_rows.as_ref().is_none_or(|(rows_width, _)| *rows_width != width) {
+                                    *content_rows = Some((width, wrap_lines(message, width)));
+                                }
+                                let rows = &content_rows.as_ref().unwrap().1;
+                                let window = line_window(rows.len(), self.content_scroll, height);
+                                let text = Text::from_iter(rows[window.clone()].iter().map(|row| &message[row.clone()]));
+                                Paragraph::new(text).render(inner_area, buf);
+                                (rows.len(), window.start)
+                            }
+                        };
+                        let footer = format!(" line {} of {} ", (first_row + 1).min(row_count), row_count);
+                        block = block.title_bottom(Line::from(footer).right_aligned());
                         area
                     }
                     EntryData::AccessGroup { name, write_perms, read_perms } => {
                         title.push_span(" Access Group: ");
                         title.push_span(name);
                         title.push_span(", owned by ");
-                        title.push_span(entry.header_data.author_id.to_string());
+                        title.push_span(self.user_label(entry.header_data.author_id));
                         title.push_span(" ");
                         if entry.header_data.author_id == self.viewer_user_id {
                             title.push_span("(Self) ");
@@ -593,68 +1595,61 @@ impl InputWidget for EntryViewer {
                         let write_read_layout = Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).split(inner_area);
                         let mut x = 0;
                         let mut sub_area = area;
-                        for ((perm_set, mut perm_name ), area) in [write_perms, read_perms].iter().copied().zip(write_read_titles).zip(write_read_layout.iter().copied()) {
+                        let summaries = self.perms_summary.map(|(write, read)| [write, read]);
+                        for (idx, ((perm_set, mut perm_name ), area)) in [write_perms, read_perms].iter().copied().zip(write_read_titles).zip(write_read_layout.iter().copied()).enumerate() {
+                            // the lengths stand in for the lists until their ids are gotten
+                            let summary = summaries.map(|summaries| summaries[idx]);
                             let block = Block::bordered();
                             let perm_set_area = block.inner(area);
                             perm_name.push_str(&perm_set.get_default_base().to_string());
                             perm_name.push_str(") ");
-                            match perm_set {
-                                DefaultedIdSet::Inherit { whitelist_ids, blacklist_ids } => {
-                                    let layout = Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).split(perm_set_area);
-                                    let mut whitelist = Text::default();
-                                    whitelist.push_line("Whitelisted:");
-                                    if (self.x_select == x) & self.is_focused {whitelist = whitelist.bold(); sub_area = layout[0]}
-                                    x += 1;
-                                    for id in whitelist_ids {
-                                        let mut line = Line::default();
-                                        line.push_span(" -  ".bold());
-                                        line.push_span(id.to_string());
-                                        whitelist.push_line(line);
-                                    }
-                                    let mut blacklist = Text::default();
-                                    blacklist.push_line("Blacklisted:");
-                                    if (self.x_select == x) & self.is_focused {blacklist = blacklist.bold(); sub_area = layout[1]}
-                                    x += 1;
-                                    for id in blacklist_ids {
-                                        let mut line = Line::default();
-                                        line.push_span(" -  ".bold());
-                                        line.push_span(id.to_string());
-                                        blacklist.push_line(line);
-                                    }
-                                    whitelist.render(layout[0], buf);
-                                    blacklist.render(layout[1], buf);
+                            // a White base has no whitelist and a Black base no blacklist
+                            let base = perm_set.get_default_base();
+                            let id_lists = [
+                                ("Whitelisted:", base != DefaultBase::White, perm_set.iter_whitelist(), summary.map_or(perm_set.len_whitelist(), |summary| summary.whitelist_len as usize)),
+                                ("Blacklisted:", base != DefaultBase::Black, perm_set.iter_blacklist(), summary.map_or(perm_set.len_blacklist(), |summary| summary.blacklist_len as usize)),
+                            ].into_iter().filter(|x| x.1).collect::<Vec<_>>();
+                            let layout = Layout::horizontal(vec![Constraint::Fill(1); id_lists.len()]).split(perm_set_area);
+                            for ((list_title, _, ids, len), list_area) in id_lists.into_iter().zip(layout.iter().copied()) {
+                                let mut list = Text::default();
+                                list.push_line(list_title);
+                                if (self.x_select == x) & self.is_focused {list = list.bold(); sub_area = list_area}
+                                let collapsed = (summary.is_some() && len > 0) || (len > MAX_SHOWN_PERM_IDS && !self.expanded_lists.contains(&x));
+                                x += 1;
+                                if collapsed {
+                                    list.push_line(Line::from(format!(" {} ids, open to list them", len)).italic());
+                                    list.render(list_area, buf);
+                                    continue;
                                 }
-                                DefaultedIdSet::White { blacklist_ids } => {
-                                    let mut blacklist = Text::default();
-                                    blacklist.push_line("Blacklisted:");
-                                    if (self.x_select == x) & self.is_focused {blacklist = blacklist.bold(); sub_area = perm_set_area}
-                                    x += 1;
-                                    for id in blacklist_ids {
-                                        let mut line = Line::default();
-                                        line.push_span(" -  ".bold());
-                                        line.push_span(id.to_string());
-                                        blacklist.push_line(line);
-                                    }
-                                    blacklist.render(perm_set_area, buf);
-                                }
-                                DefaultedIdSet::Black { whitelist_ids } => {
-                                    let mut whitelist = Text::default();
-                                    whitelist.push_line("Whitelisted:");
-                                    if (self.x_select == x) & self.is_focused {whitelist = whitelist.bold(); sub_area = perm_set_area}
-                                    x += 1;
-                                    for id in whitelist_ids {
-                                        let mut line = Line::default();
-                                        line.push_span(" -  ".bold());
-                                        line.push_span(id.to_string());
-                                        whitelist.push_line(line);
-                                    }
-                                    whitelist.render(perm_set_area, buf);
+                                for id in ids {
+                                    let mut line = Line::default();
+                                    line.push_span(" -  ".bold());
+                                    line.push_span(self.user_label(*id));
+                                    list.push_line(line);
                                 }
+                                list.render(list_area, buf);
                             }
                             block.title(perm_name).render(area, buf);
                         }
                         sub_area
                     }
+                    EntryData::Reaction { emoji, target_id } => {
+                        title.push_span(" Reaction by ");
+                        title.push_span(self.user_label(entry.header_data.author_id));
+                        title.push_span(" ");
+                        Paragraph::new(format!("{} to {}", emoji, target_id)).centered().render(inner_area, buf);
+                        area
+                    }
+                    EntryData::Attachment { filename, mime, data } => {
+                        title.push_span(" Attachment by ");
+                        title.push_span(self.user_label(entry.header_data.author_id));
+                        title.push_span(" ");
+                        Paragraph::new(vec![
+                            Line::from(filename.as_str()),
+                            Line::from(format!("{}, {} bytes", mime, data.len())),
+                        ]).centered().render(inner_area, buf);
+                        area
+                    }
                 }
             }
             None => {
@@ -662,39 +1657,70 @@ impl InputWidget for EntryViewer {
                 area
             }
         };
+        if !self.reactions.is_empty() {
+            let mut footer = Line::from(" ");
+            for (emoji, count) in &self.reactions {
+                footer.push_span(format!("{} {} ", emoji, count));
+            }
+            block = block.title_bottom(footer.left_aligned());
+        }
+        if let (Some(view_count), Some(_)) = (self.view_count, &self.entry) {
+            block = block.title_bottom(Line::from(format!(" seen by {} ", view_count)).centered());
+        }
+        if let (Some(subtree_count), Some(_)) = (self.subtree_count, &self.entry) {
+            block = block.title_bottom(Line::from(format!(" {} below ", subtree_count)).right_aligned());
+        }
 
         block.title(title).render(area, buf);
         sub_area
     }
 
     fn handle_event(&mut self, event: Event) -> Option<StateChange> {
+        // messages scroll instead of moving the selection
+        if let (Some(Entry { entry_data: EntryData::Message { .. }, .. }), Event::Key(key_event)) = (&self.entry, &event) {
+            let page = self.content_height.get().max(1);
+            let scroll = match key_event.code {
+                _ if !key_event.is_press() => None,
+                _ if down!(key_event) => Some(self.content_scroll + 1),
+                _ if up!(key_event) => Some(self.content_scroll.saturating_sub(1)),
+                KeyCode::PageDown => Some(self.content_scroll + page),
+                KeyCode::PageUp => Some(self.content_scroll.saturating_sub(page)),
+                KeyCode::Home => Some(0),
+                KeyCode::End => Some(usize::MAX),
+                _ => None,
+            };
+            if let Some(scroll) = scroll {
+                self.content_scroll = line_window(self.content_row_count(), scroll, self.content_height.get()).start;
+                return Some(StateChange::Blank);
+            }
+        }
         if let Some(entry) = &mut self.entry {
             //universal stuff
             if let Event::Key(key_event) = event.clone() {
                 let mut matched = true;
-                match key_event.code {
-                    left!() => {
+                match key_event {
+                    _ if left!(&key_event) => {
                         if self.x_select == 0 {
                             return Some(StateChange::MoveLeft)
                         } else {
                             self.x_select -= 1;
                         }
                     }
-                    up!() => {
+                    _ if up!(&key_event) => {
                         if self.y_select == 0 {
                             return Some(StateChange::MoveUp)
                         } else {
                             self.y_select -= 1;
                         }
                     }
-                    down!() => {
+                    _ if down!(&key_event) => {
                         self.y_select += 1;
                         if self.y_select >= self.y_size {
                             self.y_select = self.y_size -1;
                             return Some(StateChange::MoveDown)
                         }
                     }
-                    right!() => {
+                    _ if right!(&key_event) => {
                         self.x_select += 1;
                         if self.x_select >= self.x_size {
                             self.x_select = self.x_size -1;
@@ -706,12 +1732,17 @@ impl InputWidget for EntryViewer {
                 if matched {return Some(StateChange::Blank)}
             }
             match &mut entry.entry_data {
-                EntryData::Message { .. } => {}
+                EntryData::Message { .. } | EntryData::Reaction { .. } | EntryData::Attachment { .. } => {}
                 EntryData::AccessGroup { write_perms, read_perms, .. } => {
                     if let Event::Key(key_event) = event {
-                        match key_event.code {
-                            KeyCode::Enter => {
-                                if entry.header_data.author_id != self.viewer_user_id {return Some(StateChange::Blank)}
+                        match key_event {
+                            _ if keymap().matches(Action::Open, &key_event) => {
+                                // the ids are only gotten once a list is opened
+                                if self.perms_summary.is_some() {
+                                    self.expanded_lists.insert(self.x_select);
+                                    self.full_entry_wanted = true;
+                                    return Some(StateChange::Blank)
+                                }
                                 let mut id_lists = Vec::new();
                                 for perm_set in [write_perms, read_perms] {
                                     match perm_set {
@@ -727,6 +1758,11 @@ impl InputWidget for EntryViewer {
                                         }
                                     }
                                 }
+                                // a long list is only counted until it's first opened
+                                if id_lists[self.x_select].len() > MAX_SHOWN_PERM_IDS && self.expanded_lists.insert(self.x_select) {
+                                    return Some(StateChange::Blank)
+                                }
+                                if entry.header_data.author_id != self.viewer_user_id {return Some(StateChange::Blank)}
                                 return Some(
                                     StateChange::Push(
                                         ClientState::AccessGroupIdList(
@@ -795,6 +1831,52 @@ enum TreeViewerState {
     Unfocused
 }
 
+/// where the viewer was before a navigation, for undoing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NavigationSnapshot {
+    path: Vec<EntryId>, // below the root
+    cursor_pos: Option<usize>,
+}
+
+impl NavigationSnapshot {
+    /// the cursor to restore among `num_items` children, none if they've since shrunk past it
+    fn cursor_within(&self, num_items: usize) -> Option<usize> {
+        self.cursor_pos.filter(|cursor_pos| *cursor_pos < num_items)
+    }
+}
+
+/// the navigations which can be undone, and the undone ones which can be redone
+#[derive(Debug, Default)]
+struct NavigationHistory {
+    undo_stack: VecDeque<NavigationSnapshot>, // at most `NAVIGATION_HISTORY_LEN`, newest at the back
+    redo_stack: Vec<NavigationSnapshot>,
+}
+
+impl NavigationHistory {
+    /// records where the viewer was before a navigation, the navigations undone before it can no longer be redone
+    fn record(&mut self, snapshot: NavigationSnapshot) {
+        if self.undo_stack.len() == NAVIGATION_HISTORY_LEN {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(snapshot);
+        self.redo_stack.clear();
+    }
+
+    /// where to go back to, `current` being where the viewer is so it can be redone
+    fn undo(&mut self, current: NavigationSnapshot) -> Option<NavigationSnapshot> {
+        let snapshot = self.undo_stack.pop_back()?;
+        self.redo_stack.push(current);
+        Some(snapshot)
+    }
+
+    /// where to go forward to, `current` being where the viewer is so it can be undone again
+    fn redo(&mut self, current: NavigationSnapshot) -> Option<NavigationSnapshot> {
+        let snapshot = self.redo_stack.pop()?;
+        self.undo_stack.push_back(current);
+        Some(snapshot)
+    }
+}
+
 #[derive(Debug)]
 struct EntryTreeViewer {
     path: PathManager,
@@ -802,26 +1884,38 @@ struct EntryTreeViewer {
     viewer: EntryViewer,
     state: TreeViewerState,
     awaited_child_parent: Option<TreeViewerState>, //janked type
+    status: Option<&'static str>, // cleared on the next event
+    history: NavigationHistory,
+    allow_empty_messages: bool, // see `Config`
     
     board: Rc<RefCell<MessageBoardConnection>>,
     terminal: Rc<RefCell<Terminal>>,
 }
 
 impl EntryTreeViewer {
-    fn new(board: Rc<RefCell<MessageBoardConnection>>, terminal: Rc<RefCell<Terminal>>) -> Result<Self, DataError> {
-        let user_id = board.borrow().get_user_id().unwrap();
+    /// starts at `saved_path` (see `Config::path`) as far as it is still valid
+    fn new(board: Rc<RefCell<MessageBoardConnection>>, terminal: Rc<RefCell<Terminal>>, saved_path: &[EntryId], allow_empty_messages: bool) -> Result<Self, DataError> {
+        let Some(user_id) = *board.borrow().get_user_id() else {return Err(DataError::DoesNotExist)};
+        let root_id = board.borrow_mut().get_root()?;
         let mut viewer = Self {
-            path: PathManager::new(),
-            navigator: Navigator (ScrollContainer::new(Vec::new())),
+            path: PathManager::new(root_id),
+            navigator: Navigator::new(),
             viewer: EntryViewer::new(user_id),
             state: TreeViewerState::Unfocused,
             awaited_child_parent: None,
+            status: None,
+            history: NavigationHistory::default(),
+            allow_empty_messages,
 
             board,
             terminal,
         };
 
-        viewer.push_active_entry(ROOT_ENTRY_ID.into())?; // FIXME: scuff, really there is no "last" entry_id
+        viewer.push_active_entry(root_id)?; // FIXME: scuff, really there is no "last" entry_id
+        let saved_path = valid_path_prefix(saved_path, root_id, |entry_id| Ok(viewer.board.borrow_mut().get_entry(entry_id)?.header_data.parent_id));
+        for entry_id in saved_path {
+            if viewer.push_active_entry(entry_id).is_err() {break}
+        }
 
         Ok(viewer)
     }
@@ -829,13 +1923,23 @@ impl EntryTreeViewer {
     fn swap_active_entry(&mut self, new_entry_id: EntryId) -> Result<(), DataError> {
         let mut board = self.board.borrow_mut();
         let new_entry = board.get_entry(new_entry_id)?;
-        self.navigator.replace_items(&new_entry.header_data.children_ids); // temporary
+        // a failed prefetch only means the children are gotten one by one
+        let _ = board.prefetch_entries(&new_entry.header_data.children_ids);
+        self.navigator.replace_items(&new_entry.header_data.children_ids, |entry_id| board.get_entry(entry_id)); // temporary
+        self.viewer.set_reactions(self.navigator.reaction_counts());
+        self.navigator.update_unseen(new_entry_id, &new_entry, &mut board);
+        self.viewer.set_view_count(board.mark_read(new_entry_id).ok());
+        self.viewer.set_subtree_count(board.subtree_count(new_entry_id).ok());
+        self.viewer.set_user_labels(board.user_labels(&shown_user_ids(&new_entry)));
+        let perms_summary = group_perms_summary(&mut board, new_entry_id, &new_entry);
         let old_entry_id = self.path.peek().map(|x| x.0); //jank
         self.path.pop();
-        if (self.path.len() > 2) | (*new_entry_id != ROOT_ENTRY_ID) {
+        if (self.path.len() > 2) | (new_entry_id != self.path.root_id()) {
             self.path.push(new_entry_id, &new_entry)?;
         }
-        if let (Some(old_entry), Some(old_entry_id)) = (self.viewer.add_entry(new_entry), old_entry_id) {
+        let old_entry = self.viewer.add_entry(new_entry);
+        self.viewer.set_perms_summary(perms_summary);
+        if let (Some(old_entry), Some(old_entry_id)) = (old_entry, old_entry_id) {
             board.edit_entry(old_entry_id, old_entry)?;
         }
         Ok(())
@@ -844,10 +1948,19 @@ impl EntryTreeViewer {
     fn push_active_entry(&mut self, new_entry_id: EntryId) -> Result<(), DataError> {
         let mut board = self.board.borrow_mut();
         let new_entry = board.get_entry(new_entry_id)?;
-        self.navigator.replace_items(&new_entry.header_data.children_ids); // temporary
         let old_entry_id = self.path.peek().map(|x| x.0); //jank
         self.path.push(new_entry_id, &new_entry)?;
-        if let (Some(old_entry), Some(old_entry_id)) = (self.viewer.add_entry(new_entry), old_entry_id) {
+        let _ = board.prefetch_entries(&new_entry.header_data.children_ids);
+        self.navigator.replace_items(&new_entry.header_data.children_ids, |entry_id| board.get_entry(entry_id)); // temporary
+        self.viewer.set_reactions(self.navigator.reaction_counts());
+        self.navigator.update_unseen(new_entry_id, &new_entry, &mut board);
+        self.viewer.set_view_count(board.mark_read(new_entry_id).ok());
+        self.viewer.set_subtree_count(board.subtree_count(new_entry_id).ok());
+        self.viewer.set_user_labels(board.user_labels(&shown_user_ids(&new_entry)));
+        let perms_summary = group_perms_summary(&mut board, new_entry_id, &new_entry);
+        let old_entry = self.viewer.add_entry(new_entry);
+        self.viewer.set_perms_summary(perms_summary);
+        if let (Some(old_entry), Some(old_entry_id)) = (old_entry, old_entry_id) {
             board.edit_entry(old_entry_id, old_entry)?;
         }
         Ok(())
@@ -858,6 +1971,85 @@ impl EntryTreeViewer {
         self.reload()
     }
 
+    fn snapshot(&self) -> NavigationSnapshot {
+        NavigationSnapshot {
+            path: self.path.entry_ids(),
+            cursor_pos: self.navigator.0.cursor_pos,
+        }
+    }
+
+    /// runs the navigation, recording where the viewer was so it can be undone
+    fn navigate(&mut self, f: impl FnOnce(&mut Self) -> Result<(), DataError>) -> Result<(), DataError> {
+        let snapshot = self.snapshot();
+        f(self)?;
+        if snapshot.path != self.path.entry_ids() {
+            self.history.record(snapshot);
+            self.viewer.scroll_to_top();
+        }
+        Ok(())
+    }
+
+    /// goes back to where the snapshot was taken, as far as the path is still valid
+    fn restore(&mut self, snapshot: &NavigationSnapshot) -> Result<(), DataError> {
+        let mut board = self.board.borrow_mut();
+        let root_id = self.path.root_id();
+        let path = valid_path_prefix(&snapshot.path, root_id, |entry_id| Ok(board.get_entry(entry_id)?.header_data.parent_id));
+        let old_entry_id = self.path.peek().map(|x| x.0);
+        self.path.truncate_to(root_id);
+        let mut new_entry = board.get_entry(root_id)?;
+        for entry_id in path {
+            new_entry = board.get_entry(entry_id)?;
+            self.path.push(entry_id, &new_entry)?;
+        }
+        let _ = board.prefetch_entries(&new_entry.header_data.children_ids);
+        self.navigator.replace_items(&new_entry.header_data.children_ids, |entry_id| board.get_entry(entry_id));
+        self.viewer.set_reactions(self.navigator.reaction_counts());
+        let new_entry_id = self.path.peek().map_or(root_id, |x| x.0);
+        self.navigator.update_unseen(new_entry_id, &new_entry, &mut board);
+        self.viewer.set_view_count(board.mark_read(new_entry_id).ok());
+        self.viewer.set_subtree_count(board.subtree_count(new_entry_id).ok());
+        self.viewer.set_user_labels(board.user_labels(&shown_user_ids(&new_entry)));
+        self.navigator.0.cursor_pos = snapshot.cursor_within(self.navigator.0.items.len());
+        self.viewer.scroll_to_top();
+        let perms_summary = group_perms_summary(&mut board, new_entry_id, &new_entry);
+        let old_entry = self.viewer.add_entry(new_entry);
+        self.viewer.set_perms_summary(perms_summary);
+        if let (Some(old_entry), Some(old_entry_id)) = (old_entry, old_entry_id) {
+            board.edit_entry(old_entry_id, old_entry)?;
+        }
+        Ok(())
+    }
+
+    /// goes to the entry, rebuilding the path from its parents
+    fn jump_to(&mut self, entry_id: EntryId) -> Result<(), DataError> {
+        let path = self.board.borrow_mut().get_ancestors(entry_id)?;
+        self.restore(&NavigationSnapshot { path, cursor_pos: None })
+    }
+
+    /// gives whether there was a navigation to undo
+    fn undo_navigation(&mut self) -> Result<bool, DataError> {
+        let Some(snapshot) = self.history.undo(self.snapshot()) else {return Ok(false)};
+        self.restore(&snapshot)?;
+        Ok(true)
+    }
+
+    /// gives whether there was an undone navigation to redo
+    fn redo_navigation(&mut self) -> Result<bool, DataError> {
+        let Some(snapshot) = self.history.redo(self.snapshot()) else {return Ok(false)};
+        self.restore(&snapshot)?;
+        Ok(true)
+    }
+
+    /// gets the active entry with its perm lists, for when one is opened in the viewer
+    fn load_full_entry(&mut self) -> Result<(), DataError> {
+        let entry_id = self.path.peek().map_or(self.path.root_id(), |x| x.0);
+        let mut board = self.board.borrow_mut();
+        let entry = board.get_full_entry(entry_id)?;
+        self.viewer.set_user_labels(board.user_labels(&shown_user_ids(&entry)));
+        self.viewer.set_full_entry(entry);
+        Ok(())
+    }
+
     fn set_state(&mut self, state: TreeViewerState) {
         match state {
             TreeViewerState::Content => {
@@ -875,6 +2067,18 @@ impl EntryTreeViewer {
         }
         self.state = state;
     }
+
+    /// copies the message of the active entry, or its id if it isn't a message
+    #[cfg(feature = "clipboard")]
+    fn copy_active_entry(&self) -> Result<(), ClientError> {
+        let Some((entry_id, _)) = self.path.peek() else {return Err(internal_error!().into())};
+        let text = match self.viewer.as_entry() {
+            Some(Entry { entry_data: EntryData::Message { message, .. }, .. }) => message.clone(),
+            _ => format!("{:016X}", **entry_id),
+        };
+        let mut clipboard = arboard::Clipboard::new().map_err(|_| ClientError::ClipboardUnavailable)?;
+        clipboard.set_text(text).map_err(|_| ClientError::ClipboardUnavailable)
+    }
 }
 
 impl Drop for EntryTreeViewer {
@@ -886,6 +2090,9 @@ impl Drop for EntryTreeViewer {
                 }
             }
         }
+        // saved on the way out so the next launch starts here
+        let path = self.path.entry_ids();
+        edit_config(|config| config.path = path);
     }
 }
 
@@ -897,9 +2104,17 @@ impl InputWidget for EntryTreeViewer {
         if has_mutated {
             board.edit_entry(entry_id, to_write)?;
         }
-        let reloaded_entry = board.get_entry(entry_id)?;
-        self.navigator.replace_items(&reloaded_entry.header_data.children_ids);
+        let reloaded_entry = board.fetch_entry(entry_id)?;
+        let _ = board.prefetch_entries(&reloaded_entry.header_data.children_ids);
+        self.navigator.replace_items(&reloaded_entry.header_data.children_ids, |entry_id| board.get_entry(entry_id));
+        self.viewer.set_reactions(self.navigator.reaction_counts());
+        self.navigator.update_unseen(entry_id, &reloaded_entry, &mut board);
+        self.viewer.set_view_count(board.mark_read(entry_id).ok());
+        self.viewer.set_subtree_count(board.subtree_count(entry_id).ok());
+        self.viewer.set_user_labels(board.user_labels(&shown_user_ids(&reloaded_entry)));
+        let perms_summary = group_perms_summary(&mut board, entry_id, &reloaded_entry);
         assert!(self.viewer.add_entry(reloaded_entry).is_none());
+        self.viewer.set_perms_summary(perms_summary);
         Ok(())
     }
 
@@ -912,6 +2127,9 @@ impl InputWidget for EntryTreeViewer {
 
         //Clear.render(area, buf);
         self.path.render(path_area, buf);
+        if let Some(status) = self.status {
+            Line::from(status).right_aligned().render(Block::bordered().inner(path_area), buf);
+        }
         let mut popup_area = Layout::horizontal([Constraint::Fill(1), Constraint::Fill(2), Constraint::Fill(1)]).split(area)[1];
         popup_area = Layout::vertical([Constraint::Fill(1), Constraint::Fill(2), Constraint::Fill(1)]).split(popup_area)[1];
         let navigator_sub_area = self.navigator.render(navigator_area, buf);
@@ -927,27 +2145,144 @@ impl InputWidget for EntryTreeViewer {
     }
 
     fn handle_event(&mut self, event: Event) -> Option<StateChange> {
+        self.status = None;
         let mut matched = false;
         if let Event::Key(key_event) = event.clone() {
             matched = true;
             self.awaited_child_parent = Some(TreeViewerState::Unfocused);            
-            match key_event.code {
-                KeyCode::Char('H') if key_event.modifiers.contains(KeyModifiers::SHIFT) => {
-                    if let Err(e) = self.pop_active_entry() {
-                        return Some(StateChange::Push(ClientState::Error(vec![e])));
+            match key_event {
+                _ if keymap().matches(Action::Back, &key_event) => {
+                    if let Err(e) = self.navigate(Self::pop_active_entry) {
+                        return Some(StateChange::Push(ClientState::Error(vec![e.into()])));
                     }
                 }
-                KeyCode::Char('w') => {
+                _ if keymap().matches(Action::Redo, &key_event) => match self.redo_navigation() {
+                    Ok(true) => {},
+                    Ok(false) => self.status = Some(" Nothing to redo "),
+                    Err(e) => return Some(StateChange::Push(ClientState::Error(vec![e.into()]))),
+                }
+                _ if keymap().matches(Action::Undo, &key_event) => match self.undo_navigation() {
+                    Ok(true) => {},
+                    Ok(false) => self.status = Some(" Nothing to undo "),
+                    Err(e) => return Some(StateChange::Push(ClientState::Error(vec![e.into()]))),
+                }
+                _ if keymap().matches(Action::Write, &key_event) => {
+                    if self.board.borrow().is_guest() {
+                        self.status = Some(" Guests can't write, set a user to post ");
+                        return Some(StateChange::Blank)
+                    }
                     return Some(StateChange::Push(ClientState::WriteVarientSelection(EntryVariantSelector::new())))
                 }
+                _ if keymap().matches(Action::Sort, &key_event) => {
+                    let mut board = self.board.borrow_mut();
+                    self.navigator.cycle_order(|entry_id| board.get_entry(entry_id));
+                }
+                _ if keymap().matches(Action::Pin, &key_event) => {
+                    let Some(child_id) = self.navigator.selected() else {
+                        self.status = Some(" Select a child to pin ");
+                        return Some(StateChange::Blank)
+                    };
+                    let mut board = self.board.borrow_mut();
+                    if let Err(e) = board.set_pinned(child_id, !self.navigator.is_pinned(child_id)) {
+                        return Some(StateChange::Push(ClientState::Error(vec![e.into()])));
+                    }
+                    self.navigator.refresh_child(child_id, |entry_id| board.get_entry(entry_id));
+                }
+                _ if keymap().matches(Action::Jump, &key_event) => {
+                    return Some(StateChange::Push(ClientState::JumpInput(JumpInput::new())))
+                }
+                _ if keymap().matches(Action::Tree, &key_event) => {
+                    let mut board = self.board.borrow_mut();
+                    self.navigator.toggle_tree(|entry_id| board.get_entry(entry_id));
+                }
+                _ if keymap().matches(Action::Expand, &key_event) => {
+                    if !self.navigator.toggle_selected(&mut self.board.borrow_mut()) {
+                        self.status = Some(" Select a child in the tree to expand ");
+                    }
+                }
+                _ if keymap().matches(Action::Markdown, &key_event) => {
+                    if !self.viewer.toggle_markdown() {
+                        self.status = Some(" Built without the markdown feature ");
+                    }
+                }
+                _ if keymap().matches(Action::Save, &key_event) => {
+                    let Some(Entry { entry_data: EntryData::Attachment { filename, data, .. }, .. }) = self.viewer.as_entry() else {
+                        self.status = Some(" Not an attachment ");
+                        return Some(StateChange::Blank)
+                    };
+                    // only the final component is kept, so a filename can't write outside the current directory
+                    let Some(filename) = std::path::Path::new(filename).file_name() else {
+                        self.status = Some(" The attachment has no usable filename ");
+                        return Some(StateChange::Blank)
+                    };
+                    // an existing file is never overwritten
+                    if let Err(e) = message_board::utils::write_new_atomic(filename, data) {
+                        return Some(StateChange::Push(ClientState::Error(vec![DataError::from(e).into()])));
+                    }
+                    self.status = Some(" Saved the attachment to the current directory ");
+                }
+                _ if keymap().matches(Action::Raw, &key_event) => {
+                    // the raw entry should have the ids the group was shown without
+                    if self.viewer.perms_summary.is_some() {
+                        if let Err(e) = self.load_full_entry() {
+                            return Some(StateChange::Push(ClientState::Error(vec![e.into()])));
+                        }
+                    }
+                    let Some(entry) = self.viewer.as_entry() else {return Some(StateChange::Push(ClientState::Error(vec![internal_error!().into()])))};
+                    return Some(StateChange::Push(match RawView::new(entry) {
+                        Ok(raw_view) => ClientState::RawView(raw_view),
+                        Err(e) => ClientState::Error(vec![e.into()]),
+                    }))
+                }
                 _ => matched = false
             }
         }
         if matched {return Some(StateChange::Blank)}
+        // clicking a segment of the path goes back to it
+        if let Event::Mouse(mouse_event) = &event {
+            if let MouseEventKind::Down(MouseButton::Left) = mouse_event.kind {
+                if let Some(entry_id) = self.path.entry_at(mouse_event.column, mouse_event.row) {
+                    if self.path.peek().is_some_and(|x| x.0 != entry_id) {
+                        let result = self.navigate(|viewer| {
+                            viewer.path.truncate_to(entry_id);
+                            viewer.reload()
+                        });
+                        if let Err(e) = result {
+                            return Some(StateChange::Push(ClientState::Error(vec![e.into()])));
+                        }
+                    }
+                    return Some(StateChange::Blank);
+                }
+            }
+        }
+        // clicking the navigator focuses it
+        if let Event::Mouse(mouse_event) = &event {
+            if let TreeViewerState::Content = self.state {
+                if self.navigator.0.area_contains(mouse_event.column, mouse_event.row) {
+                    self.set_state(TreeViewerState::Navigate);
+                }
+            }
+        }
         match self.state {
             TreeViewerState::Content => {
                 self.awaited_child_parent = Some(TreeViewerState::Content);
-                if let Some(state_change) = self.viewer.handle_event(event) {
+                #[cfg(feature = "clipboard")]
+                if let Event::Key(key_event) = &event {
+                    if key_event.is_press() && key_event.code == KeyCode::Char('y') {
+                        if let Err(e) = self.copy_active_entry() {
+                            return Some(StateChange::Push(ClientState::Error(vec![e.into()])));
+                        }
+                        self.status = Some(" Copied ");
+                        return Some(StateChange::Blank);
+                    }
+                }
+                let state_change = self.viewer.handle_event(event);
+                if self.viewer.take_full_entry_wanted() {
+                    if let Err(e) = self.load_full_entry() {
+                        return Some(StateChange::Push(ClientState::Error(vec![e.into()])));
+                    }
+                }
+                if let Some(state_change) = state_change {
                     match state_change {
                         StateChange::MoveRight => {self.set_state(TreeViewerState::Navigate);},
                         other => return Some(other)
@@ -955,12 +2290,26 @@ impl InputWidget for EntryTreeViewer {
                 }
             },
             TreeViewerState::Navigate => {
+                if let Event::Key(key_event) = &event {
+                    if key_event.is_press() && keymap().matches(Action::Delete, key_event) {
+                        let Some((_, (entry_id, _))) = self.navigator.0.selection() else {
+                            self.status = Some(" Nothing selected ");
+                            return Some(StateChange::Blank);
+                        };
+                        return Some(StateChange::Push(ClientState::ConfirmDelete(ConfirmDelete::new(*entry_id))));
+                    }
+                }
                 self.awaited_child_parent = Some(TreeViewerState::Navigate);
                 if let Some(state_change) = self.navigator.handle_event(event) {
                     match state_change {
                         StateChange::Pop => {
                             let new_entry_id = self.navigator.0.selection().unwrap().1.0;
-                            if let Err(e) = self.push_active_entry(new_entry_id) {return Some(StateChange::Push(ClientState::Error(vec![e])))};
+                            // entries further down the tree preview aren't children, so the path is rebuilt
+                            let result = match self.navigator.is_child(new_entry_id) {
+                                true => self.navigate(|viewer| viewer.push_active_entry(new_entry_id)),
+                                false => self.navigate(|viewer| viewer.jump_to(new_entry_id)),
+                            };
+                            if let Err(e) = result {return Some(StateChange::Push(ClientState::Error(vec![e.into()])))};
                             return Some(StateChange::Blank);
                         },
                         StateChange::MoveLeft => {self.set_state(TreeViewerState::Content);},
@@ -992,17 +2341,14 @@ impl InputWidget for EntryTreeViewer {
                 let mut matched = true;
                 match child {
                     ClientState::WriteVarientSelection(selector) => {
-                        let header = HeaderData::new(
-                            self.path.peek().unwrap().0, 
-                            Vec::new(), 
-                            self.board.borrow().get_user_id().unwrap(), 
-                        );
+                        let parent_id = self.path.peek().unwrap().0;
+                        let author_id = self.board.borrow().get_user_id().unwrap();
                         let entry = match selector.selection().map(|x| x.1) {
                             Some(EntryVariant::Message) => {
                                 // boot up vim for the text editor
                                 let mut path = std::env::temp_dir();
                                 path.push("MessageBoardEntryDraft.txt");
-                                let Ok(_) = std::fs::File::create(&path) else {return Some(StateChange::Push(ClientState::Error(vec![internal_error!()])))};
+                                let Ok(_) = std::fs::File::create(&path) else {return Some(StateChange::Push(ClientState::Error(vec![internal_error!().into()])))};
                                 if let Err(e) = self.terminal.borrow_mut().pause(|| {
                                     ratatui::restore();
                                     let Ok(mut child) = std::process::Command::new("vim")
@@ -1011,17 +2357,16 @@ impl InputWidget for EntryTreeViewer {
                                     let Ok(_) = child.wait() else {return Err(internal_error!())};
                                     Ok(())
                                 }) {
-                                    return Some(StateChange::Push(ClientState::Error(vec![e])))
+                                    return Some(StateChange::Push(ClientState::Error(vec![e.into()])))
                                 };
-                                let Ok(message) = std::fs::read_to_string(&path) else {return Some(StateChange::Push(ClientState::Error(vec![internal_error!()])))};
+                                let Ok(message) = std::fs::read_to_string(&path) else {return Some(StateChange::Push(ClientState::Error(vec![internal_error!().into()])))};
                                 let _ = std::fs::remove_file(&path);
-                                Some(Entry {
-                                    header_data: header,
-                                    entry_data: EntryData::Message { 
-                                        timestamp: std::time::SystemTime::now().duration_since(std::time::SystemTime::UNIX_EPOCH).unwrap().as_secs(), 
-                                        message
-                                    }
-                                })
+                                let message = match prepare_message(message, self.allow_empty_messages) {
+                                    Ok(message) => message,
+                                    Err(e) => return Some(StateChange::Push(ClientState::Error(vec![e.into()]))),
+                                };
+                                let timestamp = std::time::SystemTime::now().duration_since(std::time::SystemTime::UNIX_EPOCH).unwrap().as_secs();
+                                Some(Entry::new_message(parent_id, author_id, timestamp, message))
                             }
                             Some(EntryVariant::AccessGroup) => {
                                 return Some(StateChange::Push(ClientState::AccessGroupBuilder(AccessGroupBuilder::new())));
@@ -1029,30 +2374,59 @@ impl InputWidget for EntryTreeViewer {
                             None => None
                         };
                         if let Some(entry) = entry {
-                            let result = self.board.borrow_mut().write_entry(entry);
-                            if let Err(e) = result {
-                                return Some(StateChange::Push(ClientState::Error(vec![e])));
-                            }
+                            return Some(StateChange::Push(ClientState::Confirm(ConfirmEntry::new(entry))));
                         }
                     }
                     ClientState::AccessGroupBuilder(builder) => {
                         if !builder.was_completed {return Some(StateChange::Blank)}
-                        let access_group = EntryData::AccessGroup { 
-                            name: builder.title.text.iter().copied().collect(), 
-                            write_perms: DefaultedIdSet::empty_from_base(*builder.write_base_selector.hovered().unwrap().1), 
-                            read_perms: DefaultedIdSet::empty_from_base(*builder.read_base_selector.hovered().unwrap().1),
-                        };
-                        let entry = Entry {
-                            header_data: HeaderData::new(
-                                self.path.peek().unwrap().0, 
-                                Vec::new(), 
-                                self.board.borrow().get_user_id().unwrap(),
-                            ), 
-                            entry_data: access_group
-                        };
-                        let result = self.board.borrow_mut().write_entry(entry);
-                        if let Err(e) = result {
-                            return Some(StateChange::Push(ClientState::Error(vec![e])));
+                        let entry = Entry::new_access_group(
+                            self.path.peek().unwrap().0, 
+                            self.board.borrow().get_user_id().unwrap(),
+                            builder.title.text.iter().copied().collect(), 
+                            builder.write_perms.draft.into_perms(), 
+                            builder.read_perms.draft.into_perms(),
+                        );
+                        return Some(StateChange::Push(ClientState::Confirm(ConfirmEntry::new(entry))));
+                    }
+                    ClientState::Confirm(confirm) => {
+                        // declining just drops the entry
+                        if confirm.confirmed {
+                            let result = self.board.borrow_mut().write_entry(confirm.entry);
+                            match result {
+                                Ok(Some(_)) => {
+                                    // the board is reachable, so anything queued from before can go out too
+                                    let errors = match self.board.borrow_mut().flush_outbox() {
+                                        Ok(rejections) => rejections,
+                                        Err(e) => vec![e],
+                                    };
+                                    if !errors.is_empty() {
+                                        return Some(StateChange::Push(ClientState::Error(errors.into_iter().map(ClientError::from).collect())));
+                                    }
+                                }
+                                Ok(None) => self.status = Some(" Queued, the board is unreachable "),
+                                Err(e) => return Some(StateChange::Push(ClientState::Error(vec![e.into()]))),
+                            }
+                        }
+                    }
+                    ClientState::JumpInput(jump) => {
+                        if jump.confirmed {
+                            if let Err(e) = jump.entry_id().and_then(|entry_id| self.navigate(|viewer| viewer.jump_to(entry_id))) {
+                                return Some(StateChange::Push(ClientState::Error(vec![e.into()])));
+                            }
+                        }
+                    }
+                    ClientState::ConfirmDelete(confirm) => {
+                        // back to the navigator the delete came from
+                        self.set_state(TreeViewerState::Navigate);
+                        if confirm.confirmed {
+                            // the tombstone stays in the children, refetched to show it as deleted
+                            let result = self.board.borrow_mut().delete_entry(confirm.entry_id);
+                            if let Err(e) = result.and_then(|_| self.reload()) {
+                                return Some(StateChange::Push(ClientState::Error(vec![e.into()])));
+                            }
+                            let mut board = self.board.borrow_mut();
+                            self.navigator.refresh_child(confirm.entry_id, |entry_id| board.get_entry(entry_id));
+                            self.viewer.set_reactions(self.navigator.reaction_counts());
                         }
                     }
                     ClientState::Error(_) => {}
@@ -1087,6 +2461,8 @@ struct AccessGroupBuilder {
     title: TextEntry,
     write_base_selector: DefaultBaseSelector,
     read_base_selector: DefaultBaseSelector,
+    write_perms: PermsEditor,
+    read_perms: PermsEditor,
     was_completed: bool,
     is_focused: bool,
 }
@@ -1098,6 +2474,8 @@ impl AccessGroupBuilder {
             title: TextEntry::new_unsized(),
             write_base_selector: DefaultBaseSelector::new(" Write Default Base "),
             read_base_selector: DefaultBaseSelector::new(" Read Default Base "),
+            write_perms: PermsEditor::new(" Write Perms ", DefaultBase::Inherit),
+            read_perms: PermsEditor::new(" Read Perms ", DefaultBase::Inherit),
             was_completed: false,
             is_focused: false
         }
@@ -1107,11 +2485,15 @@ impl AccessGroupBuilder {
         self.title.unfocus();
         self.write_base_selector.unfocus();
         self.read_base_selector.unfocus();
+        self.write_perms.unfocus();
+        self.read_perms.unfocus();
         if self.is_focused {
             match self.state {
                 0 => self.title.focus(),
                 1 => self.write_base_selector.focus(),
                 2 => self.read_base_selector.focus(),
+                3 => self.write_perms.focus(),
+                4 => self.read_perms.focus(),
                 _ => eprintln!("Invalid AccessGroupBuilder state val")
             }
         }
@@ -1123,7 +2505,7 @@ impl InputWidget for AccessGroupBuilder {
         let block = Block::bordered();
         let mut title = String::from(" Access Group Builder (");
         title.push_str(&(self.state + 1).to_string());
-        title.push_str(" / 2) ");
+        title.push_str(" / 5) ");
         let mut inner_area = block.inner(area);
         Clear.render(inner_area, buf);
         match self.state {
@@ -1151,6 +2533,14 @@ impl InputWidget for AccessGroupBuilder {
                 self.write_base_selector.render(areas[0], buf);
                 self.read_base_selector.render(areas[1], buf);
             }
+            3 | 4 => {
+                let areas = Layout::horizontal([
+                    Constraint::Fill(1),
+                    Constraint::Fill(1),
+                ]).split(inner_area);
+                self.write_perms.render(areas[0], buf);
+                self.read_perms.render(areas[1], buf);
+            }
             _ => eprintln!("Invalid AccessGroupBuilder state val")
         }
         block.title(title).render(area, buf);
@@ -1162,12 +2552,19 @@ impl InputWidget for AccessGroupBuilder {
             0 => self.title.handle_event(event),
             1 => self.write_base_selector.handle_event(event),
             2 => self.read_base_selector.handle_event(event),
-            _ => Some(StateChange::Push(ClientState::Error(vec![internal_error!()]))),
+            3 => self.write_perms.handle_event(event),
+            4 => self.read_perms.handle_event(event),
+            _ => Some(StateChange::Push(ClientState::Error(vec![internal_error!().into()]))),
         };
         let true_state_change = match internal_state_change {
             Some(StateChange::MoveRight | StateChange::Pop) => {
                 self.state += 1;
-                if self.state >= 3 {
+                if self.state == 3 {
+                    // ids already entered are kept in whichever lists the bases (maybe since changed) still have
+                    if let Some((_, base)) = self.write_base_selector.hovered() {self.write_perms.draft.set_base(*base)}
+                    if let Some((_, base)) = self.read_base_selector.hovered() {self.read_perms.draft.set_base(*base)}
+                }
+                if self.state >= 5 {
                     if 
                         self.write_base_selector.selection().map(|x| x.1).is_some() & 
                         self.read_base_selector.selection().map(|x| x.1).is_some() 
@@ -1175,7 +2572,7 @@ impl InputWidget for AccessGroupBuilder {
                         self.was_completed = true;
                         return Some(StateChange::Pop)
                     } else {
-                        return Some(StateChange::Push(ClientState::Error(vec![internal_error!()])))
+                        return Some(StateChange::Push(ClientState::Error(vec![internal_error!().into()])))
                     }
                 }
                 self.reload_focus();
@@ -1209,27 +2606,36 @@ impl InputWidget for AccessGroupBuilder {
 struct Client {
     terminal: Rc<RefCell<Terminal>>,
     board: Rc<RefCell<MessageBoardConnection>>,
+    ping_interval: Option<Duration>, // how long without input before pinging the board
     
     state: Vec<ClientState>,
     exit: bool,
 }
 
 impl Client {
-    fn new() -> Result<Self, DataError> {
+    fn new(guest: bool) -> Result<Self, DataError> {
         validate_config();
-        let config = get_config();
+        let mut config = get_config();
+        config.guest |= guest;
+        set_keymap(config.keymap.clone());
+        set_entry_colors(config.entry_colors.clone());
 
-        let board = Rc::new(RefCell::new(MessageBoardConnection::new(&config)));
+        let board = Rc::new(RefCell::new(MessageBoardConnection::new(&config)?));
         let terminal = Rc::new(RefCell::new(Terminal::new()));
         
         let mut client = Self { 
             terminal: terminal.clone(),
             board: board.clone(),
+            ping_interval: (config.ping_interval_secs != 0).then(|| Duration::from_secs(config.ping_interval_secs)),
             
             state: Vec::new(),
             exit: false,
         };
-        client.handle_state_change(Some(StateChange::Push(ClientState::Viewer(EntryTreeViewer::new(board, terminal)?))));
+        client.handle_state_change(Some(StateChange::Push(ClientState::Viewer(EntryTreeViewer::new(board.clone(), terminal, &config.path, config.allow_empty_messages)?))));
+        let rejections = board.borrow_mut().flush_outbox()?;
+        if !rejections.is_empty() {
+            client.handle_state_change(Some(StateChange::Push(ClientState::Error(rejections.into_iter().map(ClientError::from).collect()))));
+        }
         Ok(client)
     }
 
@@ -1283,13 +2689,36 @@ impl Client {
     fn mainloop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         while !self.exit {
             self.terminal.borrow_mut().draw(|frame| self.draw(frame))?;
+            // no input for a while, so the connection is kept from idling out instead
+            if let Some(ping_interval) = self.ping_interval {
+                if !event::poll(ping_interval)? {
+                    let result = self.board.borrow_mut().ping();
+                    if let Err(e) = result {
+                        self.push_error(e);
+                    }
+                    continue;
+                }
+            }
             self.handle_events()?;
-            self.reload().unwrap(); // fixme
+            if let Err(e) = self.reload() {
+                self.push_error(e);
+            }
         }
         
         Ok(())
     }
 
+    /// shows the error, adding to the current error popup rather than stacking another one
+    /// so a persistent failure (ie. the viewed entry was deleted) doesn't pile up popups
+    fn push_error(&mut self, error: impl Into<ClientError>) {
+        let error = error.into();
+        if let Some(ClientState::Error(errors)) = self.state.last_mut() {
+            if !errors.contains(&error) {errors.push(error);}
+        } else {
+            self.handle_state_change(Some(StateChange::Push(ClientState::Error(vec![error]))));
+        }
+    }
+
     fn reload(&mut self) -> Result<(), DataError> {
         for sub_state in &mut self.state {
             sub_state.reload()?;
@@ -1330,13 +2759,24 @@ impl Widget for &Client {
         {
             let title_line = Line::from(" Message Board - by EsotericPyramid ");
             title_line.centered().render(layout[0], buf);
+            let board = self.board.borrow();
+            let mut user_line = match board.get_user_id() {
+                _ if board.is_guest() => Line::from(" guest (read only) "),
+                Some(user_id) => Line::from(format!(" user: {}, entries: {} ", user_id, board.get_user_entry_count())),
+                None => Line::from(" user: None "),
+            };
+            if board.get_pending_count() > 0 {
+                user_line.push_span(format!("(pending: {}) ", board.get_pending_count()).bold());
+            }
+            let user_area = Rect { y: layout[0].y + 1, height: 1, ..layout[0] };
+            user_line.centered().render(user_area.intersection(layout[0]), buf);
         }
         let whole_area = area;
         let mut area = layout[1];
         eprintln!("render start");
         for sub_state in &self.state {
             eprintln!("\tsub_state: {}", sub_state.to_string());
-            if let ClientState::Error(..) = sub_state {
+            if let ClientState::Error(..) | ClientState::Confirm(..) | ClientState::ConfirmDelete(..) | ClientState::RawView(..) | ClientState::JumpInput(..) = sub_state {
                 sub_state.render(whole_area, buf);
             } else {
                 area = sub_state.render(area, buf);
@@ -1347,11 +2787,135 @@ impl Widget for &Client {
 
 impl Drop for Client {
     fn drop(&mut self) {
-        ratatui::restore();
+        restore_terminal();
     }
 }
 
 fn main() {
-    let mut client = Client::new().unwrap();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // the one argument shared by the TUI and the commands
+    let guest = match args.iter().position(|arg| arg == "--guest") {
+        Some(idx) => {args.remove(idx); true}
+        None => false,
+    };
+    match Command::parse(args.into_iter()) {
+        Ok(Some(command)) => {
+            validate_config();
+            let mut config = get_config();
+            config.guest |= guest;
+            let result = MessageBoardConnection::new(&config).and_then(|mut board| command.run(&mut board));
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok(None) => {},
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    }
+
+    let mut client = match Client::new(guest) {
+        Ok(client) => client,
+        Err(e) => {
+            // harmless if `Client`'s drop already restored it
+            restore_terminal();
+            eprintln!("Failed to start the client: {}", e);
+            std::process::exit(1);
+        }
+    };
     let _ = client.mainloop();
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a path from the root down through entries 0x10, 0x20 and 0x30
+    fn sample_path() -> PathManager {
+        let mut path = PathManager::new(ROOT_ENTRY_ID.into());
+        path.push(ROOT_ENTRY_ID.into(), &Entry::default_root()).unwrap();
+        let mut parent_id = EntryId::from(ROOT_ENTRY_ID);
+        for entry_id in [0x10u64, 0x20, 0x30].map(EntryId::from) {
+            path.push(entry_id, &Entry::new_message(parent_id, UserId::from(0x1234), 1, String::from("segment"))).unwrap();
+            parent_id = entry_id;
+        }
+        path
+    }
+
+    #[test]
+    fn path_truncation() {
+        let mut path = sample_path();
+        assert!(path.truncate_to(EntryId::from(0x20)), "Middle segment not found");
+        assert_eq!(path.entry_ids(), vec![EntryId::from(0x10), EntryId::from(0x20)], "Incorrect path truncated to a middle segment");
+        assert_eq!(path.peek().unwrap().0, EntryId::from(0x20), "Truncated past the segment");
+
+        // an id not in the path leaves it be
+        assert!(!path.truncate_to(EntryId::from(0x30)), "Truncated to a segment already removed");
+        assert!(!path.truncate_to(EntryId::from(0x99)), "Truncated to an id not in the path");
+        assert_eq!(path.entry_ids(), vec![EntryId::from(0x10), EntryId::from(0x20)], "Path changed by a missing id");
+
+        // the root is never removed, however often it's truncated to
+        for _ in 0..2 {
+            assert!(path.truncate_to(ROOT_ENTRY_ID.into()), "Root not found");
+            assert_eq!(path.len(), 1, "Root removed");
+            assert_eq!(path.peek().unwrap().0, EntryId::from(ROOT_ENTRY_ID), "Path not left at the root");
+            assert_eq!(path.depth(), 0, "Incorrect depth at the root");
+        }
+        assert_eq!(path.pop(), None, "Root popped");
+        assert_eq!(path.len(), 1, "Root removed by a pop");
+
+        // truncating to the last segment changes nothing
+        let mut path = sample_path();
+        assert!(path.truncate_to(EntryId::from(0x30)), "Last segment not found");
+        assert_eq!(path.depth(), 3, "Truncated past the last segment");
+    }
+
+    fn snapshot(depth: u64, cursor_pos: Option<usize>) -> NavigationSnapshot {
+        NavigationSnapshot { path: (1..=depth).map(EntryId::from).collect(), cursor_pos }
+    }
+
+    #[test]
+    fn navigation_undo_redo() {
+        let mut history = NavigationHistory::default();
+        assert_eq!(history.undo(snapshot(0, None)), None, "Undid with no navigations");
+        assert_eq!(history.redo(snapshot(0, None)), None, "Redid with nothing undone");
+
+        // from the root (cursor on its third child), into 1, then into 2
+        history.record(snapshot(0, Some(2)));
+        history.record(snapshot(1, Some(0)));
+        assert_eq!(history.undo(snapshot(2, None)), Some(snapshot(1, Some(0))), "Incorrect first undo");
+        assert_eq!(history.undo(snapshot(1, Some(0))), Some(snapshot(0, Some(2))), "Cursor not restored by the undo");
+        assert_eq!(history.undo(snapshot(0, Some(2))), None, "Undid past the first navigation");
+        assert_eq!(history.redo(snapshot(0, Some(2))), Some(snapshot(1, Some(0))), "Incorrect first redo");
+        assert_eq!(history.redo(snapshot(1, Some(0))), Some(snapshot(2, None)), "Incorrect second redo");
+        assert_eq!(history.redo(snapshot(2, None)), None, "Redid past the last undo");
+        assert_eq!(history.undo(snapshot(2, None)), Some(snapshot(1, Some(0))), "Undo lost after a redo");
+
+        // a new navigation can't be redone past
+        history.record(snapshot(1, Some(1)));
+        assert_eq!(history.redo(snapshot(3, None)), None, "Redo kept after a new navigation");
+        assert_eq!(history.undo(snapshot(3, None)), Some(snapshot(1, Some(1))), "New navigation not undone");
+
+        // only the newest navigations are kept
+        let mut history = NavigationHistory::default();
+        for depth in 0..NAVIGATION_HISTORY_LEN as u64 + 8 {
+            history.record(snapshot(depth, None));
+        }
+        assert_eq!(history.undo_stack.len(), NAVIGATION_HISTORY_LEN, "History not bounded");
+        let mut current = snapshot(NAVIGATION_HISTORY_LEN as u64 + 8, None);
+        let mut oldest = None;
+        while let Some(snapshot) = history.undo(current) {
+            oldest = Some(snapshot.path.len());
+            current = snapshot;
+        }
+        assert_eq!(oldest, Some(8), "Oldest navigations kept over the newest");
+
+        // a cursor past the children left is dropped rather than restored
+        assert_eq!(snapshot(0, Some(2)).cursor_within(3), Some(2), "Cursor not restored");
+        assert_eq!(snapshot(0, Some(2)).cursor_within(2), None, "Cursor restored past the children");
+    }
+}
+
@@ -1,5 +1,7 @@
-use crossterm::event::Event;
-use ratatui::layout::Size;
+use crossterm::event::{Event, MouseButton, MouseEventKind};
+use ratatui::layout::{Position, Size};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
 use ratatui::style::{Style, Stylize};
 use ratatui::{
     text::{Line, Text},
@@ -10,11 +12,18 @@ use ratatui::{
 use super::utils::*;
 use crate::*;
 
+/// max time between clicks on the same row for them to count as a double click
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+
 #[derive(Debug)]
 pub struct ScrollContainer<T> {
     pub cursor_pos: Option<usize>,
     pub is_focused: bool,
     pub items: Vec<T>,
+    inner_area: Cell<Rect>, // where the items were last rendered, for mapping clicks
+    scroll: Cell<usize>, // the first item shown, following the cursor when rendered
+    last_click: Option<(usize, Instant)>,
+    double_clicked: bool,
 }
 
 impl<T> ScrollContainer<T> {
@@ -22,16 +31,67 @@ impl<T> ScrollContainer<T> {
         Self {
             cursor_pos: None,
             is_focused: false,
-            items
+            items,
+            inner_area: Cell::new(Rect::default()),
+            scroll: Cell::new(0),
+            last_click: None,
+            double_clicked: false,
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.items.len() > 1 {
+            if let Some(cursor_pos) = &mut self.cursor_pos {
+                *cursor_pos += 1;
+                *cursor_pos %= self.items.len();
+            } else {
+                self.cursor_pos = Some(0);
+            }
+        } else {
+            self.cursor_pos = None;
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.items.len() > 1 {
+            if let Some(cursor_pos) = &mut self.cursor_pos {
+                *cursor_pos += self.items.len();
+                *cursor_pos -= 1;
+                *cursor_pos %= self.items.len();
+            } else {
+                self.cursor_pos = Some(0);
+            }
+        } else {
+            self.cursor_pos = None;
         }
     }
 
+    /// whether the position is within where the items were last rendered
+    pub fn area_contains(&self, column: u16, row: u16) -> bool {
+        self.inner_area.get().contains(Position::new(column, row))
+    }
+
+    /// the index of the item last rendered at the position
+    pub fn item_at(&self, column: u16, row: u16) -> Option<usize> {
+        if !self.area_contains(column, row) {return None}
+        let idx = (row - self.inner_area.get().y) as usize + self.scroll.get();
+        (idx < self.items.len()).then_some(idx)
+    }
+
+    /// whether the selection was double clicked since the last call, 
+    /// a double click is left unhandled by `base_handle_event` so the owner can act on it like Enter
+    pub fn take_double_click(&mut self) -> bool {
+        std::mem::take(&mut self.double_clicked)
+    }
+
+    /// does nothing without items
     pub fn to_bottom(&mut self) {
-        self.cursor_pos = Some(self.items.len() -1);
+        self.cursor_pos = self.items.len().checked_sub(1).or(self.cursor_pos);
     }
 
+    /// does nothing without items
     pub fn to_top(&mut self) {
-        self.cursor_pos = Some(0);
+        if !self.items.is_empty() {self.cursor_pos = Some(0)}
     }
 
     pub fn push(&mut self, item: T) {
@@ -89,7 +149,7 @@ impl<T> ScrollContainer<T> {
         }
     }
 
-    pub fn base_render<'a, U: Into<Line<'a>>, F: Fn(&T) -> String>(&self, area: Rect, buf: &mut Buffer, title: U, f: F) -> Rect {
+    pub fn base_render<'a, U: Into<Line<'a>>, L: Into<Line<'static>>, F: Fn(&T) -> L>(&self, area: Rect, buf: &mut Buffer, title: U, f: F) -> Rect {
         let mut block = Block::bordered()
             .title(title);
 
@@ -97,9 +157,15 @@ impl<T> ScrollContainer<T> {
             block = block.border_style(Style::new().bold());
         }
         
+        let block_inner = block.inner(area);
+        let height = block_inner.height as usize;
+        let scroll = self.cursor_pos.map_or(self.scroll.get(), |cursor_pos| message_board::utils::follow_cursor(self.scroll.get(), cursor_pos, height));
+        let window = message_board::utils::line_window(self.items.len(), scroll, height);
+        self.scroll.set(window.start);
+
         let mut text = Text::default();
-        for (idx, item) in self.items.iter().enumerate() {
-            let mut line = Line::from((f)(item));
+        for (idx, item) in self.items.iter().enumerate().skip(window.start).take(window.len()) {
+            let mut line: Line = (f)(item).into();
             if self.cursor_pos.map_or(false, |cursor_pos| cursor_pos == idx) & self.is_focused {
                 line = line.bold();
             } else {
@@ -108,11 +174,12 @@ impl<T> ScrollContainer<T> {
             text.push_line(line);
         }
         
-        let sub_area = if let Some(cursor_pos) = self.cursor_pos {
-            let block_inner = block.inner(area);
-            Rect::new(block_inner.x, block_inner.y + cursor_pos as u16, block_inner.width, 1)
-        } else {
-            block.inner(area)
+        self.inner_area.set(block_inner);
+        let sub_area = match self.cursor_pos {
+            Some(cursor_pos) if window.contains(&cursor_pos) => {
+                Rect::new(block_inner.x, block_inner.y + (cursor_pos - window.start) as u16, block_inner.width, 1)
+            }
+            _ => block_inner,
         };
         Clear.render(area, buf);
         Paragraph::new(text)
@@ -122,41 +189,44 @@ impl<T> ScrollContainer<T> {
     }
 
     pub fn base_handle_event(&mut self, event: Event) -> Option<StateChange> {
-        if let Event::Key(key_event) = event {
-            if !key_event.is_press() {return None}
-            match key_event.code {
-                down!() => {
-                    if self.items.len() > 1 {
-                        if let Some(cursor_pos) = &mut self.cursor_pos {
-                            *cursor_pos += 1;
-                            *cursor_pos %= self.items.len();
-                        } else {
-                            self.cursor_pos = Some(0);
-                        }
-                    } else {
-                        self.cursor_pos = None;
-                    }
-                    return Some(StateChange::Blank);
-                }
-                up!() => {
-                    if self.items.len() > 1 {
-                        if let Some(cursor_pos) = &mut self.cursor_pos {
-                            *cursor_pos += self.items.len();
-                            *cursor_pos -= 1;
-                            *cursor_pos %= self.items.len();
-                        } else {
-                            self.cursor_pos = Some(0);
-                        }
-                    } else {
-                        self.cursor_pos = None;
+        if let Event::Mouse(mouse_event) = event {
+            match mouse_event.kind {
+                MouseEventKind::ScrollDown => self.down(),
+                MouseEventKind::ScrollUp => self.up(),
+                MouseEventKind::Down(MouseButton::Left) => {
+                    let idx = self.item_at(mouse_event.column, mouse_event.row)?;
+                    let is_double_click = self.last_click.is_some_and(|(last_idx, time)| last_idx == idx && time.elapsed() < DOUBLE_CLICK_TIME);
+                    self.cursor_pos = Some(idx);
+                    if is_double_click {
+                        self.last_click = None;
+                        self.double_clicked = true;
+                        return None;
                     }
-                    return Some(StateChange::Blank);
-                }
-                
-                x => {
-                    let _ = pass_direction!(x);
+                    self.last_click = Some((idx, Instant::now()));
                 }
+                _ => return None
+            }
+            return Some(StateChange::Blank);
+        }
+        if let Event::Key(key_event) = event {
+            if !key_event.is_press() {return None}
+            if down!(&key_event) {
+                self.down();
+                return Some(StateChange::Blank);
+            }
+            if up!(&key_event) {
+                self.up();
+                return Some(StateChange::Blank);
+            }
+            if keymap().matches(Action::First, &key_event) {
+                self.to_top();
+                return Some(StateChange::Blank);
+            }
+            if keymap().matches(Action::Last, &key_event) {
+                self.to_bottom();
+                return Some(StateChange::Blank);
             }
+            pass_direction!(&key_event);
         }
         None
     }
@@ -246,8 +316,8 @@ impl InputWidget for TextEntry {
                         self.cursor_pos += 1;
                     }
                 },
-                x => {
-                    let _ = pass_direction!(x);
+                _ => {
+                    pass_direction!(&key_event);
                     matched = false;
                 }
             }
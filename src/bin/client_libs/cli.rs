@@ -0,0 +1,158 @@
+use message_board::*;
+use message_board::internal_error;
+use std::path::PathBuf;
+use super::utils::*;
+
+pub const USAGE: &str = "usage: client [--guest] [add-message --parent <hex id> --file <path> | get <hex id> | add-user | metrics]
+with no command the TUI is started, --guest browses read only without a user";
+
+/// a single request made from the command line, bypassing the TUI
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    AddMessage { parent_id: EntryId, file: PathBuf },
+    Get(EntryId),
+    AddUser,
+    Metrics,
+}
+
+impl Command {
+    /// gives None if there are no args, ie. the TUI should be started
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let Some(subcommand) = args.next() else {return Ok(None)};
+        let command = match subcommand.as_str() {
+            "add-message" => {
+                let mut parent_id = None;
+                let mut file = None;
+                while let Some(flag) = args.next() {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", flag))?;
+                    match flag.as_str() {
+                        "--parent" => parent_id = Some(parse_hex_id(&value)?),
+                        "--file" => file = Some(PathBuf::from(value)),
+                        _ => return Err(format!("unknown flag {}", flag)),
+                    }
+                }
+                Command::AddMessage {
+                    parent_id: parent_id.ok_or("add-message needs --parent")?,
+                    file: file.ok_or("add-message needs --file")?,
+                }
+            }
+            "get" => Command::Get(parse_hex_id(&args.next().ok_or("get needs an entry id")?)?),
+            "add-user" => Command::AddUser,
+            "metrics" => Command::Metrics,
+            _ => return Err(format!("unknown command {}", subcommand)),
+        };
+        if let Some(extra) = args.next() {return Err(format!("unexpected argument {}", extra))}
+        Ok(Some(command))
+    }
+
+    /// the request the command makes as `user_id`, ie. reading the message from its file
+    pub fn request(&self, user_id: Option<UserId>, timestamp: u64) -> Result<BoardRequest, DataError> {
+        Ok(match self {
+            Command::AddMessage { parent_id, file } => {
+                let message = std::fs::read_to_string(file)?;
+                let author_id = user_id.ok_or(DataError::MissingKey)?;
+                BoardRequest::AddEntry { user_id: author_id, entry: Entry::new_message(*parent_id, author_id, timestamp, message) }
+            }
+            Command::Get(entry_id) => BoardRequest::GetEntry { user_id: user_id.ok_or(DataError::MissingKey)?, entry_id: *entry_id },
+            Command::AddUser => BoardRequest::AddUser,
+            Command::Metrics => BoardRequest::Metrics { admin_id: user_id.ok_or(DataError::MissingKey)? },
+        })
+    }
+
+    /// makes the request, printing the result to stdout
+    pub fn run(&self, board: &mut MessageBoardConnection) -> Result<(), DataError> {
+        let timestamp = std::time::SystemTime::now().duration_since(std::time::SystemTime::UNIX_EPOCH).unwrap().as_secs();
+        // sent through the connection's own calls, which keep its outbox, cache and keys up to date
+        match self.request(*board.get_user_id(), timestamp)? {
+            BoardRequest::AddEntry { entry, .. } => {
+                match board.write_entry(entry)? {
+                    Some(entry_id) => println!("{:016X}", *entry_id),
+                    None => println!("queued, the board is unreachable"),
+                }
+            }
+            BoardRequest::GetEntry { entry_id, .. } => {
+                match board.fetch_entry(entry_id)?.entry_data {
+                    EntryData::Message { message, .. } => println!("{}", message),
+                    EntryData::AccessGroup { name, .. } => println!("{}", name),
+                    EntryData::Reaction { emoji, .. } => println!("{}", emoji),
+                    EntryData::Attachment { data, .. } => std::io::Write::write_all(&mut std::io::stdout(), &data)?,
+                }
+            }
+            BoardRequest::AddUser => {
+                board.create_user()?;
+                println!("{:016X}", *board.get_user_id().ok_or(internal_error!())?);
+            }
+            BoardRequest::Metrics { .. } => print!("{}", board.metrics()?),
+            _ => return Err(internal_error!()),
+        }
+        Ok(())
+    }
+}
+
+fn parse_hex_id(hex: &str) -> Result<EntryId, String> {
+    u64::from_str_radix(hex, 16).map(EntryId::from).map_err(|_| format!("{} isn't a hex entry id", hex))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn parse(args: &[&str]) -> Result<Option<Command>, String> {
+        Command::parse(args.iter().map(|arg| String::from(*arg)))
+    }
+
+    #[test]
+    fn commands_parsed() {
+        assert_eq!(parse(&[]), Ok(None), "TUI not started without a command");
+        let expected = Command::AddMessage { parent_id: EntryId::from(0xAB), file: PathBuf::from("message.txt") };
+        assert_eq!(parse(&["add-message", "--parent", "ab", "--file", "message.txt"]), Ok(Some(expected)), "Incorrect add-message");
+        let expected = Command::AddMessage { parent_id: EntryId::from(0xAB), file: PathBuf::from("message.txt") };
+        assert_eq!(parse(&["add-message", "--file", "message.txt", "--parent", "AB"]), Ok(Some(expected)), "Flags not taken in any order");
+        assert_eq!(parse(&["get", "00000000000000FF"]), Ok(Some(Command::Get(EntryId::from(0xFF)))), "Incorrect get");
+        assert_eq!(parse(&["add-user"]), Ok(Some(Command::AddUser)), "Incorrect add-user");
+        assert_eq!(parse(&["metrics"]), Ok(Some(Command::Metrics)), "Incorrect metrics");
+    }
+
+    #[test]
+    fn bad_arguments_rejected() {
+        for args in [
+            &["post"][..],
+            &["add-message", "--parent", "ab"],
+            &["add-message", "--file", "message.txt"],
+            &["add-message", "--parent"],
+            &["add-message", "--parent", "ab", "--file", "message.txt", "--author", "cd"],
+            &["add-message", "--parent", "not hex", "--file", "message.txt"],
+            &["get"],
+            &["get", "xyz"],
+            &["get", "ab", "cd"],
+            &["add-user", "extra"],
+            &["metrics", "--all"],
+        ] {
+            assert!(parse(args).is_err(), "Accepted {:?}", args);
+        }
+        assert_eq!(parse(&["add-message", "--parent"]), Err(String::from("--parent needs a value")), "Incorrect missing value error");
+        assert_eq!(parse(&["get", "xyz"]), Err(String::from("xyz isn't a hex entry id")), "Incorrect hex error");
+    }
+
+    #[test]
+    fn command_requests() {
+        let user_id = UserId::from(0x1234);
+        let mut file = std::env::temp_dir();
+        file.push(format!("message_board_cli_test_{:016X}", rand::rng().next_u64()));
+        std::fs::write(&file, "from a file").unwrap();
+
+        let command = Command::AddMessage { parent_id: EntryId::from(0xAB), file: file.clone() };
+        let expected = BoardRequest::AddEntry { user_id, entry: Entry::new_message(EntryId::from(0xAB), user_id, 7, String::from("from a file")) };
+        assert_eq!(command.request(Some(user_id), 7), Ok(expected), "Incorrect add-message request");
+        assert_eq!(command.request(None, 7), Err(DataError::MissingKey), "Message added without a user");
+        let _ = std::fs::remove_file(&file);
+        assert_eq!(command.request(Some(user_id), 7), Err(DataError::DoesNotExist), "Message read from a missing file");
+
+        let expected = BoardRequest::GetEntry { user_id, entry_id: EntryId::from(0xFF) };
+        assert_eq!(Command::Get(EntryId::from(0xFF)).request(Some(user_id), 7), Ok(expected), "Incorrect get request");
+        assert_eq!(Command::AddUser.request(None, 7), Ok(BoardRequest::AddUser), "Incorrect add-user request");
+        assert_eq!(Command::Metrics.request(Some(user_id), 7), Ok(BoardRequest::Metrics { admin_id: user_id }), "Incorrect metrics request");
+        assert_eq!(Command::Metrics.request(None, 7), Err(DataError::MissingKey), "Metrics asked for without a user");
+    }
+}
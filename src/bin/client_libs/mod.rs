@@ -1,2 +1,3 @@
 pub mod utils;
-pub mod base_widgets;
\ No newline at end of file
+pub mod base_widgets;
+pub mod cli;
\ No newline at end of file
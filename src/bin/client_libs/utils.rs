@@ -1,9 +1,14 @@
-use crossterm::event::Event;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
 use message_board::cryptography::{get_crypto_rng, CryptoRng};
 use message_board::*;
+use message_board::stream::*;
+use message_board::outbox::*;
+use message_board::last_seen::*;
 use ratatui::layout::{Constraint, Layout};
 use ratatui::widgets::{Clear};
-use std::io::{Read, Write};
+use ratatui::style::Color;
+use std::collections::{HashMap, VecDeque};
+use std::sync::OnceLock;
 use std::net::*;
 use ratatui::{
     text::{Line, Text},
@@ -29,6 +34,21 @@ pub fn read_long_hex_string(string: &str) -> Result<Vec<u8>, DataError> {
     Ok(data)
 }
 
+/// 16 bytes per line: offset, the bytes in hex, then the bytes as ascii (`.` if unprintable)
+pub fn hex_dump(data: &[u8]) -> Vec<String> {
+    data.chunks(16).enumerate().map(|(idx, chunk)| {
+        let mut line = format!("{:08X} ", idx * 16);
+        for byte in chunk {
+            line.push_str(&format!(" {:02X}", byte));
+        }
+        line.push_str(&"   ".repeat(16 - chunk.len()));
+        line.push_str("  |");
+        line.extend(chunk.iter().map(|byte| if byte.is_ascii_graphic() || *byte == b' ' {*byte as char} else {'.'}));
+        line.push('|');
+        line
+    }).collect()
+}
+
 pub fn write_long_hex_string(hex: &[u8]) -> String {
     let mut out = String::with_capacity(hex.len() * 2);
     for byte in hex {
@@ -37,33 +57,286 @@ pub fn write_long_hex_string(hex: &[u8]) -> String {
     out
 }
 
+/// something a key can be bound to, see `Keymap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Write,
+    Back,
+    Open,
+    Undo,
+    Redo,
+    Delete,
+    Raw,
+    Jump,
+    Sort,
+    Pin,
+    Tree,
+    Expand,
+    Markdown,
+    Save,
+    First,
+    Last,
+}
+
+impl Action {
+    pub const ALL: [Action; 20] = [Action::Up, Action::Down, Action::Left, Action::Right, Action::Write, Action::Back, Action::Open, Action::Undo, Action::Redo, Action::Delete, Action::Raw, Action::Jump, Action::Sort, Action::Pin, Action::Tree, Action::Expand, Action::Markdown, Action::Save, Action::First, Action::Last];
+
+    /// the name used in the `[keys]` table of the rc file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Write => "write",
+            Action::Back => "back",
+            Action::Open => "open",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Delete => "delete",
+            Action::Raw => "raw",
+            Action::Jump => "jump",
+            Action::Sort => "sort",
+            Action::Pin => "pin",
+            Action::Tree => "tree",
+            Action::Expand => "expand",
+            Action::Markdown => "markdown",
+            Action::Save => "save",
+            Action::First => "first",
+            Action::Last => "last",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Up => &["k", "Up"],
+            Action::Down => &["j", "Down"],
+            Action::Left => &["h", "Left"],
+            Action::Right => &["l", "Right"],
+            Action::Write => &["w"],
+            Action::Back => &["H"],
+            Action::Open => &["Enter"],
+            Action::Undo => &["u"],
+            Action::Redo => &["U"],
+            Action::Delete => &["d"],
+            Action::Raw => &["r"],
+            Action::Jump => &["g"],
+            Action::Sort => &["s"],
+            Action::Pin => &["p"],
+            Action::Tree => &["t"],
+            Action::Expand => &["e"],
+            Action::Markdown => &["m"],
+            Action::Save => &["S"],
+            Action::First => &["Home"],
+            Action::Last => &["End", "G"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySpec {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    /// parses ie. "k", "H" (shift is implied by the case), "Up", "Enter", "ctrl+x"
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = spec;
+        while let Some((modifier, rest)) = key.split_once('+').filter(|(_, rest)| !rest.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier \"{}\" in \"{}\"", modifier, spec)),
+            };
+            key = rest;
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => {
+                let c = if modifiers.contains(KeyModifiers::SHIFT) {c.to_ascii_uppercase()} else {c};
+                if c.is_ascii_uppercase() {modifiers |= KeyModifiers::SHIFT}
+                KeyCode::Char(c)
+            }
+            _ => match key.to_ascii_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                _ => return Err(format!("unknown key \"{}\"", spec)),
+            }
+        };
+        Ok(Self { code, modifiers })
+    }
+
+    /// the modifiers have to be exactly the spec's, but shift is left out for characters, 
+    /// their case already gives it and terminals don't all report it
+    pub fn matches(&self, key_event: &KeyEvent) -> bool {
+        let ignored = if matches!(self.code, KeyCode::Char(_)) {KeyModifiers::SHIFT} else {KeyModifiers::NONE};
+        key_event.code == self.code && key_event.modifiers.difference(ignored) == self.modifiers.difference(ignored)
+    }
+}
+
+/// which keys trigger which `Action`s, set from the `[keys]` table of the rc file
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<Action, Vec<KeySpec>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL.into_iter().map(|action| {
+            (action, action.default_keys().iter().map(|spec| KeySpec::parse(spec).expect("Default keys should parse")).collect())
+        }).collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// the defaults, with the actions in `keys` rebound, 
+    /// each action maps to a key spec or an array of them (see `KeySpec::parse`)
+    pub fn from_toml(keys: &dyn toml_edit::TableLike) -> Result<Self, String> {
+        let mut keymap = Self::default();
+        for (name, value) in keys.iter() {
+            let Some(action) = Action::ALL.into_iter().find(|action| action.name() == name) else {
+                let names = Action::ALL.map(|action| action.name()).join(", ");
+                return Err(format!("unknown action \"{}\", expected one of: {}", name, names))
+            };
+            let specs = match (value.as_str(), value.as_array()) {
+                (Some(spec), _) => vec![KeySpec::parse(spec)?],
+                (_, Some(specs)) => specs.iter().map(|spec| {
+                    KeySpec::parse(spec.as_str().ok_or(format!("\"{}\" should be a key or an array of keys", name))?)
+                }).collect::<Result<_, _>>()?,
+                _ => return Err(format!("\"{}\" should be a key or an array of keys", name)),
+            };
+            keymap.bindings.insert(action, specs);
+        }
+        Ok(keymap)
+    }
+
+    pub fn matches(&self, action: Action, key_event: &KeyEvent) -> bool {
+        self.bindings.get(&action).is_some_and(|specs| specs.iter().any(|spec| spec.matches(key_event)))
+    }
+}
+
+static KEYMAP: OnceLock<Keymap> = OnceLock::new();
+
+/// the keymap set at startup, the default one if it wasn't
+pub fn keymap() -> &'static Keymap {
+    KEYMAP.get_or_init(Keymap::default)
+}
+
+/// only the first call has an effect
+pub fn set_keymap(keymap: Keymap) {
+    let _ = KEYMAP.set(keymap);
+}
+
+/// the colors the navigator shows each type of entry in, set from the `[colors]` table of the rc file
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryColors {
+    pub message: Color,
+    pub access_group: Color,
+    pub reaction: Color,
+    pub attachment: Color,
+}
+
+impl Default for EntryColors {
+    fn default() -> Self {
+        Self {
+            message: Color::Reset,
+            access_group: Color::Cyan,
+            reaction: Color::Yellow,
+            attachment: Color::Green,
+        }
+    }
+}
+
+impl EntryColors {
+    /// the defaults, with the types in `colors` recolored, 
+    /// each type maps to a color name, index, or hex code (see `Color`'s `FromStr`), ie. `access_group = "#5f87ff"`
+    pub fn from_toml(colors: &dyn toml_edit::TableLike) -> Result<Self, String> {
+        let mut entry_colors = Self::default();
+        for (name, value) in colors.iter() {
+            let color = match name {
+                "message" => &mut entry_colors.message,
+                "access_group" => &mut entry_colors.access_group,
+                "reaction" => &mut entry_colors.reaction,
+                "attachment" => &mut entry_colors.attachment,
+                _ => return Err(format!("unknown entry type \"{}\", expected one of: message, access_group, reaction, attachment", name)),
+            };
+            let spec = value.as_str().ok_or(format!("\"{}\" should be a color", name))?;
+            *color = spec.parse().map_err(|_| format!("unknown color \"{}\" for \"{}\"", spec, name))?;
+        }
+        Ok(entry_colors)
+    }
+
+    /// the color of the entry type (see `EntryData::get_discriminant`)
+    pub fn of(&self, entry_type: u8) -> Color {
+        match entry_type {
+            MESSAGE => self.message,
+            ACCESS_GROUP => self.access_group,
+            REACTION => self.reaction,
+            ATTACHMENT => self.attachment,
+            _ => Color::Reset,
+        }
+    }
+}
+
+static ENTRY_COLORS: OnceLock<EntryColors> = OnceLock::new();
+
+/// the colors set at startup, the default ones if they weren't
+pub fn entry_colors() -> &'static EntryColors {
+    ENTRY_COLORS.get_or_init(EntryColors::default)
+}
+
+/// only the first call has an effect
+pub fn set_entry_colors(entry_colors: EntryColors) {
+    let _ = ENTRY_COLORS.set(entry_colors);
+}
+
 #[macro_export]
 macro_rules! left {
-    () => {KeyCode::Char('h') | KeyCode::Left};
+    ($key_event:expr) => {keymap().matches(Action::Left, $key_event)};
 }
 #[macro_export]
 macro_rules! down {
-    () => {KeyCode::Char('j') | KeyCode::Down};
+    ($key_event:expr) => {keymap().matches(Action::Down, $key_event)};
 }
 #[macro_export]
 macro_rules! up {
-    () => {KeyCode::Char('k') | KeyCode::Up};
+    ($key_event:expr) => {keymap().matches(Action::Up, $key_event)};
 }
 #[macro_export]
 macro_rules! right {
-    () => {KeyCode::Char('l') | KeyCode::Right};
+    ($key_event:expr) => {keymap().matches(Action::Right, $key_event)};
 }
 
 #[macro_export]
 macro_rules! pass_direction {
-    ($expr:expr) => {
-        match $expr {
-            left!() => {return Some(StateChange::MoveLeft)}
-            down!() => {return Some(StateChange::MoveDown)}
-            up!() => {return Some(StateChange::MoveUp)}
-            right!() => {return Some(StateChange::MoveRight)}
-            x => {x}
-        } 
+    ($key_event:expr) => {
+        {
+            let key_event = $key_event;
+            if left!(key_event) {return Some(StateChange::MoveLeft)}
+            if down!(key_event) {return Some(StateChange::MoveDown)}
+            if up!(key_event) {return Some(StateChange::MoveUp)}
+            if right!(key_event) {return Some(StateChange::MoveRight)}
+        }
     };
 }
 
@@ -97,90 +370,543 @@ pub trait InputWidget {
 }
 
 
+/// bounded cache of entries, evicting the least recently used entry when full
+#[derive(Debug)]
+pub struct EntryCache {
+    capacity: usize,
+    entries: HashMap<EntryId, Entry>,
+    recency: VecDeque<EntryId>, // least recently used at the front
+}
+
+impl EntryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { 
+            capacity, 
+            entries: HashMap::new(), 
+            recency: VecDeque::new(),
+        }
+    }
+
+    fn touch(&mut self, entry_id: EntryId) {
+        if let Some(idx) = self.recency.iter().position(|x| *x == entry_id) {
+            self.recency.remove(idx);
+        }
+        self.recency.push_back(entry_id);
+    }
+
+    pub fn get(&mut self, entry_id: EntryId) -> Option<&Entry> {
+        if !self.entries.contains_key(&entry_id) {return None}
+        self.touch(entry_id);
+        self.entries.get(&entry_id)
+    }
+
+    pub fn insert(&mut self, entry_id: EntryId, entry: Entry) {
+        if self.capacity == 0 {return}
+        self.touch(entry_id);
+        self.entries.insert(entry_id, entry);
+        while self.entries.len() > self.capacity {
+            let Some(evicted) = self.recency.pop_front() else {break;};
+            self.entries.remove(&evicted);
+        }
+    }
+
+    /// the cached entry, otherwise the one `fetch` gets, which is then cached
+    pub fn get_or_fetch(&mut self, entry_id: EntryId, fetch: impl FnOnce(EntryId) -> Result<Entry, DataError>) -> Result<Entry, DataError> {
+        if let Some(entry) = self.get(entry_id) {return Ok(entry.clone())}
+        let entry = fetch(entry_id)?;
+        self.insert(entry_id, entry.clone());
+        Ok(entry)
+    }
+
+    pub fn invalidate(&mut self, entry_id: EntryId) {
+        if let Some(idx) = self.recency.iter().position(|x| *x == entry_id) {
+            self.recency.remove(idx);
+        }
+        self.entries.remove(&entry_id);
+    }
+}
+
 #[derive(Debug)]
 pub struct MessageBoardConnection {
-    stream: TcpStream,
+    stream: BoardStream,
+    frame_reader: FrameReader,
+    max_response_size: usize,
+    max_message_size: usize, // the board's, see `refresh_limits`
+    next_request_id: u64,
+    protocol_version: u8, // agreed with the board on connecting, requests are encoded in it
+    board: String, // which of the server's boards requests are to
+    unclaimed_responses: HashMap<u64, Result<BoardResponse, DataError>>,
+    entry_cache: EntryCache,
     user_id: Option<UserId>,
+    guest: bool, // browsing as `ANONYMOUS_USER_ID`, which can only read
+    user_entry_count: usize, // as of the last `refresh_user_info`
+    display_names: HashMap<UserId, Option<String>>, // for the session, None if the user has none or couldn't be gotten
+    connected: bool, // false once a request fails to reach the board
+    outbox: Option<Outbox>, // only None while it is being flushed
+    last_seen: LastSeen,
     keys: PublicKeySet,
     crypto_rng: CryptoRng,
 }
 
 impl MessageBoardConnection {
-    pub fn new(config: &Config) -> Self {
-        let mut connected_stream = None;
-        while connected_stream.is_none() {
-            let stream = TcpStream::connect((&config.server_address as &str, PORT));
-            if let Ok(stream) = stream {
-                connected_stream = Some(stream);
-            } else if let Err(e) = stream {
-                eprintln!("Connection failed: {}", e);
+    pub fn new(config: &Config) -> Result<Self, DataError> {
+        let mut backoff = RetryBackoff::new(config.connect_retries, Duration::from_secs(config.retry_delay_secs), Duration::from_secs(MAX_RETRY_DELAY_SECS));
+        let stream = loop {
+            match Self::connect(config) {
+                Ok(stream) => break stream,
+                Err(e) => match backoff.next_delay() {
+                    Some(delay) => {
+                        eprintln!("Connection failed: {}, retrying in {}s", e, delay.as_secs());
+                        std::thread::sleep(delay);
+                    }
+                    None => {
+                        eprintln!("Gave up connecting to {}:{} after {} retries: {}", config.server_address, config.server_port, config.connect_retries, e);
+                        return Err(e.into());
+                    }
+                }
             }
+        };
+        // requests are small and waited on, so they shouldn't be held back to be batched
+        if let Err(e) = stream.set_nodelay(config.nodelay) {
+            eprintln!("Failed to set nodelay: {}", e);
         }
         
+        let stream = match Self::wrap_stream(config, stream) {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Failed to set up tls with {}: {}", config.server_address, e);
+                return Err(e.into());
+            }
+        };
+        
         let mut board = Self { 
-            stream: connected_stream.unwrap(), 
-            user_id: config.user_id, 
+            stream, 
+            frame_reader: FrameReader::with_max_frame_size(config.max_response_size),
+            max_response_size: config.max_response_size,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE, // until the board gives its own
+            next_request_id: 0,
+            protocol_version: MIN_PROTOCOL_VERSION, // until agreed, only what every board understands
+            board: config.board.clone(),
+            unclaimed_responses: HashMap::new(),
+            entry_cache: EntryCache::new(config.entry_cache_size),
+            user_id: if config.guest {Some(ANONYMOUS_USER_ID.into())} else {config.user_id}, 
+            guest: config.guest,
+            user_entry_count: 0,
+            display_names: HashMap::new(),
+            connected: true,
+            outbox: Some(Outbox::load(outbox_path())?),
+            last_seen: LastSeen::load(last_seen_path())?,
             keys: PublicKeySet::new(None, config.user_aead.as_ref().map(|x| x.clone())),
             crypto_rng: get_crypto_rng(),
         };
-        if let Some(user_id) = board.user_id {
-            if let Err(e) = board.get_user(user_id) {
-                eprintln!("User Id not found on server ({:?})", e);
+        // first, so a board on an incompatible version fails here rather than on whichever request it can't parse
+        if let Err(e) = board.negotiate_version() {
+            eprintln!("Couldn't agree on a protocol version with {}:{}: {}", config.server_address, config.server_port, e);
+            return Err(e);
+        }
+        // the handshake always goes to the main board, so this is the first request to the configured one
+        if board.board != MAIN_BOARD {
+            if let Err(e) = board.ping() {
+                if e == DataError::DoesNotExist {
+                    eprintln!("{}:{} has no \"{}\" board", config.server_address, config.server_port, board.board);
+                    if let Ok(boards) = board.list_boards() {
+                        eprintln!("Its boards are: {}", boards.join(", "));
+                    }
+                } else {
+                    eprintln!("Couldn't reach the \"{}\" board on {}:{}: {}", board.board, config.server_address, config.server_port, e);
+                }
+                return Err(e);
+            }
+        }
+        if let Err(e) = board.refresh_limits() {
+            eprintln!("Couldn't get the limits of the \"{}\" board, assuming the defaults: {}", board.board, e);
+        }
+        if board.guest {
+            // nothing to look up or mint, the board only needs the guest id
+        } else if let Some(user_id) = board.user_id {
+            if let Err(e) = board.refresh_user_info() {
+                eprintln!("User Id {} not found on server ({:?})", user_id, e);
                 eprintln!("If this is correct, set it to \"None\"");
             }
         } else {
-            let _ = board.create_user(); // FIXME: should notify in some way if a new one was minted
+            board.create_user()?; // FIXME: should notify in some way if a new one was minted
+        }
+        if let (false, Some(display_name), Some(user_id)) = (board.guest, &config.display_name, board.user_id) {
+            if board.display_names.get(&user_id) != Some(&Some(display_name.clone())) {
+                if let Err(e) = board.set_display_name(Some(display_name.clone())) {
+                    eprintln!("Failed to set the display name: {}", e);
+                }
+            }
+        }
+        board.update_kem()?;
+        Ok(board)
+    }
+
+    /// tries each of the addresses the board's address resolves to, giving the last error if none connect
+    fn connect(config: &Config) -> std::io::Result<TcpStream> {
+        let timeout = Duration::from_secs(config.connect_timeout_secs);
+        let mut last_error = std::io::Error::new(std::io::ErrorKind::NotFound, "the address didn't resolve");
+        for address in (&config.server_address as &str, config.server_port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// fails if the custom CA can't be read or used, or the board's address isn't a valid tls server name
+    #[cfg(feature = "tls")]
+    fn wrap_stream(config: &Config, stream: TcpStream) -> std::io::Result<BoardStream> {
+        if !config.tls {return Ok(BoardStream::Plain(stream))}
+        let custom_ca = match &config.tls_ca {
+            Some(path) => Some(tls::load_certs(path).map_err(|e| std::io::Error::other(format!("Failed to read the custom tls CA at {}: {}", path, e)))?),
+            None => None,
+        };
+        let tls_config = tls::client_config(custom_ca).map_err(|e| std::io::Error::other(format!("The custom tls CA was invalid: {}", e)))?;
+        BoardStream::connect_tls(stream, tls_config, &config.server_address)
+    }
+
+    #[cfg(not(feature = "tls"))]
+    fn wrap_stream(config: &Config, stream: TcpStream) -> std::io::Result<BoardStream> {
+        if config.tls || config.tls_ca.is_some() {
+            eprintln!("TLS is configured but the client was built without the \"tls\" feature, falling back to plaintext");
         }
-        board.update_kem().unwrap();
-        board
+        Ok(BoardStream::Plain(stream))
     }
 
     fn send_request(&mut self, request: BoardRequest) -> Result<BoardResponse, DataError> {
-        let request = request.secure_into_data(&mut self.crypto_rng, &mut self.keys)?;
-        let _ = self.stream.write_all(&(request.len() as u64).to_le_bytes());
-        let _ = self.stream.write_all(&request);
-        let mut num_bytes = [0; 8];
-        let _ = self.stream.read_exact(&mut num_bytes);
-        let num_bytes = u64::from_le_bytes(num_bytes) as usize;
-        let mut buffer = vec![0; num_bytes];
-        let _ = self.stream.read_exact(&mut buffer);
-        BoardResponse::secure_from_data(&buffer, &mut self.keys)
+        self.send_request_with(request, true)
+    }
+
+    /// as `send_request`, if `summarize_perms` access groups come back without their perm lists (see `get_full_entry`)
+    fn send_request_with(&mut self, request: BoardRequest, summarize_perms: bool) -> Result<BoardResponse, DataError> {
+        let request_id = self.send_request_frame(request, summarize_perms)?;
+        match self.claim_response(request_id)? {
+            BoardResponse::Error(e) => Err(e),
+            response => Ok(response),
+        }
+    }
+
+    /// sends the request without waiting on the response, 
+    /// gives the request id needed to claim its response
+    fn send_request_frame(&mut self, request: BoardRequest, summarize_perms: bool) -> Result<u64, DataError> {
+        // entries are the only responses which get large, the board still sends small ones as a single frame
+        let chunked = matches!(request, BoardRequest::GetEntry { .. } | BoardRequest::GetEntries { .. });
+        let mut flags = 0;
+        if chunked {flags |= CHUNKED_RESPONSE_FLAG}
+        // deflated bodies can only be read back with the feature
+        if chunked && cfg!(feature = "compression") {flags |= COMPRESSED_RESPONSE_FLAG}
+        if chunked && summarize_perms {flags |= SUMMARIZED_PERMS_FLAG}
+        let request = request.secure_into_data_with(&self.board, self.protocol_version, flags, &mut self.crypto_rng, &mut self.keys)?;
+        let request_id = self.next_request_id;
+        self.next_request_id = self.next_request_id.wrapping_add(1);
+        if let Err(e) = write_frame(&mut self.stream, request_id, &request) {
+            self.connected = false;
+            return Err(e.into());
+        }
+        Ok(request_id)
     }
 
+    /// blocks until the response to `request_id` is received,
+    /// responses to other requests are kept until they are claimed
+    fn claim_response(&mut self, request_id: u64) -> Result<BoardResponse, DataError> {
+        loop {
+            if let Some(response) = self.unclaimed_responses.remove(&request_id) {return response}
+            match self.frame_reader.read_part(&mut self.stream) {
+                Ok(Some(FramePart::Frame(response_id, response))) => {
+                    let response = BoardResponse::secure_from_data(&response, &mut self.keys);
+                    self.unclaimed_responses.insert(response_id, response);
+                }
+                // large responses are decoded as their chunks are read rather than once they're all in
+                Ok(Some(FramePart::Chunk(response_id, head))) => {
+                    let response = BoardResponse::read_streamed(&mut self.frame_reader, &mut self.stream, &head, &mut self.keys);
+                    match response {
+                        Err(DataError::IoError(std::io::ErrorKind::InvalidData)) => {
+                            self.connected = false;
+                            return Err(DataError::PayloadTooLarge { max: self.max_response_size });
+                        }
+                        Err(DataError::IoError(_)) => {
+                            self.connected = false;
+                            return Err(internal_error!());
+                        }
+                        response => {self.unclaimed_responses.insert(response_id, response);}
+                    }
+                }
+                // the rest of the oversized response is still on the stream, so it can't be used anymore
+                Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                    self.connected = false;
+                    return Err(DataError::PayloadTooLarge { max: self.max_response_size });
+                }
+                // the end of a chunked frame is read along with its chunks
+                Ok(Some(FramePart::End(_))) | Ok(None) | Err(_) => {
+                    self.connected = false;
+                    return Err(internal_error!());
+                }
+            }
+        }
+    }
+
+    /// gets the entry, using the cached version if there is one
     pub fn get_entry(&mut self, entry_id: EntryId) -> Result<Entry, DataError> {
+        // taken out while the entry is fetched, like the outbox while it's flushed
+        let mut entry_cache = std::mem::replace(&mut self.entry_cache, EntryCache::new(0));
+        let entry = entry_cache.get_or_fetch(entry_id, |entry_id| self.request_entry(entry_id));
+        self.entry_cache = entry_cache;
+        entry
+    }
+
+    /// gets the entry from the server, bypassing (but updating) the cache
+    pub fn fetch_entry(&mut self, entry_id: EntryId) -> Result<Entry, DataError> {
+        let entry = self.request_entry(entry_id)?;
+        self.entry_cache.insert(entry_id, entry.clone());
+        Ok(entry)
+    }
+
+    fn request_entry(&mut self, entry_id: EntryId) -> Result<Entry, DataError> {
         let request = BoardRequest::GetEntry { user_id: self.user_id.unwrap(), entry_id };
         let response = self.send_request(request)?;
-        let BoardResponse::GetEntry(entry) = response else {return Err(internal_error!())};
+        let BoardResponse::GetEntry { entry, .. } = response else {return Err(internal_error!())};
+        Ok(entry)
+    }
+
+    /// gets the entry from the server with its perm lists, which the entries gotten otherwise are without, 
+    /// their lengths can be gotten without the ids with `perms_summary`
+    pub fn get_full_entry(&mut self, entry_id: EntryId) -> Result<Entry, DataError> {
+        let request = BoardRequest::GetEntry { user_id: self.user_id.ok_or(DataError::MissingKey)?, entry_id };
+        let BoardResponse::GetEntry { entry, .. } = self.send_request_with(request, false)? else {return Err(internal_error!())};
+        self.entry_cache.insert(entry_id, entry.clone());
         Ok(entry)
     }
 
-    pub fn write_entry(&mut self, entry: Entry) -> Result<EntryId, DataError> {
+    /// the bases and list lengths of an access group's perms, as (write, read)
+    pub fn perms_summary(&mut self, entry_id: EntryId) -> Result<(PermsSummary, PermsSummary), DataError> {
+        let request = BoardRequest::GetPermsSummary { user_id: self.user_id.ok_or(DataError::MissingKey)?, entry_id };
+        let BoardResponse::GetPermsSummary { write, read } = self.send_request(request)? else {return Err(internal_error!())};
+        Ok((write, read))
+    }
+
+    /// caches the entries which aren't already in one request, 
+    /// those which couldn't be gotten are left for `get_entry` to report
+    pub fn prefetch_entries(&mut self, entry_ids: &[EntryId]) -> Result<(), DataError> {
+        let entry_ids: Vec<EntryId> = entry_ids.iter().copied().filter(|entry_id| self.entry_cache.get(*entry_id).is_none()).collect();
+        for chunk in entry_ids.chunks(MAX_REQUESTED_ENTRIES) {
+            let request = BoardRequest::GetEntries { user_id: self.user_id.ok_or(DataError::MissingKey)?, entry_ids: chunk.to_vec() };
+            let BoardResponse::GetEntries(entries) = self.send_request(request)? else {return Err(internal_error!())};
+            for (entry_id, entry) in chunk.iter().zip(entries) {
+                if let Ok(entry) = entry {self.entry_cache.insert(*entry_id, entry);}
+            }
+        }
+        Ok(())
+    }
+
+    /// the children with activity since the entry was last seen, then seeing it as of `last_activity`
+    /// 
+    /// none if it has never been seen, rather than all of them
+    pub fn unseen_children(&mut self, entry_id: EntryId, last_activity: u64) -> Result<Vec<EntryId>, DataError> {
+        // the board's own timestamps, so the client's clock doesn't matter
+        let unseen = match self.last_seen.get(entry_id) {
+            Some(since_ts) if since_ts < last_activity => {
+                let request = BoardRequest::ChildrenSince { user_id: self.user_id.ok_or(DataError::MissingKey)?, entry_id, since_ts };
+                let BoardResponse::ChildrenSince(entry_ids) = self.send_request(request)? else {return Err(internal_error!())};
+                entry_ids
+            }
+            _ => Vec::new(),
+        };
+        self.last_seen.see(entry_id, last_activity)?;
+        Ok(unseen)
+    }
+
+    /// the ids from just below the root down to the entry, worked out by the board
+    pub fn get_ancestors(&mut self, entry_id: EntryId) -> Result<Vec<EntryId>, DataError> {
+        let request = BoardRequest::GetAncestors { user_id: self.user_id.ok_or(DataError::MissingKey)?, entry_id };
+        let BoardResponse::GetAncestors(path) = self.send_request(request)? else {return Err(internal_error!())};
+        Ok(path)
+    }
+
+    /// gives None if the board couldn't be reached, the entry is then queued in the outbox (see `flush_outbox`)
+    pub fn write_entry(&mut self, entry: Entry) -> Result<Option<EntryId>, DataError> {
+        match self.add_entry(entry.clone()) {
+            Ok(entry_id) => Ok(Some(entry_id)),
+            Err(_) if !self.connected => {
+                self.outbox.as_mut().ok_or(internal_error!())?.push(entry)?;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn add_entry(&mut self, entry: Entry) -> Result<EntryId, DataError> {
+        // caught here since a message far enough over the limit has its whole frame refused, dropping the connection
+        entry.entry_data.check_size_limits(self.max_message_size)?;
+        // the parent gains a child
+        self.entry_cache.invalidate(entry.header_data.parent_id);
         let request = BoardRequest::AddEntry { user_id: self.user_id.unwrap(), entry };
         let response = self.send_request(request)?;
         let BoardResponse::AddEntry(entry_id) = response else {return Err(internal_error!())};
+        // the entry was still written, a stale count isn't worth failing over
+        let _ = self.refresh_user_info();
         Ok(entry_id)
     }
 
+    /// sends the entries queued while the board couldn't be reached, in the order they were written
+    /// 
+    /// gives the errors of any the board rejected
+    pub fn flush_outbox(&mut self) -> Result<Vec<DataError>, DataError> {
+        // a guest's writes would all be rejected, so the entries wait for the user
+        if !self.connected || self.guest {return Ok(Vec::new())}
+        let Some(mut outbox) = self.outbox.take() else {return Err(internal_error!())};
+        let result = outbox.flush(|entry| match self.add_entry(entry.clone()) {
+            Ok(_) => Delivery::Sent,
+            Err(_) if !self.connected => Delivery::Unreachable,
+            Err(e) => Delivery::Rejected(e),
+        });
+        self.outbox = Some(outbox);
+        result
+    }
+
     pub fn edit_entry(&mut self, entry_id: EntryId, entry: Entry) -> Result<(), DataError> {
+        entry.entry_data.check_size_limits(self.max_message_size)?;
+        self.entry_cache.invalidate(entry_id);
         let request = BoardRequest::EditEntry { user_id: self.user_id.unwrap(), entry_id, entry };
         let response = self.send_request(request)?;
         let BoardResponse::EditEntry = response else {return Err(internal_error!())};
         Ok(())
     }
 
+    /// records the entry as read by this user, giving how many users have read it
+    pub fn mark_read(&mut self, entry_id: EntryId) -> Result<u64, DataError> {
+        let request = BoardRequest::MarkRead { user_id: self.user_id.ok_or(DataError::MissingKey)?, entry_id };
+        let BoardResponse::MarkRead(view_count) = self.send_request(request)? else {return Err(internal_error!())};
+        Ok(view_count)
+    }
+
+    /// how many entries are below the entry, ie. its replies
+    pub fn subtree_count(&mut self, entry_id: EntryId) -> Result<u64, DataError> {
+        let request = BoardRequest::SubtreeCount { user_id: self.user_id.ok_or(DataError::MissingKey)?, entry_id };
+        let BoardResponse::SubtreeCount(count) = self.send_request(request)? else {return Err(internal_error!())};
+        Ok(count)
+    }
+
+    pub fn set_pinned(&mut self, entry_id: EntryId, pinned: bool) -> Result<(), DataError> {
+        self.entry_cache.invalidate(entry_id);
+        let request = BoardRequest::SetPinned { user_id: self.user_id.ok_or(DataError::MissingKey)?, entry_id, pinned };
+        let response = self.send_request(request)?;
+        let BoardResponse::SetPinned = response else {return Err(internal_error!())};
+        Ok(())
+    }
+
+    /// leaves a tombstone in the entry's place, its parent keeps it as a child
+    pub fn delete_entry(&mut self, entry_id: EntryId) -> Result<(), DataError> {
+        let request = BoardRequest::DeleteEntry { user_id: self.user_id.unwrap(), entry_id };
+        let response = self.send_request(request)?;
+        let BoardResponse::DeleteEntry = response else {return Err(internal_error!())};
+        self.entry_cache.invalidate(entry_id);
+        Ok(())
+    }
+
+    /// agrees on a protocol version with the board, see `negotiate_version`
+    fn negotiate_version(&mut self) -> Result<(), DataError> {
+        let request = BoardRequest::NegotiateVersion { min_version: MIN_PROTOCOL_VERSION, max_version: PROTOCOL_VERSION };
+        let BoardResponse::NegotiateVersion(version) = self.send_request(request)? else {return Err(internal_error!())};
+        // the board should only pick from the range it was given
+        if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {return Err(DataError::VersionMismatch)}
+        self.protocol_version = version;
+        Ok(())
+    }
+
+    /// gets the board's max message size from its status, 
+    /// boards from before `STATUS_LIMITS_PROTOCOL_VERSION` are assumed to have the default one
+    fn refresh_limits(&mut self) -> Result<(), DataError> {
+        if self.protocol_version < STATUS_LIMITS_PROTOCOL_VERSION {return Ok(())}
+        let BoardResponse::Status { max_message_size, .. } = self.send_request(BoardRequest::Status)? else {return Err(internal_error!())};
+        self.max_message_size = checked_usize(max_message_size)?;
+        Ok(())
+    }
+
+    /// keeps the connection from being dropped as idle
+    pub fn ping(&mut self) -> Result<(), DataError> {
+        let response = self.send_request(BoardRequest::Ping)?;
+        let BoardResponse::Pong = response else {return Err(internal_error!())};
+        Ok(())
+    }
+
+    /// the id of the board's root entry, where browsing starts, 
+    /// boards from before `GET_ROOT_PROTOCOL_VERSION` are always rooted at `ROOT_ENTRY_ID`
+    pub fn get_root(&mut self) -> Result<EntryId, DataError> {
+        if self.protocol_version < GET_ROOT_PROTOCOL_VERSION {return Ok(ROOT_ENTRY_ID.into())}
+        let BoardResponse::GetRoot(root_id) = self.send_request(BoardRequest::GetRoot)? else {return Err(internal_error!())};
+        Ok(root_id)
+    }
+
+    /// the names of the server's boards, sorted, 
+    /// asked of `MAIN_BOARD` so it can be used to find the right name when the configured board doesn't exist
+    pub fn list_boards(&mut self) -> Result<Vec<String>, DataError> {
+        // boards from before there were named boards only have the main one
+        if self.protocol_version < NAMED_BOARDS_PROTOCOL_VERSION {return Ok(vec![String::from(MAIN_BOARD)])}
+        let board = std::mem::replace(&mut self.board, String::from(MAIN_BOARD));
+        let response = self.send_request(BoardRequest::ListBoards);
+        self.board = board;
+        let BoardResponse::ListBoards(boards) = response? else {return Err(internal_error!())};
+        Ok(boards)
+    }
+
+    /// the server's counters, in the Prometheus text exposition format, only given to the board's admin
+    pub fn metrics(&mut self) -> Result<String, DataError> {
+        let request = BoardRequest::Metrics { admin_id: self.user_id.ok_or(DataError::MissingKey)? };
+        let BoardResponse::Metrics(text) = self.send_request(request)? else {return Err(internal_error!())};
+        Ok(text)
+    }
+
     pub fn get_user(&mut self, user_id: UserId) -> Result<UserData, DataError> {
-        let request = BoardRequest::GetUser { user_id };
+        let request = BoardRequest::GetUser { requester_id: self.user_id.ok_or(DataError::MissingKey)?, user_id };
         let response = self.send_request(request)?;
         let BoardResponse::GetUser(user) = response else {return Err(internal_error!())};
         Ok(user)
     }
 
+    /// refetches the info about this user shown in the header
+    pub fn refresh_user_info(&mut self) -> Result<(), DataError> {
+        let Some(user_id) = self.user_id else {return Err(DataError::DoesNotExist)};
+        let user = self.get_user(user_id)?;
+        self.user_entry_count = user.entry_ids.len();
+        self.display_names.insert(user_id, user.display_name);
+        Ok(())
+    }
+
+    /// None clears it
+    pub fn set_display_name(&mut self, display_name: Option<String>) -> Result<(), DataError> {
+        let user_id = self.user_id.ok_or(DataError::MissingKey)?;
+        let request = BoardRequest::SetDisplayName { user_id, display_name: display_name.clone() };
+        let BoardResponse::SetDisplayName = self.send_request(request)? else {return Err(internal_error!())};
+        self.display_names.insert(user_id, display_name);
+        Ok(())
+    }
+
+    /// how each of the users is shown (see `user_label`), looking up the display names not gotten yet this session, 
+    /// only up to `MAX_NAME_LOOKUPS` of them, the rest are left out to be shown by id
+    pub fn user_labels(&mut self, user_ids: &[UserId]) -> HashMap<UserId, String> {
+        let mut lookups = 0;
+        let mut user_labels = HashMap::new();
+        for user_id in user_ids {
+            if !self.display_names.contains_key(user_id) {
+                if lookups == MAX_NAME_LOOKUPS {continue}
+                lookups += 1;
+                let display_name = self.get_user(*user_id).ok().and_then(|user| user.display_name);
+                self.display_names.insert(*user_id, display_name);
+            }
+            user_labels.insert(*user_id, user_label(*user_id, self.display_names[user_id].as_deref()));
+        }
+        user_labels
+    }
+
     pub fn create_user(&mut self) -> Result<bool, DataError> {
         //if let Some(_) = self.user_id {return Ok(false)}
         let request = BoardRequest::AddUser;
         let response = self.send_request(request)?;
         let BoardResponse::AddUser{user_id, user_aead} = response else {return Err(internal_error!())};
         self.user_id = Some(user_id);
+        self.guest = false;
         self.keys.user_aead = Some(user_aead.clone());
         edit_config(|config| {config.user_id = Some(user_id); config.user_aead = Some(user_aead)});
         Ok(true)
@@ -195,6 +921,13 @@ impl MessageBoardConnection {
     }
 
     pub fn get_user_id(&self) -> &Option<UserId> {&self.user_id}
+
+    pub fn is_guest(&self) -> bool {self.guest}
+
+    pub fn get_user_entry_count(&self) -> usize {self.user_entry_count}
+
+    /// how many entries are waiting in the outbox
+    pub fn get_pending_count(&self) -> usize {self.outbox.as_ref().map_or(0, |outbox| outbox.len())}
 }
 
 impl Drop for MessageBoardConnection {
@@ -210,19 +943,32 @@ pub struct Terminal {
     term: Option<ratatui::DefaultTerminal>,
 }
 
+/// `ratatui::init` with mouse capture
+pub fn init_terminal() -> ratatui::DefaultTerminal {
+    let term = ratatui::init();
+    let _ = crossterm::execute!(std::io::stdout(), crossterm::event::EnableMouseCapture);
+    term
+}
+
+/// `ratatui::restore`, also releasing mouse capture
+pub fn restore_terminal() {
+    let _ = crossterm::execute!(std::io::stdout(), crossterm::event::DisableMouseCapture);
+    ratatui::restore();
+}
+
 impl Terminal {
     pub fn new() -> Self {
         Self {
-            term: Some(ratatui::init())
+            term: Some(init_terminal())
         }
     }
 
     pub fn pause<F: FnOnce() -> O, O>(&mut self, f: F) -> O {
         if self.term.is_none() {eprintln!("Terminal invariant broken: accessible while self.term = None")};
-        ratatui::restore();
+        restore_terminal();
         self.term = None;
         let out = f();
-        self.term = Some(ratatui::init());
+        self.term = Some(init_terminal());
         out
     }
 }
@@ -244,7 +990,32 @@ impl std::ops::DerefMut for Terminal {
 
 impl Drop for Terminal {
     fn drop(&mut self) {
-        if self.term.is_some() {ratatui::restore();}
+        if self.term.is_some() {restore_terminal();}
+    }
+}
+
+/// what the error popup shows, a board's error or one only the client can run into
+#[derive(Debug, PartialEq, Eq)]
+pub enum ClientError {
+    Data(DataError),
+    /// the system clipboard couldn't be opened or written to
+    #[cfg(feature = "clipboard")]
+    ClipboardUnavailable,
+}
+
+impl From<DataError> for ClientError {
+    fn from(error: DataError) -> Self {
+        ClientError::Data(error)
+    }
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Data(error) => error.fmt(f),
+            #[cfg(feature = "clipboard")]
+            ClientError::ClipboardUnavailable => write!(f, "Clipboard unavailable"),
+        }
     }
 }
 
@@ -255,7 +1026,11 @@ pub enum ClientState {
     TextEntry(TextEntry),
     AccessGroupBuilder(AccessGroupBuilder),
     AccessGroupIdList(AccessGroupIdList),
-    Error(Vec<DataError>),
+    Confirm(ConfirmEntry),
+    ConfirmDelete(ConfirmDelete),
+    RawView(RawView),
+    JumpInput(JumpInput),
+    Error(Vec<ClientError>),
 }
 
 impl InputWidget for ClientState {
@@ -266,6 +1041,10 @@ impl InputWidget for ClientState {
             ClientState::TextEntry(entry) => entry.reload(),
             ClientState::AccessGroupBuilder(builder) => builder.reload(),
             ClientState::AccessGroupIdList(id_list) => id_list.reload(),
+            ClientState::Confirm(confirm) => confirm.reload(),
+            ClientState::ConfirmDelete(confirm) => confirm.reload(),
+            ClientState::RawView(raw_view) => raw_view.reload(),
+            ClientState::JumpInput(jump) => jump.reload(),
             ClientState::Error(..) => Ok(()),
         }
     }
@@ -277,6 +1056,10 @@ impl InputWidget for ClientState {
             ClientState::TextEntry(entry) => entry.render(area, buf),
             ClientState::AccessGroupBuilder(builder) => builder.render(area, buf),
             ClientState::AccessGroupIdList(id_list) => id_list.render(area, buf),
+            ClientState::Confirm(confirm) => confirm.render(area, buf),
+            ClientState::ConfirmDelete(confirm) => confirm.render(area, buf),
+            ClientState::RawView(raw_view) => raw_view.render(area, buf),
+            ClientState::JumpInput(jump) => jump.render(area, buf),
             ClientState::Error(errors) => {
                 let mut layout = Layout::horizontal([Constraint::Fill(1), Constraint::Percentage(50), Constraint::Fill(1)]).split(area);
                 layout = Layout::vertical([Constraint::Fill(1), Constraint::Percentage(50), Constraint::Fill(1)]).split(layout[1]);
@@ -286,7 +1069,7 @@ impl InputWidget for ClientState {
 
                 let mut text = Text::default();
                 for error in errors {
-                    let line = Line::from(format!("{:?}", error));
+                    let line = Line::from(error.to_string());
                     text.push_line(line);
                 }
 
@@ -304,7 +1087,15 @@ impl InputWidget for ClientState {
             ClientState::TextEntry(entry) => entry.handle_event(event),
             ClientState::AccessGroupBuilder(builder) => builder.handle_event(event),
             ClientState::AccessGroupIdList(id_list) => id_list.handle_event(event),
-            ClientState::Error(_) => {Some(StateChange::Pop)},
+            ClientState::Confirm(confirm) => confirm.handle_event(event),
+            ClientState::ConfirmDelete(confirm) => confirm.handle_event(event),
+            ClientState::RawView(raw_view) => raw_view.handle_event(event),
+            ClientState::JumpInput(jump) => jump.handle_event(event),
+            ClientState::Error(_) => match event {
+                // mouse movement shouldn't dismiss the popup
+                Event::Key(_) | Event::Mouse(MouseEvent { kind: MouseEventKind::Down(_), .. }) => Some(StateChange::Pop),
+                _ => None,
+            },
         }
     }
 
@@ -315,6 +1106,10 @@ impl InputWidget for ClientState {
             ClientState::TextEntry(entry) => entry.focus(),
             ClientState::AccessGroupBuilder(builder) => builder.focus(),
             ClientState::AccessGroupIdList(id_list) => id_list.focus(),
+            ClientState::Confirm(confirm) => confirm.focus(),
+            ClientState::ConfirmDelete(confirm) => confirm.focus(),
+            ClientState::RawView(raw_view) => raw_view.focus(),
+            ClientState::JumpInput(jump) => jump.focus(),
             ClientState::Error(_) => {},
         }
     }
@@ -325,6 +1120,10 @@ impl InputWidget for ClientState {
             ClientState::TextEntry(entry) => entry.unfocus(),
             ClientState::AccessGroupBuilder(builder) => builder.unfocus(),
             ClientState::AccessGroupIdList(id_list) => id_list.unfocus(),
+            ClientState::Confirm(confirm) => confirm.unfocus(),
+            ClientState::ConfirmDelete(confirm) => confirm.unfocus(),
+            ClientState::RawView(raw_view) => raw_view.unfocus(),
+            ClientState::JumpInput(jump) => jump.unfocus(),
             ClientState::Error(_) => {},
         }
     }
@@ -336,6 +1135,10 @@ impl InputWidget for ClientState {
             ClientState::TextEntry(entry) => entry.consume_child(child),
             ClientState::AccessGroupBuilder(builder) => builder.consume_child(child),
             ClientState::AccessGroupIdList(id_list) => id_list.consume_child(child),
+            ClientState::Confirm(confirm) => confirm.consume_child(child),
+            ClientState::ConfirmDelete(confirm) => confirm.consume_child(child),
+            ClientState::RawView(raw_view) => raw_view.consume_child(child),
+            ClientState::JumpInput(jump) => jump.consume_child(child),
             ClientState::Error(_) => {Some(StateChange::Pop)},
         }
     }
@@ -349,7 +1152,115 @@ impl ToString for ClientState {
             ClientState::AccessGroupBuilder(..) => "AccessGroupBuilder",
             ClientState::AccessGroupIdList(..) => "AccessGroupIdList",
             ClientState::TextEntry(..) => "TextEntry",
+            ClientState::Confirm(..) => "Confirm",
+            ClientState::ConfirmDelete(..) => "ConfirmDelete",
+            ClientState::RawView(..) => "RawView",
+            ClientState::JumpInput(..) => "JumpInput",
             ClientState::Error(..) => "Error",
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn key_spec_matching() {
+        let spec = KeySpec::parse("k").unwrap();
+        assert!(spec.matches(&key(KeyCode::Char('k'), KeyModifiers::NONE)), "Plain key not matched");
+        assert!(!spec.matches(&key(KeyCode::Char('k'), KeyModifiers::CONTROL)), "Plain key matched with ctrl held");
+        assert!(!spec.matches(&key(KeyCode::Char('K'), KeyModifiers::SHIFT)), "Plain key matched in the wrong case");
+
+        // shift is in the character's case, whether or not the terminal reports it
+        let spec = KeySpec::parse("H").unwrap();
+        assert!(spec.matches(&key(KeyCode::Char('H'), KeyModifiers::SHIFT)), "Shifted key not matched");
+        assert!(spec.matches(&key(KeyCode::Char('H'), KeyModifiers::NONE)), "Shifted key not matched without shift reported");
+        assert_eq!(KeySpec::parse("shift+h").unwrap(), spec, "Shift modifier not the same as the case");
+
+        let spec = KeySpec::parse("ctrl+x").unwrap();
+        assert!(spec.matches(&key(KeyCode::Char('x'), KeyModifiers::CONTROL)), "Ctrl key not matched");
+        assert!(!spec.matches(&key(KeyCode::Char('x'), KeyModifiers::NONE)), "Ctrl key matched without ctrl");
+        assert!(!spec.matches(&key(KeyCode::Char('x'), KeyModifiers::CONTROL | KeyModifiers::ALT)), "Ctrl key matched with alt held too");
+
+        // shift counts for keys without a case
+        let spec = KeySpec::parse("Up").unwrap();
+        assert!(spec.matches(&key(KeyCode::Up, KeyModifiers::NONE)), "Named key not matched");
+        assert!(!spec.matches(&key(KeyCode::Up, KeyModifiers::SHIFT)), "Named key matched with shift held");
+        assert!(KeySpec::parse("shift+Up").unwrap().matches(&key(KeyCode::Up, KeyModifiers::SHIFT)), "Shifted named key not matched");
+
+        assert!(KeySpec::parse("hyper+k").is_err(), "Unknown modifier accepted");
+        assert!(KeySpec::parse("NotAKey").is_err(), "Unknown key accepted");
+        assert_eq!(KeySpec::parse("space").unwrap(), KeySpec::parse(" ").unwrap(), "Incorrect space key");
+    }
+
+    #[test]
+    fn entry_cache_hits_and_evictions() {
+        let entry = |entry_id: EntryId| Entry::new_message(ROOT_ENTRY_ID.into(), UserId::from(0x1234), *entry_id, String::from("cached"));
+        let (first_id, second_id, third_id) = (EntryId::from(1), EntryId::from(2), EntryId::from(3));
+        let mut cache = EntryCache::new(2);
+        let mut fetched = Vec::new();
+        let mut get = |cache: &mut EntryCache, entry_id: EntryId| cache.get_or_fetch(entry_id, |entry_id| {
+            fetched.push(entry_id);
+            Ok(entry(entry_id))
+        }).unwrap();
+
+        assert_eq!(get(&mut cache, first_id), entry(first_id), "Incorrect fetched entry");
+        assert_eq!(get(&mut cache, first_id), entry(first_id), "Incorrect cached entry");
+        get(&mut cache, second_id);
+        // the first is used after the second, so the second is evicted for the third
+        get(&mut cache, first_id);
+        get(&mut cache, third_id);
+        get(&mut cache, first_id);
+        get(&mut cache, third_id);
+        get(&mut cache, second_id);
+        assert_eq!(fetched, vec![first_id, second_id, third_id, second_id], "Incorrect fetches");
+        assert_eq!(cache.entries.len(), 2, "Cache over its capacity");
+        assert!(cache.get(first_id).is_none(), "Least recently used entry kept");
+
+        // a failed fetch leaves nothing cached, and an invalidated entry is fetched again
+        let response = cache.get_or_fetch(first_id, |_| Err(DataError::DoesNotExist));
+        assert_eq!(response, Err(DataError::DoesNotExist), "Fetch error lost");
+        assert!(cache.get(first_id).is_none(), "Failed fetch cached");
+        cache.invalidate(third_id);
+        let response = cache.get_or_fetch(third_id, |_| Err(DataError::DoesNotExist));
+        assert_eq!(response, Err(DataError::DoesNotExist), "Invalidated entry not fetched again");
+
+        let mut cache = EntryCache::new(0);
+        cache.insert(first_id, entry(first_id));
+        assert!(cache.get(first_id).is_none(), "Cached with no capacity");
+    }
+
+    #[test]
+    fn keymap_from_toml() {
+        let config = read_config(r#"
+            [keys]
+            up = "ctrl+p"
+            down = ["ctrl+n", "Down"]
+        "#).unwrap();
+        let keymap = Keymap::from_toml(config["keys"].as_table_like().unwrap()).unwrap();
+        assert!(keymap.matches(Action::Up, &key(KeyCode::Char('p'), KeyModifiers::CONTROL)), "Rebound key not matched");
+        assert!(!keymap.matches(Action::Up, &key(KeyCode::Char('k'), KeyModifiers::NONE)), "Rebinding kept the default");
+        assert!(keymap.matches(Action::Down, &key(KeyCode::Char('n'), KeyModifiers::CONTROL)), "First of the keys not matched");
+        assert!(keymap.matches(Action::Down, &key(KeyCode::Down, KeyModifiers::NONE)), "Second of the keys not matched");
+        // the rest keep their defaults
+        for action in Action::ALL.into_iter().filter(|action| !matches!(action, Action::Up | Action::Down)) {
+            for spec in action.default_keys() {
+                let spec = KeySpec::parse(spec).unwrap();
+                assert!(keymap.matches(action, &key(spec.code, spec.modifiers)), "Default {} key \"{:?}\" lost", action.name(), spec);
+            }
+        }
+
+        let config = read_config("[keys]\nfly = \"f\"").unwrap();
+        let error = Keymap::from_toml(config["keys"].as_table_like().unwrap()).unwrap_err();
+        assert!(error.contains("unknown action \"fly\""), "Unknown action accepted: {}", error);
+        let config = read_config("[keys]\nup = 3").unwrap();
+        assert!(Keymap::from_toml(config["keys"].as_table_like().unwrap()).is_err(), "Non-key binding accepted");
+        let config = read_config("[keys]\nup = [\"k\", \"NotAKey\"]").unwrap();
+        assert!(Keymap::from_toml(config["keys"].as_table_like().unwrap()).is_err(), "Unknown key in an array accepted");
+    }
+}
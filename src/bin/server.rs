@@ -4,32 +4,95 @@ use message_board::*;
 use std::borrow::Borrow;
 use std::hash::Hash;
 use std::collections::{HashMap, HashSet};
-use std::io::{BufReader, Read, Write};
+use std::io::Write;
 use std::net::*;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
 use std::time::{Instant, Duration};
 use rand::Rng;
 use message_board::utils::*;
+use message_board::stream::*;
+
+mod server_libs;
+use server_libs::storage::*;
+use server_libs::journal::*;
+use server_libs::metrics::*;
 // this is the 0.6.4 version, vs the 0.10.0 version from the rand crate
 use rand_chacha::rand_core::RngCore as OldRngCore;
 use rand_chacha::rand_core::CryptoRng as OldCryptoRng; 
 
 /// extended off of the user home
+/// 
+/// keys:
+///     path: where the board's files are stored, not needed if `DATA_DIR_ENV_VAR` is set,
+///     address (optional): the IP address / host name to listen on, defaults to `DEFAULT_ADDRESS`,
+///     port (optional): the port to listen on, defaults to `PORT`,
+///     threads, max_message_size, rate_limit, rate_burst (optional),
+///     admin_id (optional): the user allowed to list the board's users and read the server's metrics, an integer or hex string, 
+///         only for `MAIN_BOARD` as the other boards have their own users,
+///     default_access (optional): "allow" or "deny", whether a user is let through when no access group up to the root
+///         lists them and every one inherits, defaults to "deny",
+///     max_tree_depth (optional): how deep an entry can be for the board to walk up to the root from it, 
+///         or count the entries below one, defaults to `DEFAULT_MAX_TREE_DEPTH`,
+///     user_privacy (optional): "public", "admin" or "filtered", who can get another user's data, see `UserPrivacy`, defaults to "filtered",
+///     allow_guests (optional): whether guests (`ANONYMOUS_USER_ID`) can browse without a user, only ever reading, defaults to false,
+///     nodelay (optional): whether to send responses without waiting to batch them (disables Nagle's algorithm), defaults to true,
+///     storage (optional): "files" or "sqlite" (`sqlite` feature), how entries and users are stored, defaults to "files",
+///     boards (optional): names of the boards served alongside `MAIN_BOARD`, each with its own entries and users, 
+///         see `validate_board_name`, defaults to none,
+///     tls_cert, tls_key (optional, `tls` feature): pem files, set together
 const RC_FILE: &str = ".config/message_board/server_rc.toml";
+/// overrides the `threads` key of the rc file
+const THREADS_ENV_VAR: &str = "MESSAGE_BOARD_THREADS";
+/// overrides the `path` key of the rc file, 
+/// when set a missing rc file or missing board files are created without prompting
+const DATA_DIR_ENV_VAR: &str = "MESSAGE_BOARD_DIR";
+const DEFAULT_ADDRESS: &str = "127.0.0.1";
+/// in `file_dir`, for the "sqlite" storage
+#[cfg(feature = "sqlite")]
+const SQLITE_FILE: &str = "board.sqlite3";
+/// in `file_dir`, see `Journal`
+const JOURNAL_FILE: &str = "journal";
+/// in the main board's `file_dir`, holding a `file_dir` for each of the other boards, named after them
+const BOARDS_DIR: &str = "boards";
 
 
 
 
 const SERVER_MAINLOOP_PERIOD: Duration = Duration::new(0, 1000000); // ie. 1 ms
+/// what a request frame may hold besides a `max_message_size` message, ie. the encryption, the entry's header 
+/// and the largest of the other fields (a full access group's perms), frames past it are refused before they're read
+const REQUEST_FRAME_OVERHEAD: usize = 1 << 17;
+/// per client, overridden by the `rate_limit` (requests / sec) and `rate_burst` keys of the rc file
+const DEFAULT_RATE_LIMIT: f64 = 20.0;
+const DEFAULT_RATE_BURST: f64 = 40.0;
 
 struct StorageFile {
     kem_ek: EncapsulationKey,
     kem_dk: DecapsulationKey,
 }
 
+/// who can get another user's `UserData` with a GetUser, everyone can always get their own
+/// 
+/// another user's key is never sent, a random one stands in for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UserPrivacy {
+    Public,
+    /// only the board's `admin_id` can get other users'
+    AdminOnly,
+    /// other users' come back with only the entries the requester can read
+    Filtered,
+}
+
+/// what a command handler thread sends back, 
+/// left unencapsulated until the outgoing thread turns it into the wire `BoardResponse`
+struct HandlerResponse {
+    handler_id: u64,
+    data: MaybeBoardResponse,
+}
+
 struct GuardedUserAeadKey<'a> {
     board: &'a MessageBoard,
     user_id: UserId,
@@ -48,9 +111,8 @@ impl<'a> std::ops::DerefMut for GuardedUserAeadKey<'a> {
 
 impl<'a> Drop for GuardedUserAeadKey<'a> {
     fn drop(&mut self) {
-        let mut user = self.board.get_user(self.user_id).unwrap();
-        user.aead = self.key.clone();
-        self.board.overwrite_user_data(self.user_id, user).unwrap();
+        let key = self.key.clone();
+        self.board.update_user(self.user_id, |user| user.aead = key).unwrap();
     }
 }
 
@@ -60,12 +122,23 @@ impl<'a> Drop for GuardedUserAeadKey<'a> {
 /// 
 /// `~/.config/message_board` is the config dir:
 ///     path: file containing the path for the main file dir (hereafter `file_dir`)
+///     threads (optional): number of request handler threads, defaults to the available parallelism,
+///                         overridden by the `MESSAGE_BOARD_THREADS` env var
+///     max_message_size (optional): largest message accepted in bytes, defaults to `DEFAULT_MAX_MESSAGE_SIZE`
+///     tls_cert, tls_key (optional): paths to the pem certificate chain and private key,
+///                                   connections use tls when both are set (requires the `tls` feature)
 /// 
 /// `file_dir`:
 ///     `storage`, file containing some persistent information for the server:
 ///         kem_ek: EncapsulationKey,
 ///         kem_dk: DecapsulationKey,
 /// 
+///     `boards`, dir containing a dir for each of the other boards, named after it:
+///         each is laid out like `file_dir` but without `storage` or `boards`, the keys being shared by every board
+/// 
+///     `entries` and `users` are only used by the "files" storage, 
+///     the "sqlite" storage keeps the same data in the `entries` and `users` tables of `board.sqlite3`
+/// 
 ///     `entries`, dir containing entry files:
 ///         each entry file has no extension and is named with its id in hex
 ///         see `lib.rs` for the entry file format
@@ -87,43 +160,88 @@ impl<'a> Drop for GuardedUserAeadKey<'a> {
 ///         
 /// 
 struct MessageBoard {
-    address: String,
+    name: String,
+    named_boards: Vec<String>, // the other boards, only set on the main one
+    address: SocketAddr,
     file_dir: Box<Path>,
+    num_handlers: u64,
+    start_time: Instant,
+    max_message_size: usize,
+    rate_limit: f64,
+    rate_burst: f64,
+    admin_id: Option<UserId>,
+    default_access: bool, // when the perms resolve to nothing, see `resolve_access`
+    max_tree_depth: usize,
+    user_privacy: UserPrivacy,
+    allow_guests: bool,
+    nodelay: bool,
+    #[cfg(feature = "tls")]
+    tls_config: Option<std::sync::Arc<rustls::ServerConfig>>,
+    storage: Box<dyn Storage>,
+    journal: Journal, // mutations writing more than one entry / user go through it
     entry_ids: RwLock<HashSet<EntryId>>,
+    entry_index: RwLock<EntryIndex>, // kept up to date by `write_entry` / `overwrite_entry`
     user_ids: RwLock<HashSet<UserId>>,
+    entries_lock: Mutex<()>, // held over reading and rewriting entries, so a concurrent change to the same parent isn't lost
+    receipts_lock: Mutex<()>, // held over reading and rewriting an entry's read receipts
+    users_lock: Mutex<()>, // held over reading and rewriting a user's data
 }
 
 #[allow(unused)]
 impl MessageBoard {
     fn new() -> Self {
-        let user_home = std::env::home_dir().unwrap();
+        let env_file_dir = std::env::var_os(DATA_DIR_ENV_VAR).filter(|dir| !dir.is_empty()).map(PathBuf::from);
+        Self::from_env(std::env::home_dir().unwrap(), env_file_dir)
+    }
+
+    /// the board configured by the rc file in `user_home`, with its files in `env_file_dir` (from `DATA_DIR_ENV_VAR`) if it is set,
+    /// which also skips the prompts for a missing rc file / board files
+    fn from_env(user_home: PathBuf, env_file_dir: Option<PathBuf>) -> Self {
         let mut real_rc_config = user_home.clone();
         real_rc_config.push(RC_FILE);
         
         let mut stdin = std::io::stdin();
         let mut stdout = std::io::stdout();
         let mut input_buffer = String::new();
-        let mut rc_config_result = fs::read_to_string(&real_rc_config).map(|str| str.parse::<toml::Table>().expect("The Server Rc was misformatted"));
+        let mut rc_config_result = fs::read_to_string(&real_rc_config).map(|str| read_config(&str).expect("The Server Rc was misformatted"));
         if let Err(e) = rc_config_result {
             match e.kind() {
+                std::io::ErrorKind::NotFound if env_file_dir.is_some() => {
+                    info!("No config file, running from {} with the defaults", DATA_DIR_ENV_VAR);
+                    rc_config_result = Ok(toml_edit::DocumentMut::new());
+                }
                 std::io::ErrorKind::NotFound => {
                     print!("Config file does not exist, create a new one? (y/n): ");
                     let _ = stdout.flush();
                     input_buffer.clear();
                     let create = stdin_y_n(&mut stdin, &mut input_buffer);
                     if create {
-                        let mut contents = toml::Table::new();
-                        print!("Please enter the path for the message board's data: ");
-                        let _ = stdout.flush();
-                        input_buffer.clear();
-                        let _ = stdin.read_line(&mut input_buffer);
-                        contents.insert("path".to_string(), toml::Value::String(input_buffer.trim().to_string()));
+                        let mut contents = toml_edit::DocumentMut::new();
+                        loop {
+                            print!("Please enter the path for the message board's data: ");
+                            let _ = stdout.flush();
+                            input_buffer.clear();
+                            match stdin.read_line(&mut input_buffer) {
+                                Ok(0) => panic!("Cannot continue without a path for the board's data, terminating the server"),
+                                Ok(_) => {},
+                                Err(e) => {println!("Failed to read the path: {}", e); continue}
+                            }
+                            let path = input_buffer.trim();
+                            if path.is_empty() {continue}
+                            match fs::create_dir_all(path) {
+                                Ok(()) => {
+                                    set_config_value(&mut contents, "path", path);
+                                    break;
+                                }
+                                Err(e) => println!("Can't use {} for the board's data: {}", path, e),
+                            }
+                        }
 
                         print!("Please enter the IP address / host name for the message board: ");
                         let _ = stdout.flush();
                         input_buffer.clear();
                         let _ = stdin.read_line(&mut input_buffer);
-                        contents.insert("address".to_string(), toml::Value::String(input_buffer.trim().to_string()));
+                        set_config_value(&mut contents, "address", input_buffer.trim());
 
                         let mut parent = real_rc_config.clone();
                         parent.pop();
@@ -138,90 +256,355 @@ impl MessageBoard {
             }
         }
         let rc_config = rc_config_result.unwrap();
-        let file_dir = PathBuf::from(rc_config["path"].as_str().expect("\"path\" should be a string of the path to where files should be stored")).into_boxed_path();
-        let address = rc_config["address"].as_str().expect("\"path\" should be a string of the path to where files should be stored").to_string();
+        let dir_from_env = env_file_dir.is_some();
+        let file_dir = match env_file_dir {
+            Some(file_dir) => file_dir.into_boxed_path(),
+            None => {
+                if let Err(key) = require_config_keys(&rc_config, &["path"]) {
+                    panic!("The server RC file is missing the \"{}\" key", key)
+                }
+                PathBuf::from(rc_config["path"].as_str().expect("\"path\" should be a string of the path to where files should be stored")).into_boxed_path()
+            }
+        };
+        let address = match rc_config.get("address") {
+            Some(address) => address.as_str().expect("\"address\" should be a string of the address to bind to"),
+            None => DEFAULT_ADDRESS,
+        };
+        let port = match rc_config.get("port") {
+            Some(port) => port.as_integer().and_then(|port| u16::try_from(port).ok()).expect("\"port\" should be an integer from 0 to 65535"),
+            None => PORT,
+        };
+        let address = match (address, port).to_socket_addrs().map(|mut addrs| addrs.next()) {
+            Ok(Some(address)) => address,
+            Ok(None) => panic!("\"address\" ({}) didn't resolve to any address", address),
+            Err(e) => panic!("\"address\" ({}) should be a valid IP address / host name to bind to: {}", address, e),
+        };
+        let num_handlers = Self::read_num_handlers(std::env::var(THREADS_ENV_VAR).ok(), &rc_config);
+        let max_message_size = match rc_config.get("max_message_size") {
+            Some(size) => match size.as_integer() {
+                Some(size) if size >= 0 => size as usize,
+                _ => panic!("\"max_message_size\" should be a non-negative integer"),
+            },
+            None => DEFAULT_MAX_MESSAGE_SIZE,
+        };
+        let read_rate = |key: &str, default: f64| match rc_config.get(key) {
+            Some(rate) => match rate.as_float().or_else(|| rate.as_integer().map(|x| x as f64)) {
+                Some(rate) if rate > 0.0 => rate,
+                _ => panic!("\"{}\" should be a positive number", key),
+            },
+            None => default,
+        };
+        let rate_limit = read_rate("rate_limit", DEFAULT_RATE_LIMIT);
+        let rate_burst = read_rate("rate_burst", DEFAULT_RATE_BURST);
+        if rate_burst < 1.0 {panic!("\"rate_burst\" should be at least 1, found {}", rate_burst)}
+        let admin_id = match rc_config.get("admin_id") {
+            Some(admin_id) => parse_user_id(admin_id).unwrap_or_else(|err| panic!("\"admin_id\" is misformatted: {}", err)).map(UserId::from),
+            None => None,
+        };
+        let default_access = match rc_config.get("default_access").map(|access| access.as_str()) {
+            Some(Some("allow")) => true,
+            Some(Some("deny")) | None => false,
+            Some(_) => panic!("\"default_access\" should be \"allow\" or \"deny\""),
+        };
+        let max_tree_depth = match rc_config.get("max_tree_depth") {
+            Some(max_tree_depth) => {
+                let max_tree_depth = max_tree_depth.as_integer().expect("\"max_tree_depth\" should be an integer");
+                if max_tree_depth < 1 {panic!("\"max_tree_depth\" should be at least 1, found {}", max_tree_depth)}
+                max_tree_depth as usize
+            }
+            None => DEFAULT_MAX_TREE_DEPTH,
+        };
+        let user_privacy = match rc_config.get("user_privacy").map(|privacy| privacy.as_str()) {
+            Some(Some("public")) => UserPrivacy::Public,
+            Some(Some("admin")) => UserPrivacy::AdminOnly,
+            Some(Some("filtered")) | None => UserPrivacy::Filtered,
+            Some(_) => panic!("\"user_privacy\" should be \"public\", \"admin\" or \"filtered\""),
+        };
+        let allow_guests = match rc_config.get("allow_guests") {
+            Some(allow_guests) => allow_guests.as_bool().expect("\"allow_guests\" should be a boolean"),
+            None => false,
+        };
+        let nodelay = match rc_config.get("nodelay") {
+            Some(nodelay) => nodelay.as_bool().expect("\"nodelay\" should be a boolean"),
+            None => true,
+        };
+        #[cfg(feature = "tls")]
+        let tls_config = match (rc_config.get("tls_cert"), rc_config.get("tls_key")) {
+            (Some(cert), Some(key)) => {
+                let cert = cert.as_str().expect("\"tls_cert\" should be a string of the path to the pem certificate chain");
+                let key = key.as_str().expect("\"tls_key\" should be a string of the path to the pem private key");
+                let cert_chain = tls::load_certs(cert).expect("Failed to read the tls certificate chain");
+                let key = tls::load_private_key(key).expect("Failed to read the tls private key");
+                Some(tls::server_config(cert_chain, key).expect("The tls certificate chain / private key were invalid"))
+            }
+            (None, None) => None,
+            _ => panic!("\"tls_cert\" and \"tls_key\" should be set together"),
+        };
+        #[cfg(not(feature = "tls"))]
+        if rc_config.contains_key("tls_cert") || rc_config.contains_key("tls_key") {
+            warn!("TLS is configured but the server was built without the \"tls\" feature, falling back to plaintext");
+        }
+        let storage: Box<dyn Storage> = match rc_config.get("storage").map(|storage| storage.as_str()) {
+            Some(Some("files")) | None => Box::new(FileStorage::new(file_dir.clone())),
+            #[cfg(feature = "sqlite")]
+            Some(Some("sqlite")) => Box::new(SqliteStorage::new(file_dir.join(SQLITE_FILE))),
+            #[cfg(not(feature = "sqlite"))]
+            Some(Some("sqlite")) => panic!("\"storage\" is \"sqlite\" but the server was built without the \"sqlite\" feature"),
+            Some(_) => panic!("\"storage\" should be \"files\" or \"sqlite\""),
+        };
+        let named_boards = match rc_config.get("boards") {
+            Some(boards) => {
+                let boards = boards.as_array().expect("\"boards\" should be an array of board names");
+                let mut named_boards = Vec::new();
+                for board in boards {
+                    let board = board.as_str().expect("\"boards\" should be an array of board names");
+                    if validate_board_name(board).is_err() || board == MAIN_BOARD {
+                        panic!("\"{}\" in \"boards\" isn't a valid board name", board)
+                    }
+                    if named_boards.iter().any(|named| named == board) {
+                        panic!("\"{}\" is in \"boards\" more than once", board)
+                    }
+                    named_boards.push(String::from(board));
+                }
+                named_boards
+            }
+            None => Vec::new(),
+        };
     
+        let journal = Journal::new(Some(file_dir.join(JOURNAL_FILE)));
         let board = MessageBoard { 
+            name: String::from(MAIN_BOARD),
+            named_boards,
             address,
             file_dir,
+            num_handlers,
+            start_time: Instant::now(),
+            max_message_size,
+            rate_limit,
+            rate_burst,
+            admin_id,
+            default_access,
+            max_tree_depth,
+            user_privacy,
+            allow_guests,
+            nodelay,
+            #[cfg(feature = "tls")]
+            tls_config,
+            storage,
+            journal,
             entry_ids: RwLock::new(HashSet::new()),
+            entry_index: RwLock::new(EntryIndex::new()),
             user_ids: RwLock::new(HashSet::new()),
+            entries_lock: Mutex::new(()),
+            receipts_lock: Mutex::new(()),
+            users_lock: Mutex::new(()),
         };
         
         debug!("MessageBoard config successfully established");
         // checking / setting up the board files
 
-        let mut missing_files = false;
+        let mut missing_files = !board.storage.exists();
         {
             let mut path = PathBuf::from(board.file_dir.clone());
             missing_files |= !path.exists();
-            path.push("entries");
-            missing_files |= !path.exists();
-            path.push(format!("{:016X}", ROOT_ENTRY_ID));
-            missing_files |= !path.exists();
-            path.pop();
-            path.pop();
-            path.push("users");
-            missing_files |= !path.exists();
-            path.pop();
             path.push("storage");
             missing_files |= !path.exists();
         }
         if missing_files {
-            print!("MessageBoard is missing files at path. Create empty files as needed? (y/n): ");
-            let _ = stdout.flush();
-            input_buffer.clear();
-            let create = stdin_y_n(&mut stdin, &mut input_buffer);
+            let create = dir_from_env || {
+                print!("MessageBoard is missing files at path. Create empty files as needed? (y/n): ");
+                let _ = stdout.flush();
+                input_buffer.clear();
+                stdin_y_n(&mut stdin, &mut input_buffer)
+            };
             if create {
-                let mut crypto_rng = get_crypto_rng();
-
-                let _ = fs::create_dir_all(&board.file_dir);
-                let mut path = PathBuf::from(board.file_dir.clone());
-                path.push("entries");
-                let _ = fs::create_dir(&path);
-                path.pop();
-                path.push("users");
-                let _ = fs::create_dir(&path);
-                path.pop();
-                path.push("storage");
-                let _ = fs::write(path, &[]);
-
-                let (kem_dk, kem_ek) = get_kem_set(crypto_rng);
-                let storage = StorageFile {
-                    kem_dk,
-                    kem_ek
-                };
-                board.write_storage_file(storage);
-
-                let default_root = Entry {
-                    header_data: HeaderData { version: ENTRY_FILE_VERSION, parent_id: ROOT_ENTRY_ID.into(), children_ids: Vec::new(), author_id: SERVER_USER_ID.into() },
-                    entry_data: EntryData::AccessGroup { name: String::from("Root"), write_perms: DefaultedIdSet::White { blacklist_ids: Vec::new() }, read_perms: DefaultedIdSet::White { blacklist_ids: Vec::new() }}
-                };
-                if board.write_entry(ROOT_ENTRY_ID.into(), default_root).is_err_and(|e| if let DataError::AlreadyExists = e {false} else {true}) {
-                    error!("failed to create root entry");
+                if let Err(e) = board.create_board_files() {
+                    panic!("Failed to create the board files at {}: {}", board.file_dir.display(), e);
                 }
             } else {
                 panic!("Cannot continue without board files, terminating the server");
             }
         }
 
-        board.update_user_ids();
-        board.update_entry_ids();
+        board.load();
+        board
+    }
+
+    /// a board with the default settings on `address` (port 0 for any free port), 
+    /// creating any missing files in `file_dir` and `storage` without prompting
+    #[cfg(test)]
+    fn with_defaults(file_dir: Box<Path>, address: SocketAddr, storage: Box<dyn Storage>) -> Self {
+        let board = Self::unloaded_defaults(file_dir, address, storage);
+        if let Err(e) = board.create_board_files() {
+            panic!("Failed to create the board files at {}: {}", board.file_dir.display(), e);
+        }
+        board.load();
+        board
+    }
+
+    /// the number of handler threads, from `THREADS_ENV_VAR` if it is set (`from_env`) or else the `threads` key,
+    /// panics if it isn't at least 1
+    fn read_num_handlers(from_env: Option<String>, rc_config: &toml_edit::DocumentMut) -> u64 {
+        let num_handlers = match from_env {
+            Some(threads) => threads.trim().parse::<i64>().expect("MESSAGE_BOARD_THREADS should be an integer"),
+            None => match rc_config.get("threads") {
+                Some(threads) => threads.as_integer().expect("\"threads\" should be an integer"),
+                None => std::thread::available_parallelism().map_or(1, |x| x.get()) as i64,
+            }
+        };
+        if num_handlers < 1 {panic!("The number of handler threads should be at least 1, found {}", num_handlers)}
+        num_handlers as u64
+    }
+
+    /// a board with the default settings keeping everything in memory, 
+    /// it has no storage file (and so no kem keys) so it can't be served, only handle requests directly
+    #[cfg(test)]
+    fn in_memory() -> Self {
+        let mut board = Self::unloaded_defaults(PathBuf::new().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), Box::new(MemoryStorage::default()));
+        board.journal = Journal::new(None);
+        board.load();
         board
     }
 
+    #[cfg(test)]
+    fn unloaded_defaults(file_dir: Box<Path>, address: SocketAddr, storage: Box<dyn Storage>) -> Self {
+        let journal = Journal::new(Some(file_dir.join(JOURNAL_FILE)));
+        MessageBoard {
+            name: String::from(MAIN_BOARD),
+            named_boards: Vec::new(),
+            address,
+            file_dir,
+            num_handlers: 2,
+            start_time: Instant::now(),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            rate_limit: DEFAULT_RATE_LIMIT,
+            rate_burst: DEFAULT_RATE_BURST,
+            admin_id: None,
+            default_access: false,
+            max_tree_depth: DEFAULT_MAX_TREE_DEPTH,
+            user_privacy: UserPrivacy::Filtered,
+            allow_guests: false,
+            nodelay: true,
+            #[cfg(feature = "tls")]
+            tls_config: None,
+            storage,
+            journal,
+            entry_ids: RwLock::new(HashSet::new()),
+            entry_index: RwLock::new(EntryIndex::new()),
+            user_ids: RwLock::new(HashSet::new()),
+            entries_lock: Mutex::new(()),
+            receipts_lock: Mutex::new(()),
+            users_lock: Mutex::new(()),
+        }
+    }
+
+    /// moves files stored under older names, finishes any mutations cut short, sets up the root, and reads in the existing ids, 
+    /// the board files must already exist
+    fn load(&self) {
+        match self.storage.migrate() {
+            Ok(0) => {},
+            Ok(renamed) => info!("Moved {} files to their current names", renamed),
+            Err(e) => panic!("The board files couldn't be migrated: {}", e),
+        }
+        match self.journal.replay(&*self.storage) {
+            Ok(0) => {},
+            Ok(replayed) => info!("Finished {} mutations from the journal", replayed),
+            Err(e) => panic!("The journal couldn't be replayed: {}", e),
+        }
+        if let Err(e) = self.ensure_root() {
+            panic!("The root entry couldn't be set up: {}", e);
+        }
+
+        self.update_user_ids();
+        self.update_entry_ids();
+    }
+
+    /// writes `Entry::default_root` if the board has no root, failing if the existing root is malformed
+    fn ensure_root(&self) -> Result<(), DataError> {
+        match self.get_entry(ROOT_ENTRY_ID.into()) {
+            Ok(root) => root.validate_root(),
+            Err(DataError::DoesNotExist) => {
+                info!("Creating the root entry");
+                match self.write_entry(ROOT_ENTRY_ID.into(), Entry::default_root()) {
+                    Err(DataError::AlreadyExists) => Ok(()),
+                    result => result,
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// one of the main board's `named_boards`, with the same settings but its own files, 
+    /// creating any that are missing
+    /// 
+    /// it has no admin, the main board's `admin_id` is one of the main board's users
+    fn named_board(&self, name: &str) -> Result<Self, DataError> {
+        validate_board_name(name)?;
+        let file_dir = self.file_dir.join(BOARDS_DIR).join(name).into_boxed_path();
+        let board = MessageBoard {
+            name: String::from(name),
+            named_boards: Vec::new(),
+            address: self.address,
+            storage: self.storage.for_dir(&file_dir),
+            journal: Journal::new(Some(file_dir.join(JOURNAL_FILE))),
+            file_dir,
+            num_handlers: self.num_handlers,
+            start_time: self.start_time,
+            max_message_size: self.max_message_size,
+            rate_limit: self.rate_limit,
+            rate_burst: self.rate_burst,
+            admin_id: None,
+            default_access: self.default_access,
+            max_tree_depth: self.max_tree_depth,
+            user_privacy: self.user_privacy,
+            allow_guests: self.allow_guests,
+            nodelay: self.nodelay,
+            #[cfg(feature = "tls")]
+            tls_config: self.tls_config.clone(),
+            entry_ids: RwLock::new(HashSet::new()),
+            entry_index: RwLock::new(EntryIndex::new()),
+            user_ids: RwLock::new(HashSet::new()),
+            entries_lock: Mutex::new(()),
+            receipts_lock: Mutex::new(()),
+            users_lock: Mutex::new(()),
+        };
+        // no storage file, the main board's kem keys are used for every board
+        fs::create_dir_all(&board.file_dir)?;
+        board.storage.create()?;
+        board.load();
+        Ok(board)
+    }
+
+    /// creates whichever of the board's dir, storage file, and `Storage` are missing, the root is left to `ensure_root`
+    fn create_board_files(&self) -> Result<(), DataError> {
+        let mut path = PathBuf::from(self.file_dir.clone());
+        fs::create_dir_all(&path)?;
+        self.storage.create()?;
+        path.push("storage");
+        if !path.exists() {
+            let (kem_dk, kem_ek) = get_kem_set(get_crypto_rng());
+            let storage = StorageFile {
+                kem_dk,
+                kem_ek
+            };
+            fs::write(&path, &[])?;
+            self.write_storage_file(storage)?;
+        }
+        Ok(())
+    }
+
     fn write_new<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<(), DataError> {
-        fs::File::create_new(path).map_err(|_| DataError::AlreadyExists)?.write_all(contents).map_err(|_| internal_error!())?;
+        write_new_atomic(path, contents)?;
         Ok(())
     }
 
     fn overwrite_old<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<(), DataError> {
-        fs::File::options().write(true).truncate(true).open(&path).map_err(|_| DataError::DoesNotExist)?.write_all(contents).map_err(|_| internal_error!())?;
+        overwrite_atomic(path, contents)?;
         Ok(())
     }
 
     fn append_old<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<(), DataError> {
-        fs::File::options().write(true).append(true).open(&path).map_err(|_| DataError::DoesNotExist)?.write_all(contents).map_err(|_| internal_error!())?;
+        fs::File::options().write(true).append(true).open(&path)?.write_all(contents)?;
         Ok(())
     }
 
@@ -238,44 +621,51 @@ impl MessageBoard {
     /// 
     /// may or may not be implemented in terms of `get_entry_data`
     fn get_entry_data_iter(&self, entry_id: EntryId) -> Result<impl Iterator<Item = u8>, DataError> {
-        let mut path = PathBuf::from(self.file_dir.clone());
-        path.push(format!("entries/{:016X}", *entry_id));
-        let entry = std::fs::File::open(path).map_err(|_| DataError::DoesNotExist)?;
-        Ok(BufReader::new(entry).bytes().filter_map(|x| x.ok())) // Scuff
+        Ok(self.storage.read_entry(entry_id)?.into_iter())
     }
 
     /// encapsulation method to get a `UserData` of a `user_id`
     fn get_user(&self, user_id: UserId) -> Result<UserData, DataError> {
-        let mut path = PathBuf::from(self.file_dir.clone());
-        path.push(format!("users/{}", user_id));
-        UserData::from_data(&std::fs::read(path).map_err(|_| DataError::DoesNotExist)?)
+        UserData::from_data(&self.storage.read_user(user_id)?)
+    }
+
+    /// the data stored for the entry at `entry_id`, every entry written goes through here to be validated
+    fn entry_data(entry_id: EntryId, entry: &Entry) -> Result<Vec<u8>, DataError> {
+        entry.header_data.validate(entry_id)?;
+        entry.into_data()
     }
 
     /// encapsulation method to write an `Entry` at `entry_id`
     /// 
     /// requires that the entry_id doesn't currently exist
     fn write_entry(&self, entry_id: EntryId, entry: Entry) -> Result<(), DataError> {
-        let mut path = PathBuf::from(self.file_dir.clone());
-        path.push(format!("entries/{:016X}", *entry_id));
-        Self::write_new(path, &entry.into_data()?)
+        self.storage.write_new_entry(entry_id, &Self::entry_data(entry_id, &entry)?)?;
+        self.entry_index.write().unwrap().insert(entry_id, &entry);
+        Ok(())
     }
 
     /// encapsulation method to overwrite / edit an `Entry` at `entry_id`
     /// 
     /// requires that the entry_id currently exists
     fn overwrite_entry(&self, entry_id: EntryId, new_entry: Entry) -> Result<(), DataError> {
-        let mut path = PathBuf::from(self.file_dir.clone());
-        path.push(format!("entries/{:016X}", *entry_id));
-        Self::overwrite_old(path, &new_entry.into_data()?)
+        self.storage.overwrite_entry(entry_id, &Self::entry_data(entry_id, &new_entry)?)?;
+        self.entry_index.write().unwrap().insert(entry_id, &new_entry);
+        Ok(())
+    }
+
+    /// reads, changes, and rewrites the entry under `entries_lock`, not writing it if `update` fails
+    fn update_entry(&self, entry_id: EntryId, update: impl FnOnce(&mut Entry) -> Result<(), DataError>) -> Result<(), DataError> {
+        let _guard = self.entries_lock.lock().unwrap();
+        let mut entry = self.get_entry(entry_id)?;
+        update(&mut entry)?;
+        self.overwrite_entry(entry_id, entry)
     }
 
     /// encapsulation method to overwrite an updated `UserData` for `user_id`
     /// 
     /// requires that the user_id currently exists
     fn overwrite_user_data(&self, user_id: UserId, new_data: UserData) -> Result<(), DataError> {
-        let mut path = PathBuf::from(self.file_dir.clone());
-        path.push(format!("users/{}", user_id));
-        Self::overwrite_old(path, &new_data.into_data()?) //FIXME: completely overwrites, even for small edits
+        self.storage.overwrite_user(user_id, &new_data.into_data()?)
     }
 
     fn read_storage_file(&self) -> StorageFile {
@@ -287,13 +677,21 @@ impl MessageBoard {
         StorageFile { kem_ek, kem_dk }
     }
 
-    fn write_storage_file(&self, storage_file: StorageFile) {
+    fn write_storage_file(&self, storage_file: StorageFile) -> Result<(), DataError> {
         let mut path = PathBuf::from(self.file_dir.clone());
         path.push("storage");
         let mut data = Vec::new();
-        storage_file.kem_ek.extend_data(&mut data).unwrap();
-        storage_file.kem_dk.extend_data(&mut data).unwrap();
-        Self::overwrite_old(path, &data);
+        storage_file.kem_ek.extend_data(&mut data)?;
+        storage_file.kem_dk.extend_data(&mut data)?;
+        Self::overwrite_old(path, &data)
+    }
+
+    /// reads, changes, and rewrites the user's data under `users_lock`
+    fn update_user(&self, user_id: UserId, update: impl FnOnce(&mut UserData)) -> Result<(), DataError> {
+        let _guard = self.users_lock.lock().unwrap();
+        let mut user = self.get_user(user_id)?;
+        update(&mut user);
+        self.overwrite_user_data(user_id, user)
     }
 
     fn get_user_aead(&self, user_id: UserId) -> Result<GuardedUserAeadKey<'_>, DataError> {
@@ -306,11 +704,7 @@ impl MessageBoard {
     }
 
     fn update_user_ids(&self) -> Result<(), DataError> {
-        let mut path = PathBuf::from(self.file_dir.clone());
-        path.push("users");
-        let new = fs::read_dir(&path).map_err(|_| internal_error!())?.map(|user_file| {
-            u64::from_str_radix(user_file.unwrap().file_name().to_str().unwrap(), 16).unwrap().into()
-        }).collect();
+        let new = self.storage.user_ids()?;
         {
             *self.user_ids.write().unwrap() = new;
         }
@@ -318,11 +712,16 @@ impl MessageBoard {
     }
 
     fn update_entry_ids(&self) -> Result<(), DataError> {
-        let mut path = PathBuf::from(self.file_dir.clone());
-        path.push("entries");
-        *self.entry_ids.write().unwrap() = fs::read_dir(&path).map_err(|_| internal_error!())?.map(|entry_file| {
-            u64::from_str_radix(entry_file.unwrap().file_name().to_str().unwrap(), 16).unwrap().into()
-        }).collect();
+        let entry_ids = self.storage.entry_ids()?;
+        let mut entry_index = EntryIndex::new();
+        for entry_id in &entry_ids {
+            match self.get_entry(*entry_id) {
+                Ok(entry) => entry_index.insert(*entry_id, &entry),
+                Err(e) => warn!("Failed to index entry {}: {:?}", entry_id, e),
+            }
+        }
+        *self.entry_ids.write().unwrap() = entry_ids;
+        *self.entry_index.write().unwrap() = entry_index;
         Ok(())
     }
 
@@ -330,147 +729,254 @@ impl MessageBoard {
         Entry::from_data_iter(&mut self.get_entry_data_iter(entry_id)?)
     }
 
+    /// gets the entry if the user can read it, either being its author or having read perms to its parent
+    fn read_entry(&self, user_id: UserId, entry_id: EntryId) -> Result<Entry, DataError> {
+        let entry = self.get_entry(entry_id)?;
+        if entry.header_data.author_id != user_id && !self.has_access_perm(user_id, entry.header_data.parent_id, PermKind::Read)? {
+            return Err(DataError::InsufficientPerms)
+        }
+        Ok(entry)
+    }
+
     fn add_entry(&self, user_id: UserId, entry_id: EntryId, entry: Entry) -> Result<(), DataError> {
-        let mut parent = self.get_entry(entry.header_data.parent_id)?;
-        parent.header_data.children_ids.push(entry_id);
-        self.overwrite_entry(entry.header_data.parent_id, parent)?;
-        
+        self.add_entries(user_id, vec![(entry_id, entry)])
+    }
+
+    /// adds every entry or none of them, 
+    /// each parent having room for all of the batch's entries under it
+    fn add_entries(&self, user_id: UserId, mut entries: Vec<(EntryId, Entry)>) -> Result<(), DataError> {
+        let timestamp = Self::current_timestamp();
+        let mut changed: HashMap<EntryId, Entry> = HashMap::new();
+        let mut parent_ids = Vec::new();
+        let _entries_guard = self.entries_lock.lock().unwrap();
+        let _guard = self.users_lock.lock().unwrap();
         let mut user_data = self.get_user(user_id)?;
-        user_data.entry_ids.push(entry_id);
-        self.overwrite_user_data(user_id, user_data)?;
+        for (entry_id, entry) in &mut entries {
+            entry.header_data.last_activity = timestamp;
+            let parent_id = entry.header_data.parent_id;
+            let parent = match changed.entry(parent_id) {
+                std::collections::hash_map::Entry::Occupied(parent) => parent.into_mut(),
+                std::collections::hash_map::Entry::Vacant(parent) => parent.insert(self.get_entry(parent_id)?),
+            };
+            parent.header_data.add_child(*entry_id)?;
+            if !parent_ids.contains(&parent_id) {
+                self.check_depth(parent_id, 0)?;
+                parent_ids.push(parent_id);
+            }
+            user_data.entry_ids.push(*entry_id);
+        }
 
-        self.write_entry(entry_id, entry)?;
-        Ok(())
+        for parent_id in parent_ids {
+            self.stage_activity(parent_id, timestamp, &mut changed)?;
+        }
+        self.apply_entries(entries, changed, Some((user_id, user_data)))
     }
 
-    fn edit_entry(&self, user_id: UserId, entry_id: EntryId, entry: Entry) -> Result<(), DataError> {
-        self.overwrite_entry(entry_id, entry)
+    /// replaces the entry with `entry` if it is a valid edit of the entry as it is under `entries_lock`, 
+    /// so an edit can't put back children added or moved away since the client read it
+    fn edit_entry(&self, user_id: UserId, entry_id: EntryId, mut entry: Entry) -> Result<(), DataError> {
+        let _guard = self.entries_lock.lock().unwrap();
+        let old_entry = self.get_entry(entry_id)?;
+        if entry.header_data.author_id != user_id || old_entry.header_data.author_id != user_id {
+            return Err(DataError::InsufficientPerms)
+        }
+        let diff = old_entry.diff(&entry);
+        if diff.changes_location() {
+            return Err(DataError::EdittedLocation)
+        }
+        // deleting has its own request, and there's nothing left of a deleted entry to edit
+        if old_entry.header_data.deleted {return Err(DataError::DoesNotExist)}
+        if diff.deleted.is_some() {return Err(DataError::InsufficientPerms)}
+        // pinning has its own request with its own perms
+        entry.header_data.pinned = old_entry.header_data.pinned;
+
+        let timestamp = Self::current_timestamp();
+        entry.header_data.last_activity = timestamp;
+        let parent_id = entry.header_data.parent_id;
+        let mut changed = HashMap::from([(entry_id, entry)]);
+        self.stage_activity(parent_id, timestamp, &mut changed)?;
+        self.apply_entries(Vec::new(), changed, None)
     }
 
-    /// checks if the user has read_perms to the *children* of the entry
-    fn has_read_perm(&self, user_id: UserId, entry_id: EntryId) -> Result<bool, DataError> {
-        let mut data_iter = self.get_entry_data_iter(entry_id)?;
-        let (mut header, mut entry_type) = HeaderData::from_data_iter(&mut data_iter)?;
-        let mut current_id = entry_id;
-        loop {
-            if entry_type == ACCESS_GROUP {
-                let EntryData::AccessGroup {
-                    name: _,
-                    write_perms: _,
-                    read_perms,
-                } = EntryData::from_data_iter(&mut data_iter, entry_type)? else {panic!("EntryData read as an AccessGroup should match an AccessGroup")};
-
-                if let Some(has_perm) = read_perms.contains(user_id) {
-                    return Ok(has_perm);
-                }
+    /// raises the last activity of `entry_id` and all its ancestors to `timestamp` in `changed`, 
+    /// to be written along with whatever the activity was, entries already in `changed` being updated there
+    fn stage_activity(&self, entry_id: EntryId, timestamp: u64, changed: &mut HashMap<EntryId, Entry>) -> Result<(), DataError> {
+        let changed = std::cell::RefCell::new(changed);
+        propagate_activity(
+            entry_id, 
+            timestamp, 
+            |current_id| match changed.borrow().get(&current_id) {
+                Some(entry) => Ok(entry.clone()),
+                None => self.get_entry(current_id),
+            }, 
+            |current_id, entry| {changed.borrow_mut().insert(current_id, entry); Ok(())},
+        )
+    }
+
+    /// writes the new entries, the changed entries, and the user as a single mutation, then indexes the entries
+    fn apply_entries(&self, new_entries: Vec<(EntryId, Entry)>, changed: HashMap<EntryId, Entry>, user: Option<(UserId, UserData)>) -> Result<(), DataError> {
+        let mut writes = Vec::with_capacity(new_entries.len() + changed.len() + 1);
+        // new entries first, so a colliding id fails before anything is written
+        for (entry_id, entry) in &new_entries {
+            writes.push(JournalWrite::NewEntry(*entry_id, Self::entry_data(*entry_id, entry)?));
+        }
+        for (entry_id, entry) in &changed {
+            writes.push(JournalWrite::Entry(*entry_id, Self::entry_data(*entry_id, entry)?));
+        }
+        if let Some((user_id, user_data)) = &user {
+            writes.push(JournalWrite::User(*user_id, user_data.into_data()?));
+        }
+
+        self.journal.apply(&*self.storage, &writes)?;
+        {
+            let mut entry_index = self.entry_index.write().unwrap();
+            for (entry_id, entry) in &new_entries {
+                entry_index.insert(*entry_id, entry);
             }
-            if *current_id == ROOT_ENTRY_ID {
-                break;
+            for (entry_id, entry) in &changed {
+                entry_index.insert(*entry_id, entry);
             }
-            current_id = header.parent_id;
-            data_iter = self.get_entry_data_iter(header.parent_id)?;
-            (header, entry_type) = HeaderData::from_data_iter(&mut data_iter)?;
-        } 
-        // FIXME: should be a specialized Err
-        Ok(false)
+        }
+        self.entry_ids.write().unwrap().extend(new_entries.iter().map(|(entry_id, _)| *entry_id));
+        Ok(())
+    }
+
+    fn current_timestamp() -> u64 {
+        std::time::SystemTime::now().duration_since(std::time::SystemTime::UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    /// moves the entry from its current parent's children to `new_parent_id`'s, 
+    /// checking for cycles and the depth under `entries_lock` so a concurrent move can't make either
+    fn move_entry(&self, entry_id: EntryId, new_parent_id: EntryId) -> Result<(), DataError> {
+        let _guard = self.entries_lock.lock().unwrap();
+        if has_ancestor(new_parent_id, entry_id, |id| self.entry_index.read().unwrap().parent_id(id))? {
+            return Err(DataError::CyclicMove)
+        }
+        let height = self.entry_index.read().unwrap().subtree_height(entry_id);
+        self.check_depth(new_parent_id, height)?;
+        let mut entry = self.get_entry(entry_id)?;
+        let old_parent_id = entry.header_data.parent_id;
+        if old_parent_id == new_parent_id {return Ok(())}
+        let mut old_parent = self.get_entry(old_parent_id)?;
+        let mut new_parent = self.get_entry(new_parent_id)?;
+        old_parent.header_data.children_ids.retain(|child_id| *child_id != entry_id);
+        new_parent.header_data.add_child(entry_id)?;
+        entry.header_data.parent_id = new_parent_id;
+
+        self.journal.apply(&*self.storage, &[
+            JournalWrite::Entry(old_parent_id, Self::entry_data(old_parent_id, &old_parent)?),
+            JournalWrite::Entry(new_parent_id, Self::entry_data(new_parent_id, &new_parent)?),
+            JournalWrite::Entry(entry_id, Self::entry_data(entry_id, &entry)?),
+        ])?;
+        let mut entry_index = self.entry_index.write().unwrap();
+        entry_index.insert(old_parent_id, &old_parent);
+        entry_index.insert(new_parent_id, &new_parent);
+        entry_index.insert(entry_id, &entry);
+        Ok(())
+    }
+
+    fn view_count(&self, entry_id: EntryId) -> Result<u64, DataError> {
+        Ok(ReadReceipts::from_data(&self.storage.read_receipts(entry_id)?)?.len() as u64)
+    }
+
+    /// records the user as having read the entry, giving the entry's view count after
+    fn mark_read(&self, user_id: UserId, entry_id: EntryId) -> Result<u64, DataError> {
+        let _guard = self.receipts_lock.lock().unwrap();
+        let mut receipts = ReadReceipts::from_data(&self.storage.read_receipts(entry_id)?)?;
+        if receipts.insert(user_id) {
+            self.storage.write_receipts(entry_id, &receipts.into_data()?)?;
+        }
+        Ok(receipts.len() as u64)
+    }
+
+    /// how many entries are below `root_id`, only reading the index, see `EntryIndex::subtree_count`
+    fn subtree_count(&self, root_id: EntryId) -> Result<u64, DataError> {
+        self.entry_index.read().unwrap().subtree_count(root_id, self.max_tree_depth)
+    }
+
+    /// the ids from just below the root down to `entry_id`, only reading the index, see `ancestor_path`
+    fn ancestor_path(&self, entry_id: EntryId) -> Result<Vec<EntryId>, DataError> {
+        let entry_index = self.entry_index.read().unwrap();
+        ancestor_path(entry_id, self.max_tree_depth, |current_id| entry_index.parent_id(current_id))
+    }
+
+    /// fails with `DepthExceeded` if putting an entry with `height` levels below it under `parent_id` 
+    /// would leave any of them deeper than `max_tree_depth`
+    fn check_depth(&self, parent_id: EntryId, height: usize) -> Result<(), DataError> {
+        if self.ancestor_path(parent_id)?.len() + 1 + height > self.max_tree_depth {return Err(DataError::DepthExceeded)}
+        Ok(())
+    }
+
+    /// checks if the user has the `perm_kind` perms to the *children* of the entry
+    fn has_access_perm(&self, user_id: UserId, entry_id: EntryId, perm_kind: PermKind) -> Result<bool, DataError> {
+        resolve_access(user_id, entry_id, perm_kind, self.default_access, self.max_tree_depth, |current_id| self.access_step(current_id))
+    }
+
+    /// the entries the user has the perms to, walking each shared ancestor once
+    fn filter_accessible(&self, user_id: UserId, perm_kind: PermKind, entry_ids: &[EntryId]) -> Result<Vec<EntryId>, DataError> {
+        filter_accessible(user_id, entry_ids, perm_kind, self.default_access, self.max_tree_depth, |current_id| self.access_step(current_id))
+    }
+
+    /// the entries the user can read, as `read_entry` would allow for entries they didn't write, skipping missing ones
+    fn readable_entries(&self, user_id: UserId, entry_ids: &[EntryId]) -> Result<Vec<EntryId>, DataError> {
+        let with_parents: Vec<(EntryId, EntryId)> = {
+            let entry_index = self.entry_index.read().unwrap();
+            entry_ids.iter().filter_map(|entry_id| Some((*entry_id, entry_index.parent_id(*entry_id).ok()?))).collect()
+        };
+        let parent_ids: Vec<EntryId> = with_parents.iter().map(|(_, parent_id)| *parent_id).collect();
+        let readable_parents: HashSet<EntryId> = self.filter_accessible(user_id, PermKind::Read, &parent_ids)?.into_iter().collect();
+        Ok(with_parents.into_iter().filter(|(_, parent_id)| readable_parents.contains(parent_id)).map(|(entry_id, _)| entry_id).collect())
     }
 
-    /// checks if the user has perms to the *children* of the entry
-    fn has_write_perm(&self, user_id: UserId, entry_id: EntryId) -> Result<bool, DataError> {
+    /// what `resolve_access` needs of an entry
+    fn access_step(&self, entry_id: EntryId) -> Result<(EntryId, Option<EntryData>), DataError> {
+        // only the access groups along the way need their files read
+        let (parent_id, entry_type) = {
+            let entry_index = self.entry_index.read().unwrap();
+            let indexed = entry_index.get(entry_id).ok_or(DataError::DoesNotExist)?;
+            (indexed.parent_id, indexed.entry_type)
+        };
+        if entry_type != ACCESS_GROUP {return Ok((parent_id, None))}
         let mut data_iter = self.get_entry_data_iter(entry_id)?;
-        let (mut header, mut entry_type) = HeaderData::from_data_iter(&mut data_iter)?;
-        let mut current_id = entry_id;
-        loop {
-            if entry_type == ACCESS_GROUP {
-                let EntryData::AccessGroup {
-                    name: _,
-                    write_perms,
-                    read_perms: _,
-                } = EntryData::from_data_iter(&mut data_iter, entry_type)? else {panic!("EntryData read as an AccessGroup should match an AccessGroup")};
-
-                if let Some(has_perm) = write_perms.contains(user_id) {
-                    return Ok(has_perm);
-                }
-            }
-            if *current_id == ROOT_ENTRY_ID {
-                break;
-            }
-            current_id = header.parent_id;
-            data_iter = self.get_entry_data_iter(header.parent_id)?;
-            (header, entry_type) = HeaderData::from_data_iter(&mut data_iter)?;
-        } 
-        // FIXME: should be a specialized Err
-        Ok(false)
+        let (header, entry_type) = HeaderData::from_data_iter(&mut data_iter)?;
+        let entry_data = EntryData::from_data_iter(&mut data_iter, entry_type, header.version)?;
+        Ok((parent_id, Some(entry_data)))
+    }
+
+    /// wraps a new connection in tls if it has been configured
+    fn wrap_stream(&self, stream: TcpStream) -> std::io::Result<BoardStream> {
+        #[cfg(feature = "tls")]
+        if let Some(tls_config) = &self.tls_config {
+            return BoardStream::accept_tls(stream, tls_config.clone());
+        }
+        Ok(BoardStream::Plain(stream))
     }
 
     fn add_user(&self, crypto_rng: impl OldCryptoRng + OldRngCore, new_user_id: UserId) -> Result<UserData, DataError> {
         let key = UserAeadKey::new_random(crypto_rng);
-        let mut path = PathBuf::from(self.file_dir.clone());
-        path.push(format!("users/{}", new_user_id));
         let data = UserData::new_empty(key);
-        Self::write_new(&path, &data.into_data()?);
+        self.storage.write_new_user(new_user_id, &data.into_data()?)?;
+        self.user_ids.write().unwrap().insert(new_user_id);
         Ok(data)
     }
 
-    /// spawns a command handler thread which handles requests generated by the server and clients_read
-    fn command_handler(&'static self, response_tx: mpsc::Sender<(u64, BoardResponse)>, handler_id: u64) -> mpsc::Sender<BoardRequest> {
+    /// a `command_handler` for each of the handler ids from 0 to `num_handlers`, in order
+    fn spawn_handlers(num_handlers: u64, response_tx: &mpsc::Sender<HandlerResponse>) -> Vec<mpsc::Sender<(&'static MessageBoard, BoardRequest)>> {
+        (0..num_handlers).map(|handler_id| Self::command_handler(response_tx.clone(), handler_id)).collect()
+    }
+
+    /// spawns a command handler thread which handles requests generated by the server and clients_read, 
+    /// each to the board it's sent with
+    fn command_handler(response_tx: mpsc::Sender<HandlerResponse>, handler_id: u64) -> mpsc::Sender<(&'static MessageBoard, BoardRequest)> {
         let (tx, rx) = mpsc::channel();
         std::thread::spawn(move || {
-            fn handle_request(board: &MessageBoard, rng: impl Rng, mut crypto_rng: impl OldCryptoRng + OldRngCore, request: BoardRequest) -> MaybeBoardResponse {
-                match request {
-                    BoardRequest::GetEntry { user_id, entry_id} => {
-                        info!("Request Type: GetEntry");
-                        let entry = board.get_entry(entry_id)?;
-                        if entry.header_data.author_id != user_id && !board.has_read_perm(user_id, entry.header_data.parent_id)? {
-                            return Err(DataError::InsufficientPerms.into())
-                        }
-                        Ok(BoardResponse::GetEntry(entry))
-                    }
-                    BoardRequest::AddEntry { user_id , entry} => {
-                        info!("Request Type: AddEntry");
-                        if !board.has_write_perm(user_id, entry.header_data.parent_id)? {
-                            return Err(DataError::InsufficientPerms.into())
-                        }
-                        let entry_id = MessageBoard::generate_unique_id(rng, &board.entry_ids.read().unwrap());
-                        board.add_entry(user_id, entry_id, entry)?;
-                        Ok(BoardResponse::AddEntry(entry_id))
-                    }
-                    BoardRequest::EditEntry { user_id, entry_id, entry } => {
-                        info!("Request Type: EditEntry");
-                        let old_entry = board.get_entry(entry_id)?;
-                        if entry.header_data.author_id != user_id || old_entry.header_data.author_id != user_id {
-                            return Err(DataError::InsufficientPerms)
-                        }
-                        if (entry.header_data.children_ids != old_entry.header_data.children_ids) | (entry.header_data.parent_id != old_entry.header_data.parent_id) {
-                            return Err(DataError::EdittedLocation)
-                        }
-                        board.edit_entry(user_id, entry_id, entry)?;
-                        Ok(BoardResponse::EditEntry)
-                    }
-                    BoardRequest::GetUser { user_id } => {
-                        info!("Request Type: GetUser");
-                        let user = board.get_user(user_id)?;
-                        Ok(BoardResponse::GetUser(user))
-                    }
-                    BoardRequest::AddUser => {
-                        info!("Request Type: AddUser");
-                        let user_id = MessageBoard::generate_unique_id(rng, &board.user_ids.read().unwrap()).into();
-                        let user = board.add_user(&mut crypto_rng, user_id)?;
-                        Ok(BoardResponse::AddUser{user_id, user_aead: user.aead})
-                    }
-                    BoardRequest::GetKemEk => {//should be handled by server
-                        return Err(internal_error!()); 
-                    }
-                }
-            }
-
             let mut rng = rand::rng();
             let mut crypto_rng = get_crypto_rng();
-            for request in rx {
-                let response = (
+            for (board, request) in rx {
+                let response = HandlerResponse {
                     handler_id,
-                    BoardResponse::encapsulate_error(handle_request(&self, &mut rng, &mut crypto_rng, request)),
-                );
+                    data: handle_request(board, &mut rng, &mut crypto_rng, request),
+                };
                 let _ = response_tx.send(response);
             }
         });
@@ -478,37 +984,261 @@ impl MessageBoard {
     }
 }
 
+/// answers a request from a client, `GetKemEk`, `ListBoards` and `Metrics` are answered by the server itself
+fn handle_request(board: &MessageBoard, rng: impl Rng, mut crypto_rng: impl OldCryptoRng + OldRngCore, request: BoardRequest) -> MaybeBoardResponse {
+    // past this, a guest is only held back by the read perms like any other user
+    if request.user_id() == Some(ANONYMOUS_USER_ID.into()) && (!board.allow_guests || !request.is_read_only()) {
+        return Err(DataError::InsufficientPerms)
+    }
+    match request {
+        BoardRequest::GetEntry { user_id, entry_id} => {
+            info!("Request Type: GetEntry");
+            let entry = board.read_entry(user_id, entry_id)?;
+            Ok(BoardResponse::GetEntry { entry, view_count: board.view_count(entry_id)? })
+        }
+        BoardRequest::AddEntry { user_id , mut entry} => {
+            info!("Request Type: AddEntry");
+            entry.attach_to_target();
+            entry.validate_new(user_id)?;
+            entry.entry_data.check_size_limits(board.max_message_size)?;
+            if !board.has_access_perm(user_id, entry.header_data.parent_id, PermKind::Write)? {
+                return Err(DataError::InsufficientPerms.into())
+            }
+            let entry_id = MessageBoard::generate_unique_id(rng, &board.entry_ids.read().unwrap());
+            board.add_entry(user_id, entry_id, entry)?;
+            Ok(BoardResponse::AddEntry(entry_id))
+        }
+        BoardRequest::EditEntry { user_id, entry_id, mut entry } => {
+            info!("Request Type: EditEntry");
+            entry.entry_data.check_size_limits(board.max_message_size)?;
+            // a reaction retargeted elsewhere would be a move
+            entry.attach_to_target();
+            board.edit_entry(user_id, entry_id, entry)?;
+            Ok(BoardResponse::EditEntry)
+        }
+        BoardRequest::AddEntries { user_id, mut entries } => {
+            info!("Request Type: AddEntries");
+            // the batch is written as a single mutation, so a rejected batch leaves no partial writes
+            for entry in &mut entries {
+                entry.attach_to_target();
+                entry.validate_new(user_id)?;
+                entry.entry_data.check_size_limits(board.max_message_size)?;
+                if !board.has_access_perm(user_id, entry.header_data.parent_id, PermKind::Write)? {
+                    return Err(DataError::InsufficientPerms)
+                }
+            }
+            let mut rng = rng;
+            let mut entry_ids: Vec<EntryId> = Vec::with_capacity(entries.len());
+            {
+                let used_ids = board.entry_ids.read().unwrap();
+                while entry_ids.len() < entries.len() {
+                    let entry_id = MessageBoard::generate_unique_id(&mut rng, &used_ids);
+                    if !entry_ids.contains(&entry_id) {entry_ids.push(entry_id)}
+                }
+            }
+            board.add_entries(user_id, entry_ids.iter().copied().zip(entries).collect())?;
+            Ok(BoardResponse::AddEntries(entry_ids))
+        }
+        BoardRequest::MoveEntry { user_id, entry_id, new_parent_id } => {
+            info!("Request Type: MoveEntry");
+            if *entry_id == ROOT_ENTRY_ID {return Err(DataError::MalformedRoot)}
+            // reactions stay under what they react to
+            if board.entry_index.read().unwrap().get(entry_id).is_some_and(|indexed| indexed.entry_type == REACTION) {
+                return Err(DataError::EdittedLocation)
+            }
+            let old_parent_id = board.entry_index.read().unwrap().parent_id(entry_id)?;
+            if !board.has_access_perm(user_id, old_parent_id, PermKind::Write)? || !board.has_access_perm(user_id, new_parent_id, PermKind::Write)? {
+                return Err(DataError::InsufficientPerms)
+            }
+            board.move_entry(entry_id, new_parent_id)?;
+            Ok(BoardResponse::MoveEntry)
+        }
+        BoardRequest::DeleteEntry { user_id, entry_id } => {
+            info!("Request Type: DeleteEntry");
+            if *entry_id == ROOT_ENTRY_ID {return Err(DataError::MalformedRoot)}
+            board.update_entry(entry_id, |entry| {
+                if entry.header_data.author_id != user_id {
+                    return Err(DataError::InsufficientPerms)
+                }
+                // the entry stays where it is so its children stay reachable, deleting it again changes nothing
+                entry.tombstone();
+                Ok(())
+            })?;
+            Ok(BoardResponse::DeleteEntry)
+        }
+        BoardRequest::SetPinned { user_id, entry_id, pinned } => {
+            info!("Request Type: SetPinned");
+            if *entry_id == ROOT_ENTRY_ID {return Err(DataError::MalformedRoot)}
+            board.update_entry(entry_id, |entry| {
+                if !board.has_access_perm(user_id, entry.header_data.parent_id, PermKind::Write)? {
+                    return Err(DataError::InsufficientPerms)
+                }
+                entry.header_data.pinned = pinned;
+                Ok(())
+            })?;
+            Ok(BoardResponse::SetPinned)
+        }
+        BoardRequest::GetEntries { user_id, entry_ids } => {
+            info!("Request Type: GetEntries");
+            if entry_ids.len() > MAX_REQUESTED_ENTRIES {return Err(DataError::PayloadTooLarge { max: MAX_REQUESTED_ENTRIES })}
+            let entries = entry_ids.into_iter().map(|entry_id| board.read_entry(user_id, entry_id)).collect();
+            Ok(BoardResponse::GetEntries(entries))
+        }
+        BoardRequest::ChildrenSince { user_id, entry_id, since_ts } => {
+            info!("Request Type: ChildrenSince");
+            let children_ids = board.get_entry(entry_id)?.header_data.children_ids;
+            let can_read = board.has_access_perm(user_id, entry_id, PermKind::Read)?;
+            let mut entry_ids = Vec::new();
+            for child_id in children_ids {
+                // one missing or unreadable child shouldn't hide the activity of the rest
+                let child = match board.get_entry(child_id) {
+                    Ok(child) => child,
+                    Err(e) => {
+                        warn!("Skipping child {} of {} for ChildrenSince: {:?}", child_id, entry_id, e);
+                        continue;
+                    }
+                };
+                if child.header_data.last_activity > since_ts && (can_read || child.header_data.author_id == user_id) {
+                    entry_ids.push(child_id);
+                }
+            }
+            Ok(BoardResponse::ChildrenSince(entry_ids))
+        }
+        BoardRequest::FilterAccessible { user_id, perm_kind, entry_ids } => {
+            info!("Request Type: FilterAccessible");
+            if entry_ids.len() > MAX_REQUESTED_ENTRIES {return Err(DataError::PayloadTooLarge { max: MAX_REQUESTED_ENTRIES })}
+            Ok(BoardResponse::FilterAccessible(board.filter_accessible(user_id, perm_kind, &entry_ids)?))
+        }
+        BoardRequest::MarkRead { user_id, entry_id } => {
+            info!("Request Type: MarkRead");
+            board.read_entry(user_id, entry_id)?;
+            Ok(BoardResponse::MarkRead(board.mark_read(user_id, entry_id)?))
+        }
+        BoardRequest::SubtreeCount { user_id, entry_id } => {
+            info!("Request Type: SubtreeCount");
+            // the same access as getting the entry itself, the entries below are counted whether or not they can be read
+            board.read_entry(user_id, entry_id)?;
+            Ok(BoardResponse::SubtreeCount(board.subtree_count(entry_id)?))
+        }
+        BoardRequest::GetPermsSummary { user_id, entry_id } => {
+            info!("Request Type: GetPermsSummary");
+            let entry = board.read_entry(user_id, entry_id)?;
+            let (Some(write_perms), Some(read_perms)) = (entry.entry_data.get_perms(PermKind::Write), entry.entry_data.get_perms(PermKind::Read)) else {
+                return Err(DataError::InvalidStructure)
+            };
+            Ok(BoardResponse::GetPermsSummary { write: PermsSummary::new(write_perms)?, read: PermsSummary::new(read_perms)? })
+        }
+        BoardRequest::GetAncestors { user_id, entry_id } => {
+            info!("Request Type: GetAncestors");
+            // the same access as getting the entry itself
+            let parent_id = board.entry_index.read().unwrap().parent_id(entry_id)?;
+            if !board.has_access_perm(user_id, parent_id, PermKind::Read)? && board.get_entry(entry_id)?.header_data.author_id != user_id {
+                return Err(DataError::InsufficientPerms)
+            }
+            Ok(BoardResponse::GetAncestors(board.ancestor_path(entry_id)?))
+        }
+        BoardRequest::GetUser { requester_id, user_id } => {
+            info!("Request Type: GetUser");
+            if requester_id == user_id {return Ok(BoardResponse::GetUser(board.get_user(user_id)?))}
+            if board.user_privacy == UserPrivacy::AdminOnly && board.admin_id != Some(requester_id) {
+                return Err(DataError::InsufficientPerms)
+            }
+            let mut user = board.get_user(user_id)?;
+            if board.user_privacy == UserPrivacy::Filtered {
+                user.entry_ids = board.readable_entries(requester_id, &user.entry_ids)?;
+            }
+            user.aead = UserAeadKey::new_random(&mut crypto_rng);
+            Ok(BoardResponse::GetUser(user))
+        }
+        BoardRequest::AddUser => {
+            info!("Request Type: AddUser");
+            let user_id = MessageBoard::generate_unique_id(rng, &board.user_ids.read().unwrap()).into();
+            let user = board.add_user(&mut crypto_rng, user_id)?;
+            Ok(BoardResponse::AddUser{user_id, user_aead: user.aead})
+        }
+        BoardRequest::ListUsers { admin_id, start } => {
+            info!("Request Type: ListUsers");
+            BoardResponse::list_users(admin_id, board.admin_id, board.user_ids.read().unwrap().iter().copied(), start)
+        }
+        BoardRequest::SetDisplayName { user_id, display_name } => {
+            info!("Request Type: SetDisplayName");
+            if display_name.as_ref().is_some_and(|name| name.len() > MAX_DISPLAY_NAME_SIZE) {
+                return Err(DataError::PayloadTooLarge { max: MAX_DISPLAY_NAME_SIZE })
+            }
+            board.update_user(user_id, |user| user.display_name = display_name)?;
+            Ok(BoardResponse::SetDisplayName)
+        }
+        BoardRequest::GetKemEk | BoardRequest::ListBoards | BoardRequest::Metrics { .. } | BoardRequest::NegotiateVersion { .. } => {//should be handled by server
+            return Err(internal_error!()); 
+        }
+        BoardRequest::Ping => {
+            Ok(BoardResponse::Pong)
+        }
+        BoardRequest::GetRoot => {
+            info!("Request Type: GetRoot");
+            Ok(BoardResponse::GetRoot(ROOT_ENTRY_ID.into()))
+        }
+        BoardRequest::Status => {
+            info!("Request Type: Status");
+            Ok(BoardResponse::Status {
+                num_users: board.user_ids.read().unwrap().len() as u64,
+                num_entries: board.entry_ids.read().unwrap().len() as u64,
+                uptime_secs: board.start_time.elapsed().as_secs(),
+                max_message_size: board.max_message_size as u64,
+            })
+        }
+    }
+}
+
 struct Server {
     board: MessageBoard,
-    client_id_map: RwLock<HashMap<u64, TcpStream>>,
+    named_boards: HashMap<String, MessageBoard>, // the main board's `named_boards`
+    client_id_map: RwLock<HashMap<u64, BoardStream>>,
     next_client_id: std::cell::Cell<u64>,
     kem_ek: EncapsulationKey,
     kem_dk: DecapsulationKey,
+    metrics: Metrics,
 }
 
 impl Server {
     fn new(board: MessageBoard) -> Self {
         let storage = board.read_storage_file();
+        let named_boards = board.named_boards.iter().map(|name| {
+            let named_board = board.named_board(name).unwrap_or_else(|e| panic!("Failed to set up the \"{}\" board: {}", name, e));
+            (name.clone(), named_board)
+        }).collect();
         Server { 
             board, 
+            named_boards,
             client_id_map: RwLock::new(HashMap::new()),
             next_client_id: std::cell::Cell::new(0),
             kem_ek: storage.kem_ek,
             kem_dk: storage.kem_dk,
+            metrics: Metrics::new(),
         }
     }
 
+    /// the names of every board, sorted, for `ListBoards`
+    fn board_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.named_boards.keys().cloned().collect();
+        names.push(self.board.name.clone());
+        names.sort();
+        names
+    }
+
     fn mainloop(&'static  self) {
         let (incomind_queue_tx, incoming_queue_rx) = mpsc::channel();
         let (outgoing_queue_tx, outgoing_queue_rx) = mpsc::channel();
         let decode_error_queue_tx = outgoing_queue_tx.clone();
 
-        let Server { board, client_id_map, kem_dk, kem_ek, ..} = self;
+        let Server { board, named_boards, client_id_map, kem_dk, kem_ek, metrics, ..} = self;
+        // the board a request names, see `BoardRequest::secure_board_name`
+        let find_board = move |name: &str| if name == board.name {Some(board)} else {named_boards.get(name)};
         //let client_id_map: &_ = client_id_map;
 
         // incoming
         std::thread::spawn(move || {
-            let mut clients_read = Vec::new();
+            let mut clients_read: Vec<(u64, BoardStream, FrameReader)> = Vec::new();
             let mut read_id_set = HashSet::new();
             let mut to_remove = Vec::new();
             let timer = Instant::now();
@@ -519,25 +1249,37 @@ impl Server {
                 if ideal_iter_start_time > elapsed {std::thread::sleep(ideal_iter_start_time - elapsed)}
                 iter_start_time = timer.elapsed();
 
-                for idx in 0..clients_read.len() {
-                    let (id,  client): &mut (u64, TcpStream) = &mut clients_read[idx];
-
-                    let mut request_size = [0u8; 8];
-                    let Ok(bytes_read) = client.peek(&mut request_size) else {client_id_map.write().unwrap().remove(id); continue;}; // just assuming disconnect
-                    if bytes_read < 8 {continue;}
-                    let request_size = u64::from_le_bytes(request_size) as usize;
-                    let mut request = vec![0u8; request_size + 8];
-                    if client.read_exact(&mut request).is_err() {continue}; // should send some error
-                    info!("Received {} byte message", request_size);
-                    match BoardRequest::secure_from_data(kem_dk, |user_id| {
-                        board.get_user_aead(user_id).map_err(|e| {info!("Failed to retrieve User Aead for {}: {:?}", user_id, e); e}).ok()
-                    },&request[8..]) {
-                        Ok((re_encyption_data, request)) => {
-                            incomind_queue_tx.send((*id, re_encyption_data, request)).expect("Queue Rx should be alive");
-                        }
-                        Err(e) => {
-                            info!("Failed to Parse Request: {:?}", e); 
-                            decode_error_queue_tx.send((*id, ReEncryptionData::Exposed, BoardResponse::Error(e))).expect("Queue Rx should be alive");
+                for (id, client, frame_reader) in clients_read.iter_mut() {
+                    if to_remove.contains(id) {continue;}
+                    loop {
+                        let (request_id, request) = match frame_reader.read_frame(client) {
+                            Ok(Some(frame)) => frame,
+                            Ok(None) => break, // would block, the rest of the frame is read in a later iter
+                            // an idle client just has nothing to read (`Ok(None)`), only a closed or broken socket drops it
+                            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                                info!("Client {} disconnected", id);
+                                to_remove.push(*id);
+                                break;
+                            }
+                            Err(e) => {
+                                warn!("Dropping client {} after a socket error: {}", id, e);
+                                to_remove.push(*id);
+                                break;
+                            }
+                        };
+                        debug!("Received {} byte message from client {}", request.len(), id);
+                        // the keys to decrypt it with are the named board's
+                        let named_board = BoardRequest::secure_board_name(&request).and_then(|name| find_board(&name).ok_or(DataError::DoesNotExist));
+                        match named_board.and_then(|named_board| BoardRequest::secure_flagged_from_data(kem_dk, |user_id| {
+                            named_board.get_user_aead(user_id).map_err(|e| {info!("Failed to retrieve User Aead for {}: {:?}", user_id, e); e}).ok()
+                        }, &request).map(|(re_encyption_data, flags, request)| (named_board, re_encyption_data, flags, request))) {
+                            Ok((named_board, re_encyption_data, flags, request)) => {
+                                incomind_queue_tx.send((*id, request_id, flags, named_board, re_encyption_data, request)).expect("Queue Rx should be alive");
+                            }
+                            Err(e) => {
+                                warn!("Failed to parse request {} from client {}: {:?}", request_id, id, e);
+                                decode_error_queue_tx.send((*id, request_id, 0, board, ReEncryptionData::Exposed, Err(e))).expect("Queue Rx should be alive");
+                            }
                         }
                     }
                 }
@@ -556,8 +1298,10 @@ impl Server {
                     for (id, client) in global_id_map.iter() {
                         if !read_id_set.contains(id)  {
                             // if this fails, it will be reattempted next iter
-                            if let Ok(client) = client.try_clone() {
-                                clients_read.push((*id, client));
+                            // note: this also makes the shared socket non-blocking for the outgoing thread, see `write_frame`
+                            if let Ok(client) = client.try_clone().and_then(|client| client.set_nonblocking(true).map(|_| client)) {
+                                // only the main board's limit is known before the request is read, the named boards share it
+                                clients_read.push((*id, client, FrameReader::with_max_frame_size(board.max_message_size + REQUEST_FRAME_OVERHEAD)));
                                 read_id_set.insert(*id);
                             }
                         }
@@ -566,28 +1310,36 @@ impl Server {
             }
         });
         // distribution to and from handlers 
+        let board_names = self.board_names();
         std::thread::spawn(move || {
             let (response_tx, response_rx) = mpsc::channel();
-            let num_threads = 4;
-            let mut handler_threads = Vec::new();
+            let num_threads = board.num_handlers;
+            let mut handler_threads = MessageBoard::spawn_handlers(num_threads, &response_tx);
             let timer = Instant::now();
             let mut iter_start_time = Duration::new(0, 0);
 
-            for handler_id in 0..num_threads {
-                handler_threads.push(board.command_handler(response_tx.clone(), handler_id));
-            }
             let mut handler_clients = Vec::new();
             for _ in 0..num_threads {
                 handler_clients.push(None);
             }
             let mut num_active = 0;
+            // requests waiting on a handler to become available, dispatched fairly between clients
+            let mut pending_requests = FairQueue::new();
+            let log_response = |client_id: u64, request_id: u64, response: &MaybeBoardResponse| {
+                if let Err(e @ DataError::InternalError { .. }) = response {
+                    error!("Internal error handling request {} from client {}: {}", request_id, client_id, e);
+                }
+            };
+            let mut rate_limiters: HashMap<u64, RateLimiter> = HashMap::new();
             
             loop {
                 if num_active == num_threads {
                     // note: blocking
-                    let (handler_id, data) = response_rx.recv().expect("command_handler threads should keep response_tx alive");
-                    let (client_id, re_encryption_data) = handler_clients[handler_id as usize].take().expect("Handlers should only respond for a registered client");
-                    outgoing_queue_tx.send((client_id, re_encryption_data, data)).expect("The Outgoing Receiver should never drop");
+                    let HandlerResponse { handler_id, data } = response_rx.recv().expect("command_handler threads should keep response_tx alive");
+                    let (client_id, request_id, flags, named_board, re_encryption_data) = handler_clients[handler_id as usize].take().expect("Handlers should only respond for a registered client");
+                    pending_requests.finish(client_id);
+                    log_response(client_id, request_id, &data);
+                    outgoing_queue_tx.send((client_id, request_id, flags, named_board, re_encryption_data, data)).expect("The Outgoing Receiver should never drop");
                     num_active -= 1;
                 } else if num_active < num_threads {
                     let ideal_iter_start_time = iter_start_time + SERVER_MAINLOOP_PERIOD;
@@ -595,35 +1347,67 @@ impl Server {
                     if ideal_iter_start_time > elapsed {std::thread::sleep(ideal_iter_start_time - elapsed)}
                     iter_start_time = timer.elapsed();
 
-                    if let Ok((client_id, re_encryption_data, request)) = incoming_queue_rx.try_recv() {
-                        if let BoardRequest::GetKemEk = request {
+                    let now = Instant::now();
+                    for (client_id, request_id, flags, named_board, re_encryption_data, request) in incoming_queue_rx.try_iter() {
+                        metrics.record_request(&request);
+                        let rate_limiter = rate_limiters.entry(client_id).or_insert_with(|| RateLimiter::new(board.rate_limit, board.rate_burst, now));
+                        if !rate_limiter.try_acquire(now) {
+                            warn!("Client {} is over the rate limit, dropping request {} ({:#04x})", client_id, request_id, request.get_discriminant());
+                            outgoing_queue_tx.send((client_id, request_id, flags, named_board, re_encryption_data, Err(DataError::RateLimited))).expect("The Outgoing Receiver should never drop");
+                        } else if let BoardRequest::Ping = request {
+                            debug!("Ping from client {}", client_id);
+                            outgoing_queue_tx.send((client_id, request_id, flags, named_board, re_encryption_data, Ok(BoardResponse::Pong))).expect("The Outgoing Receiver should never drop");
+                        } else if let BoardRequest::NegotiateVersion { min_version, max_version } = request {
+                            info!("Request Type: NegotiateVersion");
+                            let response = negotiate_version(min_version, max_version).map(BoardResponse::NegotiateVersion);
+                            if response.is_err() {
+                                warn!("Client {} supports no shared protocol version ({} - {})", client_id, min_version, max_version);
+                            }
+                            outgoing_queue_tx.send((client_id, request_id, flags, named_board, re_encryption_data, response)).expect("The Outgoing Receiver should never drop");
+                        } else if let BoardRequest::GetKemEk = request {
                             info!("Request Type: GetKemEk");
-                            outgoing_queue_tx.send((client_id, re_encryption_data, BoardResponse::GetKemEk(kem_ek.clone()))).expect("The Outgoing Receiver should never drop");
+                            outgoing_queue_tx.send((client_id, request_id, flags, named_board, re_encryption_data, Ok(BoardResponse::GetKemEk(kem_ek.clone())))).expect("The Outgoing Receiver should never drop");
+                        } else if let BoardRequest::ListBoards = request {
+                            info!("Request Type: ListBoards");
+                            outgoing_queue_tx.send((client_id, request_id, flags, named_board, re_encryption_data, Ok(BoardResponse::ListBoards(board_names.clone())))).expect("The Outgoing Receiver should never drop");
+                        } else if let BoardRequest::Metrics { admin_id } = request {
+                            info!("Request Type: Metrics");
+                            let response = if named_board.admin_id == Some(admin_id) {
+                                Ok(BoardResponse::Metrics(metrics.render()))
+                            } else {
+                                Err(DataError::InsufficientPerms)
+                            };
+                            outgoing_queue_tx.send((client_id, request_id, flags, named_board, re_encryption_data, response)).expect("The Outgoing Receiver should never drop");
                         } else {
-                            let mut sent_to_handler = false;
-                            for (client, handler) in handler_clients.iter_mut().zip(&mut handler_threads) {
-                                if client.is_some() {continue;}
-                                
-                                *client = Some((client_id, re_encryption_data));
-                                handler.send(request).expect("The Command Handler should never drop");
-                                sent_to_handler = true;
-                                num_active += 1;
-                                break;
-                            }
-                            if !sent_to_handler {
-                                error!("dropped a request (no available handler)");
-                                num_active = num_threads; //evidently, they are all active
-                            }
+                            pending_requests.push(client_id, (request_id, flags, named_board, re_encryption_data, request));
                         }
                     }
-                    if let Ok((handler_id, data)) = response_rx.try_recv() {
-                        let (client_id, re_encryption_data) = handler_clients[handler_id as usize].take().expect("Handlers should only respond for a registered client");
-                        outgoing_queue_tx.send((client_id, re_encryption_data, data)).expect("The Outgoing Receiver should never drop");
+                    // no one is waiting on the requests of disconnected clients
+                    if let Ok(global_id_map) = client_id_map.try_read() {
+                        pending_requests.retain_clients(|client_id| global_id_map.contains_key(&client_id));
+                        rate_limiters.retain(|client_id, _| global_id_map.contains_key(client_id));
+                        metrics.set_active_clients(global_id_map.len());
+                    }
+                    for (client, handler) in handler_clients.iter_mut().zip(&mut handler_threads) {
+                        if client.is_some() {continue;}
+                        let Some((client_id, (request_id, flags, named_board, re_encryption_data, request))) = pending_requests.pop() else {break;};
+
+                        debug!("Dispatching request {} ({:#04x}) from client {} to the \"{}\" board", request_id, request.get_discriminant(), client_id, named_board.name);
+                        *client = Some((client_id, request_id, flags, named_board, re_encryption_data));
+                        handler.send((named_board, request)).expect("The Command Handler should never drop");
+                        num_active += 1;
+                    }
+                    metrics.set_queue_depth(pending_requests.len());
+                    if let Ok(HandlerResponse { handler_id, data }) = response_rx.try_recv() {
+                        let (client_id, request_id, flags, named_board, re_encryption_data) = handler_clients[handler_id as usize].take().expect("Handlers should only respond for a registered client");
+                        pending_requests.finish(client_id);
+                        log_response(client_id, request_id, &data);
+                        outgoing_queue_tx.send((client_id, request_id, flags, named_board, re_encryption_data, data)).expect("The Outgoing Receiver should never drop");
                         num_active -= 1;
                     }
                 } else if num_active > num_threads {
                     warn!("More active handlers than threads for handlers, attempting recovery");
-                    num_active = 4;
+                    num_active = num_threads;
                 } else {
                     warn!("Less than 0 active handlers, attempting recovery");
                     num_active = 0;
@@ -632,18 +1416,37 @@ impl Server {
         });
         //outgoing
         std::thread::spawn(move || {
-            fn send_reponse(board: &MessageBoard, crypto_rng: impl OldCryptoRng + OldRngCore, re_encryption_data: ReEncryptionData, message: BoardResponse, client: &mut TcpStream) {
-                let message = message.secure_into_data(crypto_rng, re_encryption_data, |user_id| {
-                    board.get_user_aead(user_id).ok()
-                }).unwrap_or_else(|_| {
-                    error!("Failed to encode server response"); BoardResponse::Error(internal_error!()).into_data().unwrap()
-                });
-                info!("Sending {} byte message", message.len());
-                let _ = client.write_all(&(message.len() as u64).to_le_bytes());
-                let _ = client.write_all(&message); 
+            fn send_reponse(board: &MessageBoard, crypto_rng: impl OldCryptoRng + OldRngCore, request_id: u64, flags: u8, re_encryption_data: ReEncryptionData, message: MaybeBoardResponse, client: &mut BoardStream) {
+                // the client still expects a secure response, exposed since the original encryption failed
+                fn encode_failed(crypto_rng: impl OldCryptoRng + OldRngCore) -> Vec<u8> {
+                    error!("Failed to encode server response"); 
+                    BoardResponse::Error(internal_error!()).secure_into_data(crypto_rng, ReEncryptionData::Exposed, false, |_| None::<&mut UserAeadKey>).unwrap()
+                }
+
+                let mut crypto_rng = crypto_rng;
+                let compress = flags & COMPRESSED_RESPONSE_FLAG != 0;
+                let mut message = BoardResponse::encapsulate_error(message);
+                if flags & SUMMARIZED_PERMS_FLAG != 0 {message.summarize_perms()}
+                // small responses gain nothing from chunking, so they stay a single frame
+                let result = if flags & CHUNKED_RESPONSE_FLAG != 0 && message.size_hint() > RESPONSE_CHUNK_SIZE {
+                    debug!("Streaming a message of about {} bytes", message.size_hint());
+                    match BoardResponse::secure_stream_head(&mut crypto_rng, re_encryption_data, |user_id| board.get_user_aead(user_id).ok()) {
+                        Ok((head, key)) => message.write_streamed(client, request_id, &head, key, compress),
+                        Err(_) => write_frame(client, request_id, &encode_failed(&mut crypto_rng)).map_err(DataError::from),
+                    }
+                } else {
+                    let message = message.secure_into_data(&mut crypto_rng, re_encryption_data, compress, |user_id| {
+                        board.get_user_aead(user_id).ok()
+                    }).unwrap_or_else(|_| encode_failed(&mut crypto_rng));
+                    debug!("Sending {} byte message", message.len());
+                    write_frame(client, request_id, &message).map_err(DataError::from)
+                };
+                if let Err(e) = result {
+                    warn!("Failed to send response to request {}: {}", request_id, e);
+                }
             }
 
-            let mut clients_write: HashMap<u64, TcpStream> = HashMap::new();
+            let mut clients_write: HashMap<u64, BoardStream> = HashMap::new();
             let mut unresolved_messages = Vec::new();
             let timer = Instant::now();
             let mut iter_start_time = Duration::new(0, 0);
@@ -656,9 +1459,10 @@ impl Server {
                 if ideal_iter_start_time > elapsed {std::thread::sleep(ideal_iter_start_time - elapsed)}
                 iter_start_time = timer.elapsed();
 
-                for (id, re_encryption_data, message) in outgoing_queue_rx.try_iter() {
-                    let Some(client) = clients_write.get_mut(&id) else {unresolved_messages.push((id, re_encryption_data, message)); continue;};
-                    send_reponse(board, &mut crypto_rng, re_encryption_data, message, client);
+                for (id, request_id, flags, named_board, re_encryption_data, message) in outgoing_queue_rx.try_iter() {
+                    if let Err(e) = &message {metrics.record_error(e)}
+                    let Some(client) = clients_write.get_mut(&id) else {unresolved_messages.push((id, request_id, flags, named_board, re_encryption_data, message)); continue;};
+                    send_reponse(named_board, &mut crypto_rng, request_id, flags, re_encryption_data, message, client);
                 }
 
                 if let Ok(global_id_map) = client_id_map.try_read() {               
@@ -674,21 +1478,38 @@ impl Server {
                         }
                     }
                     drop(global_id_map); // getting rid of the guard
-                    for (id, re_encryption_data, message) in unresolved_messages.drain(..) {
-                        let Some(client) = clients_write.get_mut(&id) else {info!("client for id not found, dropping unresolved message"); continue;};
-                        send_reponse(board, &mut crypto_rng, re_encryption_data, message, client);
+                    for (id, request_id, flags, named_board, re_encryption_data, message) in unresolved_messages.drain(..) {
+                        let Some(client) = clients_write.get_mut(&id) else {warn!("Client {} not found, dropping the response to request {}", id, request_id); continue;};
+                        send_reponse(named_board, &mut crypto_rng, request_id, flags, re_encryption_data, message, client);
                     }
                 }
             }
         });
     }
 
-    fn add_client(&self, client: TcpStream) {
+    /// sets up a newly accepted connection as a client
+    fn accept(&self, stream: TcpStream) {
+        let peer = stream.peer_addr().map_or_else(|_| String::from("unknown"), |addr| addr.to_string());
+        if let Err(e) = stream.set_nodelay(self.board.nodelay) {
+            warn!("Failed to set nodelay for {}: {}", peer, e);
+        }
+        match self.board.wrap_stream(stream) {
+            Ok(stream) => {
+                let client_id = self.add_client(stream);
+                info!("Connection received from {}, client {}", peer, client_id);
+            }
+            Err(e) => warn!("Failed to set up the connection from {}: {}", peer, e),
+        }
+    }
+
+    /// gives the new client's id
+    fn add_client(&self, client: BoardStream) -> u64 {
         let mut client_id_map = self.client_id_map.write().expect("The RwLock shouldnt be poisoned");
         let mut next_client_id = self.next_client_id.get();
         while client_id_map.contains_key(&next_client_id) {next_client_id += 1;}
         client_id_map.insert(next_client_id, client);
         self.next_client_id.set(next_client_id +1);
+        next_client_id
     }
 }
 
@@ -696,17 +1517,1088 @@ fn main() {
     env_logger::init();
 
     let board = MessageBoard::new();
-    let listener = TcpListener::bind((&board.address as &str, PORT)).unwrap();
+    let listener = TcpListener::bind(board.address).unwrap_or_else(|e| panic!("Failed to listen on {}: {}", board.address, e));
+    // the bound address, which differs from the configured one for port 0
+    info!("Listening on {}", listener.local_addr().map_or(board.address, |address| address));
 
     let server = Box::leak(Box::new( Server::new(board)));
     server.mainloop();
 
     for stream in listener.incoming() {
-        if let Ok(stream) = stream {
-            info!("Connection recieved");
-            server.add_client(stream);
-        } else {
-            warn!("Connection error");
+        match stream {
+            Ok(stream) => server.accept(stream),
+            Err(e) => warn!("Connection error: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use message_board::cryptography::CryptoRng;
+
+    /// a bare client speaking the protocol directly over a `TcpStream`
+    struct LoopbackClient {
+        stream: TcpStream,
+        frame_reader: FrameReader,
+        keys: PublicKeySet,
+        crypto_rng: CryptoRng,
+        next_request_id: u64,
+        board: String, // that the requests are to
+    }
+
+    impl LoopbackClient {
+        /// connects to `server` through `listener`, fetching the board's kem key
+        fn connect(server: &Server, listener: &TcpListener) -> Self {
+            Self::connect_to(server, listener, MAIN_BOARD)
+        }
+
+        /// as `connect`, sending every request to `board`
+        fn connect_to(server: &Server, listener: &TcpListener, board: &str) -> Self {
+            let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+            stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+            server.accept(listener.accept().unwrap().0);
+            let mut client = LoopbackClient {
+                stream,
+                frame_reader: FrameReader::new(),
+                keys: PublicKeySet::new(None, None),
+                crypto_rng: get_crypto_rng(),
+                next_request_id: 0,
+                board: String::from(board),
+            };
+            let BoardResponse::GetKemEk(kem_ek) = client.request(BoardRequest::GetKemEk) else {panic!("No kem key from the board")};
+            client.keys.kem = Some(kem_ek);
+            client
+        }
+
+        fn request(&mut self, request: BoardRequest) -> BoardResponse {
+            let request_id = self.send(request);
+            let (response_id, response) = self.receive();
+            assert_eq!(response_id, request_id, "Response to the wrong request");
+            response
+        }
+
+        /// sends the request without waiting on its response, giving its request id
+        fn send(&mut self, request: BoardRequest) -> u64 {
+            let request_id = self.next_request_id;
+            self.next_request_id += 1;
+            let data = request.secure_into_data_to(&self.board, PROTOCOL_VERSION, &mut self.crypto_rng, &mut self.keys).unwrap();
+            write_frame(&mut self.stream, request_id, &data).unwrap();
+            request_id
+        }
+
+        /// the next response from the board and the id of the request it's to
+        fn receive(&mut self) -> (u64, BoardResponse) {
+            let (response_id, response) = self.frame_reader.read_frame(&mut self.stream).unwrap().expect("Timed out waiting on the board");
+            (response_id, BoardResponse::secure_from_data(&response, &mut self.keys).unwrap())
+        }
+
+        fn add_user(&mut self) -> UserId {
+            let BoardResponse::AddUser { user_id, user_aead } = self.request(BoardRequest::AddUser) else {panic!("Failed to add a user")};
+            self.keys.user_aead = Some(user_aead);
+            user_id
+        }
+    }
+
+    #[test]
+    fn loopback_round_trip() {
+        loopback_flows(|dir| Box::new(FileStorage::new(dir.into())));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_loopback_round_trip() {
+        loopback_flows(|dir| Box::new(SqliteStorage::new(dir.join(SQLITE_FILE))));
+    }
+
+    /// runs through the board's requests over loopback, with the board storing its data in `storage`
+    fn loopback_flows(storage: impl FnOnce(&Path) -> Box<dyn Storage>) {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_loopback_test_{:016X}", rand::rng().next_u64()));
+        let board = MessageBoard::with_defaults(dir.clone().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), storage(&dir));
+        let listener = TcpListener::bind(board.address).unwrap();
+        let server: &'static Server = Box::leak(Box::new(Server::new(board)));
+        server.mainloop();
+
+        let mut author = LoopbackClient::connect(server, &listener);
+        let author_id = author.add_user();
+        let message = Entry::new_message(ROOT_ENTRY_ID.into(), author_id, 1, String::from("over loopback"));
+        let BoardResponse::AddEntry(entry_id) = author.request(BoardRequest::AddEntry { user_id: author_id, entry: message.clone() }) else {
+            panic!("Failed to add an entry")
+        };
+        let BoardResponse::GetEntry { entry, .. } = author.request(BoardRequest::GetEntry { user_id: author_id, entry_id }) else {
+            panic!("Failed to get the entry back")
+        };
+        assert_eq!(entry.entry_data, message.entry_data, "Entry changed on the board");
+        assert_eq!(entry.header_data.parent_id, message.header_data.parent_id, "Entry moved on the board");
+        let BoardResponse::GetEntry { entry: root, .. } = author.request(BoardRequest::GetEntry { user_id: author_id, entry_id: ROOT_ENTRY_ID.into() }) else {
+            panic!("Failed to get the root")
+        };
+        assert!(root.header_data.children_ids.contains(&entry_id), "Entry not added to the root's children");
+
+        let mut other = LoopbackClient::connect(server, &listener);
+        let other_id = other.add_user();
+        assert_ne!(other_id, author_id, "Users share an id");
+        let edit = Entry::new_message(ROOT_ENTRY_ID.into(), other_id, 2, String::from("not yours"));
+        let response = other.request(BoardRequest::EditEntry { user_id: other_id, entry_id, entry: edit });
+        assert_eq!(response, BoardResponse::Error(DataError::InsufficientPerms), "Edited another user's entry");
+        let response = other.request(BoardRequest::DeleteEntry { user_id: other_id, entry_id });
+        assert_eq!(response, BoardResponse::Error(DataError::InsufficientPerms), "Deleted another user's entry");
+        let response = other.request(BoardRequest::AddEntry { user_id: other_id, entry: message });
+        assert_eq!(response, BoardResponse::Error(DataError::InsufficientPerms), "Added an entry as another user");
+
+        let reply = Entry::new_message(entry_id, other_id, 3, String::from("a reply"));
+        let BoardResponse::AddEntry(reply_id) = other.request(BoardRequest::AddEntry { user_id: other_id, entry: reply }) else {
+            panic!("Failed to add a reply")
+        };
+        let response = other.request(BoardRequest::GetAncestors { user_id: other_id, entry_id: reply_id });
+        assert_eq!(response, BoardResponse::GetAncestors(vec![entry_id, reply_id]), "Incorrect ancestors");
+        let response = other.request(BoardRequest::GetAncestors { user_id: other_id, entry_id: ROOT_ENTRY_ID.into() });
+        assert_eq!(response, BoardResponse::GetAncestors(Vec::new()), "Root has ancestors");
+
+        // only the author can write in this group, so only they can pin in it
+        let only_author = DefaultedIdSet::Black { whitelist_ids: vec![author_id] };
+        let anyone = DefaultedIdSet::White { blacklist_ids: Vec::new() };
+        let group = Entry::new_access_group(ROOT_ENTRY_ID.into(), author_id, String::from("announcements"), only_author, anyone);
+        let BoardResponse::AddEntry(group_id) = author.request(BoardRequest::AddEntry { user_id: author_id, entry: group }) else {
+            panic!("Failed to add a group")
+        };
+        let announcement = Entry::new_message(group_id, author_id, 4, String::from("pinned"));
+        let BoardResponse::AddEntry(announcement_id) = author.request(BoardRequest::AddEntry { user_id: author_id, entry: announcement }) else {
+            panic!("Failed to add an announcement")
+        };
+        let response = other.request(BoardRequest::SetPinned { user_id: other_id, entry_id: announcement_id, pinned: true });
+        assert_eq!(response, BoardResponse::Error(DataError::InsufficientPerms), "Pinned without write perms");
+        let response = author.request(BoardRequest::SetPinned { user_id: author_id, entry_id: announcement_id, pinned: true });
+        assert_eq!(response, BoardResponse::SetPinned, "Failed to pin");
+        let BoardResponse::GetEntry { entry: announcement, .. } = other.request(BoardRequest::GetEntry { user_id: other_id, entry_id: announcement_id }) else {
+            panic!("Failed to get the announcement")
+        };
+        assert!(announcement.header_data.pinned, "Pin not saved");
+
+        let missing_id = MessageBoard::generate_unique_id(rand::rng(), &server.board.entry_ids.read().unwrap());
+        let response = other.request(BoardRequest::GetEntries { user_id: other_id, entry_ids: vec![announcement_id, missing_id, reply_id] });
+        let BoardResponse::GetEntries(entries) = response else {panic!("Failed to get the entries")};
+        assert_eq!(entries.len(), 3, "Incorrect entry count");
+        assert_eq!(entries[0], Ok(announcement), "Incorrect first entry");
+        assert_eq!(entries[1], Err(DataError::DoesNotExist), "Missing entry found");
+        assert!(entries[2].as_ref().is_ok_and(|reply| reply.header_data.parent_id == entry_id), "Incorrect reply");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn requests_queued_past_the_handlers() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_queue_test_{:016X}", rand::rng().next_u64()));
+        let mut board = MessageBoard::with_defaults(dir.clone().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), Box::new(FileStorage::new(dir.clone().into())));
+        board.num_handlers = 2;
+        let listener = TcpListener::bind(board.address).unwrap();
+        let server: &'static Server = Box::leak(Box::new(Server::new(board)));
+        server.mainloop();
+
+        // every client has several requests in at once, so most wait on a handler
+        let clients: Vec<LoopbackClient> = (0..4).map(|_| LoopbackClient::connect(server, &listener)).collect();
+        std::thread::scope(|scope| {
+            for mut client in clients {
+                scope.spawn(move || {
+                    let mut request_ids: Vec<u64> = (0..8).map(|_| client.send(BoardRequest::Status)).collect();
+                    let mut response_ids: Vec<u64> = (0..8).map(|_| {
+                        let (response_id, response) = client.receive();
+                        assert!(matches!(response, BoardResponse::Status { .. }), "Incorrect response to a queued request: {:?}", response);
+                        response_id
+                    }).collect();
+                    request_ids.sort();
+                    response_ids.sort();
+                    assert_eq!(response_ids, request_ids, "Not every queued request was responded to");
+                });
+            }
+        });
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn oversized_frames_refused() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_frame_size_test_{:016X}", rand::rng().next_u64()));
+        let board = MessageBoard::with_defaults(dir.clone().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), Box::new(FileStorage::new(dir.clone().into())));
+        let max_frame_size = board.max_message_size + REQUEST_FRAME_OVERHEAD;
+        let listener = TcpListener::bind(board.address).unwrap();
+        let server: &'static Server = Box::leak(Box::new(Server::new(board)));
+        server.mainloop();
+
+        // a declared size is refused from the header alone, before any of the frame is sent
+        let mut client = LoopbackClient::connect(server, &listener);
+        let mut prefix = [0u8; 16];
+        prefix[..8].copy_from_slice(&(1u64 << 62).to_le_bytes());
+        client.stream.write_all(&prefix).unwrap();
+        let dropped = client.frame_reader.read_frame(&mut client.stream);
+        assert!(matches!(&dropped, Err(e) if matches!(e.kind(), std::io::ErrorKind::UnexpectedEof | std::io::ErrorKind::ConnectionReset)), "Client kept after declaring an oversized frame: {:?}", dropped);
+
+        // a chunked frame has no declared size, so it's refused once its chunks go over
+        let mut client = LoopbackClient::connect(server, &listener);
+        let _ = write_chunked_frame(&mut client.stream, 0, &vec![0u8; max_frame_size + 1], RESPONSE_CHUNK_SIZE);
+        let dropped = client.frame_reader.read_frame(&mut client.stream);
+        assert!(matches!(&dropped, Err(e) if matches!(e.kind(), std::io::ErrorKind::UnexpectedEof | std::io::ErrorKind::ConnectionReset)), "Client kept after an oversized chunked frame: {:?}", dropped);
+
+        // the limit is per client
+        let mut client = LoopbackClient::connect(server, &listener);
+        assert!(matches!(client.request(BoardRequest::Status), BoardResponse::Status { .. }), "Other clients dropped with the oversized one");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    fn handle(board: &MessageBoard, request: BoardRequest) -> MaybeBoardResponse {
+        handle_request(board, rand::rng(), get_crypto_rng(), request)
+    }
+
+    fn handle_add_user(board: &MessageBoard) -> UserId {
+        let Ok(BoardResponse::AddUser { user_id, .. }) = handle(board, BoardRequest::AddUser) else {panic!("Failed to add a user")};
+        user_id
+    }
+
+    #[test]
+    fn in_memory_requests() {
+        let board = MessageBoard::in_memory();
+        let author_id = handle_add_user(&board);
+        let other_id = handle_add_user(&board);
+
+        let message = Entry::new_message(ROOT_ENTRY_ID.into(), author_id, 1, String::from("in memory"));
+        let Ok(BoardResponse::AddEntry(entry_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: message.clone() }) else {
+            panic!("Failed to add an entry")
+        };
+        let Ok(BoardResponse::GetEntry { entry, .. }) = handle(&board, BoardRequest::GetEntry { user_id: other_id, entry_id }) else {
+            panic!("Failed to get the entry back")
+        };
+        assert_eq!(entry.entry_data, message.entry_data, "Entry changed on the board");
+        assert!(board.get_user(author_id).unwrap().entry_ids.contains(&entry_id), "Entry not added to its author");
+
+        let edit = Entry::new_message(ROOT_ENTRY_ID.into(), other_id, 2, String::from("not yours"));
+        let response = handle(&board, BoardRequest::EditEntry { user_id: other_id, entry_id, entry: edit });
+        assert_eq!(response, Err(DataError::InsufficientPerms), "Edited another user's entry");
+        let response = handle(&board, BoardRequest::DeleteEntry { user_id: other_id, entry_id });
+        assert_eq!(response, Err(DataError::InsufficientPerms), "Deleted another user's entry");
+        let response = handle(&board, BoardRequest::AddEntry { user_id: other_id, entry: message });
+        assert_eq!(response, Err(DataError::InsufficientPerms), "Added an entry as another user");
+
+        // only the author can write in the first group, and read in the second
+        let only_author = DefaultedIdSet::Black { whitelist_ids: vec![author_id] };
+        let anyone = DefaultedIdSet::White { blacklist_ids: Vec::new() };
+        let write_group = Entry::new_access_group(ROOT_ENTRY_ID.into(), author_id, String::from("write"), only_author.clone(), anyone.clone());
+        let read_group = Entry::new_access_group(ROOT_ENTRY_ID.into(), author_id, String::from("read"), anyone, only_author);
+        let Ok(BoardResponse::AddEntries(group_ids)) = handle(&board, BoardRequest::AddEntries { user_id: author_id, entries: vec![write_group, read_group] }) else {
+            panic!("Failed to add the groups")
+        };
+        let (write_group_id, read_group_id) = (group_ids[0], group_ids[1]);
+
+        let reply = Entry::new_message(write_group_id, other_id, 3, String::from("let me in"));
+        let response = handle(&board, BoardRequest::AddEntry { user_id: other_id, entry: reply });
+        assert_eq!(response, Err(DataError::InsufficientPerms), "Wrote without write perms");
+        let secret = Entry::new_message(read_group_id, author_id, 4, String::from("secret"));
+        let Ok(BoardResponse::AddEntry(secret_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: secret }) else {
+            panic!("Failed to add a secret")
+        };
+        let response = handle(&board, BoardRequest::GetEntry { user_id: other_id, entry_id: secret_id });
+        assert_eq!(response, Err(DataError::InsufficientPerms), "Read without read perms");
+        assert!(handle(&board, BoardRequest::GetEntry { user_id: author_id, entry_id: secret_id }).is_ok(), "Author can't read their own entry");
+
+        // a reaction lands under its target wherever it claims to be
+        let mut reaction = Entry::new_reaction(entry_id, other_id, String::from("👍"));
+        reaction.header_data.parent_id = write_group_id;
+        let Ok(BoardResponse::AddEntry(reaction_id)) = handle(&board, BoardRequest::AddEntry { user_id: other_id, entry: reaction }) else {
+            panic!("Failed to react")
+        };
+        assert_eq!(board.get_entry(reaction_id).unwrap().header_data.parent_id, entry_id, "Reaction not attached to its target");
+
+        let Ok(BoardResponse::Status { num_users, num_entries, max_message_size, .. }) = handle(&board, BoardRequest::Status) else {panic!("No status")};
+        assert_eq!((num_users, num_entries), (2, 6), "Incorrect status");
+        assert_eq!(max_message_size, board.max_message_size as u64, "Status without the board's max message size");
+    }
+
+    #[test]
+    fn children_since_filtering() {
+        let board = MessageBoard::in_memory();
+        let author_id = handle_add_user(&board);
+        let other_id = handle_add_user(&board);
+
+        let only_author = DefaultedIdSet::Black { whitelist_ids: vec![author_id] };
+        let anyone = DefaultedIdSet::White { blacklist_ids: Vec::new() };
+        let group = Entry::new_access_group(ROOT_ENTRY_ID.into(), author_id, String::from("private"), anyone, only_author);
+        let Ok(BoardResponse::AddEntry(group_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: group }) else {
+            panic!("Failed to add the group")
+        };
+        let mut child_ids = Vec::new();
+        for (user_id, text) in [(author_id, "old"), (author_id, "new"), (other_id, "mine")] {
+            let message = Entry::new_message(group_id, user_id, 1, String::from(text));
+            let Ok(BoardResponse::AddEntry(child_id)) = handle(&board, BoardRequest::AddEntry { user_id, entry: message }) else {
+                panic!("Failed to add a child")
+            };
+            child_ids.push(child_id);
+        }
+        // pin down the activity so the cutoff doesn't depend on the clock
+        for (child_id, last_activity) in child_ids.iter().zip([10, 30, 30]) {
+            let mut child = board.get_entry(*child_id).unwrap();
+            child.header_data.last_activity = last_activity;
+            board.overwrite_entry(*child_id, child).unwrap();
+        }
+
+        let response = handle(&board, BoardRequest::ChildrenSince { user_id: author_id, entry_id: group_id, since_ts: 20 });
+        assert_eq!(response, Ok(BoardResponse::ChildrenSince(vec![child_ids[1], child_ids[2]])), "Incorrect children since");
+        let response = handle(&board, BoardRequest::ChildrenSince { user_id: author_id, entry_id: group_id, since_ts: 30 });
+        assert_eq!(response, Ok(BoardResponse::ChildrenSince(Vec::new())), "Included activity at the cutoff");
+        // without read perms only their own entries come back
+        let response = handle(&board, BoardRequest::ChildrenSince { user_id: other_id, entry_id: group_id, since_ts: 0 });
+        assert_eq!(response, Ok(BoardResponse::ChildrenSince(vec![child_ids[2]])), "Unreadable children included");
+
+        // a child id left dangling is skipped rather than failing the query
+        let mut group = board.get_entry(group_id).unwrap();
+        group.header_data.children_ids.insert(0, EntryId::from(u64::MAX));
+        board.overwrite_entry(group_id, group).unwrap();
+        let response = handle(&board, BoardRequest::ChildrenSince { user_id: author_id, entry_id: group_id, since_ts: 20 });
+        assert_eq!(response, Ok(BoardResponse::ChildrenSince(vec![child_ids[1], child_ids[2]])), "Dangling child broke the query");
+    }
+
+    /// the storages should fail the same ways, so the board behaves the same on each
+    fn storage_semantics(storage: &dyn Storage) {
+        storage.create().unwrap();
+        assert!(storage.exists(), "Storage not created");
+        let entry_id = EntryId::from(0x10);
+        assert_eq!(storage.read_entry(entry_id), Err(DataError::DoesNotExist), "Read a missing entry");
+        assert_eq!(storage.overwrite_entry(entry_id, b"new"), Err(DataError::DoesNotExist), "Overwrote a missing entry");
+        storage.write_new_entry(entry_id, b"old").unwrap();
+        assert_eq!(storage.write_new_entry(entry_id, b"new"), Err(DataError::AlreadyExists), "Wrote over an entry");
+        storage.overwrite_entry(entry_id, b"new").unwrap();
+        assert_eq!(storage.read_entry(entry_id).unwrap(), b"new", "Entry not overwritten");
+        assert_eq!(storage.entry_ids().unwrap(), HashSet::from([entry_id]), "Incorrect entry ids");
+        storage.remove_entry(entry_id).unwrap();
+        assert_eq!(storage.remove_entry(entry_id), Err(DataError::DoesNotExist), "Removed a missing entry");
+
+        let user_id = UserId::from(0x20);
+        assert_eq!(storage.read_user(user_id), Err(DataError::DoesNotExist), "Read a missing user");
+        storage.write_new_user(user_id, b"user").unwrap();
+        assert_eq!(storage.write_new_user(user_id, b"user"), Err(DataError::AlreadyExists), "Wrote over a user");
+        storage.overwrite_user(user_id, b"updated").unwrap();
+        assert_eq!(storage.read_user(user_id).unwrap(), b"updated", "User not overwritten");
+        assert_eq!(storage.user_ids().unwrap(), HashSet::from([user_id]), "Incorrect user ids");
+    }
+
+    #[test]
+    fn storages_agree() {
+        storage_semantics(&MemoryStorage::default());
+
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_storage_test_{:016X}", rand::rng().next_u64()));
+        storage_semantics(&FileStorage::new(dir.clone().into_boxed_path()));
+        #[cfg(feature = "sqlite")]
+        {
+            fs::create_dir_all(&dir).unwrap();
+            storage_semantics(&SqliteStorage::new(dir.join(SQLITE_FILE)));
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn data_dir_from_env() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_env_dir_test_{:016X}", rand::rng().next_u64()));
+        // no rc file in the home dir, and the data dir doesn't exist yet
+        let home = dir.join("home");
+        let data_dir = dir.join("data");
+        let board = MessageBoard::from_env(home.clone(), Some(data_dir.clone()));
+
+        assert_eq!(&*board.file_dir, data_dir.as_path(), "Data dir not used");
+        assert!(data_dir.join("entries").is_dir(), "Entries dir not created");
+        assert!(data_dir.join("users").is_dir(), "Users dir not created");
+        assert!(data_dir.join("storage").exists(), "Storage file not created");
+        assert!(!home.join(RC_FILE).exists(), "Rc file created");
+        board.get_entry(ROOT_ENTRY_ID.into()).expect("Root not created").validate_root().expect("Malformed root");
+        assert_eq!(board.max_message_size, DEFAULT_MAX_MESSAGE_SIZE, "Defaults not used");
+
+        // booting again reuses the existing files
+        let root = board.get_entry(ROOT_ENTRY_ID.into()).unwrap();
+        drop(board);
+        let board = MessageBoard::from_env(home, Some(data_dir));
+        assert_eq!(board.get_entry(ROOT_ENTRY_ID.into()), Ok(root), "Root not kept");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn journal_recovery() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_journal_test_{:016X}", rand::rng().next_u64()));
+        let board = MessageBoard::with_defaults(dir.clone().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), Box::new(FileStorage::new(dir.clone().into_boxed_path())));
+        let user_id = handle_add_user(&board);
+
+        // an add cut short after writing only the new entry
+        let entry_id = EntryId::from(0x10);
+        let entry = Entry::new_message(ROOT_ENTRY_ID.into(), user_id, 1, String::from("half applied"));
+        let mut root = board.get_entry(ROOT_ENTRY_ID.into()).unwrap();
+        root.header_data.add_child(entry_id).unwrap();
+        let mut user_data = board.get_user(user_id).unwrap();
+        user_data.entry_ids.push(entry_id);
+        let writes = [
+            JournalWrite::NewEntry(entry_id, entry.into_data().unwrap()),
+            JournalWrite::Entry(ROOT_ENTRY_ID.into(), root.into_data().unwrap()),
+            JournalWrite::User(user_id, user_data.into_data().unwrap()),
+        ];
+        board.journal.record(&writes).unwrap();
+        board.storage.write_new_entry(entry_id, &entry.into_data().unwrap()).unwrap();
+        drop(board);
+
+        let board = MessageBoard::with_defaults(dir.clone().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), Box::new(FileStorage::new(dir.clone().into_boxed_path())));
+        assert!(board.get_entry(ROOT_ENTRY_ID.into()).unwrap().header_data.children_ids.contains(&entry_id), "Add not finished for the parent");
+        assert!(board.get_user(user_id).unwrap().entry_ids.contains(&entry_id), "Add not finished for the user");
+        assert_eq!(board.get_entry(entry_id).unwrap().entry_data, entry.entry_data, "Added entry changed");
+        assert_eq!(board.ancestor_path(entry_id), Ok(vec![entry_id]), "Finished add not indexed");
+        assert!(!dir.join(JOURNAL_FILE).exists(), "Journal not emptied");
+
+        // a record cut short while being written is dropped, none of it was applied
+        let other_id = EntryId::from(0x20);
+        let other = Entry::new_message(ROOT_ENTRY_ID.into(), user_id, 2, String::from("never applied"));
+        board.journal.record(&[JournalWrite::NewEntry(other_id, other.into_data().unwrap())]).unwrap();
+        let journal = fs::read(dir.join(JOURNAL_FILE)).unwrap();
+        fs::write(dir.join(JOURNAL_FILE), &journal[..journal.len() - 1]).unwrap();
+        drop(board);
+
+        let board = MessageBoard::with_defaults(dir.clone().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), Box::new(FileStorage::new(dir.clone().into_boxed_path())));
+        assert_eq!(board.get_entry(other_id), Err(DataError::DoesNotExist), "Partial record applied");
+        assert!(!dir.join(JOURNAL_FILE).exists(), "Journal not emptied");
+
+        // mutations through the board leave nothing behind
+        let reply = Entry::new_message(entry_id, user_id, 3, String::from("reply"));
+        let Ok(BoardResponse::AddEntry(reply_id)) = handle(&board, BoardRequest::AddEntry { user_id, entry: reply }) else {panic!("Failed to add a reply")};
+        let response = handle(&board, BoardRequest::DeleteEntry { user_id, entry_id: reply_id });
+        assert_eq!(response, Ok(BoardResponse::DeleteEntry), "Failed to delete the reply");
+        assert!(!dir.join(JOURNAL_FILE).exists(), "Journal left after a mutation");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn journal_kept_record_finished() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_journal_kept_test_{:016X}", rand::rng().next_u64()));
+        fs::create_dir_all(&dir).unwrap();
+        let storage = MemoryStorage::default();
+        storage.create().unwrap();
+        let journal = Journal::new(Some(dir.join(JOURNAL_FILE)));
+
+        // the second write fails, as there's no entry to overwrite yet
+        let (first_id, second_id, third_id) = (EntryId::from(0x10), EntryId::from(0x20), EntryId::from(0x30));
+        let writes = [JournalWrite::NewEntry(first_id, b"first".to_vec()), JournalWrite::Entry(second_id, b"second".to_vec())];
+        assert_eq!(journal.apply(&storage, &writes), Err(DataError::DoesNotExist), "Overwrote a missing entry");
+        assert!(dir.join(JOURNAL_FILE).exists(), "Half applied record dropped");
+        storage.write_new_entry(second_id, b"old").unwrap();
+
+        journal.apply(&storage, &[JournalWrite::NewEntry(third_id, b"third".to_vec())]).unwrap();
+        assert_eq!(storage.read_entry(first_id).unwrap(), b"first", "First batch changed");
+        assert_eq!(storage.read_entry(second_id).unwrap(), b"second", "First batch not finished");
+        assert_eq!(storage.read_entry(third_id).unwrap(), b"third", "Second batch not applied");
+        assert!(!dir.join(JOURNAL_FILE).exists(), "Journal not emptied");
+
+        // a kept record which can never be finished is dropped rather than blocking later writes
+        let writes = [JournalWrite::NewEntry(0x40u64.into(), Vec::new()), JournalWrite::Entry(0x60u64.into(), Vec::new())];
+        assert_eq!(journal.apply(&storage, &writes), Err(DataError::DoesNotExist), "Overwrote a missing entry");
+        let fourth_id = EntryId::from(0x50);
+        journal.apply(&storage, &[JournalWrite::NewEntry(fourth_id, b"fourth".to_vec())]).unwrap();
+        assert_eq!(storage.read_entry(fourth_id).unwrap(), b"fourth", "Blocked by an unfinishable record");
+        assert!(!dir.join(JOURNAL_FILE).exists(), "Unfinishable record kept");
+        assert_eq!(journal.replay(&storage), Ok(0), "Unfinishable record replayed");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn read_receipts_dedup() {
+        let board = MessageBoard::in_memory();
+        let author_id = handle_add_user(&board);
+        let other_id = handle_add_user(&board);
+
+        let message = Entry::new_message(ROOT_ENTRY_ID.into(), author_id, 1, String::from("read me"));
+        let Ok(BoardResponse::AddEntry(entry_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: message }) else {
+            panic!("Failed to add an entry")
+        };
+        let response = handle(&board, BoardRequest::MarkRead { user_id: other_id, entry_id });
+        assert_eq!(response, Ok(BoardResponse::MarkRead(1)), "Read not counted");
+        let response = handle(&board, BoardRequest::MarkRead { user_id: other_id, entry_id });
+        assert_eq!(response, Ok(BoardResponse::MarkRead(1)), "Read counted twice");
+        let response = handle(&board, BoardRequest::MarkRead { user_id: author_id, entry_id });
+        assert_eq!(response, Ok(BoardResponse::MarkRead(2)), "Second reader not counted");
+        let Ok(BoardResponse::GetEntry { view_count, .. }) = handle(&board, BoardRequest::GetEntry { user_id: other_id, entry_id }) else {
+            panic!("Failed to get the entry")
+        };
+        assert_eq!(view_count, 2, "Incorrect view count");
+
+        let response = handle(&board, BoardRequest::MarkRead { user_id: other_id, entry_id: u64::MAX.into() });
+        assert_eq!(response, Err(DataError::DoesNotExist), "Marked a missing entry read");
+        // a deleted entry is left as a tombstone in its place, which keeps its receipts
+        let response = handle(&board, BoardRequest::DeleteEntry { user_id: author_id, entry_id });
+        assert_eq!(response, Ok(BoardResponse::DeleteEntry), "Failed to delete the entry");
+        assert_eq!(board.view_count(entry_id), Ok(2), "Receipts lost with the delete");
+    }
+
+    #[test]
+    fn invalid_children_rejected() {
+        let board = MessageBoard::in_memory();
+        let author_id = handle_add_user(&board);
+        let message = Entry::new_message(ROOT_ENTRY_ID.into(), author_id, 1, String::from("parent"));
+        let Ok(BoardResponse::AddEntry(entry_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: message }) else {
+            panic!("Failed to add an entry")
+        };
+
+        let mut entry = board.get_entry(entry_id).unwrap();
+        entry.header_data.children_ids = vec![entry_id];
+        assert_eq!(board.overwrite_entry(entry_id, entry.clone()), Err(DataError::InvalidStructure), "Wrote a self-referencing entry");
+        let mut root = board.get_entry(ROOT_ENTRY_ID.into()).unwrap();
+        root.header_data.children_ids.push(entry_id);
+        assert_eq!(board.overwrite_entry(ROOT_ENTRY_ID.into(), root), Err(DataError::InvalidStructure), "Wrote duplicate children");
+        assert_eq!(board.get_entry(entry_id).unwrap().header_data.children_ids, Vec::new(), "Invalid entry written anyway");
+    }
+
+    #[test]
+    fn user_privacy_policies() {
+        let mut board = MessageBoard::in_memory();
+        let author_id = handle_add_user(&board);
+        let other_id = handle_add_user(&board);
+        let admin_id = handle_add_user(&board);
+        board.admin_id = Some(admin_id);
+
+        let only_author = DefaultedIdSet::Black { whitelist_ids: vec![author_id] };
+        let anyone = DefaultedIdSet::White { blacklist_ids: Vec::new() };
+        let group = Entry::new_access_group(ROOT_ENTRY_ID.into(), author_id, String::from("private"), anyone, only_author);
+        let Ok(BoardResponse::AddEntry(group_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: group }) else {
+            panic!("Failed to add the group")
+        };
+        let public = Entry::new_message(ROOT_ENTRY_ID.into(), author_id, 1, String::from("public"));
+        let Ok(BoardResponse::AddEntry(public_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: public }) else {
+            panic!("Failed to add the public message")
+        };
+        let secret = Entry::new_message(group_id, author_id, 2, String::from("secret"));
+        assert!(handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: secret }).is_ok(), "Failed to add the secret");
+        let author = board.get_user(author_id).unwrap();
+        let get_author = |board: &MessageBoard, requester_id| match handle(board, BoardRequest::GetUser { requester_id, user_id: author_id }) {
+            Ok(BoardResponse::GetUser(user)) => Ok(user),
+            Ok(response) => panic!("Unexpected response {:?}", response),
+            Err(e) => Err(e),
+        };
+
+        for user_privacy in [UserPrivacy::Public, UserPrivacy::AdminOnly, UserPrivacy::Filtered] {
+            board.user_privacy = user_privacy;
+            assert_eq!(get_author(&board, author_id), Ok(board.get_user(author_id).unwrap()), "Own data changed under {:?}", user_privacy);
+        }
+
+        board.user_privacy = UserPrivacy::Public;
+        let user = get_author(&board, other_id).unwrap();
+        assert_eq!(user.entry_ids, author.entry_ids, "Entries hidden when public");
+        assert_ne!(user.aead, author.aead, "Key sent to another user");
+
+        board.user_privacy = UserPrivacy::AdminOnly;
+        assert_eq!(get_author(&board, other_id), Err(DataError::InsufficientPerms), "Non-admin got another user");
+        assert_eq!(get_author(&board, admin_id).unwrap().entry_ids, author.entry_ids, "Entries hidden from the admin");
+
+        board.user_privacy = UserPrivacy::Filtered;
+        assert_eq!(get_author(&board, other_id).unwrap().entry_ids, vec![group_id, public_id], "Unreadable entries not filtered");
+    }
+
+    #[test]
+    fn tombstoned_entries() {
+        let board = MessageBoard::in_memory();
+        let author_id = handle_add_user(&board);
+        let message = Entry::new_message(ROOT_ENTRY_ID.into(), author_id, 1, String::from("parent"));
+        let Ok(BoardResponse::AddEntry(entry_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: message }) else {
+            panic!("Failed to add the entry")
+        };
+        let reply = Entry::new_message(entry_id, author_id, 2, String::from("reply"));
+        let Ok(BoardResponse::AddEntry(reply_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: reply }) else {
+            panic!("Failed to add the reply")
+        };
+
+        assert_eq!(handle(&board, BoardRequest::DeleteEntry { user_id: author_id, entry_id }), Ok(BoardResponse::DeleteEntry), "Failed to delete an entry with children");
+        assert_eq!(handle(&board, BoardRequest::DeleteEntry { user_id: author_id, entry_id }), Ok(BoardResponse::DeleteEntry), "Failed to delete a tombstone");
+        let entry = board.get_entry(entry_id).unwrap();
+        assert!(entry.header_data.deleted, "Entry not marked deleted");
+        assert_eq!(entry.header_data.children_ids, vec![reply_id], "Children lost");
+        let EntryData::Message { message, .. } = &entry.entry_data else {panic!("Entry type changed")};
+        assert_eq!(message, DELETED_PLACEHOLDER, "Message kept");
+        assert!(board.get_entry(ROOT_ENTRY_ID.into()).unwrap().header_data.children_ids.contains(&entry_id), "Tombstone removed from its parent");
+
+        let response = handle(&board, BoardRequest::GetEntry { user_id: author_id, entry_id: reply_id });
+        assert!(matches!(response, Ok(BoardResponse::GetEntry { .. })), "Child unreachable");
+        let response = handle(&board, BoardRequest::GetAncestors { user_id: author_id, entry_id: reply_id });
+        assert_eq!(response, Ok(BoardResponse::GetAncestors(vec![entry_id, reply_id])), "Child's ancestors broken");
+
+        let mut edit = entry.clone();
+        edit.entry_data = EntryData::Message { timestamp: 1, message: String::from("back") };
+        assert_eq!(handle(&board, BoardRequest::EditEntry { user_id: author_id, entry_id, entry: edit }), Err(DataError::DoesNotExist), "Edited a tombstone");
+        let mut undelete = entry;
+        undelete.header_data.deleted = false;
+        assert!(handle(&board, BoardRequest::EditEntry { user_id: author_id, entry_id, entry: undelete }).is_err(), "Undeleted a tombstone");
+        let mut reply = board.get_entry(reply_id).unwrap();
+        reply.header_data.deleted = true;
+        assert_eq!(handle(&board, BoardRequest::EditEntry { user_id: author_id, entry_id: reply_id, entry: reply }), Err(DataError::InsufficientPerms), "Deleted through an edit");
+    }
+
+    #[test]
+    fn version_negotiation_handled() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_version_test_{:016X}", rand::rng().next_u64()));
+        let board = MessageBoard::with_defaults(dir.clone().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), Box::new(FileStorage::new(dir.clone().into())));
+        let listener = TcpListener::bind(board.address).unwrap();
+        let server: &'static Server = Box::leak(Box::new(Server::new(board)));
+        server.mainloop();
+
+        let mut client = LoopbackClient::connect(server, &listener);
+        let response = client.request(BoardRequest::NegotiateVersion { min_version: MIN_PROTOCOL_VERSION, max_version: u8::MAX });
+        assert_eq!(response, BoardResponse::NegotiateVersion(PROTOCOL_VERSION), "Version not agreed on");
+        let response = client.request(BoardRequest::NegotiateVersion { min_version: MIN_PROTOCOL_VERSION, max_version: MIN_PROTOCOL_VERSION });
+        assert_eq!(response, BoardResponse::NegotiateVersion(MIN_PROTOCOL_VERSION), "Oldest version not agreed on");
+        let response = client.request(BoardRequest::NegotiateVersion { min_version: PROTOCOL_VERSION + 1, max_version: u8::MAX });
+        assert_eq!(response, BoardResponse::Error(DataError::VersionMismatch), "Unsupported client accepted");
+
+        // the connection is still usable after a mismatch
+        assert!(matches!(client.request(BoardRequest::Status), BoardResponse::Status { .. }), "Failed to get the status");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn guests_only_read() {
+        let mut board = MessageBoard::in_memory();
+        let author_id = handle_add_user(&board);
+        let guest_id = UserId::from(ANONYMOUS_USER_ID);
+        let message = Entry::new_message(ROOT_ENTRY_ID.into(), author_id, 1, String::from("public"));
+        let Ok(BoardResponse::AddEntry(entry_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: message }) else {
+            panic!("Failed to add the entry")
+        };
+
+        let get_entry = BoardRequest::GetEntry { user_id: guest_id, entry_id };
+        assert_eq!(handle(&board, get_entry), Err(DataError::InsufficientPerms), "Guest let in without allow_guests");
+
+        board.allow_guests = true;
+        let response = handle(&board, BoardRequest::GetEntry { user_id: guest_id, entry_id });
+        assert!(matches!(response, Ok(BoardResponse::GetEntry { .. })), "Guest couldn't read");
+        // the root lets anyone write, but not a guest
+        let reply = Entry::new_message(ROOT_ENTRY_ID.into(), guest_id, 2, String::from("graffiti"));
+        assert_eq!(handle(&board, BoardRequest::AddEntry { user_id: guest_id, entry: reply }), Err(DataError::InsufficientPerms), "Guest wrote an entry");
+        assert_eq!(handle(&board, BoardRequest::MarkRead { user_id: guest_id, entry_id }), Err(DataError::InsufficientPerms), "Guest left a read receipt");
+        assert_eq!(handle(&board, BoardRequest::SetPinned { user_id: guest_id, entry_id, pinned: true }), Err(DataError::InsufficientPerms), "Guest pinned an entry");
+        assert_eq!(board.view_count(entry_id), Ok(0), "Guest counted as a view");
+        assert_eq!(board.get_entry(ROOT_ENTRY_ID.into()).unwrap().header_data.children_ids, vec![entry_id], "Guest's entry written anyway");
+    }
+
+    #[test]
+    fn subtree_counted() {
+        let board = MessageBoard::in_memory();
+        let author_id = handle_add_user(&board);
+        let add = |parent_id: EntryId, message: &str| {
+            let entry = Entry::new_message(parent_id, author_id, 1, String::from(message));
+            let Ok(BoardResponse::AddEntry(entry_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry }) else {
+                panic!("Failed to add an entry")
+            };
+            entry_id
+        };
+        let thread_id = add(ROOT_ENTRY_ID.into(), "thread");
+        let reply_id = add(thread_id, "reply");
+        add(reply_id, "nested reply");
+        add(thread_id, "another reply");
+
+        let response = handle(&board, BoardRequest::SubtreeCount { user_id: author_id, entry_id: thread_id });
+        assert_eq!(response, Ok(BoardResponse::SubtreeCount(3)), "Incorrect reply count");
+        let response = handle(&board, BoardRequest::SubtreeCount { user_id: author_id, entry_id: ROOT_ENTRY_ID.into() });
+        assert_eq!(response, Ok(BoardResponse::SubtreeCount(4)), "Incorrect board count");
+        let response = handle(&board, BoardRequest::SubtreeCount { user_id: author_id, entry_id: u64::MAX.into() });
+        assert_eq!(response, Err(DataError::DoesNotExist), "Counted a missing entry");
+    }
+
+    #[test]
+    fn display_names_set() {
+        let board = MessageBoard::in_memory();
+        let user_id = handle_add_user(&board);
+        let other_id = handle_add_user(&board);
+        let get_name = |requester_id| match handle(&board, BoardRequest::GetUser { requester_id, user_id }) {
+            Ok(BoardResponse::GetUser(user)) => user.display_name,
+            response => panic!("Unexpected response {:?}", response),
+        };
+        assert_eq!(get_name(other_id), None, "New user has a display name");
+
+        let response = handle(&board, BoardRequest::SetDisplayName { user_id, display_name: Some(String::from("Pyramid")) });
+        assert_eq!(response, Ok(BoardResponse::SetDisplayName), "Display name not set");
+        assert_eq!(get_name(user_id), Some(String::from("Pyramid")), "Display name not kept");
+        assert_eq!(get_name(other_id), Some(String::from("Pyramid")), "Display name hidden from other users");
+
+        let too_long = Some("a".repeat(MAX_DISPLAY_NAME_SIZE + 1));
+        let response = handle(&board, BoardRequest::SetDisplayName { user_id, display_name: too_long });
+        assert_eq!(response, Err(DataError::PayloadTooLarge { max: MAX_DISPLAY_NAME_SIZE }), "Oversized display name accepted");
+        assert_eq!(get_name(user_id), Some(String::from("Pyramid")), "Display name changed by a rejected request");
+
+        handle(&board, BoardRequest::SetDisplayName { user_id, display_name: None }).unwrap();
+        assert_eq!(get_name(other_id), None, "Display name not cleared");
+    }
+
+    #[test]
+    fn display_names_keep_entries() {
+        let board = MessageBoard::in_memory();
+        let user_id = handle_add_user(&board);
+        let entry_ids = std::thread::scope(|scope| {
+            let names = scope.spawn(|| for i in 0..50 {
+                let display_name = Some(format!("Pyramid {}", i));
+                handle(&board, BoardRequest::SetDisplayName { user_id, display_name }).unwrap();
+            });
+            let entry_ids: Vec<EntryId> = (0..50).map(|i| {
+                let entry = Entry::new_message(ROOT_ENTRY_ID.into(), user_id, i, format!("message {}", i));
+                match handle(&board, BoardRequest::AddEntry { user_id, entry }) {
+                    Ok(BoardResponse::AddEntry(entry_id)) => entry_id,
+                    response => panic!("Unexpected response {:?}", response),
+                }
+            }).collect();
+            names.join().unwrap();
+            entry_ids
+        });
+        let user = board.get_user(user_id).unwrap();
+        assert_eq!(user.display_name, Some(String::from("Pyramid 49")), "Display name lost");
+        for entry_id in entry_ids {
+            assert!(user.entry_ids.contains(&entry_id), "Entry {} lost from the user by a display name change", entry_id);
+        }
+    }
+
+    #[test]
+    fn edits_keep_added_entries() {
+        let board = MessageBoard::in_memory();
+        let user_id = handle_add_user(&board);
+        let thread = Entry::new_message(ROOT_ENTRY_ID.into(), user_id, 1, String::from("thread"));
+        let Ok(BoardResponse::AddEntry(thread_id)) = handle(&board, BoardRequest::AddEntry { user_id, entry: thread }) else {
+            panic!("Failed to add the thread")
+        };
+        let entry_ids = std::thread::scope(|scope| {
+            let edits = scope.spawn(|| for i in 0..500 {
+                let mut entry = board.get_entry(thread_id).unwrap();
+                entry.entry_data = EntryData::Message { timestamp: i, message: format!("edit {}", i) };
+                // an edit made with children since added is turned away rather than dropping them
+                match handle(&board, BoardRequest::EditEntry { user_id, entry_id: thread_id, entry }) {
+                    Ok(_) | Err(DataError::EdittedLocation) => (),
+                    response => panic!("Unexpected response {:?}", response),
+                }
+            });
+            let entry_ids: Vec<EntryId> = (0..500).map(|i| {
+                let entry = Entry::new_message(thread_id, user_id, i, format!("reply {}", i));
+                match handle(&board, BoardRequest::AddEntry { user_id, entry }) {
+                    Ok(BoardResponse::AddEntry(entry_id)) => entry_id,
+                    response => panic!("Unexpected response {:?}", response),
+                }
+            }).collect();
+            edits.join().unwrap();
+            entry_ids
+        });
+        let children_ids = board.get_entry(thread_id).unwrap().header_data.children_ids;
+        for entry_id in entry_ids {
+            assert!(children_ids.contains(&entry_id), "Entry {} lost from its parent by an edit", entry_id);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn root_given() {
+        let board = MessageBoard::in_memory();
+        assert_eq!(handle(&board, BoardRequest::GetRoot), Ok(BoardResponse::GetRoot(ROOT_ENTRY_ID.into())), "Incorrect root");
+        assert!(BoardRequest::GetRoot.is_read_only(), "Guests can't find the root");
+    }
+
+    #[test]
+    fn named_boards_isolated() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_named_boards_test_{:016X}", rand::rng().next_u64()));
+        let mut board = MessageBoard::with_defaults(dir.clone().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), Box::new(FileStorage::new(dir.clone().into())));
+        board.named_boards = vec![String::from("side")];
+        let admin_id = UserId::from(0x1234_5678_9ABC_DEF0);
+        board.admin_id = Some(admin_id);
+        let listener = TcpListener::bind(board.address).unwrap();
+        let server: &'static Server = Box::leak(Box::new(Server::new(board)));
+        server.mainloop();
+        assert!(dir.join(BOARDS_DIR).join("side").join("entries").exists(), "Side board files not created");
+
+        let mut main = LoopbackClient::connect(server, &listener);
+        let main_id = main.add_user();
+        let mut side = LoopbackClient::connect_to(server, &listener, "side");
+        let side_id = side.add_user();
+
+        let message = Entry::new_message(ROOT_ENTRY_ID.into(), main_id, 1, String::from("main only"));
+        let BoardResponse::AddEntry(entry_id) = main.request(BoardRequest::AddEntry { user_id: main_id, entry: message }) else {
+            panic!("Failed to add an entry")
+        };
+        assert!(!server.named_boards["side"].entry_ids.read().unwrap().contains(&entry_id), "Entry added to the side board");
+        assert!(!server.named_boards["side"].user_ids.read().unwrap().contains(&main_id), "User added to the side board");
+        assert!(!server.board.user_ids.read().unwrap().contains(&side_id), "Side user added to the main board");
+        let response = side.request(BoardRequest::GetEntry { user_id: side_id, entry_id });
+        assert_eq!(response, BoardResponse::Error(DataError::DoesNotExist), "Main entry read from the side board");
+        let BoardResponse::Status { num_users, num_entries, .. } = side.request(BoardRequest::Status) else {panic!("No status")};
+        assert_eq!((num_users, num_entries), (1, 1), "Side board shares the main board's data");
+        // the main board's admin is no one on the side board
+        let response = handle(&server.named_boards["side"], BoardRequest::ListUsers { admin_id, start: UserId::from(0) });
+        assert_eq!(response, Err(DataError::InsufficientPerms), "Side board's users listed by the main board's admin");
+
+        let expected = vec![String::from(MAIN_BOARD), String::from("side")];
+        assert_eq!(side.request(BoardRequest::ListBoards), BoardResponse::ListBoards(expected), "Incorrect board list");
+
+        side.board = String::from("nowhere");
+        assert_eq!(side.request(BoardRequest::Status), BoardResponse::Error(DataError::DoesNotExist), "Request to an unknown board answered");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn perms_summarized() {
+        let board = MessageBoard::in_memory();
+        let author_id = handle_add_user(&board);
+        let other_id = handle_add_user(&board);
+
+        let mut members: Vec<UserId> = (0..1024u64).map(|id| UserId::from(id + 0x1000)).collect();
+        members.push(author_id);
+        let write_perms = DefaultedIdSet::Inherit { whitelist_ids: vec![author_id], blacklist_ids: vec![other_id] };
+        let read_perms = DefaultedIdSet::Black { whitelist_ids: members };
+        let group = Entry::new_access_group(ROOT_ENTRY_ID.into(), author_id, String::from("big"), write_perms, read_perms);
+        let Ok(BoardResponse::AddEntry(group_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: group }) else {
+            panic!("Failed to add the group")
+        };
+
+        let response = handle(&board, BoardRequest::GetPermsSummary { user_id: other_id, entry_id: group_id });
+        let write = PermsSummary { base: DefaultBase::Inherit, whitelist_len: 1, blacklist_len: 1 };
+        let read = PermsSummary { base: DefaultBase::Black, whitelist_len: 1025, blacklist_len: 0 };
+        assert_eq!(response, Ok(BoardResponse::GetPermsSummary { write, read }), "Incorrect summary");
+        let summary_size = BoardResponse::GetPermsSummary { write, read }.into_data().unwrap().len();
+        assert!(summary_size < 32, "Summary carries the ids ({} bytes)", summary_size);
+
+        let message = Entry::new_message(group_id, author_id, 1, String::from("no perms here"));
+        let Ok(BoardResponse::AddEntry(message_id)) = handle(&board, BoardRequest::AddEntry { user_id: author_id, entry: message }) else {
+            panic!("Failed to add a message")
+        };
+        let response = handle(&board, BoardRequest::GetPermsSummary { user_id: author_id, entry_id: message_id });
+        assert_eq!(response, Err(DataError::InvalidStructure), "Summarized a message's perms");
+        // the same access as getting the entry
+        let response = handle(&board, BoardRequest::GetPermsSummary { user_id: other_id, entry_id: message_id });
+        assert_eq!(response, Err(DataError::InsufficientPerms), "Summarized an unreadable entry");
+        let response = handle(&board, BoardRequest::GetPermsSummary { user_id: author_id, entry_id: u64::MAX.into() });
+        assert_eq!(response, Err(DataError::DoesNotExist), "Summarized a missing entry");
+    }
+
+    #[test]
+    fn metrics_exposition() {
+        let metrics = Metrics::new();
+        metrics.record_request(&BoardRequest::Ping);
+        metrics.record_request(&BoardRequest::Ping);
+        metrics.record_request(&BoardRequest::Status);
+        metrics.record_error(&DataError::DoesNotExist);
+        metrics.record_error(&DataError::PayloadTooLarge { max: 8 });
+        metrics.record_error(&DataError::PayloadTooLarge { max: 16 });
+        metrics.set_active_clients(3);
+        metrics.set_queue_depth(5);
+
+        let text = metrics.render();
+        let lines: Vec<&str> = text.lines().collect();
+        for expected in [
+            "# TYPE message_board_requests_total counter",
+            "message_board_requests_total{type=\"Ping\"} 2",
+            "message_board_requests_total{type=\"Status\"} 1",
+            "# TYPE message_board_errors_total counter",
+            "message_board_errors_total{error=\"DoesNotExist\"} 1",
+            "message_board_errors_total{error=\"PayloadTooLarge\"} 2",
+            "# TYPE message_board_active_clients gauge",
+            "message_board_active_clients 3",
+            "# TYPE message_board_queue_depth gauge",
+            "message_board_queue_depth 5",
+        ] {
+            assert!(lines.contains(&expected), "Missing \"{}\" in:\n{}", expected, text);
+        }
+        assert!(text.ends_with('\n'), "Exposition should end with a newline");
+
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_metrics_test_{:016X}", rand::rng().next_u64()));
+        let mut board = MessageBoard::with_defaults(dir.clone().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), Box::new(FileStorage::new(dir.clone().into())));
+        let Ok(BoardResponse::AddUser { user_id: admin_id, user_aead }) = handle(&board, BoardRequest::AddUser) else {panic!("Failed to add the admin")};
+        board.admin_id = Some(admin_id);
+        let listener = TcpListener::bind(board.address).unwrap();
+        let server: &'static Server = Box::leak(Box::new(Server::new(board)));
+        server.mainloop();
+
+        let mut other = LoopbackClient::connect(server, &listener);
+        let other_id = other.add_user();
+        let response = other.request(BoardRequest::Metrics { admin_id: other_id });
+        assert_eq!(response, BoardResponse::Error(DataError::InsufficientPerms), "Metrics given to a user other than the admin");
+
+        let mut client = LoopbackClient::connect(server, &listener);
+        client.keys.user_aead = Some(user_aead);
+        let BoardResponse::Metrics(text) = client.request(BoardRequest::Metrics { admin_id }) else {
+            panic!("Metrics not answered")
+        };
+        for expected in ["message_board_requests_total{type=\"GetKemEk\"} 2", "message_board_requests_total{type=\"Metrics\"} 2"] {
+            assert!(text.lines().any(|line| line == expected), "Missing \"{}\" in:\n{}", expected, text);
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn file_names_padded() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_file_names_test_{:016X}", rand::rng().next_u64()));
+        let storage = FileStorage::new(dir.clone().into_boxed_path());
+        storage.create().unwrap();
+
+        let large_id = EntryId::from(0xFEDC_BA98_7654_3210);
+        storage.write_new_entry(large_id, b"large").unwrap();
+        assert!(dir.join("entries").join("FEDCBA9876543210").exists(), "Large id not named in full");
+        assert_eq!(storage.read_entry(large_id).unwrap(), b"large", "Large id entry not read back");
+        let small_id = UserId::from(0xFFFF);
+        storage.write_new_user(small_id, b"small").unwrap();
+        assert!(dir.join("users").join("000000000000FFFF").exists(), "Small id not padded");
+
+        // as older versions named them
+        fs::write(dir.join("entries").join("0000ABCD"), b"short").unwrap();
+        fs::write(dir.join("users").join("Admin"), b"admin").unwrap();
+        fs::write(dir.join("receipts").join("ABCD"), b"receipts").unwrap();
+        fs::write(dir.join("users").join("FFFF"), b"clashing").unwrap();
+        assert_eq!(storage.migrate(), Ok(3), "Incorrect number of files migrated");
+        assert_eq!(storage.read_entry(EntryId::from(0xABCD)).unwrap(), b"short", "Short entry not migrated");
+        assert_eq!(storage.read_user(ADMIN_USER_ID.into()).unwrap(), b"admin", "Reserved user not migrated");
+        assert_eq!(storage.read_receipts(EntryId::from(0xABCD)).unwrap(), b"receipts", "Receipts not migrated");
+        assert_eq!(storage.read_user(small_id).unwrap(), b"small", "Migration overwrote an existing file");
+        assert_eq!(storage.user_ids().unwrap(), HashSet::from([small_id, ADMIN_USER_ID.into()]), "Incorrect user ids");
+        assert_eq!(storage.migrate(), Ok(0), "Migrated the same files again");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn activity_written_with_the_change() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("message_board_activity_test_{:016X}", rand::rng().next_u64()));
+        let board = MessageBoard::with_defaults(dir.clone().into_boxed_path(), "127.0.0.1:0".parse().unwrap(), Box::new(FileStorage::new(dir.clone().into_boxed_path())));
+        let user_id = handle_add_user(&board);
+
+        let mut path = vec![EntryId::from(ROOT_ENTRY_ID)];
+        for text in ["thread", "reply"] {
+            let message = Entry::new_message(*path.last().unwrap(), user_id, 1, String::from(text));
+            let Ok(BoardResponse::AddEntry(entry_id)) = handle(&board, BoardRequest::AddEntry { user_id, entry: message }) else {
+                panic!("Failed to add an entry")
+            };
+            path.push(entry_id);
+        }
+        let reset_activity = || for entry_id in &path {
+            let mut entry = board.get_entry(*entry_id).unwrap();
+            entry.header_data.last_activity = 0;
+            board.overwrite_entry(*entry_id, entry).unwrap();
+        };
+        let check_activity = |changed_id: EntryId| {
+            let timestamp = board.get_entry(changed_id).unwrap().header_data.last_activity;
+            assert_ne!(timestamp, 0, "Activity not set on the change");
+            for entry_id in &path {
+                assert_eq!(board.get_entry(*entry_id).unwrap().header_data.last_activity, timestamp, "Activity not raised for an ancestor");
+            }
+            assert!(!dir.join(JOURNAL_FILE).exists(), "Journal left after a mutation");
+        };
+
+        reset_activity();
+        let message = Entry::new_message(*path.last().unwrap(), user_id, 2, String::from("deep"));
+        let Ok(BoardResponse::AddEntry(deep_id)) = handle(&board, BoardRequest::AddEntry { user_id, entry: message }) else {
+            panic!("Failed to add the deep reply")
+        };
+        check_activity(deep_id);
+
+        reset_activity();
+        let entry = board.get_entry(deep_id).unwrap();
+        assert_eq!(handle(&board, BoardRequest::EditEntry { user_id, entry_id: deep_id, entry }), Ok(BoardResponse::EditEntry), "Failed to edit");
+        check_activity(deep_id);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn entry_batch_all_or_nothing() {
+        let board = MessageBoard::in_memory();
+        let user_id = handle_add_user(&board);
+        let mut parent_ids = Vec::new();
+        for text in ["roomy", "nearly full"] {
+            let message = Entry::new_message(ROOT_ENTRY_ID.into(), user_id, 1, String::from(text));
+            let Ok(BoardResponse::AddEntry(parent_id)) = handle(&board, BoardRequest::AddEntry { user_id, entry: message }) else {
+                panic!("Failed to add a parent")
+            };
+            parent_ids.push(parent_id);
+        }
+        let (roomy_id, full_id) = (parent_ids[0], parent_ids[1]);
+        let mut full = board.get_entry(full_id).unwrap();
+        full.header_data.children_ids = (0..MAX_CHILDREN as u64 - 1).map(|id| EntryId::from(id + 0x1000)).collect();
+        board.overwrite_entry(full_id, full).unwrap();
+        let entry_count = board.entry_ids.read().unwrap().len();
+        let user_entries = board.get_user(user_id).unwrap().entry_ids;
+
+        // the last entry is one more than the nearly full parent has room for
+        let entries = [roomy_id, full_id, full_id].into_iter().map(|parent_id| Entry::new_message(parent_id, user_id, 2, String::from("batched"))).collect();
+        let response = handle(&board, BoardRequest::AddEntries { user_id, entries });
+        assert_eq!(response, Err(DataError::TooManyChildren), "Overfilled a parent");
+        assert!(board.get_entry(roomy_id).unwrap().header_data.children_ids.is_empty(), "Earlier entry of the batch added");
+        assert_eq!(board.get_entry(full_id).unwrap().header_data.children_ids.len(), MAX_CHILDREN - 1, "Part of the batch added");
+        assert_eq!(board.entry_ids.read().unwrap().len(), entry_count, "Part of the batch written");
+        assert_eq!(board.get_user(user_id).unwrap().entry_ids, user_entries, "Part of the batch given to the user");
+
+        let entries = [roomy_id, full_id].into_iter().map(|parent_id| Entry::new_message(parent_id, user_id, 2, String::from("batched"))).collect();
+        let Ok(BoardResponse::AddEntries(entry_ids)) = handle(&board, BoardRequest::AddEntries { user_id, entries }) else {
+            panic!("Failed to add a batch which fits")
+        };
+        assert_eq!(board.get_entry(roomy_id).unwrap().header_data.children_ids, vec![entry_ids[0]], "Batch not added");
+        assert_eq!(board.get_entry(full_id).unwrap().header_data.children_ids.last(), Some(&entry_ids[1]), "Batch not added");
+    }
+
+    #[test]
+    fn tree_depth_enforced() {
+        let mut board = MessageBoard::in_memory();
+        board.max_tree_depth = 2;
+        let user_id = handle_add_user(&board);
+        let add = |parent_id: EntryId| handle(&board, BoardRequest::AddEntry { user_id, entry: Entry::new_message(parent_id, user_id, 1, String::from("deep")) });
+
+        let Ok(BoardResponse::AddEntry(top_id)) = add(ROOT_ENTRY_ID.into()) else {panic!("Failed to add at depth 1")};
+        let Ok(BoardResponse::AddEntry(bottom_id)) = add(top_id) else {panic!("Failed to add at the max depth")};
+        assert_eq!(add(bottom_id), Err(DataError::DepthExceeded), "Added past the max depth");
+        let entries = vec![Entry::new_message(bottom_id, user_id, 1, String::from("deep"))];
+        assert_eq!(handle(&board, BoardRequest::AddEntries { user_id, entries }), Err(DataError::DepthExceeded), "Batch added past the max depth");
+        assert_eq!(handle(&board, BoardRequest::SubtreeCount { user_id, entry_id: ROOT_ENTRY_ID.into() }), Ok(BoardResponse::SubtreeCount(2)), "Count broken by the rejected adds");
+
+        // the moved entry's children move with it
+        let Ok(BoardResponse::AddEntry(other_id)) = add(ROOT_ENTRY_ID.into()) else {panic!("Failed to add at depth 1")};
+        let response = handle(&board, BoardRequest::MoveEntry { user_id, entry_id: top_id, new_parent_id: other_id });
+        assert_eq!(response, Err(DataError::DepthExceeded), "Moved children past the max depth");
+        let response = handle(&board, BoardRequest::MoveEntry { user_id, entry_id: bottom_id, new_parent_id: other_id });
+        assert_eq!(response, Ok(BoardResponse::MoveEntry), "Move within the max depth rejected");
+    }
+
+    #[test]
+    fn custom_handler_count() {
+        let rc_config = read_config("threads = 5").unwrap();
+        assert_eq!(MessageBoard::read_num_handlers(None, &rc_config), 5, "Thread count not read from the rc file");
+        assert_eq!(MessageBoard::read_num_handlers(Some(String::from(" 3\n")), &rc_config), 3, "Thread count not overridden by the env var");
+
+        let mut board = MessageBoard::in_memory();
+        board.num_handlers = MessageBoard::read_num_handlers(None, &rc_config);
+        let board: &'static MessageBoard = Box::leak(Box::new(board));
+        let (response_tx, response_rx) = mpsc::channel();
+        let handlers = MessageBoard::spawn_handlers(board.num_handlers, &response_tx);
+        assert_eq!(handlers.len(), 5, "Incorrect number of handlers");
+        // each handler answers under its own id
+        for handler in &handlers {
+            handler.send((board, BoardRequest::Status)).unwrap();
+        }
+        let mut handler_ids: Vec<u64> = (0..handlers.len()).map(|_| {
+            let response = response_rx.recv_timeout(Duration::from_secs(10)).expect("A handler never responded");
+            assert!(matches!(response.data, Ok(BoardResponse::Status { .. })), "Incorrect handler response");
+            response.handler_id
+        }).collect();
+        handler_ids.sort();
+        assert_eq!(handler_ids, (0..5).collect::<Vec<_>>(), "Not every handler registered");
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1")]
+    fn zero_handlers_rejected() {
+        MessageBoard::read_num_handlers(None, &read_config("threads = 0").unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1")]
+    fn zero_handlers_rejected_from_env() {
+        MessageBoard::read_num_handlers(Some(String::from("0")), &read_config("threads = 5").unwrap());
+    }
+}
@@ -0,0 +1,179 @@
+use log::*;
+use message_board::*;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use super::storage::Storage;
+
+/// kinds of `JournalWrite`
+const NEW_ENTRY: u8 = 0x00;
+const ENTRY: u8 = 0x01;
+const USER: u8 = 0x02;
+const REMOVE_ENTRY: u8 = 0x03;
+
+/// one of the writes making up a mutation, each can be redone without changing the outcome
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalWrite {
+    /// a new entry, written first so a colliding id fails before anything else is written
+    NewEntry(EntryId, Vec<u8>),
+    Entry(EntryId, Vec<u8>),
+    User(UserId, Vec<u8>),
+    RemoveEntry(EntryId),
+}
+
+impl JournalWrite {
+    fn extend_data(&self, data: &mut Vec<u8>) -> Result<(), DataError> {
+        let (kind, id, contents) = match self {
+            JournalWrite::NewEntry(entry_id, contents) => (NEW_ENTRY, **entry_id, Some(contents)),
+            JournalWrite::Entry(entry_id, contents) => (ENTRY, **entry_id, Some(contents)),
+            JournalWrite::User(user_id, contents) => (USER, **user_id, Some(contents)),
+            JournalWrite::RemoveEntry(entry_id) => (REMOVE_ENTRY, **entry_id, None),
+        };
+        data.push(kind);
+        data.extend_from_slice(&id.to_le_bytes());
+        if let Some(contents) = contents {
+            let len = u32::try_from(contents.len()).map_err(|_| DataError::OOBUsizeConversion)?;
+            data.extend_from_slice(&len.to_le_bytes());
+            data.extend_from_slice(contents);
+        }
+        Ok(())
+    }
+
+    fn from_data(data: &mut &[u8]) -> Result<Self, DataError> {
+        let kind = *take(data, 1)?.first().ok_or(DataError::InsufficientBytes)?;
+        let id = u64::from_le_bytes(take(data, 8)?.try_into().unwrap());
+        let mut contents = || -> Result<Vec<u8>, DataError> {
+            let len = u32::from_le_bytes(take(data, 4)?.try_into().unwrap());
+            Ok(take(data, checked_usize(len)?)?.to_vec())
+        };
+        match kind {
+            NEW_ENTRY => Ok(JournalWrite::NewEntry(id.into(), contents()?)),
+            ENTRY => Ok(JournalWrite::Entry(id.into(), contents()?)),
+            USER => Ok(JournalWrite::User(id.into(), contents()?)),
+            REMOVE_ENTRY => Ok(JournalWrite::RemoveEntry(id.into())),
+            _ => Err(DataError::InvalidDiscriminant),
+        }
+    }
+
+    /// `replaying` treats the write having already been done as success
+    fn apply(&self, storage: &dyn Storage, replaying: bool) -> Result<(), DataError> {
+        let result = match self {
+            JournalWrite::NewEntry(entry_id, contents) => storage.write_new_entry(*entry_id, contents),
+            JournalWrite::Entry(entry_id, contents) => storage.overwrite_entry(*entry_id, contents),
+            JournalWrite::User(user_id, contents) => storage.overwrite_user(*user_id, contents),
+            JournalWrite::RemoveEntry(entry_id) => storage.remove_entry(*entry_id),
+        };
+        match (self, result) {
+            (JournalWrite::NewEntry(..), Err(DataError::AlreadyExists)) if replaying => Ok(()),
+            (JournalWrite::RemoveEntry(_), Err(DataError::DoesNotExist)) if replaying => Ok(()),
+            (_, result) => result,
+        }
+    }
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], DataError> {
+    if data.len() < len {return Err(DataError::InsufficientBytes)}
+    let (taken, rest) = data.split_at(len);
+    *data = rest;
+    Ok(taken)
+}
+
+/// the writes of each mutation touching more than one entry / user, recorded before they're applied,
+/// so a crash part way through is completed by `replay` on startup rather than leaving the board inconsistent
+/// 
+/// file format: records back to back, each being 
+///     length of the rest of the record (u32), 
+///     number of writes (u32), 
+///     writes 1 - n: kind (u8), entry / user id (u64), then unless removing, length (u32) and the data
+/// 
+/// a record cut short by a crash while it was being written is dropped, none of its writes having been applied
+pub struct Journal {
+    path: Option<PathBuf>, // None to apply writes without recording them
+    lock: Mutex<()>, // held from recording a mutation until it has been applied
+}
+
+impl Journal {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path, lock: Mutex::new(()) }
+    }
+
+    /// records the writes, then applies them in order
+    /// 
+    /// if the first write fails the record is dropped, as nothing was written,
+    /// if a later one fails the record is kept, and is finished before the next writes are recorded
+    pub fn apply(&self, storage: &dyn Storage, writes: &[JournalWrite]) -> Result<(), DataError> {
+        let _guard = self.lock.lock().unwrap();
+        // clearing the journal once these are applied would otherwise lose a kept record
+        self.replay_records(storage)?;
+        self.record(writes)?;
+        for (idx, write) in writes.iter().enumerate() {
+            if let Err(e) = write.apply(storage, false) {
+                if idx == 0 {self.clear()?}
+                return Err(e);
+            }
+        }
+        self.clear()
+    }
+
+    /// appends a record of the writes, syncing it before returning
+    pub fn record(&self, writes: &[JournalWrite]) -> Result<(), DataError> {
+        let Some(path) = &self.path else {return Ok(())};
+        let mut body = Vec::new();
+        body.extend_from_slice(&(writes.len() as u32).to_le_bytes());
+        for write in writes {
+            write.extend_data(&mut body)?;
+        }
+        let len = u32::try_from(body.len()).map_err(|_| DataError::OOBUsizeConversion)?;
+        let mut file = fs::File::options().create(true).append(true).open(path)?;
+        file.write_all(&len.to_le_bytes())?;
+        file.write_all(&body)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// redoes the writes of every complete record, then empties the journal, 
+    /// giving how many records were replayed
+    /// 
+    /// a record which can never be finished, ie. overwriting an entry which has since been removed, 
+    /// is dropped with a warning rather than blocking every later mutation, 
+    /// only failing to reach the storage at all leaves the journal as it is
+    pub fn replay(&self, storage: &dyn Storage) -> Result<usize, DataError> {
+        let _guard = self.lock.lock().unwrap();
+        self.replay_records(storage)
+    }
+
+    /// `replay`, with the lock already held
+    fn replay_records(&self, storage: &dyn Storage) -> Result<usize, DataError> {
+        let Some(path) = &self.path else {return Ok(0)};
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let mut data = data.as_slice();
+        let mut replayed = 0;
+        // a record which can't be read in full is the one which was cut short
+        while let Ok(len) = take(&mut data, 4) {
+            let Ok(mut body) = take(&mut data, checked_usize(u32::from_le_bytes(len.try_into().unwrap()))?) else {break};
+            let num_writes = u32::from_le_bytes(take(&mut body, 4)?.try_into().unwrap());
+            let writes = (0..num_writes).map(|_| JournalWrite::from_data(&mut body)).collect::<Result<Vec<_>, _>>()?;
+            if let Err(e) = writes.iter().try_for_each(|write| write.apply(storage, true)) {
+                if let DataError::IoError(_) = e {return Err(e)}
+                warn!("Dropping a journal record of {} writes which can't be finished: {:?}", writes.len(), e);
+                continue;
+            }
+            replayed += 1;
+        }
+        self.clear()?;
+        Ok(replayed)
+    }
+
+    fn clear(&self) -> Result<(), DataError> {
+        let Some(path) = &self.path else {return Ok(())};
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,59 @@
+use message_board::*;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// the server's counters, given for `BoardRequest::Metrics` in the Prometheus text exposition format
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<&'static str, u64>>, // by `BoardRequest::type_name`
+    errors: Mutex<BTreeMap<&'static str, u64>>, // by `DataError::name`
+    active_clients: AtomicU64,
+    queue_depth: AtomicU64, // requests waiting on a handler
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_request(&self, request: &BoardRequest) {
+        *self.requests.lock().unwrap().entry(request.type_name()).or_default() += 1;
+    }
+
+    /// counted by variant, ie. every `PayloadTooLarge` together whatever its max
+    pub fn record_error(&self, error: &DataError) {
+        *self.errors.lock().unwrap().entry(error.name()).or_default() += 1;
+    }
+
+    pub fn set_active_clients(&self, active_clients: usize) {
+        self.active_clients.store(active_clients as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_queue_depth(&self, queue_depth: usize) {
+        self.queue_depth.store(queue_depth as u64, Ordering::Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        }
+
+        let mut out = String::new();
+        header(&mut out, "message_board_requests_total", "counter", "Requests received, by type.");
+        for (request_type, count) in self.requests.lock().unwrap().iter() {
+            let _ = writeln!(out, "message_board_requests_total{{type=\"{}\"}} {}", request_type, count);
+        }
+        header(&mut out, "message_board_errors_total", "counter", "Error responses sent, by error.");
+        for (error, count) in self.errors.lock().unwrap().iter() {
+            let _ = writeln!(out, "message_board_errors_total{{error=\"{}\"}} {}", error, count);
+        }
+        header(&mut out, "message_board_active_clients", "gauge", "Connected clients.");
+        let _ = writeln!(out, "message_board_active_clients {}", self.active_clients.load(Ordering::Relaxed));
+        header(&mut out, "message_board_queue_depth", "gauge", "Requests waiting on a handler.");
+        let _ = writeln!(out, "message_board_queue_depth {}", self.queue_depth.load(Ordering::Relaxed));
+        out
+    }
+}
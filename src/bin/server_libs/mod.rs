@@ -0,0 +1,3 @@
+pub mod storage;
+pub mod journal;
+pub mod metrics;
\ No newline at end of file
@@ -0,0 +1,417 @@
+use message_board::*;
+use message_board::utils::*;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// where the board keeps its entries and users, 
+/// each as its serialized data (see `lib.rs` for the formats) keyed by its id
+/// 
+/// the wire format is unaffected by which is used
+pub trait Storage: Send + Sync {
+    /// whether `create` has been run
+    fn exists(&self) -> bool;
+    /// the same kind of storage, empty, for a board whose files are in `file_dir`
+    fn for_dir(&self, file_dir: &Path) -> Box<dyn Storage>;
+    /// sets up whatever is missing, leaving anything already stored
+    fn create(&self) -> Result<(), DataError>;
+    /// moves anything stored the way an older version did to where it's now looked for, 
+    /// giving how much was moved
+    fn migrate(&self) -> Result<usize, DataError> {Ok(0)}
+
+    fn read_entry(&self, entry_id: EntryId) -> Result<Vec<u8>, DataError>;
+    /// fails with `AlreadyExists` if there is already an entry at `entry_id`
+    fn write_new_entry(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError>;
+    /// fails with `DoesNotExist` if there isn't an entry at `entry_id`
+    fn overwrite_entry(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError>;
+    fn remove_entry(&self, entry_id: EntryId) -> Result<(), DataError>;
+    fn entry_ids(&self) -> Result<HashSet<EntryId>, DataError>;
+
+    fn read_user(&self, user_id: UserId) -> Result<Vec<u8>, DataError>;
+    /// fails with `AlreadyExists` if there is already a user at `user_id`
+    fn write_new_user(&self, user_id: UserId, data: &[u8]) -> Result<(), DataError>;
+    /// fails with `DoesNotExist` if there isn't a user at `user_id`
+    fn overwrite_user(&self, user_id: UserId, data: &[u8]) -> Result<(), DataError>;
+    fn user_ids(&self) -> Result<HashSet<UserId>, DataError>;
+
+    /// the entry's `ReadReceipts` data, empty if no reads have been recorded
+    fn read_receipts(&self, entry_id: EntryId) -> Result<Vec<u8>, DataError>;
+    /// replaces the entry's receipts, empty `data` removes them
+    fn write_receipts(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError>;
+}
+
+/// a file per entry in `entries` and per user in `users`, named with their ids as 16 hex digits, 
+/// and the read receipts of an entry in `receipts` under the entry's id
+pub struct FileStorage {
+    file_dir: Box<Path>,
+}
+
+impl FileStorage {
+    pub fn new(file_dir: Box<Path>) -> Self {
+        Self { file_dir }
+    }
+
+    fn entry_path(&self, entry_id: EntryId) -> PathBuf {
+        let mut path = PathBuf::from(self.file_dir.clone());
+        path.push(format!("entries/{:016X}", *entry_id));
+        path
+    }
+
+    fn receipts_path(&self, entry_id: EntryId) -> PathBuf {
+        let mut path = PathBuf::from(self.file_dir.clone());
+        path.push(format!("receipts/{:016X}", *entry_id));
+        path
+    }
+
+    fn user_path(&self, user_id: UserId) -> PathBuf {
+        let mut path = PathBuf::from(self.file_dir.clone());
+        path.push(format!("users/{:016X}", *user_id));
+        path
+    }
+
+    /// the id of a file in `dir` named the way older versions did, 
+    /// ie. with fewer hex digits, or for users with the names `UserId` displays for the reserved ids
+    fn legacy_id(dir: &str, file_name: &str) -> Option<u64> {
+        if dir == "users" {
+            let reserved = [SERVER_USER_ID, ADMIN_USER_ID, ANONYMOUS_USER_ID].into_iter().find(|id| UserId::from(*id).to_string() == file_name);
+            if reserved.is_some() {return reserved}
+        }
+        if file_name.len() >= 16 || !file_name.chars().all(|c| c.is_ascii_hexdigit()) {return None}
+        u64::from_str_radix(file_name, 16).ok()
+    }
+
+    /// the ids the files in `dir` are named with,
+    /// skipping anything that isn't named with one, ie. temp files left by a crash mid write
+    fn read_ids<T: From<u64>>(&self, dir: &str) -> Result<Vec<T>, DataError> {
+        let mut path = PathBuf::from(self.file_dir.clone());
+        path.push(dir);
+        Ok(fs::read_dir(&path)?.filter_map(|file| {
+            u64::from_str_radix(file.ok()?.file_name().to_str()?, 16).ok().map(T::from)
+        }).collect())
+    }
+}
+
+impl Storage for FileStorage {
+    fn exists(&self) -> bool {
+        ["entries", "users", "receipts"].iter().all(|dir| self.file_dir.join(dir).exists())
+    }
+
+    fn for_dir(&self, file_dir: &Path) -> Box<dyn Storage> {
+        Box::new(FileStorage::new(file_dir.into()))
+    }
+
+    fn create(&self) -> Result<(), DataError> {
+        for dir in ["entries", "users", "receipts"] {
+            fs::create_dir_all(self.file_dir.join(dir))?;
+        }
+        Ok(())
+    }
+
+    fn migrate(&self) -> Result<usize, DataError> {
+        let mut renamed = 0;
+        for dir in ["entries", "users", "receipts"] {
+            let dir_path = self.file_dir.join(dir);
+            if !dir_path.exists() {continue;}
+            for file in fs::read_dir(&dir_path)? {
+                let file = file?;
+                let Some(id) = file.file_name().to_str().and_then(|name| Self::legacy_id(dir, name)) else {continue;};
+                let new_path = dir_path.join(format!("{:016X}", id));
+                // whatever is already at the new name is kept, leaving the old file to be looked at
+                if new_path.exists() {continue;}
+                fs::rename(file.path(), new_path)?;
+                renamed += 1;
+            }
+        }
+        Ok(renamed)
+    }
+
+    fn read_entry(&self, entry_id: EntryId) -> Result<Vec<u8>, DataError> {
+        Ok(fs::read(self.entry_path(entry_id))?)
+    }
+
+    fn write_new_entry(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError> {
+        Ok(write_new_atomic(self.entry_path(entry_id), data)?)
+    }
+
+    fn overwrite_entry(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError> {
+        Ok(overwrite_atomic(self.entry_path(entry_id), data)?)
+    }
+
+    fn remove_entry(&self, entry_id: EntryId) -> Result<(), DataError> {
+        Ok(fs::remove_file(self.entry_path(entry_id))?)
+    }
+
+    fn entry_ids(&self) -> Result<HashSet<EntryId>, DataError> {
+        Ok(self.read_ids("entries")?.into_iter().collect())
+    }
+
+    fn read_user(&self, user_id: UserId) -> Result<Vec<u8>, DataError> {
+        Ok(fs::read(self.user_path(user_id))?)
+    }
+
+    fn write_new_user(&self, user_id: UserId, data: &[u8]) -> Result<(), DataError> {
+        Ok(write_new_atomic(self.user_path(user_id), data)?)
+    }
+
+    fn overwrite_user(&self, user_id: UserId, data: &[u8]) -> Result<(), DataError> {
+        //FIXME: completely overwrites, even for small edits
+        Ok(overwrite_atomic(self.user_path(user_id), data)?)
+    }
+
+    fn user_ids(&self) -> Result<HashSet<UserId>, DataError> {
+        Ok(self.read_ids("users")?.into_iter().collect())
+    }
+
+    fn read_receipts(&self, entry_id: EntryId) -> Result<Vec<u8>, DataError> {
+        match fs::read(self.receipts_path(entry_id)) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            result => Ok(result?),
+        }
+    }
+
+    fn write_receipts(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError> {
+        let path = self.receipts_path(entry_id);
+        let result = if data.is_empty() {
+            fs::remove_file(&path)
+        } else {
+            overwrite_atomic(&path, data)
+        };
+        match result {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && data.is_empty() => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(write_new_atomic(&path, data)?),
+            result => Ok(result?),
+        }
+    }
+}
+
+/// an `entries`, a `users` and a `receipts` table in a single database file, 
+/// the ids are stored as their i64 bit patterns
+#[cfg(feature = "sqlite")]
+pub struct SqliteStorage {
+    path: PathBuf,
+    connection: std::sync::Mutex<Option<rusqlite::Connection>>, // opened on first use, which also creates the tables
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, connection: std::sync::Mutex::new(None) }
+    }
+
+    fn with_connection<T>(&self, f: impl FnOnce(&rusqlite::Connection) -> rusqlite::Result<T>) -> Result<T, DataError> {
+        let mut connection = self.connection.lock().unwrap();
+        if connection.is_none() {
+            let opened = rusqlite::Connection::open(&self.path).map_err(sql_error)?;
+            opened.execute_batch("
+                CREATE TABLE IF NOT EXISTS entries (id INTEGER PRIMARY KEY, data BLOB NOT NULL);
+                CREATE TABLE IF NOT EXISTS users (id INTEGER PRIMARY KEY, data BLOB NOT NULL);
+                CREATE TABLE IF NOT EXISTS receipts (id INTEGER PRIMARY KEY, data BLOB NOT NULL);
+            ").map_err(sql_error)?;
+            *connection = Some(opened);
+        }
+        f(connection.as_ref().unwrap()).map_err(sql_error)
+    }
+
+    fn read(&self, table: &str, id: u64) -> Result<Vec<u8>, DataError> {
+        self.with_connection(|connection| {
+            connection.query_row(&format!("SELECT data FROM {} WHERE id = ?1", table), [id as i64], |row| row.get(0))
+        })
+    }
+
+    fn write_new(&self, table: &str, id: u64, data: &[u8]) -> Result<(), DataError> {
+        self.with_connection(|connection| {
+            connection.execute(&format!("INSERT INTO {} (id, data) VALUES (?1, ?2)", table), rusqlite::params![id as i64, data])
+        })?;
+        Ok(())
+    }
+
+    fn overwrite(&self, table: &str, id: u64, data: &[u8]) -> Result<(), DataError> {
+        let changed = self.with_connection(|connection| {
+            connection.execute(&format!("UPDATE {} SET data = ?2 WHERE id = ?1", table), rusqlite::params![id as i64, data])
+        })?;
+        if changed == 0 {return Err(DataError::DoesNotExist)}
+        Ok(())
+    }
+
+    fn ids<T: From<u64> + Eq + std::hash::Hash>(&self, table: &str) -> Result<HashSet<T>, DataError> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare(&format!("SELECT id FROM {}", table))?;
+            let ids = statement.query_map([], |row| row.get::<_, i64>(0))?.map(|id| id.map(|id| T::from(id as u64))).collect();
+            ids
+        })
+    }
+}
+
+/// missing rows and taken ids map onto the same errors as the file storage's
+#[cfg(feature = "sqlite")]
+fn sql_error(e: rusqlite::Error) -> DataError {
+    match e {
+        rusqlite::Error::QueryReturnedNoRows => DataError::DoesNotExist,
+        rusqlite::Error::SqliteFailure(failure, _) if failure.code == rusqlite::ErrorCode::ConstraintViolation => DataError::AlreadyExists,
+        e => {
+            log::warn!("SQLite error: {}", e);
+            DataError::IoError(std::io::ErrorKind::Other)
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Storage for SqliteStorage {
+    fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    fn for_dir(&self, file_dir: &Path) -> Box<dyn Storage> {
+        let file_name = self.path.file_name().expect("The sqlite path should name a file");
+        Box::new(SqliteStorage::new(file_dir.join(file_name)))
+    }
+
+    fn create(&self) -> Result<(), DataError> {
+        self.with_connection(|_| Ok(()))
+    }
+
+    fn read_entry(&self, entry_id: EntryId) -> Result<Vec<u8>, DataError> {
+        self.read("entries", *entry_id)
+    }
+
+    fn write_new_entry(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError> {
+        self.write_new("entries", *entry_id, data)
+    }
+
+    fn overwrite_entry(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError> {
+        self.overwrite("entries", *entry_id, data)
+    }
+
+    fn remove_entry(&self, entry_id: EntryId) -> Result<(), DataError> {
+        let changed = self.with_connection(|connection| connection.execute("DELETE FROM entries WHERE id = ?1", [*entry_id as i64]))?;
+        if changed == 0 {return Err(DataError::DoesNotExist)}
+        Ok(())
+    }
+
+    fn entry_ids(&self) -> Result<HashSet<EntryId>, DataError> {
+        self.ids("entries")
+    }
+
+    fn read_user(&self, user_id: UserId) -> Result<Vec<u8>, DataError> {
+        self.read("users", *user_id)
+    }
+
+    fn write_new_user(&self, user_id: UserId, data: &[u8]) -> Result<(), DataError> {
+        self.write_new("users", *user_id, data)
+    }
+
+    fn overwrite_user(&self, user_id: UserId, data: &[u8]) -> Result<(), DataError> {
+        self.overwrite("users", *user_id, data)
+    }
+
+    fn user_ids(&self) -> Result<HashSet<UserId>, DataError> {
+        self.ids("users")
+    }
+
+    fn read_receipts(&self, entry_id: EntryId) -> Result<Vec<u8>, DataError> {
+        match self.read("receipts", *entry_id) {
+            Err(DataError::DoesNotExist) => Ok(Vec::new()),
+            result => result,
+        }
+    }
+
+    fn write_receipts(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError> {
+        self.with_connection(|connection| if data.is_empty() {
+            connection.execute("DELETE FROM receipts WHERE id = ?1", [*entry_id as i64])
+        } else {
+            connection.execute("INSERT OR REPLACE INTO receipts (id, data) VALUES (?1, ?2)", rusqlite::params![*entry_id as i64, data])
+        })?;
+        Ok(())
+    }
+}
+
+/// kept in memory only, so handlers can be tested without any files
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStorage {
+    entries: std::sync::RwLock<std::collections::HashMap<EntryId, Vec<u8>>>,
+    users: std::sync::RwLock<std::collections::HashMap<UserId, Vec<u8>>>,
+    receipts: std::sync::RwLock<std::collections::HashMap<EntryId, Vec<u8>>>,
+}
+
+#[cfg(test)]
+impl MemoryStorage {
+    fn write_new<T: Eq + std::hash::Hash>(map: &std::sync::RwLock<std::collections::HashMap<T, Vec<u8>>>, id: T, data: &[u8]) -> Result<(), DataError> {
+        let mut map = map.write().unwrap();
+        if map.contains_key(&id) {return Err(DataError::AlreadyExists)}
+        map.insert(id, data.to_vec());
+        Ok(())
+    }
+
+    fn overwrite<T: Eq + std::hash::Hash>(map: &std::sync::RwLock<std::collections::HashMap<T, Vec<u8>>>, id: T, data: &[u8]) -> Result<(), DataError> {
+        let mut map = map.write().unwrap();
+        let stored = map.get_mut(&id).ok_or(DataError::DoesNotExist)?;
+        *stored = data.to_vec();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl Storage for MemoryStorage {
+    fn exists(&self) -> bool {
+        true
+    }
+
+    fn for_dir(&self, _file_dir: &Path) -> Box<dyn Storage> {
+        Box::new(MemoryStorage::default())
+    }
+
+    fn create(&self) -> Result<(), DataError> {
+        Ok(())
+    }
+
+    fn read_entry(&self, entry_id: EntryId) -> Result<Vec<u8>, DataError> {
+        self.entries.read().unwrap().get(&entry_id).cloned().ok_or(DataError::DoesNotExist)
+    }
+
+    fn write_new_entry(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError> {
+        Self::write_new(&self.entries, entry_id, data)
+    }
+
+    fn overwrite_entry(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError> {
+        Self::overwrite(&self.entries, entry_id, data)
+    }
+
+    fn remove_entry(&self, entry_id: EntryId) -> Result<(), DataError> {
+        self.entries.write().unwrap().remove(&entry_id).map(|_| ()).ok_or(DataError::DoesNotExist)
+    }
+
+    fn entry_ids(&self) -> Result<HashSet<EntryId>, DataError> {
+        Ok(self.entries.read().unwrap().keys().copied().collect())
+    }
+
+    fn read_user(&self, user_id: UserId) -> Result<Vec<u8>, DataError> {
+        self.users.read().unwrap().get(&user_id).cloned().ok_or(DataError::DoesNotExist)
+    }
+
+    fn write_new_user(&self, user_id: UserId, data: &[u8]) -> Result<(), DataError> {
+        Self::write_new(&self.users, user_id, data)
+    }
+
+    fn overwrite_user(&self, user_id: UserId, data: &[u8]) -> Result<(), DataError> {
+        Self::overwrite(&self.users, user_id, data)
+    }
+
+    fn user_ids(&self) -> Result<HashSet<UserId>, DataError> {
+        Ok(self.users.read().unwrap().keys().copied().collect())
+    }
+
+    fn read_receipts(&self, entry_id: EntryId) -> Result<Vec<u8>, DataError> {
+        Ok(self.receipts.read().unwrap().get(&entry_id).cloned().unwrap_or_default())
+    }
+
+    fn write_receipts(&self, entry_id: EntryId, data: &[u8]) -> Result<(), DataError> {
+        let mut receipts = self.receipts.write().unwrap();
+        if data.is_empty() {
+            receipts.remove(&entry_id);
+        } else {
+            receipts.insert(entry_id, data.to_vec());
+        }
+        Ok(())
+    }
+}
\ No newline at end of file
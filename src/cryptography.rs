@@ -152,6 +152,26 @@ impl SimpleAeadKey {
         let aead = RawAead::new(&self.key);
         Ok(aead.decrypt(&[0u8; _].into(), payload)?)
     }
+
+    /// as `encrypt`, but with `index` as the nonce so a key can encrypt each of a series of chunks (ie. of a streamed response),
+    /// a chunk only decrypts at the index it was encrypted at so they can't be reordered
+    ///
+    /// the key should only be used for the one series, and not with `encrypt` as that shares the nonce of index 0
+    pub fn encrypt_chunk(&self, index: u64, to_encrypt: &[u8]) -> Result<Vec<u8>, DataError> {
+        let aead = RawAead::new(&self.key);
+        Ok(aead.encrypt(&Self::chunk_nonce(index).into(), to_encrypt)?)
+    }
+
+    pub fn decrypt_chunk(&self, index: u64, to_decrypt: &[u8]) -> Result<Vec<u8>, DataError> {
+        let aead = RawAead::new(&self.key);
+        Ok(aead.decrypt(&Self::chunk_nonce(index).into(), to_decrypt)?)
+    }
+
+    fn chunk_nonce(index: u64) -> RawAeadNonce {
+        let mut raw_nonce: RawAeadNonce = [0; _];
+        raw_nonce[..8].copy_from_slice(&index.to_le_bytes());
+        raw_nonce
+    }
 }
 
 impl AsData for SimpleAeadKey {
@@ -359,7 +379,7 @@ impl DecapsulationKey {
         }
         if len > key.len() {return Err(DataError::EncryptionError)}
         let key = key.into_iter();
-        Ok(key.take(len as usize))    
+        Ok(key.take(len))    
     }
 
     pub fn raw_decapsulate(&self, cipher_text: RawKemCipherText) -> Result<RawSharedKey, DataError> {self.key.decapsulate(&cipher_text).map_err(|_| DataError::EncryptionError)}
@@ -395,7 +415,7 @@ pub fn extend_with_exposed_block(output_stream: &mut Vec<u8>, to_encode: &[u8])
 
 pub fn read_from_exposed_block(input_stream: &mut impl Iterator<Item = u8>) -> Result<impl Iterator<Item = u8>, DataError> {
     let len = read_u64(input_stream)?;
-    Ok(input_stream.take(len as usize))
+    Ok(input_stream.take(checked_usize(len)?))
 }
 
 pub fn extend_with_full_anonymous_block(rng: impl OldCryptoRng + OldRngCore, keys: &mut PublicKeySet, output_stream: &mut Vec<u8>, to_encrypt: &[u8]) -> Result<SimpleAeadKey, DataError> {
@@ -425,7 +445,7 @@ pub fn read_from_full_anonymous_block(kem_dk: &DecapsulationKey, input_stream: &
     }
     let aead = SimpleAeadKey::new_from_key(aead_key.into());
 
-    let aead_len = read_u64(input_stream)? as usize;
+    let aead_len = checked_usize(read_u64(input_stream)?)?;
     let aead_pt = aead.decrypt(&input_stream.take(aead_len).collect::<Vec<_>>(), &[])?;
     Ok((aead, aead_pt))
 }
@@ -447,14 +467,16 @@ pub fn extend_with_user_block(mut rng: impl OldCryptoRng + OldRngCore, keys: &mu
     Ok(())
 }
 
+/// the AEAD tag of the user's key authenticates the sender, so a claimed `user_id` which 
+/// doesn't match the key used gives `BadCredentials`
 pub fn read_from_user_block<'a, F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(kem_dk: &DecapsulationKey, input_stream: &mut impl Iterator<Item = u8>, get_user_aead: F) -> Result<(UserId, Vec<u8>), DataError> {
     let kem_ct = KemCipherText::from_data_iter(input_stream)?;
     let mut packed_kem_sk = kem_dk.decapsulate(kem_ct, 8 + 16)?;
     let user_id = read_u64(&mut packed_kem_sk)?.into();
     let nonce = read_u128(&mut packed_kem_sk)? & AEAD_NONCE_MAX;
     let aead = &mut *get_user_aead(user_id).map_or(Err(DataError::MissingKey), |x| Ok(x))?;
-    let aead_len = read_u64(input_stream)? as usize;
-    let aead_pt = aead.decrypt(nonce, &input_stream.take(aead_len).collect::<Vec<_>>(), &[])?;
+    let aead_len = checked_usize(read_u64(input_stream)?)?;
+    let aead_pt = aead.decrypt(nonce, &input_stream.take(aead_len).collect::<Vec<_>>(), &[]).map_err(|_| DataError::BadCredentials)?;
     Ok((user_id, aead_pt))
 }
 
@@ -468,7 +490,7 @@ pub fn extend_with_full_anonymous_response_block(key: &SimpleAeadKey, output_str
 
 pub fn read_from_full_anonymous_response_block<'a>(keys: impl Iterator<Item = &'a SimpleAeadKey>, input_stream: &mut impl Iterator<Item = u8>) -> Result<(&'a SimpleAeadKey, Vec<u8>), DataError> {
     let len = read_u64(input_stream)?;
-    let ct = input_stream.take(len as usize).collect::<Vec<_>>();
+    let ct = input_stream.take(checked_usize(len)?).collect::<Vec<_>>();
     for key in keys {
         if let Ok(pt) = key.decrypt(&ct, &[]) {
             return Ok((key, pt));
@@ -496,11 +518,11 @@ pub fn read_from_user_response_block(key: &mut UserAeadKey, input_stream: &mut i
     let simple_key = key.derive_simple_key();
 
     let header_ct_len = read_u16(input_stream)?;
-    let header_ct = input_stream.take(header_ct_len as usize).collect::<Vec<_>>();
+    let header_ct = input_stream.take(checked_usize(header_ct_len)?).collect::<Vec<_>>();
     let nonce: [u8; 16] = simple_key.decrypt(&header_ct, &[])?.try_into().map_err(|_| internal_error!())?;
     let nonce = u128::from_le_bytes(nonce) % AEAD_NONCE_MOD;
 
     let body_ct_len = read_u64(input_stream)?;
-    let body_ct = input_stream.take(body_ct_len as usize).collect::<Vec<_>>();
+    let body_ct = input_stream.take(checked_usize(body_ct_len)?).collect::<Vec<_>>();
     key.decrypt(nonce, &body_ct, &[])
 }
\ No newline at end of file
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::*;
+
+/// when each entry was last looked at, kept on disk to tell which threads have had activity since
+///
+/// file format: (entry_id (u64), timestamp (secs since Unix Epoch) (u64)) back to back
+#[derive(Debug)]
+pub struct LastSeen {
+    path: PathBuf,
+    timestamps: HashMap<EntryId, u64>,
+}
+
+impl LastSeen {
+    /// reads the timestamps at `path`, none if there isn't a file yet
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, DataError> {
+        let path = path.into();
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut data_iter = data.into_iter().peekable();
+        let mut timestamps = HashMap::new();
+        while data_iter.peek().is_some() {
+            let entry_id = read_u64(&mut data_iter)?.into();
+            timestamps.insert(entry_id, read_u64(&mut data_iter)?);
+        }
+        Ok(Self { path, timestamps })
+    }
+
+    /// when the entry was last seen, None if it never has been
+    pub fn get(&self, entry_id: EntryId) -> Option<u64> {
+        self.timestamps.get(&entry_id).copied()
+    }
+
+    /// records the entry as seen at `timestamp`, never moving it back
+    pub fn see(&mut self, entry_id: EntryId, timestamp: u64) -> Result<(), DataError> {
+        let last_seen = self.timestamps.entry(entry_id).or_insert(timestamp);
+        *last_seen = (*last_seen).max(timestamp);
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), DataError> {
+        let mut data = Vec::with_capacity(self.timestamps.len() * 16);
+        for (entry_id, timestamp) in &self.timestamps {
+            data.extend_from_slice(&entry_id.to_le_bytes());
+            data.extend_from_slice(&timestamp.to_le_bytes());
+        }
+        match utils::overwrite_atomic(&self.path, &data) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => utils::write_new_atomic(&self.path, &data)?,
+            result => result?,
+        }
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -1,8 +1,10 @@
-use std::borrow::{Borrow, BorrowMut};
-use std::collections::VecDeque;
+use std::borrow::{Borrow, BorrowMut, Cow};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Display;
+use std::io::{IoSlice, Read, Write};
 use std::ops::{Deref, DerefMut};
 use std::string::FromUtf8Error;
+use std::time::{Duration, Instant};
 
 use crate::cryptography::*;
 
@@ -17,6 +19,7 @@ pub const USER_MAGIC_NUMBER: u16 = 0x1470;
 
 pub const SERVER_USER_ID: u64 = 0;
 pub const ADMIN_USER_ID: u64 = 1;
+/// also the id guests browse as, which the board only lets read (see `BoardRequest::is_read_only`)
 pub const ANONYMOUS_USER_ID: u64 = 2;
 
 pub const RESERVED_USER_IDS: [u64; 3] = [
@@ -27,28 +30,115 @@ pub const RESERVED_USER_IDS: [u64; 3] = [
 
 pub const ROOT_ENTRY_ID: u64 = 0;
 
+/// entry size limits, checked by the server before anything is written
+/// message size in bytes, the server's can be set with the `max_message_size` rc key
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 1 << 20;
+/// in bytes
+pub const MAX_ACCESS_GROUP_NAME_SIZE: usize = 256;
+/// per perm set
+pub const MAX_PERM_IDS: usize = 4096;
+/// per entry, the count is stored as a u16
+pub const MAX_CHILDREN: usize = u16::MAX as usize;
+/// in bytes, enough for the longer emoji sequences
+pub const MAX_REACTION_SIZE: usize = 32;
+/// in bytes, an attachment's data is held to the message size limit instead
+pub const MAX_ATTACHMENT_NAME_SIZE: usize = 255;
+pub const MAX_MIME_SIZE: usize = 255;
+/// in bytes, see `UserData::display_name`
+pub const MAX_DISPLAY_NAME_SIZE: usize = 64;
+/// how many entries deep the walks through the tree go (ie. `resolve_access`) before giving `DepthExceeded`,
+/// the server's can be set with the `max_tree_depth` rc key
+pub const DEFAULT_MAX_TREE_DEPTH: usize = 1024;
+/// in bytes, see `validate_board_name`
+pub const MAX_BOARD_NAME_SIZE: usize = 32;
+/// the board requests go to unless they name another, the only one a server without named boards has
+pub const MAIN_BOARD: &str = "main";
+/// what is left of a deleted entry's message / name / emoji, see `Entry::tombstone`
+pub const DELETED_PLACEHOLDER: &str = "[deleted]";
+
 /// file versions
-pub const ENTRY_FILE_VERSION: u8 = 0x00;
-pub const USER_FILE_VERSION: u8 = 0x00;
+pub const ENTRY_FILE_VERSION: u8 = 0x04;
+pub const USER_FILE_VERSION: u8 = 0x01;
 pub const REQUEST_FORMAT_VERSION: u8 = 0x00;
-pub const RESPONSE_FORMAT_VERSION: u8 = 0x00;
+/// secure requests to a board other than `MAIN_BOARD`, which name it after the version
+pub const NAMED_BOARD_REQUEST_VERSION: u8 = 0x01;
+/// secure requests with response flags (ie. `CHUNKED_RESPONSE_FLAG`) at the start of their encrypted body, 
+/// which name their board after the version like `NAMED_BOARD_REQUEST_VERSION`, `MAIN_BOARD` included
+pub const FLAGGED_REQUEST_VERSION: u8 = 0x02;
+pub const RESPONSE_FORMAT_VERSION: u8 = 0x01;
+/// the versions of the protocol as a whole agreed on by `negotiate_version`, 
+/// bumped along with either format version, 
+/// `NegotiateVersion` itself is always sent to `MAIN_BOARD` in the `REQUEST_FORMAT_VERSION` frame 
+/// and answered in the `RESPONSE_FORMAT_VERSION` frame, which every version keeps, 
+/// so that either side can move on without breaking the handshake
+pub const MIN_PROTOCOL_VERSION: u8 = 0x01;
+pub const PROTOCOL_VERSION: u8 = 0x03;
+/// the first protocol version with boards other than `MAIN_BOARD`, 
+/// ie. `NAMED_BOARD_REQUEST_VERSION` and `ListBoards`
+pub const NAMED_BOARDS_PROTOCOL_VERSION: u8 = 0x02;
+/// the first protocol version with `GetRoot`, every board before it is rooted at `ROOT_ENTRY_ID`
+pub const GET_ROOT_PROTOCOL_VERSION: u8 = 0x02;
+/// the first protocol version with response flags, ie. `FLAGGED_REQUEST_VERSION`
+pub const RESPONSE_FLAGS_PROTOCOL_VERSION: u8 = 0x03;
+/// the first protocol version whose `Status` responses give the board's max message size
+pub const STATUS_LIMITS_PROTOCOL_VERSION: u8 = 0x03;
 
 /// file discriminants 
 /// General Use
 pub const ERROR: u8 = 0xff;
+/// Error codes, following ERROR in responses
+pub const UNSPECIFIED_ERROR: u8 = 0x00;
+pub const RATE_LIMITED_ERROR: u8 = 0x01;
+pub const BAD_CREDENTIALS_ERROR: u8 = 0x02;
+pub const DOES_NOT_EXIST_ERROR: u8 = 0x03;
+pub const INSUFFICIENT_PERMS_ERROR: u8 = 0x04;
+pub const VERSION_MISMATCH_ERROR: u8 = 0x05;
+pub const TOO_MANY_CHILDREN_ERROR: u8 = 0x06;
+pub const INVALID_STRUCTURE_ERROR: u8 = 0x08;
+pub const DEPTH_EXCEEDED_ERROR: u8 = 0x09;
+pub const EMPTY_REACTION_ERROR: u8 = 0x0A;
+/// followed by the max (u64)
+pub const PAYLOAD_TOO_LARGE_ERROR: u8 = 0x0B;
+pub const CYCLIC_MOVE_ERROR: u8 = 0x0C;
+pub const EDITTED_LOCATION_ERROR: u8 = 0x0D;
+pub const MALFORMED_ROOT_ERROR: u8 = 0x0E;
 /// Entry
 pub const MESSAGE: u8 = 0x00;
 pub const ACCESS_GROUP: u8 = 0x01;
+pub const REACTION: u8 = 0x02;
+pub const ATTACHMENT: u8 = 0x03;
+/// Message body encoding (entry version 1+)
+pub const RAW_BODY: u8 = 0x00;
+pub const DEFLATE_BODY: u8 = 0x01;
 /// Request & Response
 /// 0x0_ & 0x1_: entry related requests
 pub const GET_ENTRY: u8 = 0x00;
 pub const ADD_ENTRY: u8 = 0x01;
 pub const EDIT_ENTRY: u8 = 0x02;
+pub const MOVE_ENTRY: u8 = 0x03;
+pub const ADD_ENTRIES: u8 = 0x04;
+pub const DELETE_ENTRY: u8 = 0x05;
+pub const GET_ANCESTORS: u8 = 0x06;
+pub const SET_PINNED: u8 = 0x07;
+pub const GET_ENTRIES: u8 = 0x08;
+pub const CHILDREN_SINCE: u8 = 0x09;
+pub const FILTER_ACCESSIBLE: u8 = 0x0A;
+pub const MARK_READ: u8 = 0x0B;
+pub const SUBTREE_COUNT: u8 = 0x0C;
+pub const GET_PERMS_SUMMARY: u8 = 0x0D;
 /// 0x2_ & 0x3_ (?): user related requests
 pub const GET_USER: u8 = 0x20;
 pub const ADD_USER: u8 = 0x21;
+pub const LIST_USERS: u8 = 0x22;
+pub const SET_DISPLAY_NAME: u8 = 0x23;
 /// 0x8_: network / connection related requests
 pub const GET_KEM_EK: u8 = 0x80;
+pub const STATUS: u8 = 0x81;
+pub const PING: u8 = 0x82;
+pub const NEGOTIATE_VERSION: u8 = 0x83;
+pub const GET_ROOT: u8 = 0x84;
+pub const LIST_BOARDS: u8 = 0x85;
+pub const METRICS: u8 = 0x86;
 /// encrypted variants
 pub const EXPOSED: u8 = 0x00;
 pub const FULL_ANON: u8 = 0x01;
@@ -58,12 +148,25 @@ pub const USER: u8 = 0x02;
 pub const INHERIT_BASE: u8 = 0x00;
 pub const WHITE_BASE: u8 = 0x01;
 pub const BLACK_BASE: u8 = 0x02;
+pub const READ_PERM: u8 = 0x00;
+pub const WRITE_PERM: u8 = 0x01;
 
 macro_rules! u64_id {
     ($struct:ident) => {
-        #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
         pub struct $struct(u64);
 
+        impl $struct {
+            pub fn from_le_bytes(bytes: [u8; 8]) -> Self {
+                Self(u64::from_le_bytes(bytes))
+            }
+
+            /// as on the wire and in files
+            pub fn to_le_bytes(self) -> [u8; 8] {
+                self.0.to_le_bytes()
+            }
+        }
+
         impl From<u64> for $struct {
             fn from(value: u64) -> Self {Self(value)}
         }
@@ -129,22 +232,215 @@ impl Display for EntryId {
 }
 
 pub mod cryptography;
+pub mod stream;
+pub mod outbox;
+pub mod last_seen;
+pub mod tree_preview;
+pub mod perms_draft;
+#[cfg(feature = "markdown")]
+pub mod markdown;
 
 #[cfg(test)]
 pub mod tests;
 
 pub mod utils {
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
     pub fn stdin_y_n(stdin: &mut std::io::Stdin, buffer: &mut String) -> bool {
-    loop {
-        let _ = stdin.read_line(buffer);
-        *buffer = buffer.trim().to_ascii_lowercase();
-        match buffer.as_ref() {
-            "y" => return true,
-            "n" => return false,
-            _ => continue
+        read_y_n(&mut stdin.lock(), buffer)
+    }
+
+    /// reads lines until one is a y or n, a closed input counts as n
+    pub fn read_y_n(input: &mut impl std::io::BufRead, buffer: &mut String) -> bool {
+        loop {
+            buffer.clear();
+            match input.read_line(buffer) {
+                Ok(0) | Err(_) => return false,
+                Ok(_) => {},
+            }
+            match buffer.trim().to_ascii_lowercase().as_ref() {
+                "y" => return true,
+                "n" => return false,
+                _ => continue
+            }
         }
     }
-}
+
+    /// parses an rc file, keeping its comments and formatting for when it is written back
+    pub fn read_config(contents: &str) -> Result<toml_edit::DocumentMut, toml_edit::TomlError> {
+        contents.parse::<toml_edit::DocumentMut>()
+    }
+
+    /// reads a user id from the config, either an integer, a hex string as ids are displayed or "None"
+    pub fn parse_user_id(value: &toml_edit::Item) -> Result<Option<u64>, String> {
+        const FORMS: &str = "an integer, a hex string (ie. \"00000000ABCD\") or \"None\"";
+        // written back as an i64, so ids past i64::MAX wrap to negative
+        if let Some(id) = value.as_integer() {return Ok(Some(id as u64))}
+        match value.as_str() {
+            Some("None") => Ok(None),
+            Some(hex) => u64::from_str_radix(hex, 16).map(Some).map_err(|_| format!("\"{}\" isn't a user id, it should be {}", hex, FORMS)),
+            None => Err(format!("a user id should be {}", FORMS)),
+        }
+    }
+
+    /// returns the first of `keys` missing from `config`
+    pub fn require_config_keys<'a>(config: &toml_edit::DocumentMut, keys: &[&'a str]) -> Result<(), &'a str> {
+        match keys.iter().find(|key| !config.contains_key(key)) {
+            Some(key) => Err(*key),
+            None => Ok(()),
+        }
+    }
+
+    /// sets `key` in place, keeping any comment trailing the old value
+    pub fn set_config_value(config: &mut toml_edit::DocumentMut, key: &str, value: impl Into<toml_edit::Value>) {
+        let mut value = value.into();
+        if let Some(old_value) = config.get(key).and_then(|item| item.as_value()) {
+            *value.decor_mut() = old_value.decor().clone();
+        }
+        config[key] = toml_edit::Item::Value(value);
+    }
+
+    /// the lines to show of `line_count` lines, `height` at a time, starting at `scroll`,
+    /// the scroll is clamped so the window doesn't run past the last line
+    pub fn line_window(line_count: usize, scroll: usize, height: usize) -> std::ops::Range<usize> {
+        let start = scroll.min(line_count.saturating_sub(height));
+        start..(start + height).min(line_count)
+    }
+
+    /// the scroll which keeps the line at `cursor` within a window of `height` lines, moving as little as possible from `scroll`
+    pub fn follow_cursor(scroll: usize, cursor: usize, height: usize) -> usize {
+        if cursor < scroll {
+            cursor
+        } else if height > 0 && cursor >= scroll + height {
+            cursor + 1 - height
+        } else {
+            scroll
+        }
+    }
+
+    /// splits the text into rows at most `width` chars wide, breaking after spaces where possible,
+    /// gives the byte range of each row (without the line ending)
+    pub fn wrap_lines(text: &str, width: usize) -> Vec<std::ops::Range<usize>> {
+        let width = width.max(1);
+        let mut rows = Vec::new();
+        let mut line_start = 0;
+        for line in text.split_inclusive('\n') {
+            let line_end = line_start + line.trim_end_matches(['\n', '\r']).len();
+            let mut row_start = line_start;
+            loop {
+                let rest = &text[row_start..line_end];
+                let Some((cut, _)) = rest.char_indices().nth(width) else {
+                    rows.push(row_start..line_end);
+                    break;
+                };
+                let row_len = if rest[cut..].starts_with(' ') {
+                    cut + 1 // the space would only trail the row
+                } else {
+                    match rest[..cut].rfind(' ') {
+                        Some(space) if space > 0 => space + 1,
+                        _ => cut,
+                    }
+                };
+                rows.push(row_start..row_start + row_len);
+                row_start += row_len;
+                if row_start == line_end {break}
+            }
+            line_start += line.len();
+        }
+        rows
+    }
+
+    /// how a list of children is ordered, messages by their timestamp
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum ChildOrder {
+        #[default]
+        Inserted,
+        OldestFirst,
+        NewestFirst,
+    }
+
+    impl ChildOrder {
+        /// cycles through the orders
+        pub fn next(self) -> Self {
+            match self {
+                ChildOrder::Inserted => ChildOrder::OldestFirst,
+                ChildOrder::OldestFirst => ChildOrder::NewestFirst,
+                ChildOrder::NewestFirst => ChildOrder::Inserted,
+            }
+        }
+
+        pub fn name(self) -> &'static str {
+            match self {
+                ChildOrder::Inserted => "inserted",
+                ChildOrder::OldestFirst => "oldest first",
+                ChildOrder::NewestFirst => "newest first",
+            }
+        }
+
+        /// compares the timestamps of two children, None for those which aren't messages,
+        /// which go before the messages
+        pub fn compare(self, a: Option<u64>, b: Option<u64>) -> std::cmp::Ordering {
+            match (self, a, b) {
+                (ChildOrder::Inserted, _, _) => std::cmp::Ordering::Equal,
+                (_, None, None) => std::cmp::Ordering::Equal,
+                (_, None, Some(_)) => std::cmp::Ordering::Less,
+                (_, Some(_), None) => std::cmp::Ordering::Greater,
+                (ChildOrder::OldestFirst, Some(a), Some(b)) => a.cmp(&b),
+                (ChildOrder::NewestFirst, Some(a), Some(b)) => b.cmp(&a),
+            }
+        }
+
+        /// sorts stably, so equal children (ie. all of them for `Inserted`) keep their order
+        pub fn sort<T>(self, children: &mut [T], mut timestamp: impl FnMut(&T) -> Option<u64>) {
+            if self == ChildOrder::Inserted {return}
+            children.sort_by(|a, b| self.compare(timestamp(a), timestamp(b)));
+        }
+    }
+
+    /// a sibling of `path` to write to before moving it into place,
+    /// unique per call so concurrent writes to the same file don't share one
+    fn temp_path(path: &Path) -> PathBuf {
+        static NEXT_TEMP_ID: AtomicU64 = AtomicU64::new(0);
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(format!(".{}.tmp", NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)));
+        path.with_file_name(file_name)
+    }
+
+    fn write_temp(path: &Path, contents: &[u8]) -> std::io::Result<PathBuf> {
+        let temp_path = temp_path(path);
+        let result = std::fs::File::create_new(&temp_path).and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        });
+        match result {
+            Ok(()) => Ok(temp_path),
+            Err(e) => {
+                let _ = std::fs::remove_file(&temp_path);
+                Err(e)
+            }
+        }
+    }
+
+    /// writes a file that doesn't exist yet, such that a crash never leaves it partially written
+    pub fn write_new_atomic(path: impl AsRef<Path>, contents: &[u8]) -> std::io::Result<()> {
+        let temp_path = write_temp(path.as_ref(), contents)?;
+        // unlike a rename, linking fails if the file already exists
+        let result = std::fs::hard_link(&temp_path, path);
+        let _ = std::fs::remove_file(&temp_path);
+        result
+    }
+
+    /// replaces an existing file, such that a crash leaves either the old or the new contents
+    pub fn overwrite_atomic(path: impl AsRef<Path>, contents: &[u8]) -> std::io::Result<()> {
+        let path = path.as_ref();
+        std::fs::metadata(path)?; // must already exist
+        let temp_path = write_temp(path, contents)?;
+        std::fs::rename(&temp_path, path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp_path);
+        })
+    }
 }
 
 pub trait AsData {
@@ -164,6 +460,14 @@ pub trait AsData {
         let mut data_iter = data.iter().copied();
         Self::from_data_iter(&mut data_iter)
     }
+
+    /// like `from_data` but also gives how many bytes were read, 
+    /// so values stored back to back can be read one after another
+    fn from_data_counted(data: &[u8]) -> Result<(Self, usize), DataError> where Self: Sized {
+        let mut data_iter = data.iter().copied();
+        let val = Self::from_data_iter(&mut data_iter)?;
+        Ok((val, data.len() - data_iter.len()))
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -174,11 +478,14 @@ pub enum DataError {
     StringError(std::string::FromUtf8Error),
     NotHex,
     UnsupportedVersion,
+    /// the client and the board share no protocol version, see `negotiate_version`
+    VersionMismatch,
 
     DoesNotExist,
     AlreadyExists,
     InsufficientPerms,
     BadCredentials,
+    IoError(std::io::ErrorKind),
     MissingKey,
     IncorrectKey,
     EncryptionError,
@@ -186,11 +493,165 @@ pub enum DataError {
     MalformedRoot,
     NonChild,
     EdittedLocation,
+    CyclicMove,
+    /// a walk through the tree went further than its max depth, see `DEFAULT_MAX_TREE_DEPTH`
+    DepthExceeded,
+    PayloadTooLarge{max: usize},
+    TooManyChildren,
+    InvalidStructure,
+    RateLimited,
+
+    /// a message with nothing but whitespace, see `prepare_message`
+    EmptyMessage,
+    /// a reaction with no emoji, only whitespace
+    EmptyReaction,
 
     InternalError{file: &'static str, line: u32, col: u32},
     OOBUsizeConversion,
 }
 
+impl Display for DataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataError::IncorrectMagicNum => write!(f, "Incorrect magic number"),
+            DataError::InsufficientBytes => write!(f, "Ran out of data"),
+            DataError::InvalidDiscriminant => write!(f, "Invalid discriminant"),
+            DataError::StringError(e) => write!(f, "Invalid UTF-8: {}", e),
+            DataError::NotHex => write!(f, "Not hex"),
+            DataError::UnsupportedVersion => write!(f, "Unsupported version"),
+            DataError::VersionMismatch => write!(f, "No protocol version supported by both the client and the board (this side supports {} - {})", MIN_PROTOCOL_VERSION, PROTOCOL_VERSION),
+            DataError::DoesNotExist => write!(f, "Does not exist"),
+            DataError::AlreadyExists => write!(f, "Already exists"),
+            DataError::InsufficientPerms => write!(f, "Insufficient permissions"),
+            DataError::BadCredentials => write!(f, "Bad credentials"),
+            DataError::IoError(kind) => write!(f, "IO error: {}", kind),
+            DataError::MissingKey => write!(f, "Missing key"),
+            DataError::IncorrectKey => write!(f, "Incorrect key"),
+            DataError::EncryptionError => write!(f, "Encryption error"),
+            DataError::MalformedRoot => write!(f, "Malformed root"),
+            DataError::DepthExceeded => write!(f, "Entry too deep in the tree"),
+            DataError::NonChild => write!(f, "Not a child of the current entry"),
+            DataError::EdittedLocation => write!(f, "Edits can't change an entry's parent or children"),
+            DataError::CyclicMove => write!(f, "Can't move an entry under itself"),
+            DataError::PayloadTooLarge { max } => write!(f, "Too large (max {})", max),
+            DataError::TooManyChildren => write!(f, "Too many children (max {})", MAX_CHILDREN),
+            DataError::InvalidStructure => write!(f, "Children repeated or including the entry itself"),
+            DataError::RateLimited => write!(f, "Too many requests, slow down"),
+            DataError::EmptyMessage => write!(f, "The message is empty"),
+            DataError::EmptyReaction => write!(f, "The reaction has no emoji"),
+            DataError::InternalError { file, line, col } => write!(f, "Internal error at {}:{}:{}", file, line, col),
+            DataError::OOBUsizeConversion => write!(f, "Size out of bounds"),
+        }
+    }
+}
+
+impl DataError {
+    /// the code sent for the error in responses, most errors aren't distinguished on the wire
+    pub fn error_code(&self) -> u8 {
+        match self {
+            DataError::RateLimited => RATE_LIMITED_ERROR,
+            DataError::BadCredentials => BAD_CREDENTIALS_ERROR,
+            DataError::DoesNotExist => DOES_NOT_EXIST_ERROR,
+            DataError::InsufficientPerms => INSUFFICIENT_PERMS_ERROR,
+            DataError::VersionMismatch => VERSION_MISMATCH_ERROR,
+            DataError::TooManyChildren => TOO_MANY_CHILDREN_ERROR,
+            DataError::InvalidStructure => INVALID_STRUCTURE_ERROR,
+            DataError::DepthExceeded => DEPTH_EXCEEDED_ERROR,
+            DataError::EmptyReaction => EMPTY_REACTION_ERROR,
+            DataError::PayloadTooLarge { .. } => PAYLOAD_TOO_LARGE_ERROR,
+            DataError::CyclicMove => CYCLIC_MOVE_ERROR,
+            DataError::EdittedLocation => EDITTED_LOCATION_ERROR,
+            DataError::MalformedRoot => MALFORMED_ROOT_ERROR,
+            _ => UNSPECIFIED_ERROR,
+        }
+    }
+
+    /// the error as it's sent in responses, its code followed by anything the code carries, ie. `PayloadTooLarge`'s max
+    pub fn extend_error_data(&self, data: &mut impl Write) -> Result<(), DataError> {
+        data.write_all(&[self.error_code()])?;
+        if let DataError::PayloadTooLarge { max } = self {
+            data.write_all(&(*max as u64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn error_data_size(&self) -> usize {
+        match self {
+            DataError::PayloadTooLarge { .. } => 1 + 8,
+            _ => 1,
+        }
+    }
+
+    /// the variant's name, ie. for metrics, every `PayloadTooLarge` has the same one whatever its max
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataError::IncorrectMagicNum => "IncorrectMagicNum",
+            DataError::InsufficientBytes => "InsufficientBytes",
+            DataError::InvalidDiscriminant => "InvalidDiscriminant",
+            DataError::StringError(_) => "StringError",
+            DataError::NotHex => "NotHex",
+            DataError::UnsupportedVersion => "UnsupportedVersion",
+            DataError::VersionMismatch => "VersionMismatch",
+            DataError::DoesNotExist => "DoesNotExist",
+            DataError::AlreadyExists => "AlreadyExists",
+            DataError::InsufficientPerms => "InsufficientPerms",
+            DataError::BadCredentials => "BadCredentials",
+            DataError::IoError(_) => "IoError",
+            DataError::MissingKey => "MissingKey",
+            DataError::IncorrectKey => "IncorrectKey",
+            DataError::EncryptionError => "EncryptionError",
+            DataError::MalformedRoot => "MalformedRoot",
+            DataError::NonChild => "NonChild",
+            DataError::EdittedLocation => "EdittedLocation",
+            DataError::CyclicMove => "CyclicMove",
+            DataError::DepthExceeded => "DepthExceeded",
+            DataError::PayloadTooLarge { .. } => "PayloadTooLarge",
+            DataError::TooManyChildren => "TooManyChildren",
+            DataError::InvalidStructure => "InvalidStructure",
+            DataError::RateLimited => "RateLimited",
+            DataError::EmptyMessage => "EmptyMessage",
+            DataError::EmptyReaction => "EmptyReaction",
+            DataError::InternalError { .. } => "InternalError",
+            DataError::OOBUsizeConversion => "OOBUsizeConversion",
+        }
+    }
+
+    pub fn from_error_code(error_code: u8) -> Self {
+        match error_code {
+            RATE_LIMITED_ERROR => DataError::RateLimited,
+            BAD_CREDENTIALS_ERROR => DataError::BadCredentials,
+            DOES_NOT_EXIST_ERROR => DataError::DoesNotExist,
+            INSUFFICIENT_PERMS_ERROR => DataError::InsufficientPerms,
+            VERSION_MISMATCH_ERROR => DataError::VersionMismatch,
+            TOO_MANY_CHILDREN_ERROR => DataError::TooManyChildren,
+            INVALID_STRUCTURE_ERROR => DataError::InvalidStructure,
+            DEPTH_EXCEEDED_ERROR => DataError::DepthExceeded,
+            EMPTY_REACTION_ERROR => DataError::EmptyReaction,
+            CYCLIC_MOVE_ERROR => DataError::CyclicMove,
+            EDITTED_LOCATION_ERROR => DataError::EdittedLocation,
+            MALFORMED_ROOT_ERROR => DataError::MalformedRoot,
+            _ => internal_error!(), //not really an internal error, it just isn't encoded atm
+        }
+    }
+
+    /// reads an error written by `extend_error_data`
+    pub fn from_error_data_iter(data_iter: &mut impl Iterator<Item = u8>) -> Result<Self, DataError> {
+        Ok(match read_u8(data_iter)? {
+            PAYLOAD_TOO_LARGE_ERROR => DataError::PayloadTooLarge { max: checked_usize(read_u64(data_iter)?)? },
+            error_code => DataError::from_error_code(error_code),
+        })
+    }
+}
+
+impl std::error::Error for DataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DataError::StringError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! internal_error {
     () => {
@@ -204,16 +665,79 @@ impl From<FromUtf8Error> for DataError {
     }
 }
 
+/// keeps not found / already exists distinct from other io failures (ie. permissions)
+impl From<std::io::Error> for DataError {
+    fn from(value: std::io::Error) -> Self {
+        match value.kind() {
+            std::io::ErrorKind::NotFound => DataError::DoesNotExist,
+            std::io::ErrorKind::AlreadyExists => DataError::AlreadyExists,
+            kind => DataError::IoError(kind),
+        }
+    }
+}
+
 impl From<aes_gcm::Error> for DataError {
     fn from(_: aes_gcm::Error) -> Self {
         Self::EncryptionError
     }
 }
 
+/// the most elements preallocated for a length read off the wire, 
+/// past this vecs grow as their elements actually arrive so a bogus length can't exhaust memory
+const MAX_DECLARED_PREALLOCATION: usize = 1024;
+/// the most user ids in a single ListUsers response, the rest are paged through with `next`
+pub const MAX_LISTED_USERS: usize = 4096;
+/// the most entries asked for in a single GetEntries
+pub const MAX_REQUESTED_ENTRIES: usize = 4096;
+
+/// how many bytes `read_string` reads between validating them
+const STRING_READ_CHUNK_SIZE: usize = 4096;
+
+/// reads (up to) `len` bytes of utf8, 
+/// validating as it goes so invalid strings fail without reading the rest of the declared length
+fn read_string(data_iter: &mut impl Iterator<Item = u8>, len: usize) -> Result<String, DataError> {
+    let mut bytes = Vec::new();
+    let mut validated = 0; // bytes[..validated] is valid utf8
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk_len = remaining.min(STRING_READ_CHUNK_SIZE);
+        let chunk_start = bytes.len();
+        bytes.extend(data_iter.by_ref().take(chunk_len));
+        remaining = match bytes.len() - chunk_start == chunk_len {
+            true => remaining - chunk_len,
+            false => 0, // ran out of data
+        };
+        match std::str::from_utf8(&bytes[validated..]) {
+            Ok(_) => validated = bytes.len(),
+            // a code point split between chunks, finished by the next one
+            Err(e) if e.error_len().is_none() && remaining > 0 => validated += e.valid_up_to(),
+            Err(_) => break,
+        }
+    }
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// like `read_string` the declared length isn't trusted up front, but running out of data is an error
+fn read_bytes(data_iter: &mut impl Iterator<Item = u8>, len: usize) -> Result<Vec<u8>, DataError> {
+    let mut bytes = Vec::with_capacity(len.min(MAX_DECLARED_PREALLOCATION));
+    bytes.extend(data_iter.by_ref().take(len));
+    if bytes.len() != len {return Err(DataError::InsufficientBytes)}
+    Ok(bytes)
+}
+
 fn read_u8(data_iter: &mut impl Iterator<Item = u8>) -> Result<u8, DataError> {
     data_iter.next().ok_or(DataError::InsufficientBytes)
 }
 
+/// a single byte, 00 or 01
+fn read_bool(data_iter: &mut impl Iterator<Item = u8>) -> Result<bool, DataError> {
+    match read_u8(data_iter)? {
+        0x00 => Ok(false),
+        0x01 => Ok(true),
+        _ => Err(DataError::InvalidDiscriminant),
+    }
+}
+
 macro_rules! read_num_impls {
     ($($ident:ident: $ty:ty, $size:literal;)*) => {
         $(
@@ -252,28 +776,420 @@ macro_rules! bounded_usize {
     };
 }
 
-/// current file version: 0
+/// the reading counterpart of `bounded_usize`, for declared lengths which may not fit the platform's usize
+pub fn checked_usize<N: TryInto<usize>>(val: N) -> Result<usize, DataError> {
+    val.try_into().map_err(|_| DataError::OOBUsizeConversion)
+}
+
+/// size of the chunks read off of a stream by `FrameReader`
+const FRAME_READ_CHUNK_SIZE: usize = 4096;
+
+/// a response flag, sent in the encrypted body of the request (see `BoardRequest::secure_extend_data_with`) so it is authenticated with it, 
+/// lets a large response be streamed (see `BoardResponse::write_streamed`)
+/// 
+/// the board encodes and encrypts the response a chunk at a time as it writes it, 
+/// and the client decrypts and decodes each chunk as it is read (see `BoardResponse::read_streamed`), 
+/// so neither side holds more than a chunk of it besides the entries themselves
+pub const CHUNKED_RESPONSE_FLAG: u8 = 1 << 0;
+
+/// a response flag like `CHUNKED_RESPONSE_FLAG`, lets the entries in the response have deflated message bodies, 
+/// which clients built without the `compression` feature can't read
+pub const COMPRESSED_RESPONSE_FLAG: u8 = 1 << 1;
+
+/// a response flag like `CHUNKED_RESPONSE_FLAG`, has the access groups in the response sent with empty perm lists (see `BoardResponse::summarize_perms`), 
+/// for clients which show them from `GetPermsSummary` and only get the ids when they're opened
+pub const SUMMARIZED_PERMS_FLAG: u8 = 1 << 2;
+
+/// responses no larger than this are sent as a single frame even if chunks were asked for, 
+/// and the most of a streamed response's body put in each chunk
+pub const RESPONSE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// the frame size marking a chunked frame, no real frame can be this large
+const CHUNKED_FRAME_SIZE: u64 = u64::MAX;
+
+/// a piece of a frame, see `FrameReader::read_part`
+#[derive(Debug, PartialEq, Eq)]
+pub enum FramePart {
+    /// a whole frame and its request id
+    Frame(u64, Vec<u8>),
+    /// the next chunk of a chunked frame and its request id
+    Chunk(u64, Vec<u8>),
+    /// the end of the chunked frame for the request id
+    End(u64),
+}
+
+/// accumulates the bytes of length prefixed frames from a stream,
+/// partial frames are kept between calls so the stream can be non-blocking
+/// 
+/// frame format, numbers are little endian:
+///     frame size (u64), does not include the request id
+///     request id (u64), chosen by the client and echoed back in the response to its request
+///     remaining [frame size] bytes are the frame
+/// 
+/// the request id lets a client pipeline requests as the server can respond out of order
+/// 
+/// chunked frame format, the frame is the chunks joined together:
+///     frame size (u64): FFFFFFFFFFFFFFFF
+///     request id (u64)
+///     chunks, each is:
+///         chunk size (u64), a chunk of size 0 ends the frame
+///         remaining [chunk size] bytes are the chunk
+#[derive(Debug, Default)]
+pub struct FrameReader {
+    buffer: Vec<u8>,
+    max_frame_size: Option<usize>,
+    chunked: Option<(u64, u64)>, // the request id and bytes so far of a partially received chunked frame
+    joined: Vec<u8>, // the chunks so far of the chunked frame being read by `read_frame`
+}
+
+impl FrameReader {
+    pub fn new() -> Self {
+        Self { buffer: Vec::new(), max_frame_size: None, chunked: None, joined: Vec::new() }
+    }
+
+    /// frames declaring more than `max_frame_size` bytes are refused before they are read,
+    /// chunked ones as soon as their chunks go over it
+    pub fn with_max_frame_size(max_frame_size: usize) -> Self {
+        Self { buffer: Vec::new(), max_frame_size: Some(max_frame_size), chunked: None, joined: Vec::new() }
+    }
+
+    /// removes the next part from the buffer if it has been fully received,
+    /// chunks are moved out of the buffer as they arrive so it only ever holds one
+    fn take_part(&mut self) -> std::io::Result<Option<FramePart>> {
+        if let Some((request_id, received)) = &mut self.chunked {
+            let request_id = *request_id;
+            let Some(chunk_size) = self.buffer.get(..8) else {return Ok(None)};
+            let chunk_size = u64::from_le_bytes(chunk_size.try_into().unwrap());
+            if chunk_size == 0 {
+                self.buffer.drain(..8);
+                self.chunked = None;
+                return Ok(Some(FramePart::End(request_id)))
+            }
+            if self.max_frame_size.is_some_and(|max| received.saturating_add(chunk_size) > max as u64) {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "frame over the size limit"))
+            }
+            let Some(chunk_end) = usize::try_from(chunk_size).ok().and_then(|x| x.checked_add(8)) else {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "chunk too large for the platform"))
+            };
+            if self.buffer.len() < chunk_end {return Ok(None)}
+            *received += chunk_size;
+            let chunk = self.buffer[8..chunk_end].to_vec();
+            self.buffer.drain(..chunk_end);
+            return Ok(Some(FramePart::Chunk(request_id, chunk)))
+        }
+        let Some(frame_size) = self.buffer.get(..8) else {return Ok(None)};
+        let frame_size = u64::from_le_bytes(frame_size.try_into().unwrap());
+        if frame_size == CHUNKED_FRAME_SIZE {
+            let Some(request_id) = self.buffer.get(8..16) else {return Ok(None)};
+            self.chunked = Some((u64::from_le_bytes(request_id.try_into().unwrap()), 0));
+            self.buffer.drain(..16);
+            return self.take_part()
+        }
+        if self.max_frame_size.is_some_and(|max| frame_size > max as u64) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "frame over the size limit"))
+        }
+        let Some(request_id) = self.buffer.get(8..16) else {return Ok(None)};
+        let request_id = u64::from_le_bytes(request_id.try_into().unwrap());
+        let Some(frame_end) = usize::try_from(frame_size).ok().and_then(|x| x.checked_add(16)) else {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "frame too large for the platform"))
+        };
+        if self.buffer.len() < frame_end {return Ok(None)}
+        let frame = self.buffer[16..frame_end].to_vec();
+        self.buffer.drain(..frame_end);
+        Ok(Some(FramePart::Frame(request_id, frame)))
+    }
+
+    /// as `read_frame`, but giving the chunks of a chunked frame one at a time rather than joined together, 
+    /// so a reader which can use them as they come (see `BoardResponse::read_streamed`) needn't hold the whole frame
+    /// 
+    /// a chunked frame begun with this should be finished with it, `read_frame` only joins the chunks it reads itself
+    pub fn read_part(&mut self, stream: &mut impl Read) -> std::io::Result<Option<FramePart>> {
+        let mut chunk = [0u8; FRAME_READ_CHUNK_SIZE];
+        loop {
+            if let Some(part) = self.take_part()? {return Ok(Some(part))}
+            match stream.read(&mut chunk) {
+                Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                Ok(bytes_read) => self.buffer.extend_from_slice(&chunk[..bytes_read]),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(None),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// reads whatever is available from the stream,
+    /// giving `Ok(None)` if the stream would block before a full frame was received
+    /// 
+    /// an `Err` means the stream is unusable (ie. disconnected),
+    /// an EOF is reported as `UnexpectedEof`, a frame over the size limit as `InvalidData`
+    pub fn read_frame(&mut self, stream: &mut impl Read) -> std::io::Result<Option<(u64, Vec<u8>)>> {
+        loop {
+            match self.read_part(stream)? {
+                Some(FramePart::Frame(request_id, frame)) => return Ok(Some((request_id, frame))),
+                Some(FramePart::Chunk(_, chunk)) => self.joined.extend_from_slice(&chunk),
+                Some(FramePart::End(request_id)) => return Ok(Some((request_id, std::mem::take(&mut self.joined)))),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// `write_all`, but retrying if the stream is non-blocking and would block
+fn write_all_blocking(stream: &mut impl Write, data: &[u8]) -> std::io::Result<()> {
+    let mut written = 0;
+    while written < data.len() {
+        match stream.write(&data[written..]) {
+            Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+            Ok(bytes_written) => written += bytes_written,
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted) => std::thread::yield_now(),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// `write_all_blocking` for several buffers, handed to the stream together 
+/// so they're only split across writes where the stream itself takes part of them
+fn write_all_vectored_blocking(stream: &mut impl Write, mut bufs: &mut [IoSlice<'_>]) -> std::io::Result<()> {
+    IoSlice::advance_slices(&mut bufs, 0); // drops any empty buffers at the start
+    while !bufs.is_empty() {
+        match stream.write_vectored(bufs) {
+            Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+            Ok(bytes_written) => IoSlice::advance_slices(&mut bufs, bytes_written),
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted) => std::thread::yield_now(),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// writes `frame` with its length prefix and request id (see `FrameReader`), then flushes,
+/// retrying if the stream is non-blocking and would block
+/// 
+/// the prefix and frame go out as one vectored write rather than being joined into one buffer,
+/// which would otherwise hold a second copy of large frames (ie. big `GetEntry` responses) while writing,
+/// any error (ie. partway through the frame) is returned, after which the stream can't be used for more frames
+pub fn write_frame(stream: &mut impl Write, request_id: u64, frame: &[u8]) -> std::io::Result<()> {
+    let mut prefix = [0u8; 16];
+    prefix[..8].copy_from_slice(&(frame.len() as u64).to_le_bytes());
+    prefix[8..].copy_from_slice(&request_id.to_le_bytes());
+    write_all_vectored_blocking(stream, &mut [IoSlice::new(&prefix), IoSlice::new(frame)])?;
+    flush_blocking(stream)
+}
+
+/// writes `frame` as a chunked frame (see `FrameReader`) of chunks at most `chunk_size` bytes,
+/// retrying if the stream is non-blocking and would block
+pub fn write_chunked_frame(stream: &mut impl Write, request_id: u64, frame: &[u8], chunk_size: usize) -> std::io::Result<()> {
+    start_chunked_frame(stream, request_id)?;
+    for chunk in frame.chunks(chunk_size.max(1)) {
+        write_chunk(stream, chunk)?;
+    }
+    end_chunked_frame(stream)
+}
+
+/// writes the start of a chunked frame (see `FrameReader`), for chunks written as they're made with `write_chunk`
+fn start_chunked_frame(stream: &mut impl Write, request_id: u64) -> std::io::Result<()> {
+    let mut prefix = [0u8; 16];
+    prefix[..8].copy_from_slice(&CHUNKED_FRAME_SIZE.to_le_bytes());
+    prefix[8..].copy_from_slice(&request_id.to_le_bytes());
+    write_all_blocking(stream, &prefix)
+}
+
+/// writes a chunk of the chunked frame begun by `start_chunked_frame`, it shouldn't be empty as that would end the frame
+fn write_chunk(stream: &mut impl Write, chunk: &[u8]) -> std::io::Result<()> {
+    let size = (chunk.len() as u64).to_le_bytes();
+    write_all_vectored_blocking(stream, &mut [IoSlice::new(&size), IoSlice::new(chunk)])
+}
+
+/// ends the chunked frame begun by `start_chunked_frame`, then flushes
+fn end_chunked_frame(stream: &mut impl Write) -> std::io::Result<()> {
+    write_all_blocking(stream, &0u64.to_le_bytes())?;
+    flush_blocking(stream)
+}
+
+/// the highest protocol version in both `min_version - max_version` and what this side supports
+pub fn negotiate_version(min_version: u8, max_version: u8) -> Result<u8, DataError> {
+    let version = max_version.min(PROTOCOL_VERSION);
+    if version < min_version || version < MIN_PROTOCOL_VERSION {return Err(DataError::VersionMismatch)}
+    Ok(version)
+}
+
+/// `flush`, but retrying if the stream is non-blocking and would block
+fn flush_blocking(stream: &mut impl Write) -> std::io::Result<()> {
+    loop {
+        match stream.flush() {
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted) => std::thread::yield_now(),
+            result => return result,
+        }
+    }
+}
+
+/// a token bucket, refilling `rate` tokens per second up to `burst`
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// starts with a full bucket
+    pub fn new(rate: f64, burst: f64, now: Instant) -> Self {
+        Self { rate, burst, tokens: burst, last_refill: now }
+    }
+
+    /// takes a token if there is one as of `now`
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = self.last_refill.max(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// items queued per client, ie. requests waiting on a handler, so one busy client can't starve the rest
+/// 
+/// `pop` takes from the client with the fewest items in flight (popped but not `finish`ed), 
+/// going round the clients in turn when they are tied
+#[derive(Debug, Clone)]
+pub struct FairQueue<T> {
+    queues: HashMap<u64, VecDeque<T>>,
+    in_flight: HashMap<u64, usize>,
+    order: Vec<u64>, // the clients with queued items, in the order they are gone round
+    next: usize, // where in `order` the next round starts
+}
+
+impl<T> Default for FairQueue<T> {
+    fn default() -> Self {
+        Self { queues: HashMap::new(), in_flight: HashMap::new(), order: Vec::new(), next: 0 }
+    }
+}
+
+impl<T> FairQueue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, client_id: u64, item: T) {
+        let order = &mut self.order;
+        self.queues.entry(client_id).or_insert_with(|| {
+            order.push(client_id);
+            VecDeque::new()
+        }).push_back(item);
+    }
+
+    /// the next item, counted as in flight for its client until `finish`ed
+    pub fn pop(&mut self) -> Option<(u64, T)> {
+        let len = self.order.len();
+        let (idx, _) = (0..len).map(|offset| (self.next + offset) % len)
+            .map(|idx| (idx, self.in_flight(self.order[idx])))
+            .reduce(|best, current| if current.1 < best.1 {current} else {best})?;
+        let client_id = self.order[idx];
+        let queue = self.queues.get_mut(&client_id).expect("Clients in the order should have a queue");
+        let item = queue.pop_front().expect("Queues should be removed once empty");
+        if queue.is_empty() {
+            self.queues.remove(&client_id);
+            self.order.remove(idx);
+            self.next = idx;
+        } else {
+            self.next = idx + 1;
+        }
+        *self.in_flight.entry(client_id).or_default() += 1;
+        Some((client_id, item))
+    }
+
+    /// one of the client's items is done with, does nothing if it has none in flight
+    pub fn finish(&mut self, client_id: u64) {
+        if let Some(in_flight) = self.in_flight.get_mut(&client_id) {
+            *in_flight -= 1;
+            if *in_flight == 0 {self.in_flight.remove(&client_id);}
+        }
+    }
+
+    pub fn in_flight(&self, client_id: u64) -> usize {
+        self.in_flight.get(&client_id).copied().unwrap_or(0)
+    }
+
+    /// drops everything of the clients `keep` is false for, ie. disconnected ones
+    pub fn retain_clients(&mut self, mut keep: impl FnMut(u64) -> bool) {
+        self.queues.retain(|client_id, _| keep(*client_id));
+        self.in_flight.retain(|client_id, _| keep(*client_id));
+        self.order.retain(|client_id| self.queues.contains_key(client_id));
+    }
+
+    /// the number of queued items, not counting those in flight
+    pub fn len(&self) -> usize {
+        self.queues.values().map(|queue| queue.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+}
+
+/// the waits between retries of something failing, ie. connecting,
+/// doubling after each retry up to `max_delay` and giving up after `max_retries`
+#[derive(Debug, Clone)]
+pub struct RetryBackoff {
+    retries_left: u32,
+    delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryBackoff {
+    pub fn new(max_retries: u32, initial_delay: Duration, max_delay: Duration) -> Self {
+        Self { retries_left: max_retries, delay: initial_delay.min(max_delay), max_delay }
+    }
+
+    /// called after a failed attempt, how long to wait before retrying, None once out of retries
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self.retries_left == 0 {return None}
+        self.retries_left -= 1;
+        let delay = self.delay;
+        self.delay = self.delay.saturating_mul(2).min(self.max_delay);
+        Some(delay)
+    }
+}
+
+/// current file version: 4, versions 0 to 3 are still read
 /// 
 /// NOTE: don't forget to update size hints
 /// 
 /// data format, numbers are little endian: 
 ///     magic number (u16):         0x1234,   
-///     file version number (u8):   00,
+///     file version number (u8):   04,
 ///     type (u8):                      
 ///         Message:                00,   
 ///         AccessGroup:            01,
+///         Reaction:               02,
+///         Attachment:             03,
 ///     parent entry id (u64),
 ///     number of children ids (u16),
 ///     children id 1 (u64),
 ///     ...
 ///     children id n (u64),
 ///     author id (u64),
+///     last activity (secs since Unix Epoch) (u64), not before version 2, 
+///         defaults to a message's own timestamp and 0 for an access group
+///     pinned (u8): 00 or 01, not before version 3, defaults to not pinned
+///     deleted (u8): 00 or 01, not before version 4, defaults to not deleted
 ///     remaining is dependent on the type
 /// 
 /// Message:
 ///     timestamp (secs since Unix Epoch) (u64),
+///     body encoding (u8), not in version 0:
+///         raw:                    00,
+///         deflate:                01, only written with the `compression` feature when it shrinks the body, 
+///             on the wire only in responses to requests with `COMPRESSED_RESPONSE_FLAG`
 ///     message size (u32),
-///     remaining [message size] bytes are the message which is a utf8 encoded string
+///     stored size (u32), not in version 0,
+///     remaining [stored size] bytes are the encoded message which is a utf8 encoded string
 /// 
 /// AccessGroup:
 ///     group name length (u32),
@@ -281,6 +1197,19 @@ macro_rules! bounded_usize {
 ///     write perms: DefaultedIdSet
 ///     read perms: DefaultedIdSet
 /// 
+/// Reaction:
+///     target entry id (u64), always the parent
+///     emoji length (u8),
+///     emoji string (utf8 encoded)
+/// 
+/// Attachment:
+///     filename length (u8),
+///     filename string (utf8 encoded),
+///     mime type length (u8),
+///     mime type string (utf8 encoded),
+///     data size (u32),
+///     remaining [data size] bytes are the data
+/// 
 /// 
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Entry {
@@ -288,53 +1217,252 @@ pub struct Entry {
     pub entry_data: EntryData,
 }
 
-impl AsData for Entry {
-    fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>) -> Result<Self, DataError> {
-        let (header_data, entry_type) = HeaderData::from_data_iter(data_iter)?;
-        let entry_data = EntryData::from_data_iter(data_iter, entry_type)?;
-        Ok(Entry {
-            header_data,
-            entry_data,
-        })
+impl Entry {
+    /// as `extend_data`, only deflating the message body if `compress`, see `EntryData::extend_data_with`
+    pub fn extend_data_with(&self, data: &mut Vec<u8>, compress: bool) -> Result<(), DataError> {
+        self.extend_into_with(data, compress)
     }
 
-    fn extend_data(&self, data: &mut Vec<u8>) -> Result<(), DataError> {
-        self.header_data.extend_data(self.entry_data.get_discriminant(), data)?;
-        self.entry_data.extend_data(data)?;
-        Ok(())
+    /// as `extend_data_with` but writing to `writer`, see `EntryData::extend_into_with`
+    pub fn extend_into_with<W: Write>(&self, writer: &mut W, compress: bool) -> Result<(), DataError> {
+        let mut header = Vec::with_capacity(self.header_data.size_hint());
+        self.header_data.extend_data(self.entry_data.get_discriminant(), &mut header)?;
+        writer.write_all(&header)?;
+        self.entry_data.extend_into_with(writer, compress)
     }
 
-    fn size_hint(&self) -> usize {
-        self.header_data.size_hint() + self.entry_data.size_hint()
+    /// a new message without children
+    pub fn new_message(parent_id: EntryId, author_id: UserId, timestamp: u64, message: String) -> Self {
+        Self {
+            header_data: HeaderData::new(parent_id, Vec::new(), author_id),
+            entry_data: EntryData::Message { timestamp, message },
+        }
     }
-}
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub struct HeaderData {
-    pub version: u8,
-    pub parent_id: EntryId,
-    pub children_ids: Vec<EntryId>,
-    pub author_id: UserId,
-}
+    /// a new access group without children
+    pub fn new_access_group(parent_id: EntryId, author_id: UserId, name: String, write_perms: DefaultedIdSet, read_perms: DefaultedIdSet) -> Self {
+        Self {
+            header_data: HeaderData::new(parent_id, Vec::new(), author_id),
+            entry_data: EntryData::AccessGroup { name, write_perms, read_perms },
+        }
+    }
 
-impl HeaderData {
-    pub fn new(parent_id: EntryId, children_ids: Vec<EntryId>, author_id: UserId) -> Self {
-        Self { version: ENTRY_FILE_VERSION, parent_id, children_ids, author_id }
+    /// a new reaction to `target_id`, which is also its parent
+    pub fn new_reaction(target_id: EntryId, author_id: UserId, emoji: String) -> Self {
+        Self {
+            header_data: HeaderData::new(target_id, Vec::new(), author_id),
+            entry_data: EntryData::Reaction { emoji, target_id },
+        }
     }
 
-    /// gives a HeaderData and the entry type
-    pub fn from_data(data: &[u8]) -> Result<(Self, u8), DataError> {
-        let mut data_iter = data.iter().copied();
-        HeaderData::from_data_iter(&mut data_iter)
+    /// a new attachment without children
+    pub fn new_attachment(parent_id: EntryId, author_id: UserId, filename: String, mime: String, data: Vec<u8>) -> Self {
+        Self {
+            header_data: HeaderData::new(parent_id, Vec::new(), author_id),
+            entry_data: EntryData::Attachment { filename, mime, data },
+        }
     }
 
-    /// gives a HeaderData and the entry type
-    pub fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>) -> Result<(Self, u8), DataError> {
-        let magic_number = read_u16(data_iter)?;
-        if magic_number != ENTRY_MAGIC_NUMBER {return Err(DataError::IncorrectMagicNum)}
+    /// deletes the entry in place, keeping it (and so its children and anything pointing at it) where it is
+    /// 
+    /// the body is replaced with `DELETED_PLACEHOLDER` (an attachment's data is dropped), 
+    /// an access group's perms are kept as they still cover its children
+    pub fn tombstone(&mut self) {
+        self.header_data.deleted = true;
+        self.header_data.pinned = false;
+        match &mut self.entry_data {
+            EntryData::Message { message, .. } => *message = String::from(DELETED_PLACEHOLDER),
+            EntryData::AccessGroup { name, .. } => *name = String::from(DELETED_PLACEHOLDER),
+            EntryData::Reaction { emoji, .. } => *emoji = String::from(DELETED_PLACEHOLDER),
+            EntryData::Attachment { filename, mime, data } => {
+                *filename = String::from(DELETED_PLACEHOLDER);
+                mime.clear();
+                data.clear();
+            }
+        }
+    }
+
+    /// empties the perm lists of an access group, keeping their bases, see `BoardResponse::summarize_perms`
+    pub fn summarize_perms(&mut self) {
+        if let EntryData::AccessGroup { write_perms, read_perms, .. } = &mut self.entry_data {
+            *write_perms = DefaultedIdSet::empty_from_base(write_perms.get_default_base());
+            *read_perms = DefaultedIdSet::empty_from_base(read_perms.get_default_base());
+        }
+    }
+
+    /// reactions always sit under the entry they react to
+    pub fn attach_to_target(&mut self) {
+        if let EntryData::Reaction { target_id, .. } = self.entry_data {
+            self.header_data.parent_id = target_id;
+        }
+    }
+
+    /// what changed going from `self` to `new`, ie. to summarize an edit, 
+    /// the server kept fields (`last_activity`, `version`) are left out
+    pub fn diff(&self, new: &Entry) -> EntryDiff {
+        fn changed<T: PartialEq>(old: T, new: T) -> Option<(T, T)> {
+            (old != new).then_some((old, new))
+        }
+        let mut diff = EntryDiff {
+            parent_id: changed(self.header_data.parent_id, new.header_data.parent_id),
+            children_changed: self.header_data.children_ids != new.header_data.children_ids,
+            author_id: changed(self.header_data.author_id, new.header_data.author_id),
+            pinned: changed(self.header_data.pinned, new.header_data.pinned),
+            deleted: changed(self.header_data.deleted, new.header_data.deleted),
+            ..Default::default()
+        };
+        match (&self.entry_data, &new.entry_data) {
+            (EntryData::Message { timestamp: old_timestamp, message: old_message }, EntryData::Message { timestamp, message }) => {
+                diff.timestamp = changed(*old_timestamp, *timestamp);
+                diff.message = (old_message != message).then(|| (old_message.clone(), message.clone()));
+            }
+            (EntryData::AccessGroup { name: old_name, read_perms: old_read_perms, write_perms: old_write_perms }, EntryData::AccessGroup { name, read_perms, write_perms }) => {
+                diff.name = (old_name != name).then(|| (old_name.clone(), name.clone()));
+                diff.read_perms = old_read_perms.diff(read_perms);
+                diff.write_perms = old_write_perms.diff(write_perms);
+            }
+            (EntryData::Reaction { emoji: old_emoji, target_id: old_target_id }, EntryData::Reaction { emoji, target_id }) => {
+                diff.emoji = (old_emoji != emoji).then(|| (old_emoji.clone(), emoji.clone()));
+                diff.target_id = changed(*old_target_id, *target_id);
+            }
+            (EntryData::Attachment { filename: old_filename, mime: old_mime, data: old_data }, EntryData::Attachment { filename, mime, data }) => {
+                diff.filename = (old_filename != filename).then(|| (old_filename.clone(), filename.clone()));
+                diff.mime = (old_mime != mime).then(|| (old_mime.clone(), mime.clone()));
+                diff.data_changed = old_data != data;
+            }
+            (old_data, data) => diff.entry_type = Some((old_data.get_discriminant(), data.get_discriminant())),
+        }
+        diff
+    }
+
+    /// the root of a blank board, an access group anyone can read and write
+    pub fn default_root() -> Self {
+        Self::new_access_group(ROOT_ENTRY_ID.into(), SERVER_USER_ID.into(), String::from("Root"), DefaultedIdSet::White { blacklist_ids: Vec::new() }, DefaultedIdSet::White { blacklist_ids: Vec::new() })
+    }
+
+    /// checks an entry sent to be added by `author_id`, 
+    /// children are only ever added by the server so a new entry can't claim any,
+    /// nor can it be pinned or deleted, which have their own requests
+    pub fn validate_new(&self, author_id: UserId) -> Result<(), DataError> {
+        if self.header_data.author_id != author_id {return Err(DataError::InsufficientPerms)}
+        if self.header_data.pinned || self.header_data.deleted {return Err(DataError::InsufficientPerms)}
+        if !self.header_data.children_ids.is_empty() {return Err(DataError::NonChild)}
+        Ok(())
+    }
+
+    /// the root must be its own parent and an access group
+    pub fn validate_root(&self) -> Result<(), DataError> {
+        if *self.header_data.parent_id != ROOT_ENTRY_ID {return Err(DataError::MalformedRoot)}
+        if !matches!(self.entry_data, EntryData::AccessGroup { .. }) {return Err(DataError::MalformedRoot)}
+        Ok(())
+    }
+}
+
+impl AsData for Entry {
+    fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>) -> Result<Self, DataError> {
+        let (mut header_data, entry_type) = HeaderData::from_data_iter(data_iter)?;
+        let entry_data = EntryData::from_data_iter(data_iter, entry_type, header_data.version)?;
+        if header_data.version < 2 && let EntryData::Message { timestamp, .. } = entry_data {
+            header_data.last_activity = timestamp;
+        }
+        Ok(Entry {
+            header_data,
+            entry_data,
+        })
+    }
+
+    fn extend_data(&self, data: &mut Vec<u8>) -> Result<(), DataError> {
+        self.extend_data_with(data, true)
+    }
+
+    fn size_hint(&self) -> usize {
+        self.header_data.size_hint() + self.entry_data.size_hint()
+    }
+}
+
+/// the changes between two versions of an entry, see `Entry::diff`, each changed field as its (old, new) values
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct EntryDiff {
+    pub parent_id: Option<(EntryId, EntryId)>,
+    pub children_changed: bool,
+    pub author_id: Option<(UserId, UserId)>,
+    pub pinned: Option<(bool, bool)>,
+    pub deleted: Option<(bool, bool)>,
+    /// if the type changed, none of the data is compared
+    pub entry_type: Option<(u8, u8)>,
+    pub timestamp: Option<(u64, u64)>,
+    pub message: Option<(String, String)>,
+    pub name: Option<(String, String)>,
+    pub read_perms: IdSetDiff,
+    pub write_perms: IdSetDiff,
+    pub emoji: Option<(String, String)>,
+    pub target_id: Option<(EntryId, EntryId)>,
+    pub filename: Option<(String, String)>,
+    pub mime: Option<(String, String)>,
+    /// the data itself isn't kept, attachments can be large
+    pub data_changed: bool,
+}
+
+impl EntryDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// whether the entry was moved or its children changed, which edits can't do
+    pub fn changes_location(&self) -> bool {
+        self.parent_id.is_some() || self.children_changed
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct HeaderData {
+    pub version: u8,
+    pub parent_id: EntryId,
+    pub children_ids: Vec<EntryId>,
+    pub author_id: UserId,
+    /// the latest time (secs since Unix Epoch) the entry or any of its descendants was added or edited, kept by the server
+    pub last_activity: u64,
+    /// shown before its unpinned siblings, set through `BoardRequest::SetPinned`
+    pub pinned: bool,
+    /// a tombstone left by `BoardRequest::DeleteEntry`, see `Entry::tombstone`
+    pub deleted: bool,
+}
+
+impl HeaderData {
+    pub fn new(parent_id: EntryId, children_ids: Vec<EntryId>, author_id: UserId) -> Self {
+        Self { version: ENTRY_FILE_VERSION, parent_id, children_ids, author_id, last_activity: 0, pinned: false, deleted: false }
+    }
+
+    /// fails if the entry already has `MAX_CHILDREN` children, adding one it already has does nothing
+    pub fn add_child(&mut self, child_id: EntryId) -> Result<(), DataError> {
+        if self.children_ids.contains(&child_id) {return Ok(())}
+        if self.children_ids.len() >= MAX_CHILDREN {return Err(DataError::TooManyChildren)}
+        self.children_ids.push(child_id);
+        Ok(())
+    }
+
+    /// checks that the children of the entry at `entry_id` have no repeats and don't include the entry itself
+    pub fn validate(&self, entry_id: EntryId) -> Result<(), DataError> {
+        let mut seen = HashSet::with_capacity(self.children_ids.len());
+        for child_id in &self.children_ids {
+            if *child_id == entry_id || !seen.insert(*child_id) {return Err(DataError::InvalidStructure)}
+        }
+        Ok(())
+    }
+
+    /// gives a HeaderData and the entry type
+    pub fn from_data(data: &[u8]) -> Result<(Self, u8), DataError> {
+        let mut data_iter = data.iter().copied();
+        HeaderData::from_data_iter(&mut data_iter)
+    }
+
+    /// gives a HeaderData and the entry type
+    pub fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>) -> Result<(Self, u8), DataError> {
+        let magic_number = read_u16(data_iter)?;
+        if magic_number != ENTRY_MAGIC_NUMBER {return Err(DataError::IncorrectMagicNum)}
 
         let version = read_u8(data_iter)?;
-        if version != 0 {return Err(DataError::UnsupportedVersion)}
+        if version > ENTRY_FILE_VERSION {return Err(DataError::UnsupportedVersion)}
 
         let entry_type = read_u8(data_iter)?;
 
@@ -346,7 +1474,11 @@ impl HeaderData {
         }
 
         let author_id = read_u64(data_iter)?.into();
-        Ok((HeaderData { version, parent_id, children_ids, author_id }, entry_type))
+        // older versions fill this in from the entry data, see `Entry::from_data_iter`
+        let last_activity = if version >= 2 {read_u64(data_iter)?} else {0};
+        let pinned = if version >= 3 {read_bool(data_iter)?} else {false};
+        let deleted = if version >= 4 {read_bool(data_iter)?} else {false};
+        Ok((HeaderData { version, parent_id, children_ids, author_id, last_activity, pinned, deleted }, entry_type))
     }
 
     pub fn into_data(&self, entry_type: u8) -> Result<Vec<u8>, DataError> {
@@ -360,15 +1492,18 @@ impl HeaderData {
         data.push(ENTRY_FILE_VERSION);
         data.push(entry_type);
         data.extend_from_slice(&self.parent_id.to_le_bytes());
-        bounded_usize!(self.children_ids.len(), u16)?;
+        if self.children_ids.len() > MAX_CHILDREN {return Err(DataError::TooManyChildren)}
         data.extend_from_slice(&(self.children_ids.len() as u16).to_le_bytes());
         data.extend(self.children_ids.iter().flat_map(|x| x.to_le_bytes()));
         data.extend_from_slice(&self.author_id.to_le_bytes());
+        data.extend_from_slice(&self.last_activity.to_le_bytes());
+        data.push(self.pinned as u8);
+        data.push(self.deleted as u8);
         Ok(())
     }
 
     pub fn size_hint(&self) -> usize {
-        2 + 1 + 1 + 8 + 2 + self.children_ids.len() * 8 + 8
+        2 + 1 + 1 + 8 + 2 + self.children_ids.len() * 8 + 8 + 8 + 1 + 1
     }
 }
 
@@ -470,6 +1605,104 @@ impl DefaultedIdSet {
         }
     }
 
+    fn whitelist(&self) -> Option<&Vec<UserId>> {
+        match self {
+            Self::Inherit { whitelist_ids, .. } | Self::Black { whitelist_ids } => Some(whitelist_ids),
+            Self::White { .. } => None,
+        }
+    }
+
+    fn blacklist(&self) -> Option<&Vec<UserId>> {
+        match self {
+            Self::Inherit { blacklist_ids, .. } | Self::White { blacklist_ids } => Some(blacklist_ids),
+            Self::Black { .. } => None,
+        }
+    }
+
+    /// the whitelisted ids, empty if this base has no whitelist
+    pub fn iter_whitelist(&self) -> std::slice::Iter<'_, UserId> {
+        self.whitelist().map_or(&[] as &[UserId], |ids| ids.as_slice()).iter()
+    }
+
+    /// the blacklisted ids, empty if this base has no blacklist
+    pub fn iter_blacklist(&self) -> std::slice::Iter<'_, UserId> {
+        self.blacklist().map_or(&[] as &[UserId], |ids| ids.as_slice()).iter()
+    }
+
+    pub fn len_whitelist(&self) -> usize {
+        self.whitelist().map_or(0, |ids| ids.len())
+    }
+
+    pub fn len_blacklist(&self) -> usize {
+        self.blacklist().map_or(0, |ids| ids.len())
+    }
+
+    fn whitelist_mut(&mut self) -> Option<&mut Vec<UserId>> {
+        match self {
+            Self::Inherit { whitelist_ids, .. } | Self::Black { whitelist_ids } => Some(whitelist_ids),
+            Self::White { .. } => None,
+        }
+    }
+
+    fn blacklist_mut(&mut self) -> Option<&mut Vec<UserId>> {
+        match self {
+            Self::Inherit { blacklist_ids, .. } | Self::White { blacklist_ids } => Some(blacklist_ids),
+            Self::Black { .. } => None,
+        }
+    }
+
+    /// adds the id to the whitelist (and removes it from the blacklist),
+    /// false if this base has no whitelist
+    pub fn add_whitelist(&mut self, id: UserId) -> bool {
+        let Some(whitelist_ids) = self.whitelist_mut() else {return false};
+        if !whitelist_ids.contains(&id) {whitelist_ids.push(id);}
+        self.remove_blacklist(id);
+        true
+    }
+
+    pub fn remove_whitelist(&mut self, id: UserId) {
+        if let Some(whitelist_ids) = self.whitelist_mut() {
+            whitelist_ids.retain(|x| *x != id);
+        }
+    }
+
+    /// adds the id to the blacklist (and removes it from the whitelist),
+    /// false if this base has no blacklist
+    pub fn add_blacklist(&mut self, id: UserId) -> bool {
+        let Some(blacklist_ids) = self.blacklist_mut() else {return false};
+        if !blacklist_ids.contains(&id) {blacklist_ids.push(id);}
+        self.remove_whitelist(id);
+        true
+    }
+
+    pub fn remove_blacklist(&mut self, id: UserId) {
+        if let Some(blacklist_ids) = self.blacklist_mut() {
+            blacklist_ids.retain(|x| *x != id);
+        }
+    }
+
+    /// switches the base, keeping whichever lists both bases have
+    /// 
+    /// ie. Inherit -> White keeps the blacklist and drops the whitelist
+    pub fn set_base(&mut self, base: DefaultBase) {
+        let whitelist_ids = self.whitelist_mut().map(std::mem::take).unwrap_or_default();
+        let blacklist_ids = self.blacklist_mut().map(std::mem::take).unwrap_or_default();
+        *self = match base {
+            DefaultBase::Inherit => Self::Inherit { whitelist_ids, blacklist_ids },
+            DefaultBase::White => Self::White { blacklist_ids },
+            DefaultBase::Black => Self::Black { whitelist_ids },
+        };
+    }
+
+    /// the number of ids in both the whitelist and blacklist
+    pub fn id_count(&self) -> usize {
+        match self {
+            Self::Inherit { whitelist_ids, blacklist_ids } => whitelist_ids.len() + blacklist_ids.len(),
+            Self::White { blacklist_ids } => blacklist_ids.len(),
+            Self::Black { whitelist_ids } => whitelist_ids.len(),
+        }
+    }
+
     pub fn get_default_base(&self) -> DefaultBase {
         match &self {
             Self::Inherit { whitelist_ids: _, blacklist_ids: _ } => DefaultBase::Inherit,
@@ -477,13 +1710,97 @@ impl DefaultedIdSet {
             Self::White { blacklist_ids: _ } => DefaultBase::White,
         }
     }
+
+    /// what changed going from `self` to `new`, the ids in the order they are listed
+    pub fn diff(&self, new: &DefaultedIdSet) -> IdSetDiff {
+        let (old_base, new_base) = (self.get_default_base(), new.get_default_base());
+        let added = |old: std::slice::Iter<'_, UserId>, new: std::slice::Iter<'_, UserId>| {
+            let old: Vec<UserId> = old.copied().collect();
+            new.copied().filter(|id| !old.contains(id)).collect::<Vec<_>>()
+        };
+        IdSetDiff {
+            base: (old_base != new_base).then_some((old_base, new_base)),
+            whitelisted: added(self.iter_whitelist(), new.iter_whitelist()),
+            unwhitelisted: added(new.iter_whitelist(), self.iter_whitelist()),
+            blacklisted: added(self.iter_blacklist(), new.iter_blacklist()),
+            unblacklisted: added(new.iter_blacklist(), self.iter_blacklist()),
+        }
+    }
+}
+
+/// a `DefaultedIdSet` without its ids, see `BoardRequest::GetPermsSummary`
+/// 
+/// data format:
+/// 
+/// DefaultBase discriminant (u8)
+/// whitelist_len (u32), 0 if the base has no whitelist
+/// blacklist_len (u32), 0 if the base has no blacklist
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct PermsSummary {
+    pub base: DefaultBase,
+    pub whitelist_len: u32,
+    pub blacklist_len: u32,
+}
+
+impl PermsSummary {
+    pub fn new(perms: &DefaultedIdSet) -> Result<Self, DataError> {
+        let len = |len: usize| u32::try_from(len).map_err(|_| DataError::OOBUsizeConversion);
+        Ok(Self {
+            base: perms.get_default_base(),
+            whitelist_len: len(perms.len_whitelist())?,
+            blacklist_len: len(perms.len_blacklist())?,
+        })
+    }
+}
+
+impl AsData for PermsSummary {
+    fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>) -> Result<Self, DataError> {
+        let base = DefaultBase::from_discriminant(read_u8(data_iter)?)?;
+        let whitelist_len = read_u32(data_iter)?;
+        let blacklist_len = read_u32(data_iter)?;
+        // only the lists the base has can have ids
+        if (base == DefaultBase::White && whitelist_len != 0) || (base == DefaultBase::Black && blacklist_len != 0) {
+            return Err(DataError::InvalidStructure)
+        }
+        Ok(Self { base, whitelist_len, blacklist_len })
+    }
+
+    fn extend_data(&self, data: &mut Vec<u8>) -> Result<(), DataError> {
+        data.push(self.base.get_discriminant());
+        data.extend_from_slice(&self.whitelist_len.to_le_bytes());
+        data.extend_from_slice(&self.blacklist_len.to_le_bytes());
+        Ok(())
+    }
+
+    fn size_hint(&self) -> usize {
+        1 + 4 + 4
+    }
+}
+
+/// the changes between two `DefaultedIdSet`s, see `DefaultedIdSet::diff`
+/// 
+/// a list the new base doesn't have counts as emptied, ie. White -> Black unblacklists everyone
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct IdSetDiff {
+    /// the old and new base, if it changed
+    pub base: Option<(DefaultBase, DefaultBase)>,
+    pub whitelisted: Vec<UserId>,
+    pub unwhitelisted: Vec<UserId>,
+    pub blacklisted: Vec<UserId>,
+    pub unblacklisted: Vec<UserId>,
+}
+
+impl IdSetDiff {
+    pub fn is_empty(&self) -> bool {
+        self.base.is_none() && self.whitelisted.is_empty() && self.unwhitelisted.is_empty() && self.blacklisted.is_empty() && self.unblacklisted.is_empty()
+    }
 }
 
 impl AsData for DefaultedIdSet {
     fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>) -> Result<Self, DataError> {
         fn read_vec(data_iter: &mut impl Iterator<Item = u8>) -> Result<Vec<UserId>, DataError> {
-            let len = read_u32(data_iter)? as usize;
-            let mut vec = Vec::with_capacity(len);
+            let len = checked_usize(read_u32(data_iter)?)?;
+            let mut vec = Vec::with_capacity(len.min(MAX_DECLARED_PREALLOCATION));
             for _ in 0..len {
                 vec.push(read_u64(data_iter)?.into());
             }
@@ -546,133 +1863,650 @@ impl AsData for DefaultedIdSet {
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub enum EntryData {
-    Message {
-        timestamp: u64,
-        message: String,
-    },
-    AccessGroup {
-        name: String,
-        write_perms: DefaultedIdSet,
-        read_perms: DefaultedIdSet,
-    },
+/// walks up from `entry_id` (inclusive) towards the root, checking if `ancestor_id` is passed,
+/// `get_parent_id` gives an entry's parent
+pub fn has_ancestor(entry_id: EntryId, ancestor_id: EntryId, mut get_parent_id: impl FnMut(EntryId) -> Result<EntryId, DataError>) -> Result<bool, DataError> {
+    let mut current_id = entry_id;
+    loop {
+        if current_id == ancestor_id {return Ok(true)}
+        if *current_id == ROOT_ENTRY_ID {return Ok(false)}
+        current_id = get_parent_id(current_id)?;
+    }
 }
 
-impl EntryData {
-    pub fn get_discriminant(&self) -> u8 {
-        match self {
-            Self::Message { timestamp: _, message: _ } => MESSAGE,
-            Self::AccessGroup { name: _, read_perms: _, write_perms: _ } => ACCESS_GROUP,
+/// raises the last activity of `entry_id` and each of its ancestors up to the root (inclusive) to `timestamp`, 
+/// `put_entry` is only called for entries which changed, a chain which loops without reaching the root gives `MalformedRoot`
+pub fn propagate_activity(entry_id: EntryId, timestamp: u64, mut get_entry: impl FnMut(EntryId) -> Result<Entry, DataError>, mut put_entry: impl FnMut(EntryId, Entry) -> Result<(), DataError>) -> Result<(), DataError> {
+    let mut visited = Vec::new();
+    let mut current_id = entry_id;
+    loop {
+        if visited.contains(&current_id) {return Err(DataError::MalformedRoot)}
+        visited.push(current_id);
+        let mut entry = get_entry(current_id)?;
+        let parent_id = entry.header_data.parent_id;
+        if entry.header_data.last_activity < timestamp {
+            entry.header_data.last_activity = timestamp;
+            put_entry(current_id, entry)?;
         }
+        if *current_id == ROOT_ENTRY_ID {return Ok(())}
+        current_id = parent_id;
     }
+}
 
-    pub fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>, entry_type: u8) -> Result<Self, DataError> {
-        Ok(match entry_type {
-            MESSAGE => { // Message
-                let timestamp = read_u64(data_iter)?;
-                let message_size = read_u32(data_iter)? as usize;
-                let message = String::from_utf8(data_iter.take(message_size).collect::<Vec<_>>()).map_err(|e| DataError::StringError(e))?;
-                //if message.len() != message_size {return Err(DataError::MessageError)}
-                EntryData::Message { timestamp, message }
-            }
-            ACCESS_GROUP => { // AccessGroup
-                let name_len = read_u32(data_iter)? as usize;
-                let name = String::from_utf8((data_iter).take(name_len).collect::<Vec<_>>()).map_err(|e| DataError::StringError(e))?;
-                let write_perms = DefaultedIdSet::from_data_iter(data_iter)?;
-                let read_perms = DefaultedIdSet::from_data_iter(data_iter)?;
-                EntryData::AccessGroup { name, write_perms, read_perms }
-            }
-            _ => {return Err(DataError::InvalidDiscriminant)}
-        })
+/// the ids from just below the root down to `entry_id` (inclusive), empty for the root itself,
+/// `get_parent_id` gives an entry's parent, a chain which loops without reaching the root gives `MalformedRoot`
+/// and one longer than `max_depth` gives `DepthExceeded`
+pub fn ancestor_path(entry_id: EntryId, max_depth: usize, mut get_parent_id: impl FnMut(EntryId) -> Result<EntryId, DataError>) -> Result<Vec<EntryId>, DataError> {
+    let mut path = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current_id = entry_id;
+    while *current_id != ROOT_ENTRY_ID {
+        if !visited.insert(current_id) {return Err(DataError::MalformedRoot)}
+        if path.len() >= max_depth {return Err(DataError::DepthExceeded)}
+        path.push(current_id);
+        current_id = get_parent_id(current_id)?;
     }
+    path.reverse();
+    Ok(path)
+}
 
-    pub fn extend_data(&self, data: &mut Vec<u8>) -> Result<(), DataError> {
-        match self {
-            Self::Message { timestamp, message } => {
-                data.extend_from_slice(&timestamp.to_le_bytes());
-                bounded_usize!(message.len(), u32)?;
-                data.extend_from_slice(&(message.len() as u32).to_le_bytes());
-                data.extend_from_slice(message.as_bytes());
-            }
-            Self::AccessGroup { name, write_perms, read_perms } => {
-                bounded_usize!(name.len(), u32)?;
-                data.extend_from_slice(&(name.len() as u32).to_le_bytes());
-                data.extend_from_slice(name.as_bytes());
-                write_perms.extend_data(data)?;
-                read_perms.extend_data(data)?;
+/// walks up from `entry_id` (inclusive) towards the root, the first access group whose `perm_kind` perms 
+/// have a say on the user decides, `default_access` if none do 
+/// (only possible when every group up to and including the root inherits without listing the user)
+/// 
+/// `get_entry` gives an entry's parent and its data if it is an access group (None lets other entries skip being read),
+/// a chain which loops without reaching the root gives `MalformedRoot` and one longer than `max_depth` gives `DepthExceeded`
+pub fn resolve_access(user_id: UserId, entry_id: EntryId, perm_kind: PermKind, default_access: bool, max_depth: usize, mut get_entry: impl FnMut(EntryId) -> Result<(EntryId, Option<EntryData>), DataError>) -> Result<bool, DataError> {
+    let mut visited = HashSet::new();
+    let mut current_id = entry_id;
+    loop {
+        if !visited.insert(current_id) {return Err(DataError::MalformedRoot)}
+        if visited.len() > max_depth + 1 {return Err(DataError::DepthExceeded)}
+        let (parent_id, entry_data) = get_entry(current_id)?;
+        if let Some(entry_data) = entry_data {
+            let perms = entry_data.get_perms(perm_kind).ok_or(internal_error!())?;
+            if let Some(has_perm) = perms.contains(user_id) {
+                return Ok(has_perm);
             }
         }
-        Ok(())
+        if *current_id == ROOT_ENTRY_ID {
+            return Ok(default_access);
+        }
+        current_id = parent_id;
     }
+}
 
-    pub fn size_hint(&self) -> usize {
-        match self {
-            EntryData::Message { message, .. } => {
-                8 + 4 + message.as_bytes().len()
+/// re-walks a saved path of entry ids (below `root_id`),
+/// keeping them up to the first which no longer exists or is no longer a child of the one before it
+pub fn valid_path_prefix(path: &[EntryId], root_id: EntryId, mut get_parent_id: impl FnMut(EntryId) -> Result<EntryId, DataError>) -> Vec<EntryId> {
+    let mut parent_id = root_id;
+    path.iter().copied().take_while(|entry_id| {
+        let is_child = get_parent_id(*entry_id).is_ok_and(|id| id == parent_id);
+        parent_id = *entry_id;
+        is_child
+    }).collect()
+}
+
+/// the indices of the path's segments which fit in `max_width`, given each's width, 
+/// segments are joined by a separator 1 wide, and those elided from just after the root by an ellipsis 1 wide
+/// 
+/// the root and the last segment are always kept, even if they don't fit
+pub fn elide_path(widths: &[usize], max_width: usize) -> Vec<usize> {
+    let full_width = widths.iter().sum::<usize>() + widths.len().saturating_sub(1);
+    if full_width <= max_width || widths.len() <= 2 {return (0..widths.len()).collect()}
+    let last = widths.len() - 1;
+    // root / … / last
+    let mut width = widths[0] + 3 + widths[last];
+    let mut first_kept = last;
+    while first_kept > 1 && width + widths[first_kept - 1] < max_width {
+        first_kept -= 1;
+        width += widths[first_kept] + 1;
+    }
+    std::iter::once(0).chain(first_kept..=last).collect()
+}
+
+/// the entries the user has `perm_kind` perms to, in the given order, as `resolve_access` would decide for each,
+/// skipping those which don't exist
+/// 
+/// the decisions along the way are shared, so each entry is only gotten once however many of the entries it is above
+pub fn filter_accessible(user_id: UserId, entry_ids: &[EntryId], perm_kind: PermKind, default_access: bool, max_depth: usize, mut get_entry: impl FnMut(EntryId) -> Result<(EntryId, Option<EntryData>), DataError>) -> Result<Vec<EntryId>, DataError> {
+    let mut decisions: HashMap<EntryId, bool> = HashMap::new();
+    let mut accessible = Vec::new();
+    for entry_id in entry_ids {
+        // the entries walked through without a say, decided by whatever is above them
+        let mut undecided = Vec::new();
+        let mut current_id = *entry_id;
+        let decision = loop {
+            if let Some(decision) = decisions.get(&current_id) {break Ok(*decision)}
+            // bounded as in `resolve_access`, the undecided being the entries walked so far
+            if undecided.contains(&current_id) {break Err(DataError::MalformedRoot)}
+            if undecided.len() > max_depth {break Err(DataError::DepthExceeded)}
+            let (parent_id, entry_data) = match get_entry(current_id) {
+                Ok(entry) => entry,
+                Err(e) => break Err(e),
+            };
+            if let Some(entry_data) = entry_data {
+                let perms = entry_data.get_perms(perm_kind).ok_or(internal_error!())?;
+                if let Some(has_perm) = perms.contains(user_id) {
+                    decisions.insert(current_id, has_perm);
+                    break Ok(has_perm);
+                }
             }
-            EntryData::AccessGroup { name, write_perms, read_perms } => {
-                4 + name.as_bytes().len() + write_perms.size_hint() + read_perms.size_hint()
+            undecided.push(current_id);
+            if *current_id == ROOT_ENTRY_ID {break Ok(default_access)}
+            current_id = parent_id;
+        };
+        match decision {
+            Ok(decision) => {
+                decisions.extend(undecided.into_iter().map(|undecided_id| (undecided_id, decision)));
+                if decision {accessible.push(*entry_id)}
             }
+            // only the entry itself can be missing, its parents exist
+            Err(DataError::DoesNotExist) if undecided.is_empty() => {},
+            Err(e) => return Err(e),
         }
     }
+    Ok(accessible)
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum EntryVariant {
-    Message,
-    AccessGroup,
-}
+/// the users who have read an entry (see `BoardRequest::MarkRead`), kept by the server beside the entry
+/// 
+/// data format:
+///     user_id 1 - n (u64 each), sorted and without repeats
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct ReadReceipts(Vec<UserId>);
 
-impl EntryVariant {
-    pub fn as_string(self) -> &'static str {
-        match self {
-            Self::Message => "Message",
-            Self::AccessGroup => "Access Group"
+impl ReadReceipts {
+    /// records the user as having read the entry, false if they already had
+    pub fn insert(&mut self, user_id: UserId) -> bool {
+        match self.0.binary_search(&user_id) {
+            Ok(_) => false,
+            Err(idx) => {
+                self.0.insert(idx, user_id);
+                true
+            }
         }
     }
-}
 
-#[derive(PartialEq, Eq, Debug)]
-pub struct UserData {
-    pub aead: UserAeadKey,
-    pub entry_ids: Vec<EntryId>,
+    pub fn contains(&self, user_id: UserId) -> bool {
+        self.0.binary_search(&user_id).is_ok()
+    }
+
+    /// the entry's view count
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
-impl UserData {
-    pub fn new_empty(key: UserAeadKey) -> Self {
-        UserData { 
-            aead: key,
-            entry_ids: Vec::new() ,
+impl AsData for ReadReceipts {
+    fn extend_data(&self, data: &mut Vec<u8>) -> Result<(), DataError> {
+        for user_id in &self.0 {
+            data.extend_from_slice(&user_id.to_le_bytes());
+        }
+        Ok(())
+    }
+
+    fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>) -> Result<Self, DataError> where Self: Sized {
+        let mut data_iter = data_iter.peekable();
+        let mut receipts = ReadReceipts::default();
+        while data_iter.peek().is_some() {
+            receipts.insert(read_u64(&mut data_iter)?.into());
         }
+        Ok(receipts)
+    }
+
+    fn size_hint(&self) -> usize {
+        self.0.len() * 8
     }
 }
 
-/// currrent file version 0
+/// what `EntryIndex` keeps of an entry, enough to walk the tree without reading entry files
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct IndexedEntry {
+    pub parent_id: EntryId,
+    pub children_ids: Vec<EntryId>,
+    pub entry_type: u8,
+    pub name: Option<String>, // only AccessGroups have one
+}
+
+/// entry id -> `IndexedEntry`, kept by the server alongside the entry files
 /// 
-/// data format, numbers are little endian:
-///     magic number (u16): see `USER_MAGIC_NUMBER`
+/// each entry is indexed as it is written, so its parent's children are updated by indexing the parent
+#[derive(Debug, Default)]
+pub struct EntryIndex {
+    entries: HashMap<EntryId, IndexedEntry>,
+}
+
+impl EntryIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// (re)indexes the entry
+    pub fn insert(&mut self, entry_id: EntryId, entry: &Entry) {
+        let name = match &entry.entry_data {
+            EntryData::AccessGroup { name, .. } => Some(name.clone()),
+            EntryData::Message { .. } | EntryData::Reaction { .. } | EntryData::Attachment { .. } => None,
+        };
+        self.entries.insert(entry_id, IndexedEntry {
+            parent_id: entry.header_data.parent_id,
+            children_ids: entry.header_data.children_ids.clone(),
+            entry_type: entry.entry_data.get_discriminant(),
+            name,
+        });
+    }
+
+    /// unindexes the entry, also removing it from its parent's children
+    pub fn remove(&mut self, entry_id: EntryId) -> Option<IndexedEntry> {
+        let removed = self.entries.remove(&entry_id)?;
+        if let Some(parent) = self.entries.get_mut(&removed.parent_id) {
+            parent.children_ids.retain(|child_id| *child_id != entry_id);
+        }
+        Some(removed)
+    }
+
+    pub fn get(&self, entry_id: EntryId) -> Option<&IndexedEntry> {
+        self.entries.get(&entry_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn parent_id(&self, entry_id: EntryId) -> Result<EntryId, DataError> {
+        self.get(entry_id).map(|entry| entry.parent_id).ok_or(DataError::DoesNotExist)
+    }
+
+    pub fn children_ids(&self, entry_id: EntryId) -> Result<&[EntryId], DataError> {
+        self.get(entry_id).map(|entry| &entry.children_ids as &[_]).ok_or(DataError::DoesNotExist)
+    }
+
+    /// every entry below `entry_id`, breadth first
+    pub fn descendants(&self, entry_id: EntryId) -> Vec<EntryId> {
+        let mut descendants = Vec::new();
+        let mut to_visit = VecDeque::from([entry_id]);
+        while let Some(current_id) = to_visit.pop_front() {
+            let Ok(children_ids) = self.children_ids(current_id) else {continue};
+            for child_id in children_ids {
+                // the root is its own parent
+                if *child_id == current_id {continue}
+                descendants.push(*child_id);
+                to_visit.push_back(*child_id);
+            }
+        }
+        descendants
+    }
+
+    /// how many levels of entries are below `entry_id`, 0 if it has no children, 
+    /// each entry is walked once even if the index is inconsistent and has a cycle
+    pub fn subtree_height(&self, entry_id: EntryId) -> usize {
+        let mut visited = HashSet::from([entry_id]);
+        let mut to_visit = vec![(entry_id, 0)];
+        let mut height = 0;
+        while let Some((current_id, depth)) = to_visit.pop() {
+            height = height.max(depth);
+            let Ok(children_ids) = self.children_ids(current_id) else {continue};
+            for child_id in children_ids {
+                if visited.insert(*child_id) {to_visit.push((*child_id, depth + 1))}
+            }
+        }
+        height
+    }
+
+    /// how many entries are below `entry_id`, only walking the index, 
+    /// each entry is counted once even if the index is inconsistent and has a cycle,
+    /// `DepthExceeded` if any are more than `max_depth` below it
+    pub fn subtree_count(&self, entry_id: EntryId, max_depth: usize) -> Result<u64, DataError> {
+        self.get(entry_id).ok_or(DataError::DoesNotExist)?;
+        let mut visited = HashSet::from([entry_id]);
+        let mut to_visit = vec![(entry_id, 0)];
+        while let Some((current_id, depth)) = to_visit.pop() {
+            let Ok(children_ids) = self.children_ids(current_id) else {continue};
+            for child_id in children_ids {
+                if !visited.insert(*child_id) {continue}
+                if depth >= max_depth {return Err(DataError::DepthExceeded)}
+                to_visit.push((*child_id, depth + 1));
+            }
+        }
+        Ok(visited.len() as u64 - 1)
+    }
+}
+
+/// which of an AccessGroup's perm sets to check
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PermKind {
+    Read,
+    Write,
+}
+
+impl PermKind {
+    pub fn get_discriminant(&self) -> u8 {
+        match self {
+            Self::Read => READ_PERM,
+            Self::Write => WRITE_PERM,
+        }
+    }
+
+    pub fn from_discriminant(discriminant: u8) -> Result<Self, DataError> {
+        match discriminant {
+            READ_PERM => Ok(Self::Read),
+            WRITE_PERM => Ok(Self::Write),
+            _ => Err(DataError::InvalidDiscriminant)
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum EntryData {
+    Message {
+        timestamp: u64,
+        message: String,
+    },
+    AccessGroup {
+        name: String,
+        write_perms: DefaultedIdSet,
+        read_perms: DefaultedIdSet,
+    },
+    Reaction {
+        emoji: String,
+        target_id: EntryId,
+    },
+    /// a file, ie. for anything which isn't utf8
+    Attachment {
+        filename: String,
+        mime: String,
+        data: Vec<u8>,
+    },
+}
+
+impl EntryData {
+    pub fn get_discriminant(&self) -> u8 {
+        match self {
+            Self::Message { timestamp: _, message: _ } => MESSAGE,
+            Self::AccessGroup { name: _, read_perms: _, write_perms: _ } => ACCESS_GROUP,
+            Self::Reaction { emoji: _, target_id: _ } => REACTION,
+            Self::Attachment { filename: _, mime: _, data: _ } => ATTACHMENT,
+        }
+    }
+
+    /// gives the perm set of the matching kind, None if the entry doesn't have perms
+    pub fn get_perms(&self, perm_kind: PermKind) -> Option<&DefaultedIdSet> {
+        match (self, perm_kind) {
+            (Self::AccessGroup { read_perms, .. }, PermKind::Read) => Some(read_perms),
+            (Self::AccessGroup { write_perms, .. }, PermKind::Write) => Some(write_perms),
+            (Self::Message { .. } | Self::Reaction { .. } | Self::Attachment { .. }, _) => None,
+        }
+    }
+
+    /// checks the entry against `max_message_size` (in bytes, for attachment data as well), `MAX_ACCESS_GROUP_NAME_SIZE`, `MAX_PERM_IDS`, 
+    /// `MAX_REACTION_SIZE`, `MAX_ATTACHMENT_NAME_SIZE`, and `MAX_MIME_SIZE`,
+    /// also giving `EmptyReaction` for a reaction without an emoji
+    pub fn check_size_limits(&self, max_message_size: usize) -> Result<(), DataError> {
+        match self {
+            Self::Message { message, .. } => {
+                if message.len() > max_message_size {return Err(DataError::PayloadTooLarge { max: max_message_size })}
+            }
+            Self::AccessGroup { name, write_perms, read_perms } => {
+                if name.len() > MAX_ACCESS_GROUP_NAME_SIZE {return Err(DataError::PayloadTooLarge { max: MAX_ACCESS_GROUP_NAME_SIZE })}
+                if write_perms.id_count().max(read_perms.id_count()) > MAX_PERM_IDS {return Err(DataError::PayloadTooLarge { max: MAX_PERM_IDS })}
+            }
+            Self::Reaction { emoji, .. } => {
+                if emoji.len() > MAX_REACTION_SIZE {return Err(DataError::PayloadTooLarge { max: MAX_REACTION_SIZE })}
+                if emoji.trim().is_empty() {return Err(DataError::EmptyReaction)}
+            }
+            Self::Attachment { filename, mime, data } => {
+                if filename.len() > MAX_ATTACHMENT_NAME_SIZE {return Err(DataError::PayloadTooLarge { max: MAX_ATTACHMENT_NAME_SIZE })}
+                if mime.len() > MAX_MIME_SIZE {return Err(DataError::PayloadTooLarge { max: MAX_MIME_SIZE })}
+                if data.len() > max_message_size {return Err(DataError::PayloadTooLarge { max: max_message_size })}
+            }
+        }
+        Ok(())
+    }
+
+    /// `version` is the entry file version from the header
+    pub fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>, entry_type: u8, version: u8) -> Result<Self, DataError> {
+        Ok(match entry_type {
+            MESSAGE => { // Message
+                let timestamp = read_u64(data_iter)?;
+                let body_encoding = if version == 0 {RAW_BODY} else {read_u8(data_iter)?};
+                let message_size = checked_usize(read_u32(data_iter)?)?;
+                let stored_size = if version == 0 {message_size} else {checked_usize(read_u32(data_iter)?)?};
+                let message = match body_encoding {
+                    RAW_BODY => read_string(data_iter, stored_size)?,
+                    DEFLATE_BODY => {
+                        let stored = data_iter.take(stored_size).collect::<Vec<_>>();
+                        String::from_utf8(decompress_body(&stored, message_size)?)?
+                    }
+                    _ => return Err(DataError::InvalidDiscriminant),
+                };
+                //if message.len() != message_size {return Err(DataError::MessageError)}
+                EntryData::Message { timestamp, message }
+            }
+            ACCESS_GROUP => { // AccessGroup
+                let name_len = checked_usize(read_u32(data_iter)?)?;
+                let name = read_string(data_iter, name_len)?;
+                let write_perms = DefaultedIdSet::from_data_iter(data_iter)?;
+                let read_perms = DefaultedIdSet::from_data_iter(data_iter)?;
+                EntryData::AccessGroup { name, write_perms, read_perms }
+            }
+            REACTION => { // Reaction
+                let target_id = read_u64(data_iter)?.into();
+                let emoji_len = read_u8(data_iter)? as usize;
+                let emoji = read_string(data_iter, emoji_len)?;
+                EntryData::Reaction { emoji, target_id }
+            }
+            ATTACHMENT => { // Attachment
+                let filename_len = read_u8(data_iter)? as usize;
+                let filename = read_string(data_iter, filename_len)?;
+                let mime_len = read_u8(data_iter)? as usize;
+                let mime = read_string(data_iter, mime_len)?;
+                let data_size = checked_usize(read_u32(data_iter)?)?;
+                let data = read_bytes(data_iter, data_size)?;
+                EntryData::Attachment { filename, mime, data }
+            }
+            _ => {return Err(DataError::InvalidDiscriminant)}
+        })
+    }
+
+    pub fn extend_data(&self, data: &mut Vec<u8>) -> Result<(), DataError> {
+        self.extend_data_with(data, true)
+    }
+
+    /// a message body is only deflated if `compress` and it gets smaller (with the `compression` feature), 
+    /// entries go to disk compressed but are only sent compressed to clients which ask for it
+    pub fn extend_data_with(&self, data: &mut Vec<u8>, compress: bool) -> Result<(), DataError> {
+        self.extend_into_with(data, compress)
+    }
+
+    /// as `extend_data_with` but writing to `writer`, a message or attachment body is written from the entry itself
+    /// rather than being copied into a buffer first, see `BoardResponse::write_streamed`
+    pub fn extend_into_with<W: Write>(&self, writer: &mut W, compress: bool) -> Result<(), DataError> {
+        // everything ahead of the body is small, so it is gathered up and written at once
+        let mut head = Vec::new();
+        let body = match self {
+            Self::Message { timestamp, message } => {
+                head.extend_from_slice(&timestamp.to_le_bytes());
+                bounded_usize!(message.len(), u32)?;
+                match compress.then(|| compress_body(message)).flatten() {
+                    Some(compressed) => {
+                        head.push(DEFLATE_BODY);
+                        head.extend_from_slice(&(message.len() as u32).to_le_bytes());
+                        head.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+                        Cow::Owned(compressed)
+                    }
+                    None => {
+                        head.push(RAW_BODY);
+                        head.extend_from_slice(&(message.len() as u32).to_le_bytes());
+                        head.extend_from_slice(&(message.len() as u32).to_le_bytes());
+                        Cow::Borrowed(message.as_bytes())
+                    }
+                }
+            }
+            Self::AccessGroup { name, write_perms, read_perms } => {
+                bounded_usize!(name.len(), u32)?;
+                head.extend_from_slice(&(name.len() as u32).to_le_bytes());
+                head.extend_from_slice(name.as_bytes());
+                write_perms.extend_data(&mut head)?;
+                read_perms.extend_data(&mut head)?;
+                Cow::Borrowed(&[][..])
+            }
+            Self::Reaction { emoji, target_id } => {
+                bounded_usize!(emoji.len(), u8)?;
+                head.extend_from_slice(&target_id.to_le_bytes());
+                head.push(emoji.len() as u8);
+                head.extend_from_slice(emoji.as_bytes());
+                Cow::Borrowed(&[][..])
+            }
+            Self::Attachment { filename, mime, data: attachment_data } => {
+                bounded_usize!(filename.len(), u8)?;
+                bounded_usize!(mime.len(), u8)?;
+                bounded_usize!(attachment_data.len(), u32)?;
+                head.push(filename.len() as u8);
+                head.extend_from_slice(filename.as_bytes());
+                head.push(mime.len() as u8);
+                head.extend_from_slice(mime.as_bytes());
+                head.extend_from_slice(&(attachment_data.len() as u32).to_le_bytes());
+                Cow::Borrowed(&attachment_data[..])
+            }
+        };
+        writer.write_all(&head)?;
+        writer.write_all(&body)?;
+        Ok(())
+    }
+
+    /// exact, except that a message body is counted at its raw size, 
+    /// which is more than it takes up compressed, rather than compressing it just to measure it
+    pub fn size_hint(&self) -> usize {
+        match self {
+            EntryData::Message { message, .. } => {
+                8 + 1 + 4 + 4 + message.len()
+            }
+            EntryData::AccessGroup { name, write_perms, read_perms } => {
+                4 + name.as_bytes().len() + write_perms.size_hint() + read_perms.size_hint()
+            }
+            EntryData::Reaction { emoji, .. } => {
+                8 + 1 + emoji.len()
+            }
+            EntryData::Attachment { filename, mime, data } => {
+                1 + filename.len() + 1 + mime.len() + 4 + data.len()
+            }
+        }
+    }
+}
+
+/// how many of each emoji there are, most common first and otherwise in the order first seen
+pub fn count_reactions<'a>(emojis: impl IntoIterator<Item = &'a str>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for emoji in emojis {
+        match counts.iter_mut().find(|(counted, _)| counted == emoji) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((emoji.to_string(), 1)),
+        }
+    }
+    // stable, so ties keep the order first seen
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts
+}
+
+/// deflates the message body, None if that wouldn't make it smaller
+#[cfg(feature = "compression")]
+fn compress_body(message: &str) -> Option<Vec<u8>> {
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(message.as_bytes()).ok()?;
+    let compressed = encoder.finish().ok()?;
+    (compressed.len() < message.len()).then_some(compressed)
+}
+
+#[cfg(not(feature = "compression"))]
+fn compress_body(_message: &str) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(feature = "compression")]
+fn decompress_body(stored: &[u8], message_size: usize) -> Result<Vec<u8>, DataError> {
+    let mut body = Vec::new();
+    flate2::read::DeflateDecoder::new(stored).take(message_size as u64).read_to_end(&mut body)?;
+    if body.len() != message_size {return Err(DataError::InsufficientBytes)}
+    Ok(body)
+}
+
+/// compressed entries can't be read without the `compression` feature
+#[cfg(not(feature = "compression"))]
+fn decompress_body(_stored: &[u8], _message_size: usize) -> Result<Vec<u8>, DataError> {
+    Err(DataError::UnsupportedVersion)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EntryVariant {
+    Message,
+    AccessGroup,
+}
+
+impl EntryVariant {
+    pub fn as_string(self) -> &'static str {
+        match self {
+            Self::Message => "Message",
+            Self::AccessGroup => "Access Group"
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct UserData {
+    pub aead: UserAeadKey,
+    pub entry_ids: Vec<EntryId>,
+    /// shown in place of the user's id (see `user_label`), set through `BoardRequest::SetDisplayName`
+    pub display_name: Option<String>,
+}
+
+impl UserData {
+    pub fn new_empty(key: UserAeadKey) -> Self {
+        UserData { 
+            aead: key,
+            entry_ids: Vec::new() ,
+            display_name: None,
+        }
+    }
+}
+
+/// currrent file version 1
+/// 
+/// data format, numbers are little endian:
+///     magic number (u16): see `USER_MAGIC_NUMBER`
 ///     version number (u8)
 ///     - UserAeadKey data - 
 ///     number of entry ids (u32),
 ///     entry id 1 (u64),
 ///     ...
-///     entry id n (u64)
+///     entry id n (u64),
+///     display name length (u8), 0 for none (version 1+),
+///     display name string (utf8 encoded) (version 1+)
 impl AsData for UserData {
     fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>) -> Result<Self, DataError> {
         let magic_number = read_u16(data_iter)?;
         if magic_number != USER_MAGIC_NUMBER {return Err(DataError::IncorrectMagicNum)};
         let version = read_u8(data_iter)?;
-        if version != 0 {return Err(DataError::UnsupportedVersion)};
+        if version > USER_FILE_VERSION {return Err(DataError::UnsupportedVersion)};
         let aead = UserAeadKey::from_data_iter(data_iter)?;
-        let num_entries = read_u32(data_iter)? as usize;
-        let mut entry_ids = Vec::with_capacity(num_entries);
+        let num_entries = checked_usize(read_u32(data_iter)?)?;
+        let mut entry_ids = Vec::with_capacity(num_entries.min(MAX_DECLARED_PREALLOCATION));
         for _ in 0..num_entries {
             entry_ids.push(read_u64(data_iter)?.into());
         }
+        // version 0 users have no display name
+        let display_name = if version >= 1 {read_display_name(data_iter)?} else {None};
         Ok(UserData { 
             aead,
-            entry_ids
+            entry_ids,
+            display_name,
         })
     }
 
@@ -683,11 +2517,12 @@ impl AsData for UserData {
         bounded_usize!(self.entry_ids.len(), u32)?;
         data.extend_from_slice(&(self.entry_ids.len() as u32).to_le_bytes());
         data.extend(self.entry_ids.iter().flat_map(|x| x.to_le_bytes()));
+        extend_with_display_name(self.display_name.as_deref(), data)?;
         Ok(())
     }
 
     fn size_hint(&self) -> usize {
-        2 + 1 + 4 + self.entry_ids.len() * 8
+        2 + 1 + self.aead.size_hint() + 4 + self.entry_ids.len() * 8 + 1 + self.display_name.as_ref().map_or(0, |name| name.len())
     }
     fn sanitize(&mut self) {
         self.aead.sanitize();
@@ -758,24 +2593,141 @@ pub enum ReEncryptionData {
 ///     user_id (u64)
 ///     - Entry data - 
 /// 
-/// EditEntry, 0x03:
+/// EditEntry, 0x02:
 ///     user_id (u64),
 ///     entry_id (u64)
 ///     - Entry data -
 /// 
+/// MoveEntry, 0x03:
+///     user_id (u64),
+///     entry_id (u64),
+///     new_parent_id (u64)
+/// 
+/// AddEntries, 0x04:
+///     user_id (u64),
+///     number of entries (u32),
+///     - Entry data - (for each entry)
+/// 
+/// DeleteEntry, 0x05:
+///     user_id (u64),
+///     entry_id (u64)
+/// 
+/// GetAncestors, 0x06:
+///     user_id (u64),
+///     entry_id (u64)
+/// 
+/// SetPinned, 0x07:
+///     user_id (u64),
+///     entry_id (u64),
+///     pinned (u8): 00 or 01
+/// 
+/// GetEntries, 0x08:
+///     user_id (u64),
+///     number of entry_ids (u32),
+///     entry_id 1 - n (u64 each)
+/// 
+/// ChildrenSince, 0x09:
+///     user_id (u64),
+///     entry_id (u64),
+///     since_ts (secs since Unix Epoch) (u64)
+/// 
+/// FilterAccessible, 0x0A:
+///     user_id (u64),
+///     perm_kind (u8),
+///     number of entry_ids (u32),
+///     entry_id 1 - n (u64 each)
+/// 
+/// MarkRead, 0x0B:
+///     user_id (u64),
+///     entry_id (u64)
+/// 
+/// SubtreeCount, 0x0C:
+///     user_id (u64),
+///     entry_id (u64)
+/// 
+/// GetPermsSummary, 0x0D:
+///     user_id (u64),
+///     entry_id (u64)
+/// 
 /// GetUser, 0x20:
+///     requester_id (u64),
 ///     user_id (u64)
 /// 
 /// AddUser, 0x21:
 ///     - no data -
+/// 
+/// ListUsers, 0x22:
+///     admin_id (u64),
+///     start (u64)
+/// 
+/// SetDisplayName, 0x23:
+///     user_id (u64),
+///     display name length (u8), 0 for none,
+///     display name string (utf8 encoded)
+/// 
+/// Status, 0x81:
+///     - no data -
+/// 
+/// Ping, 0x82:
+///     - no data -
+/// 
+/// NegotiateVersion, 0x83:
+///     min_version (u8)
+///     max_version (u8)
+/// 
+/// GetRoot, 0x84:
+///     - no data -
+/// 
+/// ListBoards, 0x85:
+///     - no data -
+/// 
+/// Metrics, 0x86:
+///     admin_id (u64)
 #[derive(PartialEq, Eq, Debug)]
 pub enum BoardRequest {
     GetEntry { user_id: UserId, entry_id: EntryId },
     AddEntry { user_id: UserId, entry: Entry },
     EditEntry { user_id: UserId, entry_id: EntryId, entry: Entry },
-    GetUser { user_id: UserId },
+    MoveEntry { user_id: UserId, entry_id: EntryId, new_parent_id: EntryId },
+    AddEntries { user_id: UserId, entries: Vec<Entry> },
+    /// leaves a tombstone in the entry's place, see `Entry::tombstone`
+    DeleteEntry { user_id: UserId, entry_id: EntryId },
+    /// the ids from just below the root down to the entry, see `ancestor_path`
+    GetAncestors { user_id: UserId, entry_id: EntryId },
+    /// needs write perms to the entry's parent
+    SetPinned { user_id: UserId, entry_id: EntryId, pinned: bool },
+    /// each entry as if by GetEntry, a missing or unreadable one doesn't fail the rest
+    GetEntries { user_id: UserId, entry_ids: Vec<EntryId> },
+    /// the children of the entry with activity (see `HeaderData::last_activity`) after `since_ts`
+    ChildrenSince { user_id: UserId, entry_id: EntryId, since_ts: u64 },
+    /// which of the entries the user has the perms to, see `filter_accessible`
+    FilterAccessible { user_id: UserId, perm_kind: PermKind, entry_ids: Vec<EntryId> },
+    /// records that the user has read the entry, once however often they do, needs read perms to it
+    MarkRead { user_id: UserId, entry_id: EntryId },
+    /// how many entries are below the entry, see `EntryIndex::subtree_count`
+    SubtreeCount { user_id: UserId, entry_id: EntryId },
+    /// the base and list lengths of an access group's perms, for groups too big to want the ids of, 
+    /// needs the same perms as `GetEntry`
+    GetPermsSummary { user_id: UserId, entry_id: EntryId },
+    /// another user's data is only given as far as the server's `user_privacy` allows
+    GetUser { requester_id: UserId, user_id: UserId },
     AddUser,
+    /// the page of user ids starting at `start`, only for the board's configured admin
+    ListUsers { admin_id: UserId, start: UserId },
+    /// at most `MAX_DISPLAY_NAME_SIZE` bytes, None clears it
+    SetDisplayName { user_id: UserId, display_name: Option<String> },
     GetKemEk,
+    Status,
+    /// keeps an idle connection alive, answered with `BoardResponse::Pong`
+    Ping,
+    /// the first request on a connection, the board picks a version in the range (see `negotiate_version`)
+    NegotiateVersion { min_version: u8, max_version: u8 },
+    /// the id of the board's root entry, where clients start rather than assuming `ROOT_ENTRY_ID`
+    GetRoot,
+    /// the names of the boards the server hosts, see `MAIN_BOARD`
+    ListBoards,
+    /// the server's counters in the Prometheus text exposition format, only given to the board's admin
+    Metrics { admin_id: UserId },
 }
 
 impl AsData for BoardRequest {
@@ -790,20 +2742,103 @@ impl AsData for BoardRequest {
             BoardRequest::AddEntry { user_id, entry } => {
                 data.push(ADD_ENTRY);
                 data.extend_from_slice(&user_id.to_le_bytes());
-                entry.extend_data(data)?;
+                entry.extend_data_with(data, false)?;
             },
             BoardRequest::EditEntry { user_id, entry_id, entry } => {
                 data.push(EDIT_ENTRY);
                 data.extend_from_slice(&user_id.to_le_bytes());
                 data.extend_from_slice(&entry_id.to_le_bytes());
-                entry.extend_data(data)?;
+                entry.extend_data_with(data, false)?;
             }
-            BoardRequest::GetUser { user_id } => {
+            BoardRequest::MoveEntry { user_id, entry_id, new_parent_id } => {
+                data.push(MOVE_ENTRY);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                data.extend_from_slice(&entry_id.to_le_bytes());
+                data.extend_from_slice(&new_parent_id.to_le_bytes());
+            }
+            BoardRequest::AddEntries { user_id, entries } => {
+                data.push(ADD_ENTRIES);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                extend_with_entries(entries, data)?;
+            }
+            BoardRequest::DeleteEntry { user_id, entry_id } => {
+                data.push(DELETE_ENTRY);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                data.extend_from_slice(&entry_id.to_le_bytes());
+            }
+            BoardRequest::GetAncestors { user_id, entry_id } => {
+                data.push(GET_ANCESTORS);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                data.extend_from_slice(&entry_id.to_le_bytes());
+            }
+            BoardRequest::SetPinned { user_id, entry_id, pinned } => {
+                data.push(SET_PINNED);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                data.extend_from_slice(&entry_id.to_le_bytes());
+                data.push(*pinned as u8);
+            }
+            BoardRequest::GetEntries { user_id, entry_ids } => {
+                data.push(GET_ENTRIES);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                extend_with_ids(entry_ids, data)?;
+            }
+            BoardRequest::ChildrenSince { user_id, entry_id, since_ts } => {
+                data.push(CHILDREN_SINCE);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                data.extend_from_slice(&entry_id.to_le_bytes());
+                data.extend_from_slice(&since_ts.to_le_bytes());
+            }
+            BoardRequest::FilterAccessible { user_id, perm_kind, entry_ids } => {
+                data.push(FILTER_ACCESSIBLE);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                data.push(perm_kind.get_discriminant());
+                extend_with_ids(entry_ids, data)?;
+            }
+            BoardRequest::MarkRead { user_id, entry_id } => {
+                data.push(MARK_READ);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                data.extend_from_slice(&entry_id.to_le_bytes());
+            }
+            BoardRequest::SubtreeCount { user_id, entry_id } => {
+                data.push(SUBTREE_COUNT);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                data.extend_from_slice(&entry_id.to_le_bytes());
+            }
+            BoardRequest::GetPermsSummary { user_id, entry_id } => {
+                data.push(GET_PERMS_SUMMARY);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                data.extend_from_slice(&entry_id.to_le_bytes());
+            }
+            BoardRequest::GetUser { requester_id, user_id } => {
                 data.push(GET_USER);
+                data.extend_from_slice(&requester_id.to_le_bytes());
                 data.extend_from_slice(&user_id.to_le_bytes());
             },
             BoardRequest::AddUser => data.push(ADD_USER),
-            BoardRequest::GetKemEk => data.push(GET_KEM_EK)
+            BoardRequest::ListUsers { admin_id, start } => {
+                data.push(LIST_USERS);
+                data.extend_from_slice(&admin_id.to_le_bytes());
+                data.extend_from_slice(&start.to_le_bytes());
+            }
+            BoardRequest::SetDisplayName { user_id, display_name } => {
+                data.push(SET_DISPLAY_NAME);
+                data.extend_from_slice(&user_id.to_le_bytes());
+                extend_with_display_name(display_name.as_deref(), data)?;
+            }
+            BoardRequest::GetKemEk => data.push(GET_KEM_EK),
+            BoardRequest::Status => data.push(STATUS),
+            BoardRequest::Ping => data.push(PING),
+            BoardRequest::NegotiateVersion { min_version, max_version } => {
+                data.push(NEGOTIATE_VERSION);
+                data.push(*min_version);
+                data.push(*max_version);
+            }
+            BoardRequest::GetRoot => data.push(GET_ROOT),
+            BoardRequest::ListBoards => data.push(LIST_BOARDS),
+            BoardRequest::Metrics { admin_id } => {
+                data.push(METRICS);
+                data.extend_from_slice(&admin_id.to_le_bytes());
+            }
         };
         Ok(())
     }
@@ -830,18 +2865,109 @@ impl AsData for BoardRequest {
                 let entry = Entry::from_data_iter(data_iter)?;
                 BoardRequest::EditEntry { user_id, entry_id, entry }
             }
+            MOVE_ENTRY => {
+                let user_id = read_u64(data_iter)?.into();
+                let entry_id = read_u64(data_iter)?.into();
+                let new_parent_id = read_u64(data_iter)?.into();
+                BoardRequest::MoveEntry { user_id, entry_id, new_parent_id }
+            }
+            ADD_ENTRIES => {
+                let user_id = read_u64(data_iter)?.into();
+                let entries = read_entries(data_iter)?;
+                BoardRequest::AddEntries { user_id, entries }
+            }
+            DELETE_ENTRY => {
+                let user_id = read_u64(data_iter)?.into();
+                let entry_id = read_u64(data_iter)?.into();
+                BoardRequest::DeleteEntry { user_id, entry_id }
+            }
+            GET_ANCESTORS => {
+                let user_id = read_u64(data_iter)?.into();
+                let entry_id = read_u64(data_iter)?.into();
+                BoardRequest::GetAncestors { user_id, entry_id }
+            }
+            SET_PINNED => {
+                let user_id = read_u64(data_iter)?.into();
+                let entry_id = read_u64(data_iter)?.into();
+                let pinned = read_bool(data_iter)?;
+                BoardRequest::SetPinned { user_id, entry_id, pinned }
+            }
+            GET_ENTRIES => {
+                let user_id = read_u64(data_iter)?.into();
+                let entry_ids = read_ids(data_iter)?;
+                BoardRequest::GetEntries { user_id, entry_ids }
+            }
+            CHILDREN_SINCE => {
+                let user_id = read_u64(data_iter)?.into();
+                let entry_id = read_u64(data_iter)?.into();
+                let since_ts = read_u64(data_iter)?;
+                BoardRequest::ChildrenSince { user_id, entry_id, since_ts }
+            }
+            FILTER_ACCESSIBLE => {
+                let user_id = read_u64(data_iter)?.into();
+                let perm_kind = PermKind::from_discriminant(read_u8(data_iter)?)?;
+                let entry_ids = read_ids(data_iter)?;
+                BoardRequest::FilterAccessible { user_id, perm_kind, entry_ids }
+            }
+            MARK_READ => {
+                let user_id = read_u64(data_iter)?.into();
+                let entry_id = read_u64(data_iter)?.into();
+                BoardRequest::MarkRead { user_id, entry_id }
+            }
+            SUBTREE_COUNT => {
+                let user_id = read_u64(data_iter)?.into();
+                let entry_id = read_u64(data_iter)?.into();
+                BoardRequest::SubtreeCount { user_id, entry_id }
+            }
+            GET_PERMS_SUMMARY => {
+                let user_id = read_u64(data_iter)?.into();
+                let entry_id = read_u64(data_iter)?.into();
+                BoardRequest::GetPermsSummary { user_id, entry_id }
+            }
             // user requests
             GET_USER => { // GetUser
+                let requester_id = read_u64(data_iter)?.into();
                 let user_id = read_u64(data_iter)?.into();
-                BoardRequest::GetUser { user_id }
+                BoardRequest::GetUser { requester_id, user_id }
             }
             ADD_USER => { // AddUser
                 BoardRequest::AddUser
             }
+            LIST_USERS => {
+                let admin_id = read_u64(data_iter)?.into();
+                let start = read_u64(data_iter)?.into();
+                BoardRequest::ListUsers { admin_id, start }
+            }
+            SET_DISPLAY_NAME => {
+                let user_id = read_u64(data_iter)?.into();
+                let display_name = read_display_name(data_iter)?;
+                BoardRequest::SetDisplayName { user_id, display_name }
+            }
             // network requests
             GET_KEM_EK => {
                 BoardRequest::GetKemEk
             }
+            STATUS => {
+                BoardRequest::Status
+            }
+            PING => {
+                BoardRequest::Ping
+            }
+            NEGOTIATE_VERSION => {
+                let min_version = read_u8(data_iter)?;
+                let max_version = read_u8(data_iter)?;
+                BoardRequest::NegotiateVersion { min_version, max_version }
+            }
+            GET_ROOT => {
+                BoardRequest::GetRoot
+            }
+            LIST_BOARDS => {
+                BoardRequest::ListBoards
+            }
+            METRICS => {
+                let admin_id = read_u64(data_iter)?.into();
+                BoardRequest::Metrics { admin_id }
+            }
             _ => {return Err(DataError::InvalidDiscriminant)}
         })
     }
@@ -857,15 +2983,51 @@ impl AsData for BoardRequest {
             BoardRequest::EditEntry { entry, .. } => {
                 1 + 1 + 8 + 8 + entry.size_hint()
             }
+            BoardRequest::MoveEntry { .. } => {
+                1 + 1 + 8 + 8 + 8
+            }
+            BoardRequest::AddEntries { entries, .. } => {
+                1 + 1 + 8 + 4 + entries.iter().map(|entry| entry.size_hint()).sum::<usize>()
+            }
+            BoardRequest::DeleteEntry { .. } | BoardRequest::GetAncestors { .. } | BoardRequest::MarkRead { .. } | BoardRequest::SubtreeCount { .. } | BoardRequest::GetPermsSummary { .. } => {
+                1 + 1 + 8 + 8
+            }
+            BoardRequest::SetPinned { .. } => {
+                1 + 1 + 8 + 8 + 1
+            }
+            BoardRequest::GetEntries { entry_ids, .. } => {
+                1 + 1 + 8 + 4 + entry_ids.len() * 8
+            }
+            BoardRequest::ChildrenSince { .. } => {
+                1 + 1 + 8 + 8 + 8
+            }
+            BoardRequest::FilterAccessible { entry_ids, .. } => {
+                1 + 1 + 8 + 1 + 4 + entry_ids.len() * 8
+            }
             BoardRequest::GetUser { .. } => {
-                1 + 1 + 8
+                1 + 1 + 8 + 8
             }
             BoardRequest::AddUser => {
                 1 + 1
             }
+            BoardRequest::ListUsers { .. } => {
+                1 + 1 + 8 + 8
+            }
+            BoardRequest::SetDisplayName { display_name, .. } => {
+                1 + 1 + 8 + 1 + display_name.as_ref().map_or(0, |name| name.len())
+            }
             BoardRequest::GetKemEk => {
                 1 + 1
             }
+            BoardRequest::Status | BoardRequest::Ping | BoardRequest::GetRoot | BoardRequest::ListBoards => {
+                1 + 1
+            }
+            BoardRequest::Metrics { .. } => {
+                1 + 1 + 8
+            }
+            BoardRequest::NegotiateVersion { .. } => {
+                1 + 1 + 1 + 1
+            }
         }
     }
 }
@@ -893,19 +3055,195 @@ impl AsData for BoardRequest {
 /// AddEntry, 0x01 (user):
 ///     - Entry data - 
 /// 
-/// EditEntry, 0x03 (user): 
+/// EditEntry, 0x02 (user): 
 ///     entry_id (u64)
 ///     - Entry data -
 /// 
-/// GetUser, 0x20 (any):
+/// MoveEntry, 0x03 (user):
+///     entry_id (u64)
+///     new_parent_id (u64)
+/// 
+/// AddEntries, 0x04 (user):
+///     number of entries (u32)
+///     - Entry data - (for each entry)
+/// 
+/// DeleteEntry, 0x05 (user):
+///     entry_id (u64)
+/// 
+/// GetAncestors, 0x06 (user):
+///     entry_id (u64)
+/// 
+/// SetPinned, 0x07 (user):
+///     entry_id (u64)
+///     pinned (u8): 00 or 01
+/// 
+/// GetEntries, 0x08 (user):
+///     number of entry_ids (u32)
+///     entry_id 1 - n (u64 each)
+/// 
+/// ChildrenSince, 0x09 (user):
+///     entry_id (u64)
+///     since_ts (secs since Unix Epoch) (u64)
+/// 
+/// FilterAccessible, 0x0A (user):
+///     perm_kind (u8)
+///     number of entry_ids (u32)
+///     entry_id 1 - n (u64 each)
+/// 
+/// MarkRead, 0x0B (user):
+///     entry_id (u64)
+/// 
+/// SubtreeCount, 0x0C (user):
+///     entry_id (u64)
+/// 
+/// GetPermsSummary, 0x0D (user):
+///     entry_id (u64)
+/// 
+/// GetUser, 0x20 (user):
 ///     user_id (u64)
 /// 
 /// AddUser, 0x21 (any):
 ///     - no data -
+/// 
+/// ListUsers, 0x22 (user):
+///     start (u64)
+/// 
+/// SetDisplayName, 0x23 (user):
+///     display name length (u8), 0 for none,
+///     display name string (utf8 encoded)
+/// 
+/// Status, 0x81 (exposed):
+///     - no data -
+/// 
+/// Ping, 0x82 (exposed):
+///     - no data -
+/// 
+/// NegotiateVersion, 0x83 (exposed):
+///     min_version (u8)
+///     max_version (u8)
+/// 
+/// GetRoot, 0x84 (exposed):
+///     - no data -
+/// 
+/// ListBoards, 0x85 (exposed):
+///     - no data -
+/// 
+/// Metrics, 0x86 (user):
+///     - no data -
 impl BoardRequest {
+    pub fn get_discriminant(&self) -> u8 {
+        match self {
+            BoardRequest::GetEntry { .. } => GET_ENTRY,
+            BoardRequest::AddEntry { .. } => ADD_ENTRY,
+            BoardRequest::EditEntry { .. } => EDIT_ENTRY,
+            BoardRequest::MoveEntry { .. } => MOVE_ENTRY,
+            BoardRequest::AddEntries { .. } => ADD_ENTRIES,
+            BoardRequest::DeleteEntry { .. } => DELETE_ENTRY,
+            BoardRequest::GetAncestors { .. } => GET_ANCESTORS,
+            BoardRequest::SetPinned { .. } => SET_PINNED,
+            BoardRequest::GetEntries { .. } => GET_ENTRIES,
+            BoardRequest::ChildrenSince { .. } => CHILDREN_SINCE,
+            BoardRequest::FilterAccessible { .. } => FILTER_ACCESSIBLE,
+            BoardRequest::MarkRead { .. } => MARK_READ,
+            BoardRequest::SubtreeCount { .. } => SUBTREE_COUNT,
+            BoardRequest::GetPermsSummary { .. } => GET_PERMS_SUMMARY,
+            BoardRequest::GetUser { .. } => GET_USER,
+            BoardRequest::AddUser => ADD_USER,
+            BoardRequest::ListUsers { .. } => LIST_USERS,
+            BoardRequest::SetDisplayName { .. } => SET_DISPLAY_NAME,
+            BoardRequest::GetKemEk => GET_KEM_EK,
+            BoardRequest::Status => STATUS,
+            BoardRequest::Ping => PING,
+            BoardRequest::NegotiateVersion { .. } => NEGOTIATE_VERSION,
+            BoardRequest::GetRoot => GET_ROOT,
+            BoardRequest::ListBoards => LIST_BOARDS,
+            BoardRequest::Metrics { .. } => METRICS,
+        }
+    }
+
+    /// the variant's name, ie. for logs and metrics
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            BoardRequest::GetEntry { .. } => "GetEntry",
+            BoardRequest::AddEntry { .. } => "AddEntry",
+            BoardRequest::EditEntry { .. } => "EditEntry",
+            BoardRequest::MoveEntry { .. } => "MoveEntry",
+            BoardRequest::AddEntries { .. } => "AddEntries",
+            BoardRequest::DeleteEntry { .. } => "DeleteEntry",
+            BoardRequest::GetAncestors { .. } => "GetAncestors",
+            BoardRequest::SetPinned { .. } => "SetPinned",
+            BoardRequest::GetEntries { .. } => "GetEntries",
+            BoardRequest::ChildrenSince { .. } => "ChildrenSince",
+            BoardRequest::FilterAccessible { .. } => "FilterAccessible",
+            BoardRequest::MarkRead { .. } => "MarkRead",
+            BoardRequest::SubtreeCount { .. } => "SubtreeCount",
+            BoardRequest::GetPermsSummary { .. } => "GetPermsSummary",
+            BoardRequest::GetUser { .. } => "GetUser",
+            BoardRequest::AddUser => "AddUser",
+            BoardRequest::ListUsers { .. } => "ListUsers",
+            BoardRequest::SetDisplayName { .. } => "SetDisplayName",
+            BoardRequest::GetKemEk => "GetKemEk",
+            BoardRequest::Status => "Status",
+            BoardRequest::Ping => "Ping",
+            BoardRequest::NegotiateVersion { .. } => "NegotiateVersion",
+            BoardRequest::GetRoot => "GetRoot",
+            BoardRequest::ListBoards => "ListBoards",
+            BoardRequest::Metrics { .. } => "Metrics",
+        }
+    }
+
+    /// the user the request is made as, None for those not made as anyone
+    pub fn user_id(&self) -> Option<UserId> {
+        match self {
+            BoardRequest::GetEntry { user_id, .. } | BoardRequest::AddEntry { user_id, ..} | BoardRequest::EditEntry { user_id, .. } | BoardRequest::MoveEntry { user_id, .. } | BoardRequest::AddEntries { user_id, .. } | BoardRequest::DeleteEntry { user_id, .. } | BoardRequest::GetAncestors { user_id, .. } | BoardRequest::SetPinned { user_id, .. } | BoardRequest::GetEntries { user_id, .. } | BoardRequest::ChildrenSince { user_id, .. } | BoardRequest::FilterAccessible { user_id, .. } | BoardRequest::MarkRead { user_id, .. } | BoardRequest::SubtreeCount { user_id, .. } | BoardRequest::GetPermsSummary { user_id, .. } | BoardRequest::GetUser { requester_id: user_id, .. } | BoardRequest::ListUsers { admin_id: user_id, .. } | BoardRequest::SetDisplayName { user_id, .. } | BoardRequest::Metrics { admin_id: user_id } => Some(*user_id),
+            BoardRequest::AddUser | BoardRequest::GetKemEk | BoardRequest::Status | BoardRequest::Ping | BoardRequest::NegotiateVersion { .. } | BoardRequest::GetRoot | BoardRequest::ListBoards => None,
+        }
+    }
+
+    /// whether the request leaves the board as it was, the only requests guests (`ANONYMOUS_USER_ID`) can make
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            BoardRequest::GetEntry { .. } | BoardRequest::GetAncestors { .. } | BoardRequest::GetEntries { .. } | BoardRequest::ChildrenSince { .. } | BoardRequest::FilterAccessible { .. } | BoardRequest::SubtreeCount { .. } | BoardRequest::GetPermsSummary { .. } | BoardRequest::GetUser { .. } | BoardRequest::ListUsers { .. } => true,
+            BoardRequest::GetKemEk | BoardRequest::Status | BoardRequest::Ping | BoardRequest::NegotiateVersion { .. } | BoardRequest::GetRoot | BoardRequest::ListBoards | BoardRequest::Metrics { .. } => true,
+            // read receipts are written, so even marking an entry read isn't
+            BoardRequest::AddEntry { .. } | BoardRequest::EditEntry { .. } | BoardRequest::MoveEntry { .. } | BoardRequest::AddEntries { .. } | BoardRequest::DeleteEntry { .. } | BoardRequest::SetPinned { .. } | BoardRequest::MarkRead { .. } | BoardRequest::SetDisplayName { .. } | BoardRequest::AddUser => false,
+        }
+    }
+
     pub fn secure_extend_data(&self, rng: impl OldCryptoRng + OldRngCore, keys: &mut PublicKeySet, data: &mut Vec<u8>) -> Result<(), DataError> {
-        data.push(REQUEST_FORMAT_VERSION); //version
+        self.secure_extend_data_to(MAIN_BOARD, PROTOCOL_VERSION, rng, keys, data)
+    }
+
+    /// as `secure_extend_data`, but for the named board, 
+    /// requests to `MAIN_BOARD` are left in the format from before there were named boards
+    /// 
+    /// `protocol_version` is the one agreed with the board, see `negotiate_version`
+    pub fn secure_extend_data_to(&self, board: &str, protocol_version: u8, rng: impl OldCryptoRng + OldRngCore, keys: &mut PublicKeySet, data: &mut Vec<u8>) -> Result<(), DataError> {
+        self.secure_extend_data_with(board, protocol_version, 0, rng, keys, data)
+    }
+
+    /// as `secure_extend_data_to`, with response flags for the board (ie. `CHUNKED_RESPONSE_FLAG`), 
+    /// they're dropped for a `protocol_version` before `RESPONSE_FLAGS_PROTOCOL_VERSION` as the board couldn't read them
+    pub fn secure_extend_data_with(&self, board: &str, protocol_version: u8, flags: u8, rng: impl OldCryptoRng + OldRngCore, keys: &mut PublicKeySet, data: &mut Vec<u8>) -> Result<(), DataError> {
+        // the handshake is understood by every version, so it's the one request that can be sent before agreeing on one
+        let negotiating = matches!(self, BoardRequest::NegotiateVersion { .. });
+        if !negotiating && !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&protocol_version) {
+            return Err(DataError::VersionMismatch)
+        }
+        if board != MAIN_BOARD && !negotiating && protocol_version < NAMED_BOARDS_PROTOCOL_VERSION {
+            return Err(DataError::VersionMismatch)
+        }
+        let flagged = !negotiating && protocol_version >= RESPONSE_FLAGS_PROTOCOL_VERSION;
+        if (board == MAIN_BOARD && !flagged) || negotiating {
+            data.push(REQUEST_FORMAT_VERSION); //version
+        } else {
+            validate_board_name(board)?;
+            data.push(if flagged {FLAGGED_REQUEST_VERSION} else {NAMED_BOARD_REQUEST_VERSION});
+            data.push(board.len() as u8);
+            data.extend_from_slice(board.as_bytes());
+        }
         let mut body = Vec::new();
+        if flagged {body.push(flags)}
         match self {
             // todo: change Vec::new's into Vec::with_capacity
             BoardRequest::GetEntry { entry_id, .. } => {
@@ -914,26 +3252,94 @@ impl BoardRequest {
             },
             BoardRequest::AddEntry { entry, .. } => {
                 body.push(ADD_ENTRY);
-                entry.extend_data(&mut body)?;
+                entry.extend_data_with(&mut body, false)?;
             },
             BoardRequest::EditEntry { entry_id, entry, .. } => {
                 body.push(EDIT_ENTRY);
                 body.extend_from_slice(&entry_id.to_le_bytes());
-                entry.extend_data(&mut body)?;
+                entry.extend_data_with(&mut body, false)?;
+            }
+            BoardRequest::MoveEntry { entry_id, new_parent_id, .. } => {
+                body.push(MOVE_ENTRY);
+                body.extend_from_slice(&entry_id.to_le_bytes());
+                body.extend_from_slice(&new_parent_id.to_le_bytes());
             }
-            BoardRequest::GetUser { user_id } => {
+            BoardRequest::AddEntries { entries, .. } => {
+                body.push(ADD_ENTRIES);
+                extend_with_entries(entries, &mut body)?;
+            }
+            BoardRequest::DeleteEntry { entry_id, .. } => {
+                body.push(DELETE_ENTRY);
+                body.extend_from_slice(&entry_id.to_le_bytes());
+            }
+            BoardRequest::GetAncestors { entry_id, .. } => {
+                body.push(GET_ANCESTORS);
+                body.extend_from_slice(&entry_id.to_le_bytes());
+            }
+            BoardRequest::SetPinned { entry_id, pinned, .. } => {
+                body.push(SET_PINNED);
+                body.extend_from_slice(&entry_id.to_le_bytes());
+                body.push(*pinned as u8);
+            }
+            BoardRequest::GetEntries { entry_ids, .. } => {
+                body.push(GET_ENTRIES);
+                extend_with_ids(entry_ids, &mut body)?;
+            }
+            BoardRequest::ChildrenSince { entry_id, since_ts, .. } => {
+                body.push(CHILDREN_SINCE);
+                body.extend_from_slice(&entry_id.to_le_bytes());
+                body.extend_from_slice(&since_ts.to_le_bytes());
+            }
+            BoardRequest::FilterAccessible { perm_kind, entry_ids, .. } => {
+                body.push(FILTER_ACCESSIBLE);
+                body.push(perm_kind.get_discriminant());
+                extend_with_ids(entry_ids, &mut body)?;
+            }
+            BoardRequest::MarkRead { entry_id, .. } => {
+                body.push(MARK_READ);
+                body.extend_from_slice(&entry_id.to_le_bytes());
+            }
+            BoardRequest::SubtreeCount { entry_id, .. } => {
+                body.push(SUBTREE_COUNT);
+                body.extend_from_slice(&entry_id.to_le_bytes());
+            }
+            BoardRequest::GetPermsSummary { entry_id, .. } => {
+                body.push(GET_PERMS_SUMMARY);
+                body.extend_from_slice(&entry_id.to_le_bytes());
+            }
+            BoardRequest::GetUser { user_id, .. } => {
                 body.push(GET_USER);
                 body.extend_from_slice(&user_id.to_le_bytes());
             },
             BoardRequest::AddUser => body.push(ADD_USER),
-            BoardRequest::GetKemEk => body.push(GET_KEM_EK)
+            BoardRequest::ListUsers { start, .. } => {
+                body.push(LIST_USERS);
+                body.extend_from_slice(&start.to_le_bytes());
+            }
+            BoardRequest::SetDisplayName { display_name, .. } => {
+                body.push(SET_DISPLAY_NAME);
+                extend_with_display_name(display_name.as_deref(), &mut body)?;
+            }
+            BoardRequest::GetKemEk => body.push(GET_KEM_EK),
+            BoardRequest::Status => body.push(STATUS),
+            BoardRequest::Ping => body.push(PING),
+            BoardRequest::NegotiateVersion { min_version, max_version } => {
+                body.push(NEGOTIATE_VERSION);
+                body.push(*min_version);
+                body.push(*max_version);
+            }
+            BoardRequest::GetRoot => body.push(GET_ROOT),
+            BoardRequest::ListBoards => body.push(LIST_BOARDS),
+            BoardRequest::Metrics { .. } => body.push(METRICS),
         };
-        match self {
-            BoardRequest::GetEntry { user_id, .. } | BoardRequest::AddEntry { user_id, ..} | BoardRequest::EditEntry { user_id, .. } => {
+        // guests have no key of their own, so they're sent as anonymously as adding a user
+        let guest = self.user_id() == Some(ANONYMOUS_USER_ID.into());
+        match self.user_id() {
+            Some(user_id) if !guest => {
                 data.push(USER);
-                extend_with_user_block(rng, keys, *user_id, data, &mut body)?;
+                extend_with_user_block(rng, keys, user_id, data, &mut body)?;
             }
-            BoardRequest::GetUser { .. } | BoardRequest::AddUser { .. } if keys.kem.is_some() => {
+            _ if (guest || matches!(self, BoardRequest::AddUser)) && keys.kem.is_some() => {
                 data.push(FULL_ANON);
                 let simple_aead = extend_with_full_anonymous_block(rng, keys, data, &mut body)?;
                 keys.simple_aead.push_back(simple_aead);
@@ -952,8 +3358,30 @@ impl BoardRequest {
         Ok(out)
     }
 
+    pub fn secure_into_data_to(&self, board: &str, protocol_version: u8, rng: impl OldCryptoRng + OldRngCore, keys: &mut PublicKeySet) -> Result<Vec<u8>, DataError> {
+        self.secure_into_data_with(board, protocol_version, 0, rng, keys)
+    }
+
+    pub fn secure_into_data_with(&self, board: &str, protocol_version: u8, flags: u8, rng: impl OldCryptoRng + OldRngCore, keys: &mut PublicKeySet) -> Result<Vec<u8>, DataError> {
+        let mut out = Vec::new();
+        self.secure_extend_data_with(board, protocol_version, flags, rng, keys, &mut out)?;
+        Ok(out)
+    }
+
+    /// which board the secure request is to, read ahead of the rest so its keys can be used to decrypt it
+    pub fn secure_board_name(data: &[u8]) -> Result<String, DataError> {
+        let mut data_iter = data.iter().copied();
+        read_board_name(&mut data_iter).map(|(name, _)| name)
+    }
+
     pub fn secure_from_data_iter<'a, F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(kem_dk: &DecapsulationKey, get_user_aead: F, data_iter: &mut impl Iterator<Item = u8>) -> Result<(ReEncryptionData, Self), DataError> {
-        if read_u8(data_iter)? != REQUEST_FORMAT_VERSION {return Err(DataError::UnsupportedVersion)}
+        Self::secure_flagged_from_data_iter(kem_dk, get_user_aead, data_iter).map(|(re_encryptor, _, request)| (re_encryptor, request))
+    }
+
+    /// as `secure_from_data_iter`, with the response flags the request was sent with (see `secure_extend_data_with`), 
+    /// none for requests in a format from before them
+    pub fn secure_flagged_from_data_iter<F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(kem_dk: &DecapsulationKey, get_user_aead: F, data_iter: &mut impl Iterator<Item = u8>) -> Result<(ReEncryptionData, u8, Self), DataError> {
+        let (_, flagged) = read_board_name(data_iter)?;
         let mut user_id = None;
         let (re_encryptor, body) = match read_u8(data_iter)? {
             EXPOSED => {
@@ -972,34 +3400,114 @@ impl BoardRequest {
             _ => {return Err(DataError::InvalidDiscriminant)}
         };
         let mut body = body.into_iter();
+        let flags = if flagged {read_u8(&mut body)?} else {0};
         let discriminant = read_u8(&mut body)?;
-        Ok((re_encryptor, match discriminant {
+        // requests without a user block are a guest's, see `ANONYMOUS_USER_ID`
+        let sender_id = user_id.unwrap_or(ANONYMOUS_USER_ID.into());
+        Ok((re_encryptor, flags, match discriminant {
             // entry requests
             GET_ENTRY => { // GetEntry
                 let entry_id = read_u64(&mut body)?.into();
-                BoardRequest::GetEntry { user_id: user_id.unwrap(), entry_id }
+                BoardRequest::GetEntry { user_id: sender_id, entry_id }
             }
             ADD_ENTRY => { // AddEntry
                 let entry = Entry::from_data_iter(&mut body)?;
-                BoardRequest::AddEntry { user_id: user_id.unwrap(), entry }
+                BoardRequest::AddEntry { user_id: sender_id, entry }
+            }
+            EDIT_ENTRY => {
+                let entry_id = read_u64(&mut body)?.into();
+                let entry = Entry::from_data_iter(&mut body)?;
+                BoardRequest::EditEntry { user_id: sender_id, entry_id, entry }
+            }
+            MOVE_ENTRY => {
+                let entry_id = read_u64(&mut body)?.into();
+                let new_parent_id = read_u64(&mut body)?.into();
+                BoardRequest::MoveEntry { user_id: sender_id, entry_id, new_parent_id }
+            }
+            ADD_ENTRIES => {
+                let entries = read_entries(&mut body)?;
+                BoardRequest::AddEntries { user_id: sender_id, entries }
+            }
+            DELETE_ENTRY => {
+                let entry_id = read_u64(&mut body)?.into();
+                BoardRequest::DeleteEntry { user_id: sender_id, entry_id }
+            }
+            GET_ANCESTORS => {
+                let entry_id = read_u64(&mut body)?.into();
+                BoardRequest::GetAncestors { user_id: sender_id, entry_id }
+            }
+            SET_PINNED => {
+                let entry_id = read_u64(&mut body)?.into();
+                let pinned = read_bool(&mut body)?;
+                BoardRequest::SetPinned { user_id: sender_id, entry_id, pinned }
+            }
+            GET_ENTRIES => {
+                let entry_ids = read_ids(&mut body)?;
+                BoardRequest::GetEntries { user_id: sender_id, entry_ids }
+            }
+            CHILDREN_SINCE => {
+                let entry_id = read_u64(&mut body)?.into();
+                let since_ts = read_u64(&mut body)?;
+                BoardRequest::ChildrenSince { user_id: sender_id, entry_id, since_ts }
+            }
+            FILTER_ACCESSIBLE => {
+                let perm_kind = PermKind::from_discriminant(read_u8(&mut body)?)?;
+                let entry_ids = read_ids(&mut body)?;
+                BoardRequest::FilterAccessible { user_id: sender_id, perm_kind, entry_ids }
+            }
+            MARK_READ => {
+                let entry_id = read_u64(&mut body)?.into();
+                BoardRequest::MarkRead { user_id: sender_id, entry_id }
+            }
+            SUBTREE_COUNT => {
+                let entry_id = read_u64(&mut body)?.into();
+                BoardRequest::SubtreeCount { user_id: sender_id, entry_id }
             }
-            EDIT_ENTRY => {
+            GET_PERMS_SUMMARY => {
                 let entry_id = read_u64(&mut body)?.into();
-                let entry = Entry::from_data_iter(&mut body)?;
-                BoardRequest::EditEntry { user_id: user_id.unwrap(), entry_id, entry }
+                BoardRequest::GetPermsSummary { user_id: sender_id, entry_id }
             }
             // user requests
             GET_USER => { // GetUser
+                let requester_id = user_id.ok_or(DataError::InsufficientPerms)?;
                 let user_id = read_u64(&mut body)?.into();
-                BoardRequest::GetUser { user_id }
+                BoardRequest::GetUser { requester_id, user_id }
             }
             ADD_USER => { // AddUser
                 BoardRequest::AddUser
             }
+            LIST_USERS => {
+                let start = read_u64(&mut body)?.into();
+                BoardRequest::ListUsers { admin_id: user_id.ok_or(DataError::InsufficientPerms)?, start }
+            }
+            SET_DISPLAY_NAME => {
+                let display_name = read_display_name(&mut body)?;
+                BoardRequest::SetDisplayName { user_id: user_id.ok_or(DataError::InsufficientPerms)?, display_name }
+            }
             // network requests
             GET_KEM_EK => {
                 BoardRequest::GetKemEk
             }
+            STATUS => {
+                BoardRequest::Status
+            }
+            PING => {
+                BoardRequest::Ping
+            }
+            NEGOTIATE_VERSION => {
+                let min_version = read_u8(&mut body)?;
+                let max_version = read_u8(&mut body)?;
+                BoardRequest::NegotiateVersion { min_version, max_version }
+            }
+            GET_ROOT => {
+                BoardRequest::GetRoot
+            }
+            LIST_BOARDS => {
+                BoardRequest::ListBoards
+            }
+            METRICS => {
+                BoardRequest::Metrics { admin_id: user_id.ok_or(DataError::InsufficientPerms)? }
+            }
             _ => {return Err(DataError::InvalidDiscriminant)}
         }))
     }
@@ -1007,24 +3515,399 @@ impl BoardRequest {
     pub fn secure_from_data<'a, F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(kem_dk: &DecapsulationKey, get_user_aead: F, data: &[u8]) -> Result<(ReEncryptionData, Self), DataError> {
         Self::secure_from_data_iter(kem_dk, get_user_aead, &mut data.into_iter().copied())
     }
+
+    pub fn secure_flagged_from_data<F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(kem_dk: &DecapsulationKey, get_user_aead: F, data: &[u8]) -> Result<(ReEncryptionData, u8, Self), DataError> {
+        Self::secure_flagged_from_data_iter(kem_dk, get_user_aead, &mut data.iter().copied())
+    }
+}
+
+/// the version, security and encrypted body of a secure response (see `BoardResponse::secure_extend_data`)
+fn extend_with_secure_body<F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(rng: impl OldCryptoRng + OldRngCore, re_encryptor: ReEncryptionData, data: &mut Vec<u8>, body: &[u8], get_user_aead: F) -> Result<(), DataError> {
+    data.push(RESPONSE_FORMAT_VERSION);
+    match re_encryptor {
+        ReEncryptionData::Exposed => {
+            data.push(EXPOSED);
+            extend_with_exposed_block(data, body)?;
+        }
+        ReEncryptionData::FullAnonymous(key) => {
+            data.push(FULL_ANON);
+            extend_with_full_anonymous_response_block(&key, data, body)?;
+        }
+        ReEncryptionData::User(user_id) => {
+            data.push(USER);
+            let mut aead = get_user_aead(user_id).ok_or(DataError::MissingKey)?;
+            extend_with_user_response_block(rng, &mut aead, data, body)?;
+        }
+    }
+    Ok(())
+}
+
+/// the decrypted body of a secure response, see `extend_with_secure_body`
+fn read_secure_body(data_iter: &mut impl Iterator<Item = u8>, keys: &mut PublicKeySet) -> Result<Vec<u8>, DataError> {
+    let version = read_u8(data_iter)?;
+    if version != RESPONSE_FORMAT_VERSION {return Err(DataError::UnsupportedVersion)}
+    Ok(match read_u8(data_iter)? {
+        EXPOSED => {
+            read_from_exposed_block(data_iter)?.collect::<Vec<_>>()
+        }
+        FULL_ANON => {
+            let (true_key, body) = read_from_full_anonymous_response_block(keys.simple_aead.iter(), data_iter)?;
+            let mut true_key_idx = 0;
+            for (key_idx, key) in keys.simple_aead.iter().enumerate() {
+                if key == true_key {true_key_idx = key_idx; break;}
+            }
+            keys.simple_aead.remove(true_key_idx);
+            body
+        }
+        USER => {
+            let Some(user_aead) = &mut keys.user_aead else {return Err(DataError::MissingKey)};
+            read_from_user_response_block(user_aead, data_iter)?
+        }
+        _ => {return Err(DataError::InvalidDiscriminant)}
+    })
+}
+
+/// encrypts the body of a streamed response into chunks as it is written, see `BoardResponse::write_streamed`
+struct ChunkSealer<'a, W: Write> {
+    stream: &'a mut W,
+    key: SimpleAeadKey,
+    index: u64,
+    chunk: Vec<u8>, // the last chunk byte, then the body not yet written
+}
+
+impl<W: Write> ChunkSealer<'_, W> {
+    fn seal(&mut self, last: bool) -> Result<(), DataError> {
+        self.chunk[0] = last as u8;
+        let sealed = self.key.encrypt_chunk(self.index, &self.chunk)?;
+        write_chunk(self.stream, &sealed)?;
+        self.index += 1;
+        self.chunk.truncate(1);
+        Ok(())
+    }
+
+    /// writes what is left as the last chunk, then ends the frame
+    fn finish(mut self) -> Result<(), DataError> {
+        self.seal(true)?;
+        end_chunked_frame(self.stream)?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ChunkSealer<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let taken = buf.len().min(RESPONSE_CHUNK_SIZE + 1 - self.chunk.len());
+        self.chunk.extend_from_slice(&buf[..taken]);
+        if self.chunk.len() > RESPONSE_CHUNK_SIZE {
+            self.seal(false).map_err(|e| match e {
+                DataError::IoError(kind) => kind.into(),
+                e => std::io::Error::other(e.to_string()),
+            })?;
+        }
+        Ok(taken)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// the body of a streamed response, decrypted from the chunks as they're read off of the stream (see `BoardResponse::read_streamed`), 
+/// it ends early on the first error, which is kept
+struct StreamedBody<'a, S: Read> {
+    frame_reader: &'a mut FrameReader,
+    stream: &'a mut S,
+    key: Option<&'a SimpleAeadKey>, // None if it couldn't be read, the chunks are then only read off
+    index: u64,
+    chunk: std::vec::IntoIter<u8>,
+    last: bool, // whether the last chunk has been read
+    error: Option<DataError>,
+}
+
+impl<S: Read> StreamedBody<'_, S> {
+    /// the next chunk's bytes, or None once the frame has ended, `Err` if it couldn't be read
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, DataError> {
+        let sealed = match self.frame_reader.read_part(self.stream)? {
+            Some(FramePart::Chunk(_, sealed)) => sealed,
+            Some(FramePart::End(_)) if self.last || self.key.is_none() => return Ok(None),
+            Some(FramePart::End(_)) => return Err(DataError::InsufficientBytes),
+            // a whole frame can't come in the middle of a chunked one
+            Some(FramePart::Frame(..)) => return Err(internal_error!()),
+            None => return Err(DataError::IoError(std::io::ErrorKind::TimedOut)),
+        };
+        let Some(key) = self.key else {return Ok(Some(Vec::new()))};
+        if self.last {return Err(DataError::InvalidStructure)}
+        let mut chunk = key.decrypt_chunk(self.index, &sealed)?;
+        self.index += 1;
+        self.last = match chunk.first() {
+            Some(0x00) => false,
+            Some(0x01) => true,
+            _ => return Err(DataError::InvalidStructure),
+        };
+        chunk.remove(0);
+        Ok(Some(chunk))
+    }
+
+    /// reads off the rest of the frame, so the stream is left at the next one
+    fn drain(&mut self) {
+        // the error means the frame is already over or the stream can't be read
+        if self.error.is_some() {return}
+        self.chunk = Vec::new().into_iter();
+        while self.next().is_some() {
+            self.chunk = Vec::new().into_iter();
+        }
+    }
+}
+
+impl<S: Read> Iterator for StreamedBody<'_, S> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        loop {
+            if let Some(byte) = self.chunk.next() {return Some(byte)}
+            if self.error.is_some() {return None}
+            match self.next_chunk() {
+                Ok(Some(chunk)) => self.chunk = chunk.into_iter(),
+                Ok(None) => return None,
+                Err(e) => {
+                    // anything left of the frame after a bad chunk is read off without being decrypted
+                    if !matches!(e, DataError::IoError(_) | DataError::InsufficientBytes) {
+                        self.key = None;
+                        while let Ok(Some(_)) = self.next_chunk() {}
+                    }
+                    self.error = Some(e);
+                    return None
+                }
+            }
+        }
+    }
+}
+
+/// for requests, which are never compressed since the board can't be assumed to read it (it compresses what it stores)
+fn extend_with_entries(entries: &[Entry], data: &mut Vec<u8>) -> Result<(), DataError> {
+    bounded_usize!(entries.len(), u32)?;
+    data.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    for entry in entries {
+        entry.extend_data_with(data, false)?;
+    }
+    Ok(())
+}
+
+fn read_entries(data_iter: &mut impl Iterator<Item = u8>) -> Result<Vec<Entry>, DataError> {
+    let len = checked_usize(read_u32(data_iter)?)?;
+    let mut entries = Vec::with_capacity(len.min(MAX_DECLARED_PREALLOCATION));
+    for _ in 0..len {
+        entries.push(Entry::from_data_iter(data_iter)?);
+    }
+    Ok(entries)
+}
+
+fn extend_with_maybe_entries(entries: &[MaybeEntry], data: &mut impl Write, compress: bool) -> Result<(), DataError> {
+    bounded_usize!(entries.len(), u32)?;
+    data.write_all(&(entries.len() as u32).to_le_bytes())?;
+    for entry in entries {
+        match entry {
+            Ok(entry) => {
+                data.write_all(&[0x00])?;
+                entry.extend_into_with(data, compress)?;
+            }
+            Err(e) => {
+                data.write_all(&[ERROR])?;
+                e.extend_error_data(data)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_maybe_entries(data_iter: &mut impl Iterator<Item = u8>) -> Result<Vec<MaybeEntry>, DataError> {
+    let len = checked_usize(read_u32(data_iter)?)?;
+    let mut entries = Vec::with_capacity(len.min(MAX_DECLARED_PREALLOCATION));
+    for _ in 0..len {
+        entries.push(match read_u8(data_iter)? {
+            0x00 => Ok(Entry::from_data_iter(data_iter)?),
+            ERROR => Err(DataError::from_error_data_iter(data_iter)?),
+            _ => return Err(DataError::InvalidDiscriminant),
+        });
+    }
+    Ok(entries)
+}
+
+fn extend_with_ids<T: Copy>(ids: &[T], data: &mut Vec<u8>) -> Result<(), DataError> where u64: From<T> {
+    bounded_usize!(ids.len(), u32)?;
+    data.extend_from_slice(&(ids.len() as u32).to_le_bytes());
+    data.extend(ids.iter().flat_map(|x| u64::from(*x).to_le_bytes()));
+    Ok(())
+}
+
+fn read_ids<T: From<u64>>(data_iter: &mut impl Iterator<Item = u8>) -> Result<Vec<T>, DataError> {
+    let len = checked_usize(read_u32(data_iter)?)?;
+    let mut ids = Vec::with_capacity(len.min(MAX_DECLARED_PREALLOCATION));
+    for _ in 0..len {
+        ids.push(read_u64(data_iter)?.into());
+    }
+    Ok(ids)
+}
+
+/// an empty name is sent as none
+fn extend_with_display_name(display_name: Option<&str>, data: &mut Vec<u8>) -> Result<(), DataError> {
+    let display_name = display_name.unwrap_or_default();
+    bounded_usize!(display_name.len(), u8)?;
+    data.push(display_name.len() as u8);
+    data.extend_from_slice(display_name.as_bytes());
+    Ok(())
+}
+
+fn read_display_name(data_iter: &mut impl Iterator<Item = u8>) -> Result<Option<String>, DataError> {
+    let len = read_u8(data_iter)? as usize;
+    let display_name = read_string(data_iter, len)?;
+    Ok((!display_name.is_empty()).then_some(display_name))
+}
+
+/// a message as written in an editor, without the newline it ends the file with, 
+/// `EmptyMessage` if there's nothing but whitespace unless `allow_empty`
+pub fn prepare_message(mut message: String, allow_empty: bool) -> Result<String, DataError> {
+    if message.ends_with("\r\n") {
+        message.truncate(message.len() - 2);
+    } else if message.ends_with('\n') {
+        message.pop();
+    }
+    if !allow_empty && message.trim().is_empty() {return Err(DataError::EmptyMessage)}
+    Ok(message)
+}
+
+/// a board's name, as used in requests and for its dir on the server, 
+/// 1 - `MAX_BOARD_NAME_SIZE` ascii letters, digits, '-' or '_'
+pub fn validate_board_name(name: &str) -> Result<(), DataError> {
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if name.is_empty() || name.len() > MAX_BOARD_NAME_SIZE || !name.chars().all(valid_char) {
+        return Err(DataError::InvalidStructure)
+    }
+    Ok(())
+}
+
+/// the version of a secure request and the board named after it if it has one, see `NAMED_BOARD_REQUEST_VERSION`, 
+/// and whether its body starts with response flags, see `FLAGGED_REQUEST_VERSION`
+fn read_board_name(data_iter: &mut impl Iterator<Item = u8>) -> Result<(String, bool), DataError> {
+    match read_u8(data_iter)? {
+        REQUEST_FORMAT_VERSION => Ok((String::from(MAIN_BOARD), false)),
+        version @ (NAMED_BOARD_REQUEST_VERSION | FLAGGED_REQUEST_VERSION) => {
+            let len = read_u8(data_iter)? as usize;
+            let name = read_string(data_iter, len)?;
+            validate_board_name(&name)?;
+            Ok((name, version == FLAGGED_REQUEST_VERSION))
+        }
+        _ => Err(DataError::UnsupportedVersion),
+    }
+}
+
+fn extend_with_board_names(boards: &[String], data: &mut Vec<u8>) -> Result<(), DataError> {
+    bounded_usize!(boards.len(), u16)?;
+    data.extend_from_slice(&(boards.len() as u16).to_le_bytes());
+    for board in boards {
+        bounded_usize!(board.len(), u8)?;
+        data.push(board.len() as u8);
+        data.extend_from_slice(board.as_bytes());
+    }
+    Ok(())
+}
+
+fn extend_with_long_string(string: &str, data: &mut Vec<u8>) -> Result<(), DataError> {
+    bounded_usize!(string.len(), u32)?;
+    data.extend_from_slice(&(string.len() as u32).to_le_bytes());
+    data.extend_from_slice(string.as_bytes());
+    Ok(())
+}
+
+fn read_long_string(data_iter: &mut impl Iterator<Item = u8>) -> Result<String, DataError> {
+    let len = checked_usize(read_u32(data_iter)?)?;
+    read_string(data_iter, len)
+}
+
+fn read_board_names(data_iter: &mut impl Iterator<Item = u8>) -> Result<Vec<String>, DataError> {
+    let num_boards = read_u16(data_iter)?;
+    (0..num_boards).map(|_| {
+        let len = read_u8(data_iter)? as usize;
+        read_string(data_iter, len)
+    }).collect()
+}
+
+/// how a user is shown, by their display name if they have one, otherwise by their id (see `UserId`'s `Display`), 
+/// the reserved users always go by their ids' names, and control characters are left out of display names
+pub fn user_label(user_id: UserId, display_name: Option<&str>) -> String {
+    let display_name = display_name.map(|name| name.chars().filter(|c| !c.is_control()).collect::<String>());
+    match display_name {
+        Some(name) if !name.trim().is_empty() && *user_id > ANONYMOUS_USER_ID => name,
+        _ => user_id.to_string(),
+    }
+}
+
+fn extend_with_user_page(user_ids: &[UserId], next: Option<UserId>, data: &mut Vec<u8>) -> Result<(), DataError> {
+    extend_with_ids(user_ids, data)?;
+    match next {
+        Some(next) => {
+            data.push(0x01);
+            data.extend_from_slice(&next.to_le_bytes());
+        }
+        None => data.push(0x00),
+    }
+    Ok(())
+}
+
+fn read_user_page(data_iter: &mut impl Iterator<Item = u8>) -> Result<(Vec<UserId>, Option<UserId>), DataError> {
+    let user_ids = read_ids(data_iter)?;
+    let next = match read_u8(data_iter)? {
+        0x00 => None,
+        0x01 => Some(read_u64(data_iter)?.into()),
+        _ => return Err(DataError::InvalidDiscriminant),
+    };
+    Ok((user_ids, next))
 }
 
 /// the response 
 #[derive(PartialEq, Debug)]
 pub enum BoardResponse {
-    GetEntry(Entry),
+    /// `view_count` is how many users have marked the entry read
+    GetEntry { entry: Entry, view_count: u64 },
     AddEntry(EntryId),
     EditEntry,
+    MoveEntry,
+    AddEntries(Vec<EntryId>),
+    DeleteEntry,
+    GetAncestors(Vec<EntryId>),
+    SetPinned,
+    /// in the order of the request's entry_ids
+    GetEntries(Vec<MaybeEntry>),
+    ChildrenSince(Vec<EntryId>),
+    FilterAccessible(Vec<EntryId>),
+    /// the entry's view count, including the new read
+    MarkRead(u64),
+    SubtreeCount(u64),
+    GetPermsSummary { write: PermsSummary, read: PermsSummary },
 
     GetUser(UserData),
     AddUser{user_id: UserId, user_aead: UserAeadKey},
+    /// sorted, `next` is where the following page starts if there is one
+    ListUsers{user_ids: Vec<UserId>, next: Option<UserId>},
+    SetDisplayName,
 
     GetKemEk(EncapsulationKey),
+    /// `max_message_size` is the largest message (or attachment) the board accepts, in bytes
+    Status{num_users: u64, num_entries: u64, uptime_secs: u64, max_message_size: u64},
+    Pong,
+    /// the agreed protocol version
+    NegotiateVersion(u8),
+    GetRoot(EntryId),
+    /// sorted, `MAIN_BOARD` among them
+    ListBoards(Vec<String>),
+    /// see `BoardRequest::Metrics`
+    Metrics(String),
     
     Error(DataError),
 }
 
 pub type MaybeBoardResponse = Result<BoardResponse, DataError>;
+/// an entry of a GetEntries response, only the error's code is sent (see `DataError::error_code`)
+pub type MaybeEntry = Result<Entry, DataError>;
 
 impl BoardResponse {
     pub fn encapsulate_error(val: MaybeBoardResponse) -> Self {
@@ -1033,14 +3916,38 @@ impl BoardResponse {
             Ok(v) => v
         }
     }
+
+    /// empties the perm lists of the access groups in the response, keeping their bases, 
+    /// for requests with `SUMMARIZED_PERMS_FLAG`
+    pub fn summarize_perms(&mut self) {
+        match self {
+            BoardResponse::GetEntry { entry, .. } => entry.summarize_perms(),
+            BoardResponse::GetEntries(entries) => for entry in entries.iter_mut().flatten() {
+                entry.summarize_perms();
+            }
+            _ => {}
+        }
+    }
+
+    /// answers a ListUsers from `requester_id` with up to `MAX_LISTED_USERS` of `user_ids`, starting at `start`,
+    /// only `admin_id` may list the users, no one can if there is no admin
+    pub fn list_users(requester_id: UserId, admin_id: Option<UserId>, user_ids: impl IntoIterator<Item = UserId>, start: UserId) -> MaybeBoardResponse {
+        if admin_id != Some(requester_id) {return Err(DataError::InsufficientPerms)}
+        let mut user_ids: Vec<UserId> = user_ids.into_iter().filter(|user_id| *user_id >= start).collect();
+        user_ids.sort_unstable();
+        let next = user_ids.get(MAX_LISTED_USERS).copied();
+        user_ids.truncate(MAX_LISTED_USERS);
+        Ok(BoardResponse::ListUsers { user_ids, next })
+    }
 }
 
 /// data format:
-///     version (u8): 0
+///     version (u8): 01
 ///     variant discriminant (u8) (listed with each variant)
 /// 
 /// GetEntry, 0x00:
 ///     - Entry Data -
+///     view_count (u64)
 /// 
 /// AddEntry, 0x01:
 ///     entry_id (u64)
@@ -1048,21 +3955,98 @@ impl BoardResponse {
 /// EditEntry, 0x02:
 ///     - no data -
 /// 
+/// MoveEntry, 0x03:
+///     - no data -
+/// 
+/// AddEntries, 0x04:
+///     number of entry_ids (u32)
+///     entry_id 1 - n (u64 each), in the order of the request's entries
+/// 
+/// DeleteEntry, 0x05:
+///     - no data -
+/// 
+/// GetAncestors, 0x06:
+///     number of entry_ids (u32)
+///     entry_id 1 - n (u64 each), from just below the root down to the requested entry
+/// 
+/// SetPinned, 0x07:
+///     - no data -
+/// 
+/// GetEntries, 0x08:
+///     number of entries (u32)
+///     for each entry, in the order of the request's entry_ids:
+///         status (u8):
+///             found:              00, followed by - Entry Data -
+///             error:              ff, followed by the error code (u8)
+/// 
+/// ChildrenSince, 0x09:
+///     number of entry_ids (u32)
+///     entry_id 1 - n (u64 each), in the order of the entry's children
+/// 
+/// FilterAccessible, 0x0A:
+///     number of entry_ids (u32)
+///     entry_id 1 - n (u64 each), those accessible in the order requested
+/// 
+/// MarkRead, 0x0B:
+///     view_count (u64)
+/// 
+/// SubtreeCount, 0x0C:
+///     count (u64)
+/// 
+/// GetPermsSummary, 0x0D:
+///     write - Perms Summary -
+///     read - Perms Summary -
+/// 
 /// GetUser, 0x20:
 ///     - User Data -
 /// 
 /// AddUser, 0x21:
 ///     user_id (u64)
 /// 
+/// ListUsers, 0x22:
+///     number of user_ids (u32)
+///     user_id 1 - n (u64 each), sorted
+///     has next (u8): 00 or 01
+///     next user_id (u64), only if there is a next
+/// 
+/// SetDisplayName, 0x23:
+///     - no data -
+/// 
+/// Status, 0x81:
+///     num_users (u64)
+///     num_entries (u64)
+///     uptime_secs (u64)
+///     max_message_size (u64)
+/// 
+/// Pong, 0x82:
+///     - no data -
+/// 
+/// NegotiateVersion, 0x83:
+///     version (u8)
+/// 
+/// GetRoot, 0x84:
+///     root_id (u64)
+/// 
+/// ListBoards, 0x85:
+///     number of boards (u16)
+///     board 1 - n: name length (u8), name (utf8 encoded)
+/// 
+/// Metrics, 0x86:
+///     text length (u32)
+///     text (utf8 encoded)
+/// 
 /// Error, 0xff:
-///     - no data - 
+///     error code (u8), see `DataError::error_code`, 
+///         an unknown code (or the unspecified one) is read back as an internal error
+///     max (u64), for `PAYLOAD_TOO_LARGE_ERROR`
 impl AsData for BoardResponse {
     fn extend_data(&self, data: &mut Vec<u8>) -> Result<(), DataError> {
         data.push(RESPONSE_FORMAT_VERSION);
         match self {
-            BoardResponse::GetEntry(entry) => {
+            BoardResponse::GetEntry { entry, view_count } => {
                 data.push(GET_ENTRY);
-                entry.extend_data(data)?;
+                entry.extend_data_with(data, false)?;
+                data.extend_from_slice(&view_count.to_le_bytes());
             }
             BoardResponse::AddEntry(entry_id) => {
                 data.push(ADD_ENTRY);
@@ -1071,6 +4055,48 @@ impl AsData for BoardResponse {
             BoardResponse::EditEntry => {
                 data.push(EDIT_ENTRY);
             }
+            BoardResponse::MoveEntry => {
+                data.push(MOVE_ENTRY);
+            }
+            BoardResponse::AddEntries(entry_ids) => {
+                data.push(ADD_ENTRIES);
+                extend_with_ids(entry_ids, data)?;
+            }
+            BoardResponse::DeleteEntry => {
+                data.push(DELETE_ENTRY);
+            }
+            BoardResponse::GetAncestors(entry_ids) => {
+                data.push(GET_ANCESTORS);
+                extend_with_ids(entry_ids, data)?;
+            }
+            BoardResponse::GetEntries(entries) => {
+                data.push(GET_ENTRIES);
+                extend_with_maybe_entries(entries, data, false)?;
+            }
+            BoardResponse::ChildrenSince(entry_ids) => {
+                data.push(CHILDREN_SINCE);
+                extend_with_ids(entry_ids, data)?;
+            }
+            BoardResponse::FilterAccessible(entry_ids) => {
+                data.push(FILTER_ACCESSIBLE);
+                extend_with_ids(entry_ids, data)?;
+            }
+            BoardResponse::MarkRead(view_count) => {
+                data.push(MARK_READ);
+                data.extend_from_slice(&view_count.to_le_bytes());
+            }
+            BoardResponse::SubtreeCount(count) => {
+                data.push(SUBTREE_COUNT);
+                data.extend_from_slice(&count.to_le_bytes());
+            }
+            BoardResponse::GetPermsSummary { write, read } => {
+                data.push(GET_PERMS_SUMMARY);
+                write.extend_data(data)?;
+                read.extend_data(data)?;
+            }
+            BoardResponse::SetPinned => {
+                data.push(SET_PINNED);
+            }
             BoardResponse::GetUser(user) => {
                 data.push(GET_USER);
                 user.extend_data(data)?;
@@ -1080,13 +4106,47 @@ impl AsData for BoardResponse {
                 data.extend_from_slice(&user_id.to_le_bytes());
                 user_aead.extend_data(data)?;
             }
+            BoardResponse::ListUsers{user_ids, next} => {
+                data.push(LIST_USERS);
+                extend_with_user_page(user_ids, *next, data)?;
+            }
+            BoardResponse::SetDisplayName => {
+                data.push(SET_DISPLAY_NAME);
+            }
             BoardResponse::GetKemEk(kem_ek) => {
                 data.push(GET_KEM_EK);
                 kem_ek.extend_data(data)?;
             }
-            BoardResponse::Error(e) => { // TODO: should consider the error
+            BoardResponse::Status{num_users, num_entries, uptime_secs, max_message_size} => {
+                data.push(STATUS);
+                data.extend_from_slice(&num_users.to_le_bytes());
+                data.extend_from_slice(&num_entries.to_le_bytes());
+                data.extend_from_slice(&uptime_secs.to_le_bytes());
+                data.extend_from_slice(&max_message_size.to_le_bytes());
+            }
+            BoardResponse::Pong => {
+                data.push(PING);
+            }
+            BoardResponse::NegotiateVersion(version) => {
+                data.push(NEGOTIATE_VERSION);
+                data.push(*version);
+            }
+            BoardResponse::GetRoot(root_id) => {
+                data.push(GET_ROOT);
+                data.extend_from_slice(&root_id.to_le_bytes());
+            }
+            BoardResponse::ListBoards(boards) => {
+                data.push(LIST_BOARDS);
+                extend_with_board_names(boards, data)?;
+            }
+            BoardResponse::Metrics(text) => {
+                data.push(METRICS);
+                extend_with_long_string(text, data)?;
+            }
+            BoardResponse::Error(e) => {
                 info!("Sending Error: {:?}", e);
                 data.push(ERROR);
+                e.extend_error_data(data)?;
             }
         }
         Ok(())
@@ -1094,18 +4154,34 @@ impl AsData for BoardResponse {
 
     fn from_data_iter(data_iter: &mut impl Iterator<Item = u8>) -> Result<Self, DataError> where Self: Sized {
         let version = read_u8(data_iter)?;
-        if version != 0 {return Err(DataError::UnsupportedVersion)}
+        if version != RESPONSE_FORMAT_VERSION {return Err(DataError::UnsupportedVersion)}
         Ok(match read_u8(data_iter)? {
             // entry requests
             GET_ENTRY => { // GetEntry
                 let entry = Entry::from_data_iter(data_iter)?;
-                BoardResponse::GetEntry(entry)
+                let view_count = read_u64(data_iter)?;
+                BoardResponse::GetEntry { entry, view_count }
             }
             ADD_ENTRY => { // AddEntry
                 let entry_id = read_u64(data_iter)?.into();
                 BoardResponse::AddEntry(entry_id)
             }
             EDIT_ENTRY => BoardResponse::EditEntry,
+            MOVE_ENTRY => BoardResponse::MoveEntry,
+            ADD_ENTRIES => BoardResponse::AddEntries(read_ids(data_iter)?),
+            DELETE_ENTRY => BoardResponse::DeleteEntry,
+            GET_ANCESTORS => BoardResponse::GetAncestors(read_ids(data_iter)?),
+            GET_ENTRIES => BoardResponse::GetEntries(read_maybe_entries(data_iter)?),
+            CHILDREN_SINCE => BoardResponse::ChildrenSince(read_ids(data_iter)?),
+            FILTER_ACCESSIBLE => BoardResponse::FilterAccessible(read_ids(data_iter)?),
+            MARK_READ => BoardResponse::MarkRead(read_u64(data_iter)?),
+            SUBTREE_COUNT => BoardResponse::SubtreeCount(read_u64(data_iter)?),
+            GET_PERMS_SUMMARY => {
+                let write = PermsSummary::from_data_iter(data_iter)?;
+                let read = PermsSummary::from_data_iter(data_iter)?;
+                BoardResponse::GetPermsSummary { write, read }
+            }
+            SET_PINNED => BoardResponse::SetPinned,
             // user requests
             GET_USER => { // GetUser
                 let user = UserData::from_data_iter(data_iter)?;
@@ -1116,13 +4192,30 @@ impl AsData for BoardResponse {
                 let user_aead = UserAeadKey::from_data_iter(data_iter)?;
                 BoardResponse::AddUser{user_id, user_aead}
             }
+            LIST_USERS => {
+                let (user_ids, next) = read_user_page(data_iter)?;
+                BoardResponse::ListUsers{user_ids, next}
+            }
+            SET_DISPLAY_NAME => BoardResponse::SetDisplayName,
             // network responses
             GET_KEM_EK => {
                 let kem_ek = EncapsulationKey::from_data_iter(data_iter)?;
                 BoardResponse::GetKemEk(kem_ek)
             }
+            STATUS => {
+                let num_users = read_u64(data_iter)?;
+                let num_entries = read_u64(data_iter)?;
+                let uptime_secs = read_u64(data_iter)?;
+                let max_message_size = read_u64(data_iter)?;
+                BoardResponse::Status{num_users, num_entries, uptime_secs, max_message_size}
+            }
+            PING => BoardResponse::Pong,
+            NEGOTIATE_VERSION => BoardResponse::NegotiateVersion(read_u8(data_iter)?),
+            GET_ROOT => BoardResponse::GetRoot(read_u64(data_iter)?.into()),
+            LIST_BOARDS => BoardResponse::ListBoards(read_board_names(data_iter)?),
+            METRICS => BoardResponse::Metrics(read_long_string(data_iter)?),
             ERROR => {
-                BoardResponse::Error(internal_error!()) //not really an internal error, it just isn't encoded atm
+                BoardResponse::Error(DataError::from_error_data_iter(data_iter)?)
             }
             _ => {return Err(DataError::InvalidDiscriminant)}
         })
@@ -1130,33 +4223,63 @@ impl AsData for BoardResponse {
 
     fn size_hint(&self) -> usize {
         match self {
-            BoardResponse::GetEntry(entry) => {
-                1 + 1 + entry.size_hint()
+            BoardResponse::GetEntry { entry, .. } => {
+                1 + 1 + entry.size_hint() + 8
             }
-            BoardResponse::AddEntry(_) => {
+            BoardResponse::AddEntry(_) | BoardResponse::MarkRead(_) | BoardResponse::SubtreeCount(_) => {
                 1 + 1 + 8
             }
-            BoardResponse::EditEntry => {
+            BoardResponse::GetPermsSummary { write, read } => {
+                1 + 1 + write.size_hint() + read.size_hint()
+            }
+            BoardResponse::EditEntry | BoardResponse::MoveEntry | BoardResponse::DeleteEntry | BoardResponse::SetPinned | BoardResponse::SetDisplayName => {
                 1 + 1
             }
+            BoardResponse::AddEntries(entry_ids) | BoardResponse::GetAncestors(entry_ids) | BoardResponse::ChildrenSince(entry_ids) | BoardResponse::FilterAccessible(entry_ids) => {
+                1 + 1 + 4 + entry_ids.len() * 8
+            }
+            BoardResponse::GetEntries(entries) => {
+                1 + 1 + 4 + entries.iter().map(|entry| entry.as_ref().map_or_else(|e| 1 + e.error_data_size(), |entry| 1 + entry.size_hint())).sum::<usize>()
+            }
             BoardResponse::GetUser(user) => {
                 1 + 1 + user.size_hint()
             }
             BoardResponse::AddUser{user_id: _, user_aead } => {
                 1 + 1 + 8 + user_aead.size_hint()
             }
+            BoardResponse::ListUsers{user_ids, next} => {
+                1 + 1 + 4 + user_ids.len() * 8 + 1 + next.map_or(0, |_| 8)
+            }
             BoardResponse::GetKemEk(kem_ek) =>{
                 1 + 1 + kem_ek.size_hint()
             }
-            BoardResponse::Error(_) => {
+            BoardResponse::Status{..} => {
+                1 + 1 + 8 + 8 + 8 + 8
+            }
+            BoardResponse::Pong => {
                 1 + 1
             }
+            BoardResponse::NegotiateVersion(_) => {
+                1 + 1 + 1
+            }
+            BoardResponse::GetRoot(_) => {
+                1 + 1 + 8
+            }
+            BoardResponse::ListBoards(boards) => {
+                1 + 1 + 2 + boards.iter().map(|board| 1 + board.len()).sum::<usize>()
+            }
+            BoardResponse::Metrics(text) => {
+                1 + 1 + 4 + text.len()
+            }
+            BoardResponse::Error(e) => {
+                1 + 1 + e.error_data_size()
+            }
         }
     }
 }
 
 /// secure data format:
-///     version (u8): 0
+///     version (u8): 01
 ///     encryption discriminant:
 ///     encrypted body
 /// 
@@ -1166,6 +4289,7 @@ impl AsData for BoardResponse {
 /// 
 /// GetEntry, 0x00:
 ///     - Entry Data -
+///     view_count (u64)
 /// 
 /// AddEntry, 0x01:
 ///     entry_id (u64)
@@ -1173,22 +4297,106 @@ impl AsData for BoardResponse {
 /// EditEntry, 0x02:
 ///     - no data -
 /// 
+/// MoveEntry, 0x03:
+///     - no data -
+/// 
+/// AddEntries, 0x04:
+///     number of entry_ids (u32)
+///     entry_id 1 - n (u64 each), in the order of the request's entries
+/// 
+/// DeleteEntry, 0x05:
+///     - no data -
+/// 
+/// GetAncestors, 0x06:
+///     number of entry_ids (u32)
+///     entry_id 1 - n (u64 each), from just below the root down to the requested entry
+/// 
+/// SetPinned, 0x07:
+///     - no data -
+/// 
+/// GetEntries, 0x08:
+///     number of entries (u32)
+///     for each entry, in the order of the request's entry_ids:
+///         status (u8):
+///             found:              00, followed by - Entry Data -
+///             error:              ff, followed by the error code (u8)
+/// 
+/// ChildrenSince, 0x09:
+///     number of entry_ids (u32)
+///     entry_id 1 - n (u64 each), in the order of the entry's children
+/// 
+/// FilterAccessible, 0x0A:
+///     number of entry_ids (u32)
+///     entry_id 1 - n (u64 each), those accessible in the order requested
+/// 
+/// MarkRead, 0x0B:
+///     view_count (u64)
+/// 
+/// SubtreeCount, 0x0C:
+///     count (u64)
+/// 
+/// GetPermsSummary, 0x0D:
+///     write - Perms Summary -
+///     read - Perms Summary -
+/// 
 /// GetUser, 0x20:
 ///     - User Data -
 /// 
 /// AddUser, 0x21:
 ///     user_id (u64)
 /// 
+/// ListUsers, 0x22:
+///     number of user_ids (u32)
+///     user_id 1 - n (u64 each), sorted
+///     has next (u8): 00 or 01
+///     next user_id (u64), only if there is a next
+/// 
+/// SetDisplayName, 0x23:
+///     - no data -
+/// 
+/// Status, 0x81:
+///     num_users (u64)
+///     num_entries (u64)
+///     uptime_secs (u64)
+///     max_message_size (u64)
+/// 
+/// Pong, 0x82:
+///     - no data -
+/// 
+/// NegotiateVersion, 0x83:
+///     version (u8)
+/// 
+/// GetRoot, 0x84:
+///     root_id (u64)
+/// 
+/// ListBoards, 0x85:
+///     number of boards (u16)
+///     board 1 - n: name length (u8), name (utf8 encoded)
+/// 
+/// Metrics, 0x86:
+///     text length (u32)
+///     text (utf8 encoded)
+/// 
 /// Error, 0xff:
-///     - no data - 
+///     error code (u8), see `DataError::error_code`, 
+///         an unknown code (or the unspecified one) is read back as an internal error
+///     max (u64), for `PAYLOAD_TOO_LARGE_ERROR`
 impl BoardResponse {
-    pub fn secure_extend_data<'a, F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(&self, rng: impl OldCryptoRng + OldRngCore, re_encryptor: ReEncryptionData, data: &mut Vec<u8>, get_user_aead: F) -> Result<(), DataError> {
-        data.push(RESPONSE_FORMAT_VERSION);
-        let mut body = Vec::new();
+    /// `compress` lets message bodies be deflated, for requests with `COMPRESSED_RESPONSE_FLAG`
+    pub fn secure_extend_data<'a, F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(&self, rng: impl OldCryptoRng + OldRngCore, re_encryptor: ReEncryptionData, compress: bool, data: &mut Vec<u8>, get_user_aead: F) -> Result<(), DataError> {
+        // sized up front so large entries aren't reallocated while being encoded
+        let mut body = Vec::with_capacity(self.size_hint());
+        self.extend_body(&mut body, compress)?;
+        extend_with_secure_body(rng, re_encryptor, data, &body, get_user_aead)
+    }
+
+    /// the discriminant and fields which are encrypted, see `secure_extend_data`
+    fn extend_body(&self, body: &mut Vec<u8>, compress: bool) -> Result<(), DataError> {
         match self {
-            BoardResponse::GetEntry(entry) => {
+            BoardResponse::GetEntry { entry, view_count } => {
                 body.push(GET_ENTRY);
-                entry.extend_data(&mut body)?;
+                entry.extend_data_with(body, compress)?;
+                body.extend_from_slice(&view_count.to_le_bytes());
             }
             BoardResponse::AddEntry(entry_id) => {
                 body.push(ADD_ENTRY);
@@ -1197,98 +4405,242 @@ impl BoardResponse {
             BoardResponse::EditEntry => {
                 body.push(EDIT_ENTRY);
             }
+            BoardResponse::MoveEntry => {
+                body.push(MOVE_ENTRY);
+            }
+            BoardResponse::AddEntries(entry_ids) => {
+                body.push(ADD_ENTRIES);
+                extend_with_ids(entry_ids, body)?;
+            }
+            BoardResponse::DeleteEntry => {
+                body.push(DELETE_ENTRY);
+            }
+            BoardResponse::GetAncestors(entry_ids) => {
+                body.push(GET_ANCESTORS);
+                extend_with_ids(entry_ids, body)?;
+            }
+            BoardResponse::GetEntries(entries) => {
+                body.push(GET_ENTRIES);
+                extend_with_maybe_entries(entries, body, compress)?;
+            }
+            BoardResponse::ChildrenSince(entry_ids) => {
+                body.push(CHILDREN_SINCE);
+                extend_with_ids(entry_ids, body)?;
+            }
+            BoardResponse::FilterAccessible(entry_ids) => {
+                body.push(FILTER_ACCESSIBLE);
+                extend_with_ids(entry_ids, body)?;
+            }
+            BoardResponse::MarkRead(view_count) => {
+                body.push(MARK_READ);
+                body.extend_from_slice(&view_count.to_le_bytes());
+            }
+            BoardResponse::SubtreeCount(count) => {
+                body.push(SUBTREE_COUNT);
+                body.extend_from_slice(&count.to_le_bytes());
+            }
+            BoardResponse::GetPermsSummary { write, read } => {
+                body.push(GET_PERMS_SUMMARY);
+                write.extend_data(body)?;
+                read.extend_data(body)?;
+            }
+            BoardResponse::SetPinned => {
+                body.push(SET_PINNED);
+            }
             BoardResponse::GetUser(user) => {
                 body.push(GET_USER);
-                user.extend_data(&mut body)?;
+                user.extend_data(body)?;
             }
             BoardResponse::AddUser{user_id, user_aead} => {
                 body.push(ADD_USER);
                 body.extend_from_slice(&user_id.to_le_bytes());
-                user_aead.extend_data(&mut body)?;
+                user_aead.extend_data(body)?;
+            }
+            BoardResponse::ListUsers{user_ids, next} => {
+                body.push(LIST_USERS);
+                extend_with_user_page(user_ids, *next, body)?;
+            }
+            BoardResponse::SetDisplayName => {
+                body.push(SET_DISPLAY_NAME);
             }
             BoardResponse::GetKemEk(kem_ek) => {
                 body.push(GET_KEM_EK);
-                kem_ek.extend_data(&mut body)?;
+                kem_ek.extend_data(body)?;
+            }
+            BoardResponse::Status{num_users, num_entries, uptime_secs, max_message_size} => {
+                body.push(STATUS);
+                body.extend_from_slice(&num_users.to_le_bytes());
+                body.extend_from_slice(&num_entries.to_le_bytes());
+                body.extend_from_slice(&uptime_secs.to_le_bytes());
+                body.extend_from_slice(&max_message_size.to_le_bytes());
+            }
+            BoardResponse::Pong => {
+                body.push(PING);
+            }
+            BoardResponse::NegotiateVersion(version) => {
+                body.push(NEGOTIATE_VERSION);
+                body.push(*version);
             }
-            BoardResponse::Error(e) => { // TODO: should consider the error
+            BoardResponse::GetRoot(root_id) => {
+                body.push(GET_ROOT);
+                body.extend_from_slice(&root_id.to_le_bytes());
+            }
+            BoardResponse::ListBoards(boards) => {
+                body.push(LIST_BOARDS);
+                extend_with_board_names(boards, body)?;
+            }
+            BoardResponse::Metrics(text) => {
+                body.push(METRICS);
+                extend_with_long_string(text, body)?;
+            }
+            BoardResponse::Error(e) => {
                 info!("Sending Error: {:?}", e);
                 body.push(ERROR);
+                e.extend_error_data(body)?;
             }
         }
-        match re_encryptor {
-            ReEncryptionData::Exposed => {
-                data.push(EXPOSED);
-                extend_with_exposed_block(data, &body)?;
+        Ok(())
+    }
+
+    /// as `extend_body` but writing to `writer`, entries are written straight from their bodies (see `Entry::extend_into_with`), 
+    /// the other responses are small enough to be buffered
+    fn write_body<W: Write>(&self, writer: &mut W, compress: bool) -> Result<(), DataError> {
+        match self {
+            BoardResponse::GetEntry { entry, view_count } => {
+                writer.write_all(&[GET_ENTRY])?;
+                entry.extend_into_with(writer, compress)?;
+                writer.write_all(&view_count.to_le_bytes())?;
             }
-            ReEncryptionData::FullAnonymous(key) => {
-                data.push(FULL_ANON);
-                extend_with_full_anonymous_response_block(&key, data, &body)?;
+            BoardResponse::GetEntries(entries) => {
+                writer.write_all(&[GET_ENTRIES])?;
+                extend_with_maybe_entries(entries, writer, compress)?;
             }
-            ReEncryptionData::User(user_id) => {
-                data.push(USER);
-                let mut aead = get_user_aead(user_id).map_or(Err(DataError::MissingKey), |x| Ok(x))?;
-                extend_with_user_response_block(rng, &mut *aead, data, &body)?;
+            _ => {
+                let mut body = Vec::new();
+                self.extend_body(&mut body, compress)?;
+                writer.write_all(&body)?;
             }
         }
         Ok(())
     }
 
-    pub fn secure_into_data<'a, F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(&self, rng: impl OldCryptoRng + OldRngCore, re_encryptor: ReEncryptionData, get_user_aead: F) -> Result<Vec<u8>, DataError> {
-        let mut out = Vec::new();
-        self.secure_extend_data(rng, re_encryptor, &mut out, get_user_aead)?;
+    /// the first chunk of a streamed response and the key the rest of it is encrypted with (see `write_streamed`), 
+    /// made ahead of writing anything so a response which can't be encrypted to the client can still be sent as an error
+    pub fn secure_stream_head<F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(mut rng: impl OldCryptoRng + OldRngCore, re_encryptor: ReEncryptionData, get_user_aead: F) -> Result<(Vec<u8>, SimpleAeadKey), DataError> {
+        let key = SimpleAeadKey::new_random(&mut rng);
+        let mut head = Vec::new();
+        extend_with_secure_body(rng, re_encryptor, &mut head, &key.into_data()?, get_user_aead)?;
+        Ok((head, key))
+    }
+
+    /// writes the response as a chunked frame (see `FrameReader`), encoding and encrypting its body a chunk at a time 
+    /// so it is never held whole, `head` and `key` are from `secure_stream_head`
+    /// 
+    /// streamed response format:
+    ///     first chunk: as a secure response (see `secure_extend_data`) whose body is the key the other chunks are encrypted with
+    ///     other chunks: encrypted with the key at their index from 0 (see `SimpleAeadKey::encrypt_chunk`), each is:
+    ///         last chunk (u8): 0x01 on the last, 0x00 on the rest, so a stream cut short is caught
+    ///         up to `RESPONSE_CHUNK_SIZE` bytes of the body
+    /// 
+    /// any error (ie. partway through the frame) is returned, after which the stream can't be used for more frames
+    pub fn write_streamed(&self, stream: &mut impl Write, request_id: u64, head: &[u8], key: SimpleAeadKey, compress: bool) -> Result<(), DataError> {
+        start_chunked_frame(stream, request_id)?;
+        write_chunk(stream, head)?;
+        let mut sealer = ChunkSealer { stream, key, index: 0, chunk: vec![0x00] };
+        self.write_body(&mut sealer, compress)?;
+        sealer.finish()
+    }
+
+    /// reads the rest of a streamed response (see `write_streamed`) from its first chunk, `head`, 
+    /// decrypting and decoding the other chunks as they are read off of the stream so the body is never held whole
+    /// 
+    /// the stream is read until the frame ends, so it blocks and a stream which would (ie. timed out) is an error, 
+    /// as is a frame ending before its last chunk, a failed read is given as `IoError` and leaves the stream unusable
+    pub fn read_streamed(frame_reader: &mut FrameReader, stream: &mut impl Read, head: &[u8], keys: &mut PublicKeySet) -> Result<Self, DataError> {
+        let key = read_secure_body(&mut head.iter().copied(), keys).and_then(|key| SimpleAeadKey::from_data_iter(&mut key.into_iter()));
+        let mut body = StreamedBody { frame_reader, stream, key: key.as_ref().ok(), index: 0, chunk: Vec::new().into_iter(), last: false, error: None };
+        let response = Self::from_body_iter(&mut body);
+        // the rest of the frame is read off even if it couldn't be decoded, so the stream can still be used
+        body.drain();
+        match (body.error, key) {
+            (Some(e), _) | (None, Err(e)) => Err(e),
+            (None, Ok(_)) => response,
+        }
+    }
+
+    pub fn secure_into_data<'a, F: FnOnce(UserId) -> Option<T>, T: Deref<Target = UserAeadKey> + DerefMut>(&self, rng: impl OldCryptoRng + OldRngCore, re_encryptor: ReEncryptionData, compress: bool, get_user_aead: F) -> Result<Vec<u8>, DataError> {
+        let mut out = Vec::with_capacity(self.size_hint());
+        self.secure_extend_data(rng, re_encryptor, compress, &mut out, get_user_aead)?;
         Ok(out)
     }
 
     pub fn secure_from_data_iter(data_iter: &mut impl Iterator<Item = u8>, keys: &mut PublicKeySet) -> Result<Self, DataError> {
-        let version = read_u8(data_iter)?;
-        if version != 0 {return Err(DataError::UnsupportedVersion)}
-        let mut body = match read_u8(data_iter)? {
-            EXPOSED => {
-                read_from_exposed_block(data_iter)?.collect::<Vec<_>>()
-            }
-            FULL_ANON => {
-                let (true_key, body) = read_from_full_anonymous_response_block(keys.simple_aead.iter(), data_iter)?;
-                let mut true_key_idx = 0;
-                for (key_idx, key) in keys.simple_aead.iter().enumerate() {
-                    if key == true_key {true_key_idx = key_idx; break;}
-                }
-                keys.simple_aead.remove(true_key_idx);
-                body
-            }
-            USER => {
-                let Some(user_aead) = &mut keys.user_aead else {return Err(DataError::MissingKey)};
-                read_from_user_response_block(user_aead, data_iter)?
-            }
-            _ => {return Err(DataError::InvalidDiscriminant)}
-        }.into_iter();
-        Ok(match read_u8(&mut body)? {
+        Self::from_body_iter(&mut read_secure_body(data_iter, keys)?.into_iter())
+    }
+
+    /// reads what `extend_body` writes
+    fn from_body_iter(body: &mut impl Iterator<Item = u8>) -> Result<Self, DataError> {
+        Ok(match read_u8(body)? {
             // entry responses
             GET_ENTRY => { // GetEntry
-                let entry = Entry::from_data_iter(&mut body)?;
-                BoardResponse::GetEntry(entry)
+                let entry = Entry::from_data_iter(body)?;
+                let view_count = read_u64(body)?;
+                BoardResponse::GetEntry { entry, view_count }
             }
             ADD_ENTRY => { // AddEntry
-                let entry_id = read_u64(&mut body)?.into();
+                let entry_id = read_u64(body)?.into();
                 BoardResponse::AddEntry(entry_id)
             }
             EDIT_ENTRY => BoardResponse::EditEntry,
+            MOVE_ENTRY => BoardResponse::MoveEntry,
+            ADD_ENTRIES => BoardResponse::AddEntries(read_ids(body)?),
+            DELETE_ENTRY => BoardResponse::DeleteEntry,
+            GET_ANCESTORS => BoardResponse::GetAncestors(read_ids(body)?),
+            GET_ENTRIES => BoardResponse::GetEntries(read_maybe_entries(body)?),
+            CHILDREN_SINCE => BoardResponse::ChildrenSince(read_ids(body)?),
+            FILTER_ACCESSIBLE => BoardResponse::FilterAccessible(read_ids(body)?),
+            MARK_READ => BoardResponse::MarkRead(read_u64(body)?),
+            SUBTREE_COUNT => BoardResponse::SubtreeCount(read_u64(body)?),
+            GET_PERMS_SUMMARY => {
+                let write = PermsSummary::from_data_iter(body)?;
+                let read = PermsSummary::from_data_iter(body)?;
+                BoardResponse::GetPermsSummary { write, read }
+            }
+            SET_PINNED => BoardResponse::SetPinned,
             // user responses
             GET_USER => { // GetUser
-                let user = UserData::from_data_iter(&mut body)?;
+                let user = UserData::from_data_iter(body)?;
                 BoardResponse::GetUser(user)
             }
             ADD_USER => { // AddUser
-                let user_id = read_u64(&mut body)?.into();
-                let user_aead = UserAeadKey::from_data_iter(&mut body)?;
+                let user_id = read_u64(body)?.into();
+                let user_aead = UserAeadKey::from_data_iter(body)?;
                 BoardResponse::AddUser{user_id, user_aead}
             }
+            LIST_USERS => {
+                let (user_ids, next) = read_user_page(body)?;
+                BoardResponse::ListUsers{user_ids, next}
+            }
+            SET_DISPLAY_NAME => BoardResponse::SetDisplayName,
             // network responses
             GET_KEM_EK => {
-                let kem_ek = EncapsulationKey::from_data_iter(&mut body)?;
+                let kem_ek = EncapsulationKey::from_data_iter(body)?;
                 BoardResponse::GetKemEk(kem_ek)
             }
+            STATUS => {
+                let num_users = read_u64(body)?;
+                let num_entries = read_u64(body)?;
+                let uptime_secs = read_u64(body)?;
+                let max_message_size = read_u64(body)?;
+                BoardResponse::Status{num_users, num_entries, uptime_secs, max_message_size}
+            }
+            PING => BoardResponse::Pong,
+            NEGOTIATE_VERSION => BoardResponse::NegotiateVersion(read_u8(body)?),
+            GET_ROOT => BoardResponse::GetRoot(read_u64(body)?.into()),
+            LIST_BOARDS => BoardResponse::ListBoards(read_board_names(body)?),
+            METRICS => BoardResponse::Metrics(read_long_string(body)?),
             ERROR => {
-                BoardResponse::Error(internal_error!()) //not really an internal error, it just isn't encoded atm
+                BoardResponse::Error(DataError::from_error_data_iter(body)?)
             }
             _ => {return Err(DataError::InvalidDiscriminant)}
         })
@@ -0,0 +1,174 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use crate::utils::wrap_lines;
+
+/// how a run of a message's text is shown, the client picks the actual colors / modifiers
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct SpanStyle {
+    pub bold: bool,
+    pub italic: bool,
+    /// inline code or a code block
+    pub code: bool,
+    pub heading: bool,
+}
+
+/// the runs of a line, in order
+pub type StyledLine = Vec<(String, SpanStyle)>;
+
+/// the message's lines with the markdown (headings, bold / italic, lists and code) turned into styles,
+/// None if the parsed events don't nest, for the caller to fall back to the plain text
+///
+/// anything else (links, html, ...) is kept as its text
+pub fn styled_lines(source: &str) -> Option<Vec<StyledLine>> {
+    let mut builder = LineBuilder::default();
+    for event in Parser::new(source) {
+        builder.push_event(event)?;
+    }
+    builder.finish_line();
+    if builder.bold != 0 || builder.italic != 0 || builder.heading || builder.code_block || !builder.lists.is_empty() {return None}
+    Some(builder.lines)
+}
+
+/// splits the line into rows at most `width` chars wide, as `wrap_lines` would the line's text
+pub fn wrap_styled_line(line: &[(String, SpanStyle)], width: usize) -> Vec<StyledLine> {
+    let text: String = line.iter().map(|(text, _)| text.as_str()).collect();
+    wrap_lines(&text, width).into_iter().map(|row| {
+        let mut styled_row = Vec::new();
+        let mut span_start = 0;
+        for (span_text, style) in line {
+            let span_end = span_start + span_text.len();
+            let (start, end) = (row.start.max(span_start), row.end.min(span_end));
+            if start < end {
+                styled_row.push((text[start..end].to_string(), *style));
+            }
+            span_start = span_end;
+        }
+        styled_row
+    }).collect()
+}
+
+#[derive(Default)]
+struct LineBuilder {
+    lines: Vec<StyledLine>,
+    current: StyledLine,
+    // nesting depths, so `**a *b* c**` is still bold after the `*b*`
+    bold: usize,
+    italic: usize,
+    heading: bool,
+    code_block: bool,
+    lists: Vec<Option<u64>>, // the next number of each ordered list, None for bulleted ones
+    blank_pending: bool, // a blank line goes between blocks, but not after the last one
+}
+
+impl LineBuilder {
+    fn style(&self, code: bool) -> SpanStyle {
+        SpanStyle { bold: self.bold > 0, italic: self.italic > 0, code: code || self.code_block, heading: self.heading }
+    }
+
+    fn push_text(&mut self, text: &str, code: bool) {
+        if text.is_empty() {return}
+        let style = self.style(code);
+        self.current.push((text.to_string(), style));
+    }
+
+    fn finish_line(&mut self) {
+        if !self.current.is_empty() {
+            self.lines.push(std::mem::take(&mut self.current));
+        }
+    }
+
+    fn start_block(&mut self) {
+        self.finish_line();
+        if self.blank_pending && !self.lines.is_empty() {
+            self.lines.push(Vec::new());
+        }
+        self.blank_pending = false;
+    }
+
+    fn end_block(&mut self) {
+        self.finish_line();
+        self.blank_pending = self.lists.is_empty();
+    }
+
+    fn push_event(&mut self, event: Event) -> Option<()> {
+        match event {
+            Event::Start(tag) => match tag {
+                // the paragraphs of a loose list's items stay on the item's line
+                Tag::Paragraph if !self.lists.is_empty() => {},
+                Tag::Paragraph => self.start_block(),
+                Tag::Heading { .. } => {
+                    self.start_block();
+                    self.heading = true;
+                }
+                Tag::CodeBlock(_) => {
+                    self.start_block();
+                    self.code_block = true;
+                }
+                Tag::List(start) => {
+                    if self.lists.is_empty() {self.start_block()}
+                    self.finish_line();
+                    self.lists.push(start);
+                }
+                Tag::Item => {
+                    self.finish_line();
+                    let depth = self.lists.len().checked_sub(1)?;
+                    let marker = match self.lists.last_mut()? {
+                        Some(number) => {
+                            *number += 1;
+                            format!("{}. ", *number - 1)
+                        }
+                        None => String::from("- "),
+                    };
+                    self.current.push((format!("{}{}", "  ".repeat(depth), marker), SpanStyle::default()));
+                }
+                Tag::Strong => self.bold += 1,
+                Tag::Emphasis => self.italic += 1,
+                _ => {},
+            }
+            Event::End(tag_end) => match tag_end {
+                TagEnd::Paragraph if !self.lists.is_empty() => {},
+                TagEnd::Paragraph => self.end_block(),
+                TagEnd::Heading(_) => {
+                    if !self.heading {return None}
+                    self.heading = false;
+                    self.end_block();
+                }
+                TagEnd::CodeBlock => {
+                    if !self.code_block {return None}
+                    self.code_block = false;
+                    self.end_block();
+                }
+                TagEnd::List(_) => {
+                    self.lists.pop()?;
+                    self.finish_line();
+                    if self.lists.is_empty() {self.blank_pending = true}
+                }
+                TagEnd::Item => self.finish_line(),
+                TagEnd::Strong => self.bold = self.bold.checked_sub(1)?,
+                TagEnd::Emphasis => self.italic = self.italic.checked_sub(1)?,
+                _ => {},
+            }
+            Event::Text(text) if self.code_block => {
+                // every line of the block is kept, blank ones included
+                for line in text.lines() {
+                    self.push_text(line, true);
+                    if self.current.is_empty() {
+                        self.lines.push(Vec::new());
+                    } else {
+                        self.finish_line();
+                    }
+                }
+            }
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => self.push_text(&text, false),
+            Event::Code(text) => self.push_text(&text, true),
+            Event::SoftBreak => self.push_text(" ", false),
+            Event::HardBreak => self.finish_line(),
+            Event::Rule => {
+                self.start_block();
+                self.current.push((String::from("───"), SpanStyle::default()));
+                self.end_block();
+            }
+            _ => {},
+        }
+        Some(())
+    }
+}
\ No newline at end of file
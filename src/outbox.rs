@@ -0,0 +1,89 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use crate::*;
+
+/// how sending a queued entry went
+#[derive(Debug)]
+pub enum Delivery {
+    Sent,
+    /// the board got the entry but refused it, it is dropped from the queue
+    Rejected(DataError),
+    /// the board couldn't be reached, the entry stays queued
+    Unreachable,
+}
+
+/// entries which couldn't be sent, kept on disk until the board can be reached again
+///
+/// file format: the queued entries (see `Entry`) back to back, oldest first
+#[derive(Debug)]
+pub struct Outbox {
+    path: PathBuf,
+    entries: VecDeque<Entry>,
+}
+
+impl Outbox {
+    /// reads the outbox at `path`, empty if there isn't one yet
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, DataError> {
+        let path = path.into();
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut data_iter = data.into_iter().peekable();
+        let mut entries = VecDeque::new();
+        while data_iter.peek().is_some() {
+            entries.push_back(Entry::from_data_iter(&mut data_iter)?);
+        }
+        Ok(Self { path, entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// queues the entry behind the rest
+    pub fn push(&mut self, entry: Entry) -> Result<(), DataError> {
+        self.entries.push_back(entry);
+        self.save()
+    }
+
+    /// sends the queued entries in order, stopping at the first which can't be delivered
+    ///
+    /// gives the errors of the entries the board rejected
+    pub fn flush(&mut self, mut send: impl FnMut(&Entry) -> Delivery) -> Result<Vec<DataError>, DataError> {
+        let mut rejections = Vec::new();
+        while let Some(entry) = self.entries.front() {
+            match send(entry) {
+                Delivery::Sent => {},
+                Delivery::Rejected(e) => rejections.push(e),
+                Delivery::Unreachable => break,
+            }
+            self.entries.pop_front();
+        }
+        self.save()?;
+        Ok(rejections)
+    }
+
+    fn save(&self) -> Result<(), DataError> {
+        if self.entries.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        }
+        let mut data = Vec::new();
+        for entry in &self.entries {
+            entry.extend_data(&mut data)?;
+        }
+        match utils::overwrite_atomic(&self.path, &data) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => utils::write_new_atomic(&self.path, &data)?,
+            result => result?,
+        }
+        Ok(())
+    }
+}
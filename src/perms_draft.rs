@@ -0,0 +1,111 @@
+use crate::*;
+
+/// the most hex digits a typed id can have, enough for any `u64`
+pub const MAX_ID_HEX_DIGITS: usize = 16;
+
+/// an in-progress `DefaultedIdSet`, edited a list at a time by typing ids in as hex and removing selected ones
+#[derive(Debug, Clone)]
+pub struct PermsDraft {
+    perms: DefaultedIdSet,
+    blacklist_focused: bool, // otherwise the whitelist, always one the base has
+    input: String, // the hex of the id being typed
+    selected: Option<usize>, // in the focused list
+}
+
+impl PermsDraft {
+    pub fn new(perms: DefaultedIdSet) -> Self {
+        let blacklist_focused = perms.whitelist().is_none();
+        Self { perms, blacklist_focused, input: String::new(), selected: None }
+    }
+
+    pub fn perms(&self) -> &DefaultedIdSet {
+        &self.perms
+    }
+
+    pub fn into_perms(self) -> DefaultedIdSet {
+        self.perms
+    }
+
+    /// switches the base (see `DefaultedIdSet::set_base`), refocusing if the focused list was dropped
+    pub fn set_base(&mut self, base: DefaultBase) {
+        self.perms.set_base(base);
+        let has_focused = if self.blacklist_focused {self.perms.blacklist().is_some()} else {self.perms.whitelist().is_some()};
+        if !has_focused {self.blacklist_focused = !self.blacklist_focused}
+        self.selected = None;
+    }
+
+    pub fn blacklist_focused(&self) -> bool {
+        self.blacklist_focused
+    }
+
+    pub fn focused_ids(&self) -> &[UserId] {
+        let ids = if self.blacklist_focused {self.perms.blacklist()} else {self.perms.whitelist()};
+        ids.map_or(&[], |ids| ids.as_slice())
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// focuses the other list, false if the base only has the one
+    pub fn toggle_list(&mut self) -> bool {
+        if self.perms.whitelist().is_none() || self.perms.blacklist().is_none() {return false}
+        self.blacklist_focused = !self.blacklist_focused;
+        self.selected = None;
+        true
+    }
+
+    /// types a digit of the id, false (leaving the input as is) if it isn't hex or the id is already at `MAX_ID_HEX_DIGITS`
+    pub fn push_char(&mut self, c: char) -> bool {
+        if !c.is_ascii_hexdigit() || self.input.len() >= MAX_ID_HEX_DIGITS {return false}
+        self.input.push(c.to_ascii_uppercase());
+        true
+    }
+
+    pub fn pop_char(&mut self) -> Option<char> {
+        self.input.pop()
+    }
+
+    /// adds the typed id to the focused list and clears the input, selecting the id,
+    /// NotHex if nothing was typed and AlreadyExists if it's already in the list, the input being kept for either
+    ///
+    /// an id in the other list is moved rather than rejected, as the builder methods do
+    pub fn add_input(&mut self) -> Result<UserId, DataError> {
+        let id = UserId::from(u64::from_str_radix(&self.input, 16).map_err(|_| DataError::NotHex)?);
+        if self.focused_ids().contains(&id) {return Err(DataError::AlreadyExists)}
+        let added = if self.blacklist_focused {self.perms.add_blacklist(id)} else {self.perms.add_whitelist(id)};
+        if !added {return Err(internal_error!())}
+        self.input.clear();
+        self.selected = Some(self.focused_ids().len() - 1);
+        Ok(id)
+    }
+
+    /// removes the selected id from the focused list, selecting the one after it if any
+    pub fn remove_selected(&mut self) -> Option<UserId> {
+        let id = *self.focused_ids().get(self.selected?)?;
+        if self.blacklist_focused {self.perms.remove_blacklist(id)} else {self.perms.remove_whitelist(id)};
+        let len = self.focused_ids().len();
+        self.selected = self.selected.filter(|_| len > 0).map(|selected| selected.min(len - 1));
+        Some(id)
+    }
+
+    pub fn select_up(&mut self) {
+        self.selected = match self.selected {
+            _ if self.focused_ids().is_empty() => None,
+            None => Some(self.focused_ids().len() - 1),
+            Some(selected) => Some(selected.saturating_sub(1)),
+        };
+    }
+
+    pub fn select_down(&mut self) {
+        self.selected = match self.selected {
+            _ if self.focused_ids().is_empty() => None,
+            None => Some(0),
+            Some(selected) => Some((selected + 1).min(self.focused_ids().len() - 1)),
+        };
+    }
+}
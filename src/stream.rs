@@ -0,0 +1,134 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+#[cfg(feature = "tls")]
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// a connection between a client and the board,
+/// plaintext unless built with the `tls` feature and configured to use it
+///
+/// the framing (see `FrameReader`) is the same either way
+#[derive(Debug)]
+pub enum BoardStream {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    ServerTls(Arc<Mutex<rustls::StreamOwned<rustls::ServerConnection, TcpStream>>>),
+    #[cfg(feature = "tls")]
+    ClientTls(Arc<Mutex<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>>),
+}
+
+#[cfg(feature = "tls")]
+fn lock<T>(stream: &Mutex<T>) -> MutexGuard<'_, T> {
+    stream.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl BoardStream {
+    /// starts the server side of a tls connection, the handshake happens on the first read / write
+    #[cfg(feature = "tls")]
+    pub fn accept_tls(stream: TcpStream, config: Arc<rustls::ServerConfig>) -> std::io::Result<Self> {
+        let connection = rustls::ServerConnection::new(config).map_err(std::io::Error::other)?;
+        Ok(BoardStream::ServerTls(Arc::new(Mutex::new(rustls::StreamOwned::new(connection, stream)))))
+    }
+
+    /// starts the client side of a tls connection, the handshake happens on the first read / write
+    #[cfg(feature = "tls")]
+    pub fn connect_tls(stream: TcpStream, config: Arc<rustls::ClientConfig>, server_name: &str) -> std::io::Result<Self> {
+        let server_name = rustls::pki_types::ServerName::try_from(server_name.to_string()).map_err(std::io::Error::other)?;
+        let connection = rustls::ClientConnection::new(config, server_name).map_err(std::io::Error::other)?;
+        Ok(BoardStream::ClientTls(Arc::new(Mutex::new(rustls::StreamOwned::new(connection, stream)))))
+    }
+
+    /// like `TcpStream::try_clone`, both handles refer to the same connection
+    pub fn try_clone(&self) -> std::io::Result<Self> {
+        Ok(match self {
+            BoardStream::Plain(stream) => BoardStream::Plain(stream.try_clone()?),
+            #[cfg(feature = "tls")]
+            BoardStream::ServerTls(stream) => BoardStream::ServerTls(stream.clone()),
+            #[cfg(feature = "tls")]
+            BoardStream::ClientTls(stream) => BoardStream::ClientTls(stream.clone()),
+        })
+    }
+
+    /// like `TcpStream::set_nonblocking`, affects every handle to the connection
+    pub fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        match self {
+            BoardStream::Plain(stream) => stream.set_nonblocking(nonblocking),
+            #[cfg(feature = "tls")]
+            BoardStream::ServerTls(stream) => lock(stream).sock.set_nonblocking(nonblocking),
+            #[cfg(feature = "tls")]
+            BoardStream::ClientTls(stream) => lock(stream).sock.set_nonblocking(nonblocking),
+        }
+    }
+}
+
+impl Read for BoardStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            BoardStream::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            BoardStream::ServerTls(stream) => lock(stream).read(buf),
+            #[cfg(feature = "tls")]
+            BoardStream::ClientTls(stream) => lock(stream).read(buf),
+        }
+    }
+}
+
+impl Write for BoardStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            BoardStream::Plain(stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            BoardStream::ServerTls(stream) => lock(stream).write(buf),
+            #[cfg(feature = "tls")]
+            BoardStream::ClientTls(stream) => lock(stream).write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            BoardStream::Plain(stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            BoardStream::ServerTls(stream) => lock(stream).flush(),
+            #[cfg(feature = "tls")]
+            BoardStream::ClientTls(stream) => lock(stream).flush(),
+        }
+    }
+}
+
+/// building the rustls configs from pem files
+#[cfg(feature = "tls")]
+pub mod tls {
+    use std::path::Path;
+    use std::sync::Arc;
+    use rustls::pki_types::pem::{self, PemObject};
+    pub use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+    /// reads every certificate in a pem file, in order
+    pub fn load_certs(path: impl AsRef<Path>) -> Result<Vec<CertificateDer<'static>>, pem::Error> {
+        CertificateDer::pem_file_iter(path)?.collect()
+    }
+
+    /// reads the first private key in a pem file
+    pub fn load_private_key(path: impl AsRef<Path>) -> Result<PrivateKeyDer<'static>, pem::Error> {
+        PrivateKeyDer::from_pem_file(path)
+    }
+
+    pub fn server_config(cert_chain: Vec<CertificateDer<'static>>, key: PrivateKeyDer<'static>) -> Result<Arc<rustls::ServerConfig>, rustls::Error> {
+        Ok(Arc::new(rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(cert_chain, key)?))
+    }
+
+    /// trusts only `custom_ca` if given, otherwise the bundled webpki roots
+    pub fn client_config(custom_ca: Option<Vec<CertificateDer<'static>>>) -> Result<Arc<rustls::ClientConfig>, rustls::Error> {
+        let mut roots = rustls::RootCertStore::empty();
+        match custom_ca {
+            Some(certs) => for cert in certs {
+                roots.add(cert)?;
+            }
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+        Ok(Arc::new(rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth()))
+    }
+}
@@ -2,6 +2,7 @@
 use rand::distr::uniform::SampleRange;
 use rand::seq::SliceRandom;
 use rand::{distr::Distribution, Rng, RngExt};
+use std::io::Write;
 
 // this is the 0.6.4 version, vs the 0.10.0 version from the rand crate
 use rand_chacha::rand_core::RngCore as OldRngCore;
@@ -70,7 +71,7 @@ fn rand_entry(mut rng: impl Rng, mut char_rng: impl Iterator<Item = char>) -> En
         children_ids.push(rng.next_u64().into());
     }
 
-    let entry_data = match rng.random_range(0..2) {
+    let entry_data = match rng.random_range(0..4) {
         0 => {
             EntryData::Message { 
                 timestamp: rng.next_u64(), 
@@ -84,6 +85,19 @@ fn rand_entry(mut rng: impl Rng, mut char_rng: impl Iterator<Item = char>) -> En
                 read_perms: rand_defaulted_id_set(&mut rng, &mut char_rng),
             }
         }
+        2 => {
+            EntryData::Reaction { 
+                emoji: (&mut char_rng).take(rng.random_range(1..8)).collect(), 
+                target_id: rng.next_u64().into(),
+            }
+        }
+        3 => {
+            EntryData::Attachment { 
+                filename: (&mut char_rng).take(rng.random_range(1..50)).collect(), 
+                mime: (&mut char_rng).take(rng.random_range(1..50)).collect(), 
+                data: rand_bytes(&mut rng, 0..10000),
+            }
+        }
         _ => panic!("entry type should be in range")
     };
 
@@ -94,6 +108,9 @@ fn rand_entry(mut rng: impl Rng, mut char_rng: impl Iterator<Item = char>) -> En
             parent_id: rng.next_u64().into(), 
             children_ids, 
             author_id: rng.next_u64().into(),
+            last_activity: rng.next_u64(),
+            pinned: rng.random_bool(0.5),
+            deleted: rng.random_bool(0.5),
         },
     };
 
@@ -106,15 +123,17 @@ fn rand_user(mut rng: impl Rng, crypto_rng: impl OldCryptoRng + OldRngCore) -> U
         entry_ids.push(rng.next_u64().into());
     }
     let aead = UserAeadKey::new_random(crypto_rng);
+    let display_name = rng.random_bool(0.5).then(|| format!("user {:X}", rng.next_u64()));
     let user = UserData { 
         aead,
-        entry_ids
+        entry_ids,
+        display_name,
     };
     user
 }
 
 fn rand_request(mut rng: impl Rng, mut char_rng: impl Iterator<Item = char>) -> BoardRequest {
-    match rng.random_range(0..5) {
+    match rng.random_range(0..24) {
         0 => {
             let user_id = rng.next_u64().into();
             let entry_id = rng.next_u64().into();
@@ -133,17 +152,103 @@ fn rand_request(mut rng: impl Rng, mut char_rng: impl Iterator<Item = char>) ->
         }
         3 => {
             let user_id = rng.next_u64().into();
-            BoardRequest::GetUser { user_id }
+            BoardRequest::GetUser { requester_id: rng.next_u64().into(), user_id }
         }
         4 => {
             BoardRequest::AddUser
         }
+        5 => {
+            BoardRequest::Status
+        }
+        6 => {
+            let user_id = rng.next_u64().into();
+            let entry_id = rng.next_u64().into();
+            let new_parent_id = rng.next_u64().into();
+            BoardRequest::MoveEntry { user_id, entry_id, new_parent_id }
+        }
+        7 => {
+            let user_id = rng.next_u64().into();
+            let entries = (0..rng.random_range(0..4)).map(|_| rand_entry(&mut rng, &mut char_rng)).collect();
+            BoardRequest::AddEntries { user_id, entries }
+        }
+        8 => {
+            let user_id = rng.next_u64().into();
+            let entry_id = rng.next_u64().into();
+            BoardRequest::DeleteEntry { user_id, entry_id }
+        }
+        9 => {
+            BoardRequest::Ping
+        }
+        10 => {
+            let admin_id = rng.next_u64().into();
+            let start = rng.next_u64().into();
+            BoardRequest::ListUsers { admin_id, start }
+        }
+        11 => {
+            let user_id = rng.next_u64().into();
+            let entry_id = rng.next_u64().into();
+            BoardRequest::GetAncestors { user_id, entry_id }
+        }
+        12 => {
+            let user_id = rng.next_u64().into();
+            let entry_id = rng.next_u64().into();
+            BoardRequest::SetPinned { user_id, entry_id, pinned: rng.random_bool(0.5) }
+        }
+        13 => {
+            let user_id = rng.next_u64().into();
+            let entry_ids = (0..rng.random_range(0..8)).map(|_| rng.next_u64().into()).collect();
+            BoardRequest::GetEntries { user_id, entry_ids }
+        }
+        14 => {
+            let user_id = rng.next_u64().into();
+            let entry_id = rng.next_u64().into();
+            BoardRequest::ChildrenSince { user_id, entry_id, since_ts: rng.next_u64() }
+        }
+        15 => {
+            let user_id = rng.next_u64().into();
+            let perm_kind = if rng.random_bool(0.5) {PermKind::Read} else {PermKind::Write};
+            let entry_ids = (0..rng.random_range(0..8)).map(|_| rng.next_u64().into()).collect();
+            BoardRequest::FilterAccessible { user_id, perm_kind, entry_ids }
+        }
+        16 => {
+            let user_id = rng.next_u64().into();
+            let entry_id = rng.next_u64().into();
+            BoardRequest::MarkRead { user_id, entry_id }
+        }
+        17 => {
+            BoardRequest::NegotiateVersion { min_version: rng.random(), max_version: rng.random() }
+        }
+        18 => {
+            let user_id = rng.next_u64().into();
+            let entry_id = rng.next_u64().into();
+            BoardRequest::SubtreeCount { user_id, entry_id }
+        }
+        19 => {
+            let user_id = rng.next_u64().into();
+            let display_name = rng.random_bool(0.5).then(|| (&mut char_rng).take(rng.random_range(1..16)).collect());
+            BoardRequest::SetDisplayName { user_id, display_name }
+        }
+        20 => {
+            BoardRequest::GetRoot
+        }
+        21 => {
+            BoardRequest::ListBoards
+        }
+        22 => {
+            let user_id = rng.next_u64().into();
+            let entry_id = rng.next_u64().into();
+            BoardRequest::GetPermsSummary { user_id, entry_id }
+        }
+        23 => {
+            let admin_id = rng.next_u64().into();
+            BoardRequest::Metrics { admin_id }
+        }
         _ => panic!("Request Type should be in range")
     }
 }
 
 fn new_rand_request(mut rng: impl Rng, mut char_rng: impl Iterator<Item = char>, sender_user_id: UserId) -> BoardRequest {
-    match rng.random_range(0..5) {
+    match rng.random_range(0..24) {
         0 => {
             let entry_id = rng.next_u64().into();
             BoardRequest::GetEntry { user_id: sender_user_id, entry_id }
@@ -159,19 +264,91 @@ fn new_rand_request(mut rng: impl Rng, mut char_rng: impl Iterator<Item = char>,
         }
         3 => {
             let user_id = rng.next_u64().into();
-            BoardRequest::GetUser { user_id }
+            BoardRequest::GetUser { requester_id: sender_user_id, user_id }
         }
         4 => {
             BoardRequest::AddUser
         }
+        5 => {
+            BoardRequest::Status
+        }
+        6 => {
+            let entry_id = rng.next_u64().into();
+            let new_parent_id = rng.next_u64().into();
+            BoardRequest::MoveEntry { user_id: sender_user_id, entry_id, new_parent_id }
+        }
+        7 => {
+            let entries = (0..rng.random_range(0..4)).map(|_| rand_entry(&mut rng, &mut char_rng)).collect();
+            BoardRequest::AddEntries { user_id: sender_user_id, entries }
+        }
+        8 => {
+            let entry_id = rng.next_u64().into();
+            BoardRequest::DeleteEntry { user_id: sender_user_id, entry_id }
+        }
+        9 => {
+            BoardRequest::Ping
+        }
+        10 => {
+            let start = rng.next_u64().into();
+            BoardRequest::ListUsers { admin_id: sender_user_id, start }
+        }
+        11 => {
+            let entry_id = rng.next_u64().into();
+            BoardRequest::GetAncestors { user_id: sender_user_id, entry_id }
+        }
+        12 => {
+            let entry_id = rng.next_u64().into();
+            BoardRequest::SetPinned { user_id: sender_user_id, entry_id, pinned: rng.random_bool(0.5) }
+        }
+        13 => {
+            let entry_ids = (0..rng.random_range(0..8)).map(|_| rng.next_u64().into()).collect();
+            BoardRequest::GetEntries { user_id: sender_user_id, entry_ids }
+        }
+        14 => {
+            let entry_id = rng.next_u64().into();
+            BoardRequest::ChildrenSince { user_id: sender_user_id, entry_id, since_ts: rng.next_u64() }
+        }
+        15 => {
+            let perm_kind = if rng.random_bool(0.5) {PermKind::Read} else {PermKind::Write};
+            let entry_ids = (0..rng.random_range(0..8)).map(|_| rng.next_u64().into()).collect();
+            BoardRequest::FilterAccessible { user_id: sender_user_id, perm_kind, entry_ids }
+        }
+        16 => {
+            let entry_id = rng.next_u64().into();
+            BoardRequest::MarkRead { user_id: sender_user_id, entry_id }
+        }
+        17 => {
+            BoardRequest::NegotiateVersion { min_version: rng.random(), max_version: rng.random() }
+        }
+        18 => {
+            let entry_id = rng.next_u64().into();
+            BoardRequest::SubtreeCount { user_id: sender_user_id, entry_id }
+        }
+        19 => {
+            let display_name = rng.random_bool(0.5).then(|| (&mut char_rng).take(rng.random_range(1..16)).collect());
+            BoardRequest::SetDisplayName { user_id: sender_user_id, display_name }
+        }
+        20 => {
+            BoardRequest::GetRoot
+        }
+        21 => {
+            BoardRequest::ListBoards
+        }
+        22 => {
+            let entry_id = rng.next_u64().into();
+            BoardRequest::GetPermsSummary { user_id: sender_user_id, entry_id }
+        }
+        23 => {
+            BoardRequest::Metrics { admin_id: sender_user_id }
+        }
         _ => panic!("Request Type should be in range")
     }
 }
 
-fn rand_response(mut rng: impl Rng, char_rng: impl Iterator<Item = char>, crypto_rng: impl OldCryptoRng + OldRngCore) -> BoardResponse {
-    match rng.random_range(0..6) {
+fn rand_response(mut rng: impl Rng, mut char_rng: impl Iterator<Item = char>, crypto_rng: impl OldCryptoRng + OldRngCore) -> BoardResponse {
+    match rng.random_range(0..25) {
         0 => {
-            BoardResponse::GetEntry(rand_entry(rng, char_rng))
+            BoardResponse::GetEntry { entry: rand_entry(&mut rng, char_rng), view_count: rng.next_u64() }
         }
         1 => {
             BoardResponse::AddEntry(rng.next_u64().into())
@@ -189,8 +366,80 @@ fn rand_response(mut rng: impl Rng, char_rng: impl Iterator<Item = char>, crypto
             }
         }
         5 => {
+            BoardResponse::Status{
+                num_users: rng.next_u64(), 
+                num_entries: rng.next_u64(), 
+                uptime_secs: rng.next_u64(), 
+                max_message_size: rng.next_u64()
+            }
+        }
+        6 => {
+            BoardResponse::MoveEntry
+        }
+        7 => {
+            BoardResponse::AddEntries((0..rng.random_range(0..8)).map(|_| rng.next_u64().into()).collect())
+        }
+        8 => {
             BoardResponse::Error(internal_error!())
         }
+        9 => {
+            BoardResponse::DeleteEntry
+        }
+        10 => {
+            BoardResponse::Pong
+        }
+        11 => {
+            BoardResponse::ListUsers{
+                user_ids: (0..rng.random_range(0..8)).map(|_| rng.next_u64().into()).collect(),
+                next: rng.random_bool(0.5).then(|| rng.next_u64().into()),
+            }
+        }
+        12 => {
+            BoardResponse::GetAncestors((0..rng.random_range(0..8)).map(|_| rng.next_u64().into()).collect())
+        }
+        13 => {
+            BoardResponse::SetPinned
+        }
+        14 => {
+            BoardResponse::GetEntries((0..rng.random_range(0..4)).map(|_| match rng.random_range(0..3) {
+                0 => Err(DataError::DoesNotExist),
+                1 => Err(DataError::InsufficientPerms),
+                _ => Ok(rand_entry(&mut rng, &mut char_rng)),
+            }).collect())
+        }
+        15 => {
+            BoardResponse::ChildrenSince((0..rng.random_range(0..8)).map(|_| rng.next_u64().into()).collect())
+        }
+        16 => {
+            BoardResponse::FilterAccessible((0..rng.random_range(0..8)).map(|_| rng.next_u64().into()).collect())
+        }
+        17 => {
+            BoardResponse::MarkRead(rng.next_u64())
+        }
+        18 => {
+            BoardResponse::NegotiateVersion(rng.random())
+        }
+        19 => {
+            BoardResponse::SubtreeCount(rng.next_u64())
+        }
+        20 => {
+            BoardResponse::SetDisplayName
+        }
+        21 => {
+            BoardResponse::GetRoot(rng.next_u64().into())
+        }
+        22 => {
+            let boards = (0..rng.random_range(0..4)).map(|_| (&mut char_rng).take(rng.random_range(1..16)).collect()).collect();
+            BoardResponse::ListBoards(boards)
+        }
+        23 => {
+            let write = PermsSummary::new(&rand_defaulted_id_set(&mut rng, &mut char_rng)).unwrap();
+            let read = PermsSummary::new(&rand_defaulted_id_set(&mut rng, &mut char_rng)).unwrap();
+            BoardResponse::GetPermsSummary { write, read }
+        }
+        24 => {
+            BoardResponse::Metrics((&mut char_rng).take(rng.random_range(100..10000)).collect())
+        }
         _ => panic!("Request Type should be in range")
     }
 }
@@ -220,6 +469,10 @@ fn entry_size_hint() {
     let mut char_rng = get_char_rng(rng.clone());
     for _ in 0..RANDOM_TEST_RETRIES {
         let entry = rand_entry(&mut rng, &mut char_rng);
+        // a compressed body is counted at its raw size
+        #[cfg(feature = "compression")]
+        assert!(entry.size_hint() >= entry.into_data().unwrap().len(), "Size hint below the encoded size");
+        #[cfg(not(feature = "compression"))]
         assert_eq!(entry.size_hint(), entry.into_data().unwrap().len(), "Incorrect size hint");
     }
 }
@@ -477,7 +730,7 @@ fn secure_board_response_data_conversion() {
         if let ReEncryptionData::FullAnonymous(key) = &re_encryptor {
             user_key.simple_aead.push_back(key.clone()); // this has to be artificial since it would normally be done when the request leading to this response was sent
         }
-        let encoded = response.secure_into_data(&mut crypto_rng, re_encryptor,  |key_user_id| 
+        let encoded = response.secure_into_data(&mut crypto_rng, re_encryptor, rng.random_bool(0.5), |key_user_id| 
             if key_user_id == user_id {Some(&mut servers_user_aead_key)} else {None}
         ).unwrap();
         let decoded = BoardResponse::secure_from_data(&encoded, &mut user_key).unwrap();
@@ -487,4 +740,2077 @@ fn secure_board_response_data_conversion() {
             assert_eq!(response, decoded, "Invalid Request Conversion");
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn frame_reader_partial_frames() {
+    let mut rng = rand::rng();
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let mut sender = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut receiver, _) = listener.accept().unwrap();
+    receiver.set_nonblocking(true).unwrap();
+
+    let frame = rand_bytes(&mut rng, 1..65536);
+    let request_id = rng.next_u64();
+    let mut data = Vec::new();
+    write_frame(&mut data, request_id, &frame).unwrap();
+    let split = rng.random_range(1..data.len());
+
+    let mut frame_reader = FrameReader::new();
+    sender.write_all(&data[..split]).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    assert_eq!(frame_reader.read_frame(&mut receiver).unwrap(), None, "Partial frame was returned");
+
+    sender.write_all(&data[split..]).unwrap();
+    let mut received = None;
+    for _ in 0..RANDOM_TEST_RETRIES {
+        received = frame_reader.read_frame(&mut receiver).unwrap();
+        if received.is_some() {break;}
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert_eq!(received, Some((request_id, frame)), "Frame split over two chunks was not reassembled");
+
+    drop(sender);
+    let mut disconnected = false;
+    for _ in 0..RANDOM_TEST_RETRIES {
+        match frame_reader.read_frame(&mut receiver) {
+            Ok(None) => std::thread::sleep(std::time::Duration::from_millis(10)),
+            Ok(Some(_)) => panic!("Frame received after disconnect"),
+            Err(_) => {disconnected = true; break;}
+        }
+    }
+    assert!(disconnected, "Disconnect was not distinguished from would block");
+}
+
+#[test]
+fn frame_reader_request_ids() {
+    let mut rng = rand::rng();
+    let mut data = Vec::new();
+    let mut frames = Vec::new();
+    for request_id in 0..RANDOM_TEST_RETRIES as u64 {
+        frames.push((request_id, rand_bytes(&mut rng, 0..1024)));
+    }
+    // responses can come back in any order
+    frames.shuffle(&mut rng);
+    for (request_id, frame) in &frames {
+        write_frame(&mut data, *request_id, frame).unwrap();
+    }
+
+    let mut frame_reader = FrameReader::new();
+    let mut data_stream = &data[..];
+    for frame in frames {
+        assert_eq!(frame_reader.read_frame(&mut data_stream).unwrap(), Some(frame), "Request id not kept with its frame");
+    }
+    let _ = frame_reader.read_frame(&mut data_stream).expect_err("Frame read past the end of the stream");
+}
+
+#[test]
+fn access_group_perm_kinds() {
+    let mut rng = rand::rng();
+    let user_id: UserId = rng.next_u64().into();
+    let other_id: UserId = rng.next_u64().into();
+    let entry_data = EntryData::AccessGroup { 
+        name: String::from("Read Only"), 
+        write_perms: DefaultedIdSet::Black { whitelist_ids: vec![other_id] }, 
+        read_perms: DefaultedIdSet::White { blacklist_ids: Vec::new() },
+    };
+    assert_eq!(entry_data.get_perms(PermKind::Read).unwrap().contains(user_id), Some(true), "Read perms not granted");
+    assert_eq!(entry_data.get_perms(PermKind::Write).unwrap().contains(user_id), Some(false), "Write perms not denied");
+    assert_eq!(entry_data.get_perms(PermKind::Write).unwrap().contains(other_id), Some(true), "Write perms not granted to the whitelist");
+
+    let message = EntryData::Message { timestamp: 0, message: String::new() };
+    assert_eq!(message.get_perms(PermKind::Read), None, "Message has perms");
+    assert_eq!(message.get_perms(PermKind::Write), None, "Message has perms");
+}
+
+#[test]
+fn config_edit_keeps_comments() {
+    let contents = "# the board to connect to\naddress = \"localhost\"\n\n# set by the client on registration\nuser_id = \"None\" # not registered yet\nuser_aead = \"None\"\n";
+    let mut config = utils::read_config(contents).unwrap();
+    assert_eq!(utils::require_config_keys(&config, &["address", "user_id"]), Ok(()), "Present keys reported missing");
+    assert_eq!(utils::require_config_keys(&config, &["address", "entry_cache_size"]), Err("entry_cache_size"), "Missing key not reported");
+
+    utils::set_config_value(&mut config, "user_id", 0x1234);
+    let written = config.to_string();
+    assert_eq!(config["user_id"].as_integer(), Some(0x1234), "Value not set");
+    assert!(written.contains("# the board to connect to\naddress = \"localhost\"\n"), "Comment lost: {}", written);
+    assert!(written.contains("# set by the client on registration\nuser_id = 4660 # not registered yet\n"), "Comment lost: {}", written);
+    assert!(written.find("address") < written.find("user_id"), "Keys reordered: {}", written);
+}
+
+#[cfg(feature = "tls")]
+#[test]
+fn tls_loopback_frames() {
+    use crate::stream::{tls, BoardStream};
+    use std::net::{TcpListener, TcpStream};
+
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert = certified.cert.der().clone();
+    let key = tls::PrivateKeyDer::Pkcs8(certified.key_pair.serialize_der().into());
+    let server_config = tls::server_config(vec![cert.clone()], key).unwrap();
+    let client_config = tls::client_config(Some(vec![cert])).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut stream = BoardStream::accept_tls(stream, server_config).unwrap();
+        let mut frame_reader = FrameReader::new();
+        let (request_id, frame) = frame_reader.read_frame(&mut stream).unwrap().expect("Blocking read should give a frame");
+        write_frame(&mut stream, request_id, &frame).unwrap();
+    });
+
+    let mut rng = rand::rng();
+    let mut stream = BoardStream::connect_tls(TcpStream::connect(address).unwrap(), client_config, "localhost").unwrap();
+    let request_id = rng.next_u64();
+    let frame = rand_bytes(&mut rng, 0..1000);
+    write_frame(&mut stream, request_id, &frame).unwrap();
+    let mut frame_reader = FrameReader::new();
+    let echoed = frame_reader.read_frame(&mut stream).unwrap().expect("Blocking read should give a frame");
+    assert_eq!(echoed, (request_id, frame), "Frame changed over tls");
+    server.join().unwrap();
+}
+
+#[test]
+fn entry_size_limits() {
+    let at_limit = EntryData::Message { timestamp: 0, message: "a".repeat(DEFAULT_MAX_MESSAGE_SIZE) };
+    assert_eq!(at_limit.check_size_limits(DEFAULT_MAX_MESSAGE_SIZE), Ok(()), "Message at the limit rejected");
+    let over_limit = EntryData::Message { timestamp: 0, message: "a".repeat(DEFAULT_MAX_MESSAGE_SIZE + 1) };
+    assert_eq!(over_limit.check_size_limits(DEFAULT_MAX_MESSAGE_SIZE), Err(DataError::PayloadTooLarge { max: DEFAULT_MAX_MESSAGE_SIZE }), "Message over the limit accepted");
+
+    let long_name = EntryData::AccessGroup { 
+        name: "a".repeat(MAX_ACCESS_GROUP_NAME_SIZE + 1), 
+        write_perms: DefaultedIdSet::empty_from_base(DefaultBase::Inherit), 
+        read_perms: DefaultedIdSet::empty_from_base(DefaultBase::Inherit),
+    };
+    assert_eq!(long_name.check_size_limits(DEFAULT_MAX_MESSAGE_SIZE), Err(DataError::PayloadTooLarge { max: MAX_ACCESS_GROUP_NAME_SIZE }), "Name over the limit accepted");
+    let many_ids = EntryData::AccessGroup { 
+        name: String::new(), 
+        write_perms: DefaultedIdSet::empty_from_base(DefaultBase::Inherit), 
+        read_perms: DefaultedIdSet::Black { whitelist_ids: (0..=MAX_PERM_IDS as u64).map(UserId::from).collect() },
+    };
+    assert_eq!(many_ids.check_size_limits(DEFAULT_MAX_MESSAGE_SIZE), Err(DataError::PayloadTooLarge { max: MAX_PERM_IDS }), "Perm ids over the limit accepted");
+}
+
+#[test]
+fn io_error_conversion() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("message_board_io_test_{:016X}", rand::rng().next_u64()));
+    std::fs::create_dir(&dir).unwrap();
+
+    let mut missing = dir.clone();
+    missing.push("missing");
+    assert_eq!(DataError::from(std::fs::read(&missing).unwrap_err()), DataError::DoesNotExist, "Missing file not DoesNotExist");
+    // a dir exists but can't be read as a file
+    let unreadable = DataError::from(std::fs::read(&dir).unwrap_err());
+    assert!(matches!(unreadable, DataError::IoError(_)), "Unreadable file not an IoError: {:?}", unreadable);
+    assert_eq!(DataError::from(std::fs::create_dir(&dir).unwrap_err()), DataError::AlreadyExists, "Existing dir not AlreadyExists");
+
+    std::fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn data_error_display() {
+    let errors = [
+        DataError::IncorrectMagicNum,
+        DataError::InsufficientBytes,
+        DataError::InvalidDiscriminant,
+        DataError::StringError(String::from_utf8(vec![0xff]).unwrap_err()),
+        DataError::NotHex,
+        DataError::UnsupportedVersion,
+        DataError::VersionMismatch,
+        DataError::DoesNotExist,
+        DataError::AlreadyExists,
+        DataError::InsufficientPerms,
+        DataError::BadCredentials,
+        DataError::IoError(std::io::ErrorKind::PermissionDenied),
+        DataError::MissingKey,
+        DataError::IncorrectKey,
+        DataError::EncryptionError,
+        DataError::MalformedRoot,
+        DataError::NonChild,
+        DataError::EdittedLocation,
+        DataError::CyclicMove,
+        DataError::DepthExceeded,
+        DataError::PayloadTooLarge { max: DEFAULT_MAX_MESSAGE_SIZE },
+        DataError::TooManyChildren,
+        DataError::InvalidStructure,
+        DataError::RateLimited,
+        DataError::EmptyMessage,
+        DataError::EmptyReaction,
+        internal_error!(),
+        DataError::OOBUsizeConversion,
+    ];
+    for error in errors {
+        assert!(!error.to_string().is_empty(), "Empty Display for {:?}", error);
+    }
+    let boxed: Box<dyn std::error::Error> = Box::new(DataError::StringError(String::from_utf8(vec![0xff]).unwrap_err()));
+    assert!(boxed.source().is_some(), "StringError lost its source");
+}
+
+#[test]
+fn move_cycle_detection() {
+    // root -> a -> b -> c, root -> d
+    let parents: std::collections::HashMap<EntryId, EntryId> = [(1u64, 0u64), (2, 1), (3, 2), (4, 0)].into_iter()
+        .map(|(entry_id, parent_id)| (entry_id.into(), parent_id.into()))
+        .collect();
+    let get_parent_id = |entry_id: EntryId| parents.get(&entry_id).copied().ok_or(DataError::DoesNotExist);
+
+    assert_eq!(has_ancestor(EntryId::from(3), EntryId::from(1), get_parent_id), Ok(true), "Moving a under c not a cycle");
+    assert_eq!(has_ancestor(EntryId::from(1), EntryId::from(1), get_parent_id), Ok(true), "Moving a under itself not a cycle");
+    assert_eq!(has_ancestor(EntryId::from(4), EntryId::from(1), get_parent_id), Ok(false), "Moving a under d a cycle");
+    assert_eq!(has_ancestor(EntryId::from(1), EntryId::from(3), get_parent_id), Ok(false), "Moving c under a a cycle");
+    assert_eq!(has_ancestor(EntryId::from(5), EntryId::from(1), get_parent_id), Err(DataError::DoesNotExist), "Missing entry not reported");
+}
+
+#[test]
+fn saved_path_pruning() {
+    // root -> a -> b -> c, b was moved under root
+    let parents: std::collections::HashMap<EntryId, EntryId> = [(1u64, 0u64), (2, 0), (3, 2)].into_iter()
+        .map(|(entry_id, parent_id)| (entry_id.into(), parent_id.into()))
+        .collect();
+    let get_parent_id = |entry_id: EntryId| parents.get(&entry_id).copied().ok_or(DataError::DoesNotExist);
+    let ids = |ids: &[u64]| ids.iter().copied().map(EntryId::from).collect::<Vec<_>>();
+
+    assert_eq!(valid_path_prefix(&ids(&[2, 3]), ROOT_ENTRY_ID.into(), get_parent_id), ids(&[2, 3]), "Valid path pruned");
+    assert_eq!(valid_path_prefix(&ids(&[1, 2, 3]), ROOT_ENTRY_ID.into(), get_parent_id), ids(&[1]), "Moved entry not pruned");
+    assert_eq!(valid_path_prefix(&ids(&[1, 4, 3]), ROOT_ENTRY_ID.into(), get_parent_id), ids(&[1]), "Missing entry not pruned");
+    assert_eq!(valid_path_prefix(&ids(&[3]), ROOT_ENTRY_ID.into(), get_parent_id), ids(&[]), "Non-child of the root not pruned");
+    assert_eq!(valid_path_prefix(&ids(&[]), ROOT_ENTRY_ID.into(), get_parent_id), ids(&[]), "Empty path changed");
+}
+
+#[test]
+fn large_frame_round_trip() {
+    let mut rng = rand::rng();
+    let frame = rand_bytes(&mut rng, DEFAULT_MAX_MESSAGE_SIZE..DEFAULT_MAX_MESSAGE_SIZE + 1);
+    let request_id = rng.next_u64();
+    let mut data = Vec::new();
+    write_frame(&mut data, request_id, &frame).unwrap();
+    assert_eq!(data.len(), 16 + frame.len(), "Incorrect framed size");
+
+    let mut frame_reader = FrameReader::new();
+    let decoded = frame_reader.read_frame(&mut &data[..]).unwrap().expect("Full frame not read");
+    assert_eq!(decoded, (request_id, frame), "Invalid Frame Conversion");
+}
+
+#[test]
+fn defaulted_id_set_mutation() {
+    let white_id = UserId::from(1);
+    let black_id = UserId::from(2);
+    let new_id = UserId::from(3);
+    let bases = [DefaultBase::Inherit, DefaultBase::White, DefaultBase::Black];
+    for from_base in bases {
+        for to_base in bases {
+            let mut set = DefaultedIdSet::empty_from_base(from_base);
+            set.add_whitelist(white_id);
+            set.add_blacklist(black_id);
+            set.set_base(to_base);
+            assert_eq!(set.get_default_base(), to_base, "Base not set");
+
+            let kept_whitelist = matches!(from_base, DefaultBase::Inherit | DefaultBase::Black) & matches!(to_base, DefaultBase::Inherit | DefaultBase::Black);
+            let kept_blacklist = matches!(from_base, DefaultBase::Inherit | DefaultBase::White) & matches!(to_base, DefaultBase::Inherit | DefaultBase::White);
+            let expected_white = match to_base {DefaultBase::White => Some(true), _ if kept_whitelist => Some(true), DefaultBase::Black => Some(false), DefaultBase::Inherit => None};
+            let expected_black = match to_base {DefaultBase::Black => Some(false), _ if kept_blacklist => Some(false), DefaultBase::White => Some(true), DefaultBase::Inherit => None};
+            assert_eq!(set.contains(white_id), expected_white, "Whitelist not carried over {} -> {}", from_base, to_base);
+            assert_eq!(set.contains(black_id), expected_black, "Blacklist not carried over {} -> {}", from_base, to_base);
+        }
+    }
+
+    let mut set = DefaultedIdSet::empty_from_base(DefaultBase::Inherit);
+    assert!(set.add_whitelist(new_id), "Inherit has a whitelist");
+    assert!(set.add_whitelist(new_id), "Inherit has a whitelist");
+    assert_eq!(set.id_count(), 1, "Whitelist not deduped");
+    assert!(set.add_blacklist(new_id), "Inherit has a blacklist");
+    assert_eq!(set.contains(new_id), Some(false), "Blacklisting didn't remove from the whitelist");
+    assert_eq!(set.id_count(), 1, "Id in both lists");
+    set.remove_blacklist(new_id);
+    assert_eq!(set.contains(new_id), None, "Id not removed");
+
+    assert!(!DefaultedIdSet::empty_from_base(DefaultBase::White).add_whitelist(new_id), "White has no whitelist");
+    assert!(!DefaultedIdSet::empty_from_base(DefaultBase::Black).add_blacklist(new_id), "Black has no blacklist");
+}
+
+#[test]
+fn message_compression_round_trip() {
+    let header_data = HeaderData::new(ROOT_ENTRY_ID.into(), Vec::new(), 1u64.into());
+    let compressible = "message board ".repeat(1000);
+    let incompressible = String::from("hi");
+    for message in [compressible.clone(), incompressible.clone()] {
+        let entry = Entry { header_data: header_data.clone(), entry_data: EntryData::Message { timestamp: 0, message } };
+        let data = entry.into_data().unwrap();
+        assert!(entry.size_hint() >= data.len(), "Size hint below the encoded size");
+        assert_eq!(entry, Entry::from_data(&data).unwrap(), "Invalid Entry Conversion");
+    }
+    let entry = Entry { header_data: header_data.clone(), entry_data: EntryData::Message { timestamp: 0, message: incompressible.clone() } };
+    assert_eq!(entry.size_hint(), entry.into_data().unwrap().len(), "Incorrect size hint");
+
+    let raw_size = |message: &String| {
+        let entry = Entry { header_data: header_data.clone(), entry_data: EntryData::Message { timestamp: 0, message: message.clone() } };
+        entry.into_data().unwrap().len() - header_data.size_hint() - 8 - 1 - 4 - 4
+    };
+    #[cfg(feature = "compression")]
+    assert!(raw_size(&compressible) < compressible.len(), "Compressible message not compressed");
+    #[cfg(not(feature = "compression"))]
+    assert_eq!(raw_size(&compressible), compressible.len(), "Message compressed without the feature");
+    assert_eq!(raw_size(&incompressible), incompressible.len(), "Compression grew the message");
+
+    // only sent compressed to clients which ask for it, and never in requests
+    let compressible_entry = Entry { header_data: header_data.clone(), entry_data: EntryData::Message { timestamp: 0, message: compressible.clone() } };
+    let response = BoardResponse::GetEntry { entry: compressible_entry.clone(), view_count: 0 };
+    let mut crypto_rng = get_crypto_rng();
+    let (_, ek) = get_kem_set(&mut crypto_rng);
+    let mut keys = PublicKeySet::new(Some(ek), Some(UserAeadKey::new_random(&mut crypto_rng)));
+    let raw = response.secure_into_data(&mut crypto_rng, ReEncryptionData::Exposed, false, |_| None::<&mut UserAeadKey>).unwrap();
+    assert!(raw.len() > compressible.len(), "Response compressed without being asked");
+    let compressed = response.secure_into_data(&mut crypto_rng, ReEncryptionData::Exposed, true, |_| None::<&mut UserAeadKey>).unwrap();
+    #[cfg(feature = "compression")]
+    assert!(compressed.len() < compressible.len(), "Response not compressed when asked");
+    assert_eq!(BoardResponse::secure_from_data(&compressed, &mut keys).unwrap(), response, "Invalid compressed response Conversion");
+    let request = BoardRequest::AddEntry { user_id: 1u64.into(), entry: compressible_entry };
+    assert!(request.into_data().unwrap().len() > compressible.len(), "Request compressed");
+    assert!(request.secure_into_data(&mut crypto_rng, &mut keys).unwrap().len() > compressible.len(), "Secure request compressed");
+
+    // version 0 entries have no body encoding or stored size
+    let mut data = Vec::new();
+    header_data.extend_data(MESSAGE, &mut data).unwrap();
+    data[2] = 0x00;
+    data.truncate(data.len() - 10); // nor a last activity, pinned or deleted flag
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&(incompressible.len() as u32).to_le_bytes());
+    data.extend_from_slice(incompressible.as_bytes());
+    let entry = Entry::from_data(&data).unwrap();
+    assert_eq!(entry.entry_data, EntryData::Message { timestamp: 0, message: incompressible }, "Version 0 entry misread");
+}
+
+#[test]
+fn rate_limiter_burst() {
+    let start = std::time::Instant::now();
+    let mut rate_limiter = RateLimiter::new(2.0, 5.0, start);
+    let accepted = (0..8).filter(|_| rate_limiter.try_acquire(start)).count();
+    assert_eq!(accepted, 5, "Burst not limited");
+    assert!(rate_limiter.try_acquire(start + std::time::Duration::from_millis(500)), "Bucket not refilled");
+    assert!(!rate_limiter.try_acquire(start + std::time::Duration::from_millis(500)), "Bucket overfilled");
+    assert_eq!((0..8).filter(|_| rate_limiter.try_acquire(start + std::time::Duration::from_secs(60))).count(), 5, "Bucket refilled past the burst");
+
+    let response = BoardResponse::Error(DataError::RateLimited);
+    assert_eq!(BoardResponse::from_data(&response.into_data().unwrap()).unwrap(), response, "Rate limited response not preserved");
+    assert_eq!(response.size_hint(), response.into_data().unwrap().len(), "Incorrect size hint");
+}
+
+#[test]
+fn children_limit() {
+    let mut header_data = HeaderData::new(ROOT_ENTRY_ID.into(), (0..MAX_CHILDREN as u64 - 1).map(EntryId::from).collect(), 1u64.into());
+    assert_eq!(header_data.add_child(EntryId::from(MAX_CHILDREN as u64)), Ok(()), "Child under the limit rejected");
+    let full = Entry { header_data: header_data.clone(), entry_data: EntryData::Message { timestamp: 0, message: String::new() } };
+    assert_eq!(Entry::from_data(&full.into_data().unwrap()).unwrap(), full, "Full entry not preserved");
+
+    assert_eq!(header_data.add_child(EntryId::from(MAX_CHILDREN as u64 + 1)), Err(DataError::TooManyChildren), "Child added to a full parent");
+    assert_eq!(header_data.children_ids.len(), MAX_CHILDREN, "Rejected child still added");
+
+    header_data.children_ids.push(EntryId::from(MAX_CHILDREN as u64 + 1));
+    let overfull = Entry { header_data, entry_data: EntryData::Message { timestamp: 0, message: String::new() } };
+    assert_eq!(overfull.into_data(), Err(DataError::TooManyChildren), "Overfull entry encoded");
+}
+
+#[test]
+fn atomic_writes() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("message_board_atomic_test_{:016X}", rand::rng().next_u64()));
+    std::fs::create_dir(&dir).unwrap();
+    let mut path = dir.clone();
+    path.push("file");
+
+    assert_eq!(utils::overwrite_atomic(&path, b"old").unwrap_err().kind(), std::io::ErrorKind::NotFound, "Overwrote a missing file");
+    utils::write_new_atomic(&path, b"old").unwrap();
+    assert_eq!(utils::write_new_atomic(&path, b"new").unwrap_err().kind(), std::io::ErrorKind::AlreadyExists, "Wrote over an existing file");
+    assert_eq!(std::fs::read(&path).unwrap(), b"old", "Failed write changed the file");
+    utils::overwrite_atomic(&path, b"new").unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"new", "File not overwritten");
+
+    let leftovers: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|x| x.unwrap().file_name()).filter(|name| name != "file").collect();
+    assert!(leftovers.is_empty(), "Temp files left behind: {:?}", leftovers);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn outbox_flush() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("message_board_outbox_test_{:016X}", rand::rng().next_u64()));
+    std::fs::create_dir(&dir).unwrap();
+    let mut path = dir.clone();
+    path.push("outbox");
+
+    let message = |timestamp: u64| Entry {
+        header_data: HeaderData::new(ROOT_ENTRY_ID.into(), Vec::new(), 1u64.into()),
+        entry_data: EntryData::Message { timestamp, message: format!("draft {}", timestamp) },
+    };
+    let mut outbox = outbox::Outbox::load(&path).unwrap();
+    assert!(outbox.is_empty(), "Missing outbox not empty");
+    for timestamp in 0..4 {
+        outbox.push(message(timestamp)).unwrap();
+    }
+    let mut outbox = outbox::Outbox::load(&path).unwrap();
+    assert_eq!(outbox.len(), 4, "Outbox not persisted");
+
+    // 0 is sent, 1 is rejected, the board is unreachable by 2
+    let mut sent = Vec::new();
+    let rejections = outbox.flush(|entry| {
+        let EntryData::Message { timestamp, .. } = entry.entry_data else {panic!("Only messages were queued")};
+        sent.push(timestamp);
+        match timestamp {
+            0 => outbox::Delivery::Sent,
+            1 => outbox::Delivery::Rejected(DataError::InsufficientPerms),
+            _ => outbox::Delivery::Unreachable,
+        }
+    }).unwrap();
+    assert_eq!(sent, vec![0, 1, 2], "Entries not sent in order / sent after the board was unreachable");
+    assert_eq!(rejections, vec![DataError::InsufficientPerms], "Rejection not reported");
+    let mut outbox = outbox::Outbox::load(&path).unwrap();
+    assert_eq!(outbox.len(), 2, "Undelivered entries not kept");
+
+    let rejections = outbox.flush(|_| outbox::Delivery::Sent).unwrap();
+    assert!(rejections.is_empty(), "Rejections without any rejected");
+    assert!(!path.exists(), "Empty outbox left on disk");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn early_invalid_utf8() {
+    let header_data = HeaderData::new(ROOT_ENTRY_ID.into(), Vec::new(), 1u64.into());
+    let mut data = Vec::new();
+    header_data.extend_data(MESSAGE, &mut data).unwrap();
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(RAW_BODY);
+    let declared_size = 1u32 << 30;
+    data.extend_from_slice(&declared_size.to_le_bytes());
+    data.extend_from_slice(&declared_size.to_le_bytes());
+    data.extend_from_slice(b"ok\xff");
+
+    // a declared gigabyte of zeros, of which only the first chunk should be read
+    let mut bytes_read = 0;
+    let mut data_iter = data.iter().copied().chain(std::iter::repeat(0u8).take(declared_size as usize)).inspect(|_| bytes_read += 1);
+    let result = Entry::from_data_iter(&mut data_iter);
+    assert!(matches!(result, Err(DataError::StringError(_))), "Invalid UTF-8 accepted: {:?}", result.map(|_| ()));
+    assert!(bytes_read < data.len() + (1 << 16), "Read {} bytes before failing", bytes_read);
+
+    // code points split between chunks are still valid
+    let message = "é".repeat(10000) + "a" + &"é".repeat(10000);
+    let entry = Entry { header_data, entry_data: EntryData::Message { timestamp: 0, message } };
+    assert_eq!(Entry::from_data(&entry.into_data().unwrap()).unwrap(), entry, "Invalid Entry Conversion");
+}
+
+#[test]
+fn entry_index_maintenance() {
+    let access_group = |parent_id: u64, children_ids: &[u64], name: &str| Entry {
+        header_data: HeaderData::new(parent_id.into(), children_ids.iter().copied().map(EntryId::from).collect(), 1u64.into()),
+        entry_data: EntryData::AccessGroup { name: name.to_string(), write_perms: DefaultedIdSet::empty_from_base(DefaultBase::Inherit), read_perms: DefaultedIdSet::empty_from_base(DefaultBase::Inherit) },
+    };
+    let message = |parent_id: u64| Entry {
+        header_data: HeaderData::new(parent_id.into(), Vec::new(), 1u64.into()),
+        entry_data: EntryData::Message { timestamp: 0, message: String::from("hi") },
+    };
+
+    // root -> group -> message, written the way the server does: the entry, then its parent
+    let mut index = EntryIndex::new();
+    index.insert(ROOT_ENTRY_ID.into(), &access_group(ROOT_ENTRY_ID, &[], "Root"));
+    index.insert(1u64.into(), &access_group(ROOT_ENTRY_ID, &[], "group"));
+    index.insert(ROOT_ENTRY_ID.into(), &access_group(ROOT_ENTRY_ID, &[1], "Root"));
+    index.insert(2u64.into(), &message(1));
+    index.insert(1u64.into(), &access_group(ROOT_ENTRY_ID, &[2], "group"));
+
+    assert_eq!(index.len(), 3, "Entries missing from the index");
+    assert_eq!(index.children_ids(ROOT_ENTRY_ID.into()), Ok(&[EntryId::from(1u64)] as &[_]), "Root's children not indexed");
+    assert_eq!(index.parent_id(2u64.into()), Ok(EntryId::from(1u64)), "Parent not indexed");
+    assert_eq!(index.get(1u64.into()).unwrap().name.as_deref(), Some("group"), "Name not indexed");
+    assert_eq!(index.get(2u64.into()).unwrap().entry_type, MESSAGE, "Type not indexed");
+    assert_eq!(index.descendants(ROOT_ENTRY_ID.into()), vec![EntryId::from(1u64), EntryId::from(2u64)], "Incorrect descendants");
+
+    let removed = index.remove(2u64.into());
+    assert_eq!(removed.map(|x| x.parent_id), Some(EntryId::from(1u64)), "Removed entry not given back");
+    assert_eq!(index.children_ids(1u64.into()), Ok(&[] as &[EntryId]), "Removed entry still a child");
+    assert_eq!(index.parent_id(2u64.into()), Err(DataError::DoesNotExist), "Removed entry still indexed");
+    assert_eq!(index.descendants(ROOT_ENTRY_ID.into()), vec![EntryId::from(1u64)], "Removed entry still a descendant");
+}
+
+#[test]
+fn access_resolution() {
+    let entry = |parent_id: u64, entry_data: EntryData| Entry {
+        header_data: HeaderData::new(parent_id.into(), Vec::new(), 1u64.into()),
+        entry_data,
+    };
+    // root -> group (1) -> message (2), stored encoded like the server's entry files
+    let entries: std::collections::HashMap<EntryId, Vec<u8>> = [
+        (ROOT_ENTRY_ID, entry(ROOT_ENTRY_ID, EntryData::AccessGroup { 
+            name: String::from("Root"), 
+            write_perms: DefaultedIdSet::Black { whitelist_ids: vec![1u64.into()] }, 
+            read_perms: DefaultedIdSet::White { blacklist_ids: Vec::new() },
+        })),
+        (1, entry(ROOT_ENTRY_ID, EntryData::AccessGroup { 
+            name: String::from("group"), 
+            write_perms: DefaultedIdSet::Inherit { whitelist_ids: vec![3u64.into()], blacklist_ids: vec![1u64.into()] }, 
+            read_perms: DefaultedIdSet::Black { whitelist_ids: vec![2u64.into()] },
+        })),
+        (2, entry(1, EntryData::Message { timestamp: 0, message: String::from("hi") })),
+    ].into_iter().map(|(entry_id, entry)| (entry_id.into(), entry.into_data().unwrap())).collect();
+    let get_entry = |entry_id: EntryId| -> Result<(EntryId, Option<EntryData>), DataError> {
+        let entry = Entry::from_data(entries.get(&entry_id).ok_or(DataError::DoesNotExist)?)?;
+        let is_access_group = entry.entry_data.get_discriminant() == ACCESS_GROUP;
+        Ok((entry.header_data.parent_id, is_access_group.then_some(entry.entry_data)))
+    };
+    let resolve = |user_id: u64, entry_id: u64, perm_kind| resolve_access(user_id.into(), entry_id.into(), perm_kind, false, DEFAULT_MAX_TREE_DEPTH, get_entry);
+
+    assert_eq!(resolve(2, 2, PermKind::Read), Ok(true), "Whitelisted reader denied");
+    assert_eq!(resolve(3, 2, PermKind::Read), Ok(false), "Black base let an unlisted reader through");
+    assert_eq!(resolve(3, 2, PermKind::Write), Ok(true), "Whitelisted writer denied");
+    assert_eq!(resolve(1, 2, PermKind::Write), Ok(false), "Closer blacklist didn't override the root's whitelist");
+    assert_eq!(resolve(4, 2, PermKind::Write), Ok(false), "Inherit didn't fall through to the root's base");
+    assert_eq!(resolve(1, ROOT_ENTRY_ID, PermKind::Write), Ok(true), "Root whitelist ignored");
+    assert_eq!(resolve(1, 5, PermKind::Read), Err(DataError::DoesNotExist), "Missing entry resolved");
+}
+
+#[test]
+fn response_encapsulation() {
+    assert_eq!(BoardResponse::encapsulate_error(Ok(BoardResponse::EditEntry)), BoardResponse::EditEntry, "Ok response changed");
+    let rate_limited = BoardResponse::encapsulate_error(Err(DataError::RateLimited));
+    assert_eq!(rate_limited, BoardResponse::Error(DataError::RateLimited), "Err not turned into an Error response");
+    assert_eq!(BoardResponse::from_data(&rate_limited.into_data().unwrap()).unwrap(), rate_limited, "Distinguished error lost on the wire");
+
+    // errors without their own code still arrive as errors
+    let does_not_exist = BoardResponse::encapsulate_error(Err(DataError::DoesNotExist));
+    let decoded = BoardResponse::from_data(&does_not_exist.into_data().unwrap()).unwrap();
+    assert!(matches!(decoded, BoardResponse::Error(_)), "Undistinguished error decoded as {:?}", decoded);
+}
+
+#[test]
+fn line_windowing() {
+    assert_eq!(utils::line_window(100, 0, 10), 0..10, "Top window wrong");
+    assert_eq!(utils::line_window(100, 42, 10), 42..52, "Middle window wrong");
+    assert_eq!(utils::line_window(100, 95, 10), 90..100, "Window ran past the last line");
+    assert_eq!(utils::line_window(100, usize::MAX, 10), 90..100, "Scrolling to the end wasn't clamped");
+    assert_eq!(utils::line_window(5, 3, 10), 0..5, "Short text scrolled");
+    assert_eq!(utils::line_window(0, 7, 10), 0..0, "Empty text gave lines");
+    assert_eq!(utils::line_window(100, 42, 0), 42..42, "Zero height window gave lines");
+}
+
+#[test]
+fn cursor_following() {
+    assert_eq!(utils::follow_cursor(20, 25, 10), 20, "Visible cursor scrolled");
+    assert_eq!(utils::follow_cursor(20, 0, 10), 0, "Jump to the first line not followed");
+    assert_eq!(utils::follow_cursor(0, 99, 10), 90, "Jump to the last line not followed");
+    assert_eq!(utils::follow_cursor(0, 10, 10), 1, "Cursor just below the window not followed");
+    assert_eq!(utils::follow_cursor(20, 19, 10), 19, "Cursor just above the window not followed");
+    assert_eq!(utils::follow_cursor(20, 99, 0), 20, "Zero height window scrolled");
+    assert_eq!(utils::line_window(100, utils::follow_cursor(0, 99, 10), 10), 90..100, "Last line not in the window");
+}
+
+#[test]
+fn line_wrapping() {
+    let rows = |text: &str, width: usize| utils::wrap_lines(text, width).into_iter().map(|row| text[row].to_string()).collect::<Vec<_>>();
+    assert_eq!(rows("short", 10), ["short"], "Short line wrapped");
+    assert_eq!(rows("one two three", 8), ["one two ", "three"], "Didn't break after a space");
+    assert_eq!(rows("abcd efgh", 4), ["abcd ", "efgh"], "Space at the break started the next row");
+    assert_eq!(rows("abcdefghij", 4), ["abcd", "efgh", "ij"], "Long word not broken");
+    assert_eq!(rows("a\r\n\nb\n", 4), ["a", "", "b"], "Line endings mishandled");
+    assert_eq!(rows("ééééé", 2), ["éé", "éé", "é"], "Wrapped by bytes instead of chars");
+    assert!(utils::wrap_lines("", 4).is_empty(), "Empty text gave rows");
+}
+
+#[test]
+fn ping_data_conversion() {
+    let mut crypto_rng = get_crypto_rng();
+    let (kem_dk, kem_ek) = get_kem_set(&mut crypto_rng);
+    let mut user_key = PublicKeySet::new(Some(kem_ek), None);
+
+    assert_eq!(BoardRequest::from_data(&BoardRequest::Ping.into_data().unwrap()).unwrap(), BoardRequest::Ping, "Invalid Ping Conversion");
+    assert_eq!(BoardResponse::from_data(&BoardResponse::Pong.into_data().unwrap()).unwrap(), BoardResponse::Pong, "Invalid Pong Conversion");
+
+    // pings need no user, so they go exposed
+    let encoded = BoardRequest::Ping.secure_into_data(&mut crypto_rng, &mut user_key).unwrap();
+    let (re_encryptor, decoded) = BoardRequest::secure_from_data(&kem_dk, |_| None::<&mut UserAeadKey>, &encoded).unwrap();
+    assert_eq!(decoded, BoardRequest::Ping, "Invalid secure Ping Conversion");
+    let encoded = BoardResponse::Pong.secure_into_data(&mut crypto_rng, re_encryptor, false, |_| None::<&mut UserAeadKey>).unwrap();
+    assert_eq!(BoardResponse::secure_from_data(&encoded, &mut user_key).unwrap(), BoardResponse::Pong, "Invalid secure Pong Conversion");
+}
+
+#[test]
+fn forged_user_request_rejection() {
+    let mut rng = rand::rng();
+    let mut crypto_rng = get_crypto_rng();
+
+    let user_id: UserId = rng.random::<u64>().into();
+    let mut server_aead_key = UserAeadKey::new_random(&mut crypto_rng);
+    let (kem_dk, kem_ek) = get_kem_set(&mut crypto_rng);
+    let mut user_key = PublicKeySet::new(Some(kem_ek.clone()), Some(server_aead_key.clone()));
+    let mut forger_key = PublicKeySet::new(Some(kem_ek), Some(UserAeadKey::new_random(&mut crypto_rng)));
+
+    let request = BoardRequest::GetEntry { user_id, entry_id: rng.next_u64().into() };
+    let genuine = request.secure_into_data(&mut crypto_rng, &mut user_key).unwrap();
+    let (_, decoded) = BoardRequest::secure_from_data(&kem_dk, |x| if x == user_id {Some(&mut server_aead_key)} else {None}, &genuine).unwrap();
+    assert_eq!(request, decoded, "Genuine request not decoded");
+
+    let forged = request.secure_into_data(&mut crypto_rng, &mut forger_key).unwrap();
+    let result = BoardRequest::secure_from_data(&kem_dk, |x| if x == user_id {Some(&mut server_aead_key)} else {None}, &forged);
+    assert_eq!(result.err(), Some(DataError::BadCredentials), "Forged request not rejected");
+
+    assert_eq!(DataError::from_error_code(DataError::BadCredentials.error_code()), DataError::BadCredentials, "BadCredentials lost on the wire");
+}
+
+#[test]
+fn y_n_prompt_reading() {
+    let mut buffer = String::new();
+    assert!(utils::read_y_n(&mut std::io::Cursor::new("Y\n"), &mut buffer), "Y not read as yes");
+    assert!(!utils::read_y_n(&mut std::io::Cursor::new(" n \n"), &mut buffer), "n not read as no");
+    assert!(utils::read_y_n(&mut std::io::Cursor::new("maybe\n\ny\n"), &mut buffer), "Invalid answers not skipped");
+    assert!(!utils::read_y_n(&mut std::io::Cursor::new("maybe\n"), &mut buffer), "Closed input not read as no");
+    assert!(!utils::read_y_n(&mut std::io::Cursor::new(""), &mut buffer), "Empty input not read as no");
+}
+
+#[test]
+fn default_root_bootstrap() {
+    let root = Entry::default_root();
+    assert_eq!(root.validate_root(), Ok(()), "Default root is malformed");
+    let decoded = Entry::from_data(&root.into_data().unwrap()).unwrap();
+    assert_eq!(decoded, root, "Default root changed on disk");
+
+    // a blank board with only the root is navigable by anyone
+    let get_entry = |entry_id: EntryId| -> Result<(EntryId, Option<EntryData>), DataError> {
+        if *entry_id != ROOT_ENTRY_ID {return Err(DataError::DoesNotExist)}
+        Ok((decoded.header_data.parent_id, Some(decoded.entry_data.clone())))
+    };
+    for user_id in [3u64, 42, u64::MAX] {
+        assert_eq!(resolve_access(user_id.into(), ROOT_ENTRY_ID.into(), PermKind::Read, false, DEFAULT_MAX_TREE_DEPTH, get_entry), Ok(true), "Default root not readable");
+        assert_eq!(resolve_access(user_id.into(), ROOT_ENTRY_ID.into(), PermKind::Write, false, DEFAULT_MAX_TREE_DEPTH, get_entry), Ok(true), "Default root not writable");
+    }
+
+    let mut not_self_parented = Entry::default_root();
+    not_self_parented.header_data.parent_id = 1u64.into();
+    assert_eq!(not_self_parented.validate_root(), Err(DataError::MalformedRoot), "Root with a parent accepted");
+    let message_root = Entry { header_data: HeaderData::new(ROOT_ENTRY_ID.into(), Vec::new(), 1u64.into()), entry_data: EntryData::Message { timestamp: 0, message: String::from("hi") } };
+    assert_eq!(message_root.validate_root(), Err(DataError::MalformedRoot), "Message root accepted");
+}
+
+#[test]
+fn checked_usize_conversion() {
+    assert_eq!(checked_usize(u16::MAX), Ok(u16::MAX as usize), "u16 didn't fit");
+    assert_eq!(checked_usize(u64::MAX as u128 + 1), Err(DataError::OOBUsizeConversion), "Length past any usize accepted");
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(checked_usize(u64::MAX), Ok(usize::MAX), "u64 didn't fit a 64 bit usize");
+    #[cfg(not(target_pointer_width = "64"))]
+    assert_eq!(checked_usize(u64::MAX), Err(DataError::OOBUsizeConversion), "u64 fit a narrower usize");
+}
+
+#[test]
+fn huge_declared_length_rejection() {
+    // overwrites the u32 length which precedes `ids` at the end of `data`
+    fn declare_huge_len(mut data: Vec<u8>, ids: usize) -> Vec<u8> {
+        let len_start = data.len() - ids * 8 - 4;
+        data[len_start..len_start + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        data
+    }
+    let ids = vec![UserId::from(1u64), 2u64.into()];
+
+    let id_set = DefaultedIdSet::White { blacklist_ids: ids.clone() };
+    let data = declare_huge_len(id_set.into_data().unwrap(), ids.len());
+    assert_eq!(DefaultedIdSet::from_data(&data), Err(DataError::InsufficientBytes), "Huge id set length not rejected");
+
+    let user = UserData { aead: UserAeadKey::new_random(get_crypto_rng()), entry_ids: vec![1u64.into(), 2u64.into()], display_name: None };
+    // without the (empty) display name after the ids
+    let data = user.into_data().unwrap();
+    let data = declare_huge_len(data[..data.len() - 1].to_vec(), user.entry_ids.len());
+    assert_eq!(UserData::from_data(&data), Err(DataError::InsufficientBytes), "Huge user entry count not rejected");
+
+    let response = BoardResponse::AddEntries(vec![1u64.into(), 2u64.into()]);
+    let data = declare_huge_len(response.into_data().unwrap(), 2);
+    assert_eq!(BoardResponse::from_data(&data), Err(DataError::InsufficientBytes), "Huge response id count not rejected");
+}
+
+#[test]
+fn entry_construction() {
+    let message = Entry::new_message(5u64.into(), 7u64.into(), 1234, String::from("hi"));
+    assert_eq!(message.header_data, HeaderData { version: ENTRY_FILE_VERSION, parent_id: 5u64.into(), children_ids: Vec::new(), author_id: 7u64.into(), last_activity: 0, pinned: false, deleted: false }, "Incorrect message header");
+    assert_eq!(message.entry_data, EntryData::Message { timestamp: 1234, message: String::from("hi") }, "Incorrect message data");
+
+    let write_perms = DefaultedIdSet::Black { whitelist_ids: vec![7u64.into()] };
+    let read_perms = DefaultedIdSet::empty_from_base(DefaultBase::Inherit);
+    let group = Entry::new_access_group(5u64.into(), 7u64.into(), String::from("group"), write_perms.clone(), read_perms.clone());
+    assert_eq!(group.header_data, HeaderData::new(5u64.into(), Vec::new(), 7u64.into()), "Incorrect access group header");
+    assert_eq!(group.entry_data, EntryData::AccessGroup { name: String::from("group"), write_perms, read_perms }, "Incorrect access group data");
+
+    for entry in [message, group] {
+        assert_eq!(Entry::from_data(&entry.into_data().unwrap()).unwrap(), entry, "Constructed entry didn't round trip");
+    }
+}
+
+#[test]
+fn counted_data_conversion() {
+    let mut rng = rand::rng();
+    let mut char_rng = get_char_rng(rng.clone());
+    for _ in 0..RANDOM_TEST_RETRIES {
+        let first = rand_entry(&mut rng, &mut char_rng);
+        let second = rand_entry(&mut rng, &mut char_rng);
+        let mut data = first.into_data().unwrap();
+        let first_len = data.len();
+        second.extend_data(&mut data).unwrap();
+
+        let (decoded, read) = Entry::from_data_counted(&data).unwrap();
+        assert_eq!((decoded, read), (first, first_len), "Incorrect first entry or count");
+        let (decoded, read) = Entry::from_data_counted(&data[read..]).unwrap();
+        assert_eq!((decoded, read), (second, data.len() - first_len), "Incorrect second entry or count");
+    }
+
+    let user = UserData { aead: UserAeadKey::new_random(get_crypto_rng()), entry_ids: vec![1u64.into()], display_name: None };
+    let mut data = user.into_data().unwrap();
+    let user_len = data.len();
+    data.push(0xff);
+    assert_eq!(UserData::from_data_counted(&data).unwrap().1, user_len, "Trailing byte counted");
+}
+
+#[test]
+fn new_entry_validation() {
+    let author_id = UserId::from(7u64);
+    let entry = Entry::new_message(1u64.into(), author_id, 0, String::from("hi"));
+    assert_eq!(entry.validate_new(author_id), Ok(()), "Valid new entry rejected");
+    assert_eq!(entry.validate_new(8u64.into()), Err(DataError::InsufficientPerms), "Spoofed author accepted");
+
+    let mut with_children = entry.clone();
+    with_children.header_data.add_child(2u64.into()).unwrap();
+    assert_eq!(with_children.validate_new(author_id), Err(DataError::NonChild), "New entry with children accepted");
+}
+
+#[test]
+fn ancestor_path_reconstruction() {
+    // root -> 1 -> 2 -> 3, root -> 4, 5 <-> 6
+    let parents: std::collections::HashMap<EntryId, EntryId> = [(1u64, 0u64), (2, 1), (3, 2), (4, 0), (5, 6), (6, 5)].into_iter()
+        .map(|(entry_id, parent_id)| (entry_id.into(), parent_id.into())).collect();
+    let get_parent_id = |entry_id: EntryId| parents.get(&entry_id).copied().ok_or(DataError::DoesNotExist);
+    let path = |entry_id: u64| ancestor_path(entry_id.into(), DEFAULT_MAX_TREE_DEPTH, get_parent_id);
+
+    assert_eq!(path(3), Ok(vec![1u64.into(), 2u64.into(), 3u64.into()]), "Incorrect deep path");
+    assert_eq!(path(4), Ok(vec![4u64.into()]), "Incorrect shallow path");
+    assert_eq!(path(ROOT_ENTRY_ID), Ok(Vec::new()), "Root has a path");
+    assert_eq!(path(7), Err(DataError::DoesNotExist), "Missing entry has a path");
+    assert_eq!(path(5), Err(DataError::MalformedRoot), "Cycle not detected");
+}
+
+#[test]
+fn frame_reader_size_limit() {
+    let mut data = Vec::new();
+    write_frame(&mut data, 1, &[0u8; 1024]).unwrap();
+    let mut frame_reader = FrameReader::with_max_frame_size(1024);
+    assert_eq!(frame_reader.read_frame(&mut &data[..]).unwrap(), Some((1, vec![0u8; 1024])), "Frame at the limit refused");
+
+    // only the prefix of a huge frame is ever sent
+    let mut oversized = Vec::new();
+    oversized.extend_from_slice(&(1u64 << 40).to_le_bytes());
+    oversized.extend_from_slice(&2u64.to_le_bytes());
+    oversized.extend_from_slice(&[0u8; 16]);
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let mut sender = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut receiver, _) = listener.accept().unwrap();
+    sender.write_all(&oversized).unwrap();
+    let mut frame_reader = FrameReader::with_max_frame_size(1024);
+    let error = frame_reader.read_frame(&mut receiver).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "Oversized frame not refused");
+}
+
+#[test]
+fn id_set_iteration() {
+    let ids = |ids: &[u64]| ids.iter().map(|x| UserId::from(*x)).collect::<Vec<_>>();
+    let inherit = DefaultedIdSet::Inherit { whitelist_ids: ids(&[1, 2]), blacklist_ids: ids(&[3]) };
+    assert_eq!(inherit.iter_whitelist().copied().collect::<Vec<_>>(), ids(&[1, 2]), "Incorrect Inherit whitelist");
+    assert_eq!(inherit.iter_blacklist().copied().collect::<Vec<_>>(), ids(&[3]), "Incorrect Inherit blacklist");
+    assert_eq!((inherit.len_whitelist(), inherit.len_blacklist()), (2, 1), "Incorrect Inherit lengths");
+
+    let white = DefaultedIdSet::White { blacklist_ids: ids(&[4]) };
+    assert_eq!(white.iter_whitelist().count(), 0, "White base has a whitelist");
+    assert_eq!(white.iter_blacklist().copied().collect::<Vec<_>>(), ids(&[4]), "Incorrect White blacklist");
+    assert_eq!((white.len_whitelist(), white.len_blacklist()), (0, 1), "Incorrect White lengths");
+
+    let black = DefaultedIdSet::Black { whitelist_ids: ids(&[5, 6, 7]) };
+    assert_eq!(black.iter_whitelist().copied().collect::<Vec<_>>(), ids(&[5, 6, 7]), "Incorrect Black whitelist");
+    assert_eq!(black.iter_blacklist().count(), 0, "Black base has a blacklist");
+    assert_eq!((black.len_whitelist(), black.len_blacklist()), (3, 0), "Incorrect Black lengths");
+
+    for id_set in [inherit, white, black] {
+        assert_eq!(id_set.len_whitelist() + id_set.len_blacklist(), id_set.id_count(), "Lengths disagree with id_count");
+    }
+}
+
+#[test]
+fn child_ordering() {
+    use std::cmp::Ordering;
+    use utils::ChildOrder;
+    assert_eq!(ChildOrder::OldestFirst.compare(Some(1), Some(2)), Ordering::Less, "Older message not first");
+    assert_eq!(ChildOrder::NewestFirst.compare(Some(1), Some(2)), Ordering::Greater, "Newer message not first");
+    assert_eq!(ChildOrder::NewestFirst.compare(None, Some(2)), Ordering::Less, "Non message not before messages");
+    assert_eq!(ChildOrder::OldestFirst.compare(Some(2), None), Ordering::Greater, "Message not after non messages");
+    assert_eq!(ChildOrder::Inserted.compare(Some(2), Some(1)), Ordering::Equal, "Inserted order compared timestamps");
+
+    // (id, timestamp), None for access groups
+    let children = [(1, Some(30)), (2, None), (3, Some(10)), (4, Some(30)), (5, None), (6, Some(20))];
+    let sorted = |order: ChildOrder| {
+        let mut children = children.to_vec();
+        order.sort(&mut children, |child| child.1);
+        children.into_iter().map(|child| child.0).collect::<Vec<_>>()
+    };
+    assert_eq!(sorted(ChildOrder::Inserted), vec![1, 2, 3, 4, 5, 6], "Inserted order changed");
+    assert_eq!(sorted(ChildOrder::OldestFirst), vec![2, 5, 3, 6, 1, 4], "Incorrect oldest first order");
+    assert_eq!(sorted(ChildOrder::NewestFirst), vec![2, 5, 1, 4, 6, 3], "Incorrect newest first order");
+
+    let mut order = ChildOrder::default();
+    for _ in 0..3 {order = order.next()}
+    assert_eq!(order, ChildOrder::default(), "Orders don't cycle");
+}
+
+#[test]
+fn activity_propagation() {
+    // root <- group (1) <- thread (2) <- reply (3), plus an unrelated message (4) under the root
+    let mut entries = std::collections::HashMap::new();
+    entries.insert(EntryId::from(ROOT_ENTRY_ID), Entry::default_root());
+    let group = Entry::new_access_group(ROOT_ENTRY_ID.into(), 0u64.into(), String::from("group"), DefaultedIdSet::Inherit { whitelist_ids: Vec::new(), blacklist_ids: Vec::new() }, DefaultedIdSet::Inherit { whitelist_ids: Vec::new(), blacklist_ids: Vec::new() });
+    entries.insert(EntryId::from(1), group);
+    entries.insert(EntryId::from(2), Entry::new_message(1u64.into(), 0u64.into(), 10, String::from("thread")));
+    entries.insert(EntryId::from(3), Entry::new_message(2u64.into(), 0u64.into(), 20, String::from("reply")));
+    entries.insert(EntryId::from(4), Entry::new_message(ROOT_ENTRY_ID.into(), 0u64.into(), 5, String::from("other")));
+
+    let entries = std::cell::RefCell::new(entries);
+    let propagate = |entry_id: u64, timestamp: u64| {
+        propagate_activity(
+            entry_id.into(), 
+            timestamp, 
+            |entry_id| entries.borrow().get(&entry_id).cloned().ok_or(DataError::DoesNotExist), 
+            |entry_id, entry| {entries.borrow_mut().insert(entry_id, entry); Ok(())},
+        )
+    };
+    propagate(3, 100).unwrap();
+    propagate(4, 50).unwrap();
+    let last_activity = |entry_id: u64| entries.borrow()[&EntryId::from(entry_id)].header_data.last_activity;
+    for entry_id in [3, 2, 1, ROOT_ENTRY_ID] {
+        assert_eq!(last_activity(entry_id), 100, "Activity not propagated to an ancestor");
+    }
+    assert_eq!(last_activity(4), 50, "Activity not set on the entry itself");
+
+    let mut looped = Entry::new_message(6u64.into(), 0u64.into(), 0, String::new());
+    entries.borrow_mut().insert(EntryId::from(5), looped.clone());
+    looped.header_data.parent_id = 5u64.into();
+    entries.borrow_mut().insert(EntryId::from(6), looped);
+    assert_eq!(propagate(5, 200), Err(DataError::MalformedRoot), "Looping ancestors not detected");
+
+    // a version 1 file has no last activity and falls back to the message's timestamp
+    let mut data = Entry::new_message(2u64.into(), 0u64.into(), 42, String::from("old")).into_data().unwrap();
+    data[2] = 1;
+    data.drain(22..32); // nor a pinned or deleted flag
+    let old_entry = Entry::from_data(&data).unwrap();
+    assert_eq!(old_entry.header_data.last_activity, 42, "Old entry didn't default to its timestamp");
+}
+
+#[test]
+fn user_id_parsing() {
+    use utils::{parse_user_id, read_config};
+    let config = read_config(r#"
+        integer = 43981
+        wrapped = -1
+        hex = "00000000ABCD"
+        short_hex = "abcd"
+        none = "None"
+        not_hex = "0xABCD"
+        too_long = "10000000000000000"
+        empty = ""
+        boolean = true
+    "#).unwrap();
+    assert_eq!(parse_user_id(&config["integer"]), Ok(Some(0xABCD)), "Integer id misread");
+    assert_eq!(parse_user_id(&config["wrapped"]), Ok(Some(u64::MAX)), "Wrapped integer id misread");
+    assert_eq!(parse_user_id(&config["hex"]), Ok(Some(0xABCD)), "Hex id misread");
+    assert_eq!(parse_user_id(&config["short_hex"]), Ok(Some(0xABCD)), "Lowercase hex id misread");
+    assert_eq!(parse_user_id(&config["none"]), Ok(None), "\"None\" misread");
+    for key in ["not_hex", "too_long", "empty", "boolean"] {
+        assert!(parse_user_id(&config[key]).is_err(), "Accepted {} as a user id", key);
+    }
+}
+
+#[test]
+fn user_listing() {
+    let admin_id = UserId::from(1);
+    let user_ids = (0..MAX_LISTED_USERS as u64 + 10).rev().map(UserId::from).collect::<Vec<_>>();
+    assert_eq!(BoardResponse::list_users(5u64.into(), Some(admin_id), user_ids.clone(), 0u64.into()), Err(DataError::InsufficientPerms), "Non admin listed users");
+    assert_eq!(BoardResponse::list_users(admin_id, None, user_ids.clone(), 0u64.into()), Err(DataError::InsufficientPerms), "Listed users without an admin");
+
+    let Ok(BoardResponse::ListUsers { user_ids: first_page, next }) = BoardResponse::list_users(admin_id, Some(admin_id), user_ids.clone(), 0u64.into()) else {
+        panic!("Admin couldn't list users")
+    };
+    assert_eq!(first_page, (0..MAX_LISTED_USERS as u64).map(UserId::from).collect::<Vec<_>>(), "Incorrect first page");
+    assert_eq!(next, Some(UserId::from(MAX_LISTED_USERS as u64)), "Incorrect next page start");
+    let Ok(BoardResponse::ListUsers { user_ids: last_page, next }) = BoardResponse::list_users(admin_id, Some(admin_id), user_ids, next.unwrap()) else {
+        panic!("Admin couldn't list users")
+    };
+    assert_eq!(last_page.len(), 10, "Incorrect last page");
+    assert_eq!(next, None, "Last page has a next page");
+
+    let response = BoardResponse::ListUsers { user_ids: first_page, next: Some(UserId::from(7)) };
+    let data = response.into_data().unwrap();
+    assert_eq!(data.len(), response.size_hint(), "Incorrect size hint");
+    assert_eq!(BoardResponse::from_data(&data).unwrap(), response, "Invalid ListUsers Conversion");
+    let request = BoardRequest::ListUsers { admin_id, start: 3u64.into() };
+    assert_eq!(BoardRequest::from_data(&request.into_data().unwrap()).unwrap(), request, "Invalid ListUsers request Conversion");
+}
+
+#[test]
+fn default_access_fallback() {
+    let inherit = || DefaultedIdSet::Inherit { whitelist_ids: vec![1u64.into()], blacklist_ids: vec![2u64.into()] };
+    let group = |parent_id: u64| Entry::new_access_group(parent_id.into(), 1u64.into(), String::from("group"), inherit(), inherit());
+    // root -> group (1) -> group (2) -> message (3), inheriting all the way up
+    let entries: std::collections::HashMap<EntryId, Entry> = [
+        (ROOT_ENTRY_ID, group(ROOT_ENTRY_ID)),
+        (1, group(ROOT_ENTRY_ID)),
+        (2, group(1)),
+        (3, Entry::new_message(2u64.into(), 1u64.into(), 0, String::from("hi"))),
+    ].into_iter().map(|(entry_id, entry)| (entry_id.into(), entry)).collect();
+    let get_entry = |entry_id: EntryId| -> Result<(EntryId, Option<EntryData>), DataError> {
+        let entry = entries.get(&entry_id).ok_or(DataError::DoesNotExist)?;
+        let is_access_group = entry.entry_data.get_discriminant() == ACCESS_GROUP;
+        Ok((entry.header_data.parent_id, is_access_group.then(|| entry.entry_data.clone())))
+    };
+    for perm_kind in [PermKind::Read, PermKind::Write] {
+        assert_eq!(resolve_access(7u64.into(), 3u64.into(), perm_kind, false, DEFAULT_MAX_TREE_DEPTH, get_entry), Ok(false), "Unlisted user allowed by a deny default");
+        assert_eq!(resolve_access(7u64.into(), 3u64.into(), perm_kind, true, DEFAULT_MAX_TREE_DEPTH, get_entry), Ok(true), "Unlisted user denied by an allow default");
+        // listed users are decided before the default is reached
+        assert_eq!(resolve_access(1u64.into(), 3u64.into(), perm_kind, false, DEFAULT_MAX_TREE_DEPTH, get_entry), Ok(true), "Whitelisted user fell through to the default");
+        assert_eq!(resolve_access(2u64.into(), 3u64.into(), perm_kind, true, DEFAULT_MAX_TREE_DEPTH, get_entry), Ok(false), "Blacklisted user fell through to the default");
+    }
+}
+
+#[test]
+fn pinned_flag() {
+    let mut entry = Entry::new_message(1u64.into(), 2u64.into(), 3, String::from("announcement"));
+    assert!(!entry.header_data.pinned, "New entry pinned");
+    assert_eq!(entry.validate_new(2u64.into()), Ok(()), "Unpinned entry rejected");
+    entry.header_data.pinned = true;
+    assert_eq!(entry.validate_new(2u64.into()), Err(DataError::InsufficientPerms), "New entry pinned itself");
+    let data = entry.into_data().unwrap();
+    assert_eq!(data.len(), entry.size_hint(), "Incorrect size hint");
+    assert_eq!(Entry::from_data(&data).unwrap(), entry, "Pinned flag lost");
+
+    // a version 2 file has no flag and isn't pinned
+    let mut old_data = data.clone();
+    old_data[2] = 2;
+    old_data.drain(30..32);
+    let old_entry = Entry::from_data(&old_data).unwrap();
+    assert!(!old_entry.header_data.pinned, "Old entry pinned");
+    assert_eq!(old_entry.entry_data, entry.entry_data, "Old entry misread");
+
+    let mut bad_flag = data;
+    bad_flag[30] = 2;
+    assert_eq!(Entry::from_data(&bad_flag), Err(DataError::InvalidDiscriminant), "Bad pinned flag accepted");
+
+    let request = BoardRequest::SetPinned { user_id: 2u64.into(), entry_id: 1u64.into(), pinned: true };
+    assert_eq!(BoardRequest::from_data(&request.into_data().unwrap()).unwrap(), request, "Invalid SetPinned Conversion");
+}
+
+#[test]
+fn reactions() {
+    let mut entry = Entry::new_reaction(1u64.into(), 2u64.into(), String::from("👍"));
+    let data = entry.into_data().unwrap();
+    assert_eq!(data.len(), entry.size_hint(), "Incorrect size hint");
+    assert_eq!(Entry::from_data(&data).unwrap(), entry, "Invalid Reaction Conversion");
+
+    // the parent always follows the target
+    entry.header_data.parent_id = 3u64.into();
+    entry.attach_to_target();
+    assert_eq!(entry.header_data.parent_id, 1u64.into(), "Reaction not attached to its target");
+
+    assert_eq!(entry.entry_data.check_size_limits(DEFAULT_MAX_MESSAGE_SIZE), Ok(()), "Short reaction rejected");
+    let long_reaction = EntryData::Reaction { emoji: "👍".repeat(MAX_REACTION_SIZE), target_id: 1u64.into() };
+    assert_eq!(long_reaction.check_size_limits(DEFAULT_MAX_MESSAGE_SIZE), Err(DataError::PayloadTooLarge { max: MAX_REACTION_SIZE }), "Long reaction accepted");
+    for emoji in ["", " ", "\t\n"] {
+        let empty_reaction = EntryData::Reaction { emoji: String::from(emoji), target_id: 1u64.into() };
+        assert_eq!(empty_reaction.check_size_limits(DEFAULT_MAX_MESSAGE_SIZE), Err(DataError::EmptyReaction), "Accepted the empty reaction {:?}", emoji);
+    }
+}
+
+#[test]
+fn reaction_counting() {
+    assert_eq!(count_reactions([]), Vec::new(), "Counted reactions from nothing");
+    let counts = count_reactions(["❤", "👍", "🎉", "👍", "❤", "👍"]);
+    assert_eq!(counts, vec![
+        (String::from("👍"), 3),
+        (String::from("❤"), 2),
+        (String::from("🎉"), 1),
+    ], "Incorrect reaction counts");
+    // ties keep the order first seen
+    let counts = count_reactions(["🎉", "❤"]);
+    assert_eq!(counts, vec![(String::from("🎉"), 1), (String::from("❤"), 1)], "Ties reordered");
+}
+
+#[test]
+fn multiple_entry_fetching() {
+    let request = BoardRequest::GetEntries { user_id: 1u64.into(), entry_ids: vec![2u64.into(), 3u64.into(), 4u64.into()] };
+    let data = request.into_data().unwrap();
+    assert_eq!(data.len(), request.size_hint(), "Incorrect request size hint");
+    assert_eq!(BoardRequest::from_data(&data).unwrap(), request, "Invalid GetEntries Conversion");
+
+    // present, missing, and unreadable ids each keep their place
+    let present = Entry::new_message(5u64.into(), 1u64.into(), 6, String::from("present"));
+    let response = BoardResponse::GetEntries(vec![Ok(present.clone()), Err(DataError::DoesNotExist), Err(DataError::InsufficientPerms), Ok(present)]);
+    let data = response.into_data().unwrap();
+    assert_eq!(data.len(), response.size_hint(), "Incorrect response size hint");
+    assert_eq!(BoardResponse::from_data(&data).unwrap(), response, "Invalid GetEntries Response Conversion");
+
+    let empty = BoardResponse::GetEntries(Vec::new());
+    assert_eq!(BoardResponse::from_data(&empty.into_data().unwrap()).unwrap(), empty, "Invalid Empty GetEntries Conversion");
+
+    let mut bad_status = data;
+    bad_status[6] = 0x01;
+    assert_eq!(BoardResponse::from_data(&bad_status), Err(DataError::InvalidDiscriminant), "Bad entry status accepted");
+}
+
+#[test]
+fn retry_backoff() {
+    let second = std::time::Duration::from_secs(1);
+    let mut backoff = RetryBackoff::new(5, second, second * 6);
+    let delays: Vec<_> = std::iter::from_fn(|| backoff.next_delay()).collect();
+    assert_eq!(delays, vec![second, second * 2, second * 4, second * 6, second * 6], "Incorrect delays");
+    assert_eq!(backoff.next_delay(), None, "Retried after giving up");
+
+    let mut no_retries = RetryBackoff::new(0, second, second);
+    assert_eq!(no_retries.next_delay(), None, "Retried without any retries");
+
+    // the first delay is capped too
+    let mut capped = RetryBackoff::new(1, second * 10, second);
+    assert_eq!(capped.next_delay(), Some(second), "First delay not capped");
+}
+
+#[test]
+fn last_seen_timestamps() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("message_board_last_seen_test_{:016X}", rand::rng().next_u64()));
+    std::fs::create_dir(&dir).unwrap();
+    let mut path = dir.clone();
+    path.push("last_seen");
+
+    let mut last_seen = last_seen::LastSeen::load(&path).unwrap();
+    assert_eq!(last_seen.get(1u64.into()), None, "Seen without a file");
+    last_seen.see(1u64.into(), 20).unwrap();
+    last_seen.see(2u64.into(), 30).unwrap();
+    // an older timestamp doesn't move it back
+    last_seen.see(1u64.into(), 10).unwrap();
+
+    let last_seen = last_seen::LastSeen::load(&path).unwrap();
+    assert_eq!(last_seen.get(1u64.into()), Some(20), "Last seen moved back / not persisted");
+    assert_eq!(last_seen.get(2u64.into()), Some(30), "Last seen not persisted");
+    assert_eq!(last_seen.get(3u64.into()), None, "Unseen entry seen");
+
+    std::fs::write(&path, [0; 12]).unwrap();
+    assert!(last_seen::LastSeen::load(&path).is_err(), "Truncated file accepted");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn children_since_conversion() {
+    let request = BoardRequest::ChildrenSince { user_id: 1u64.into(), entry_id: 2u64.into(), since_ts: 3 };
+    let data = request.into_data().unwrap();
+    assert_eq!(data.len(), request.size_hint(), "Incorrect request size hint");
+    assert_eq!(BoardRequest::from_data(&data).unwrap(), request, "Invalid ChildrenSince Conversion");
+
+    let response = BoardResponse::ChildrenSince(vec![4u64.into(), 5u64.into()]);
+    let data = response.into_data().unwrap();
+    assert_eq!(data.len(), response.size_hint(), "Incorrect response size hint");
+    assert_eq!(BoardResponse::from_data(&data).unwrap(), response, "Invalid ChildrenSince Response Conversion");
+}
+
+#[test]
+fn path_elision() {
+    // root/group1/group2/group3/current, 33 wide in full
+    let widths = [4, 6, 6, 6, 7];
+    assert_eq!(elide_path(&widths, 40), vec![0, 1, 2, 3, 4], "Elided a path which fits");
+    assert_eq!(elide_path(&widths, 33), vec![0, 1, 2, 3, 4], "Elided a path which just fits");
+    // root/…/group2/group3/current
+    assert_eq!(elide_path(&widths, 32), vec![0, 2, 3, 4], "Elided too much / too little");
+    assert_eq!(elide_path(&widths, 25), vec![0, 3, 4], "Elided too much / too little");
+    assert_eq!(elide_path(&widths, 21), vec![0, 3, 4], "Elided a segment which just fits");
+    assert_eq!(elide_path(&widths, 20), vec![0, 4], "Elided too little");
+    // the root and current are kept even when they don't fit
+    assert_eq!(elide_path(&widths, 5), vec![0, 4], "Root or current elided");
+    assert_eq!(elide_path(&[4, 20], 5), vec![0, 1], "Root or current elided");
+    assert_eq!(elide_path(&[4], 0), vec![0], "Root elided");
+    assert_eq!(elide_path(&[], 10), Vec::<usize>::new(), "Segments made up");
+}
+
+#[test]
+fn bulk_access_filtering() {
+    let entry = |parent_id: u64, entry_data: EntryData| Entry {
+        header_data: HeaderData::new(parent_id.into(), Vec::new(), 1u64.into()),
+        entry_data,
+    };
+    let message = || EntryData::Message { timestamp: 0, message: String::from("hi") };
+    // root -> open group (1) -> messages (3 - 6), root -> closed group (2) -> messages (7, 8)
+    let entries: std::collections::HashMap<EntryId, Entry> = [
+        (ROOT_ENTRY_ID, Entry::default_root()),
+        (1, entry(ROOT_ENTRY_ID, EntryData::AccessGroup {
+            name: String::from("open"),
+            write_perms: DefaultedIdSet::Black { whitelist_ids: Vec::new() },
+            read_perms: DefaultedIdSet::Inherit { whitelist_ids: Vec::new(), blacklist_ids: Vec::new() },
+        })),
+        (2, entry(ROOT_ENTRY_ID, EntryData::AccessGroup {
+            name: String::from("closed"),
+            write_perms: DefaultedIdSet::Black { whitelist_ids: Vec::new() },
+            read_perms: DefaultedIdSet::Black { whitelist_ids: vec![2u64.into()] },
+        })),
+        (3, entry(1, message())),
+        (4, entry(1, message())),
+        (5, entry(1, message())),
+        (6, entry(1, message())),
+        (7, entry(2, message())),
+        (8, entry(2, message())),
+    ].into_iter().map(|(entry_id, entry)| (entry_id.into(), entry)).collect();
+    let gotten = std::cell::RefCell::new(Vec::new());
+    let get_entry = |entry_id: EntryId| -> Result<(EntryId, Option<EntryData>), DataError> {
+        gotten.borrow_mut().push(entry_id);
+        let entry = entries.get(&entry_id).ok_or(DataError::DoesNotExist)?;
+        let is_access_group = entry.entry_data.get_discriminant() == ACCESS_GROUP;
+        Ok((entry.header_data.parent_id, is_access_group.then_some(entry.entry_data.clone())))
+    };
+    let ids = |ids: &[u64]| ids.iter().map(|id| EntryId::from(*id)).collect::<Vec<_>>();
+
+    let requested = ids(&[3, 7, 4, 9, 8, 5, 6]);
+    let accessible = filter_accessible(1u64.into(), &requested, PermKind::Read, false, DEFAULT_MAX_TREE_DEPTH, get_entry).unwrap();
+    assert_eq!(accessible, ids(&[3, 4, 5, 6]), "Incorrect accessible entries");
+    let mut gotten_ids = gotten.borrow().clone();
+    gotten_ids.sort();
+    gotten_ids.dedup();
+    assert_eq!(gotten.borrow().len(), gotten_ids.len(), "Shared ancestors walked more than once");
+    for entry_id in &requested {
+        let single = resolve_access(1u64.into(), *entry_id, PermKind::Read, false, DEFAULT_MAX_TREE_DEPTH, get_entry).unwrap_or(false);
+        assert_eq!(single, accessible.contains(entry_id), "Disagreed with resolve_access");
+    }
+
+    let accessible = filter_accessible(2u64.into(), &requested, PermKind::Read, false, DEFAULT_MAX_TREE_DEPTH, get_entry).unwrap();
+    assert_eq!(accessible, ids(&[3, 7, 4, 8, 5, 6]), "Whitelisted reader denied");
+    let accessible = filter_accessible(2u64.into(), &requested, PermKind::Write, false, DEFAULT_MAX_TREE_DEPTH, get_entry).unwrap();
+    assert!(accessible.is_empty(), "Black based write perms let users through");
+}
+
+#[test]
+fn filter_accessible_conversion() {
+    let request = BoardRequest::FilterAccessible { user_id: 1u64.into(), perm_kind: PermKind::Write, entry_ids: vec![2u64.into(), 3u64.into()] };
+    let data = request.into_data().unwrap();
+    assert_eq!(data.len(), request.size_hint(), "Incorrect request size hint");
+    assert_eq!(BoardRequest::from_data(&data).unwrap(), request, "Invalid FilterAccessible Conversion");
+
+    let mut bad_perm_kind = data;
+    bad_perm_kind[10] = 0x02;
+    assert_eq!(BoardRequest::from_data(&bad_perm_kind), Err(DataError::InvalidDiscriminant), "Bad perm kind accepted");
+}
+
+#[test]
+fn tree_preview_expansion() {
+    use tree_preview::*;
+    let ids = |ids: &[u64]| ids.iter().map(|id| EntryId::from(*id)).collect::<Vec<_>>();
+    let mut preview = TreePreview::new();
+    preview.set_children(ids(&[1, 2, 3]));
+    assert_eq!(preview.rows(), vec![(1, 1u64.into()), (1, 2u64.into()), (1, 3u64.into())], "Collapsed children shown wrong");
+    assert!(preview.to_fetch().is_empty(), "Fetching for collapsed children");
+
+    assert!(preview.toggle(2u64.into()), "Child not expandable");
+    assert!(preview.is_expanded(2u64.into()), "Child not expanded");
+    assert_eq!(preview.to_fetch(), ids(&[2]), "Expanded child not fetched");
+    preview.set_grandchildren(2u64.into(), ids(&[20, 21]));
+    assert!(preview.to_fetch().is_empty(), "Fetched child fetched again");
+    assert_eq!(preview.rows(), vec![(1, 1u64.into()), (1, 2u64.into()), (2, 20u64.into()), (2, 21u64.into()), (1, 3u64.into())], "Expanded child's children not shown under it");
+
+    // the fetch is bounded, grandchildren can't be expanded
+    assert!(!preview.toggle(20u64.into()), "Grandchild expanded");
+    preview.set_grandchildren(20u64.into(), ids(&[200]));
+    assert!(preview.rows().iter().all(|(depth, _)| *depth <= MAX_PREVIEW_DEPTH), "Preview deeper than the max");
+
+    // collapsing keeps what was fetched
+    assert!(preview.toggle(2u64.into()), "Child not collapsible");
+    assert!(!preview.is_expanded(2u64.into()), "Child not collapsed");
+    assert_eq!(preview.rows().len(), 3, "Collapsed child's children shown");
+    preview.toggle(2u64.into());
+    assert!(preview.to_fetch().is_empty(), "Re-expanded child fetched again");
+
+    // reloading keeps the children still there, navigating away drops them
+    preview.set_children(ids(&[2, 3]));
+    assert!(preview.is_expanded(2u64.into()) && preview.to_fetch().is_empty(), "Remaining child's expansion dropped");
+    preview.set_children(ids(&[4]));
+    preview.set_children(ids(&[2]));
+    assert!(!preview.is_expanded(2u64.into()), "Expansion kept after navigating away");
+    preview.toggle(2u64.into());
+    assert_eq!(preview.to_fetch(), ids(&[2]), "Fetched children kept after navigating away");
+}
+
+#[test]
+fn entry_diffing() {
+    let message = Entry::new_message(1u64.into(), 2u64.into(), 3, String::from("draft"));
+    assert!(message.diff(&message).is_empty(), "Unchanged entry diffed");
+
+    let mut edited = message.clone();
+    edited.entry_data = EntryData::Message { timestamp: 4, message: String::from("final") };
+    edited.header_data.last_activity = 5;
+    let diff = message.diff(&edited);
+    assert_eq!(diff.message, Some((String::from("draft"), String::from("final"))), "Message change missed");
+    assert_eq!(diff.timestamp, Some((3, 4)), "Timestamp change missed");
+    assert!(!diff.changes_location(), "Text edit moved the entry");
+    assert_eq!(diff, EntryDiff { message: diff.message.clone(), timestamp: Some((3, 4)), ..Default::default() }, "Unchanged fields diffed");
+
+    let mut moved = message.clone();
+    moved.header_data.parent_id = 6u64.into();
+    assert!(message.diff(&moved).changes_location(), "Move missed");
+    let mut adopted = message.clone();
+    adopted.header_data.children_ids.push(7u64.into());
+    assert!(message.diff(&adopted).changes_location(), "Children change missed");
+
+    // a different type only reports the type
+    let group = Entry::new_access_group(1u64.into(), 2u64.into(), String::from("group"), DefaultedIdSet::empty_from_base(DefaultBase::White), DefaultedIdSet::empty_from_base(DefaultBase::White));
+    let diff = message.diff(&group);
+    assert_eq!(diff.entry_type, Some((MESSAGE, ACCESS_GROUP)), "Type change missed");
+    assert_eq!(diff.message, None, "Data of different types compared");
+
+    // perms
+    let mut regrouped = group.clone();
+    let EntryData::AccessGroup { name, read_perms, write_perms } = &mut regrouped.entry_data else {panic!("Not a group")};
+    *name = String::from("renamed");
+    read_perms.add_blacklist(8u64.into());
+    write_perms.set_base(DefaultBase::Black);
+    write_perms.add_whitelist(9u64.into());
+    let diff = group.diff(&regrouped);
+    assert_eq!(diff.name, Some((String::from("group"), String::from("renamed"))), "Name change missed");
+    assert_eq!(diff.read_perms, IdSetDiff { blacklisted: vec![8u64.into()], ..Default::default() }, "Read perms diffed wrong");
+    assert_eq!(diff.write_perms, IdSetDiff { base: Some((DefaultBase::White, DefaultBase::Black)), whitelisted: vec![9u64.into()], ..Default::default() }, "Write perms diffed wrong");
+}
+
+#[test]
+fn id_set_diffing() {
+    let ids = |ids: &[u64]| ids.iter().map(|id| UserId::from(*id)).collect::<Vec<_>>();
+    let old = DefaultedIdSet::Inherit { whitelist_ids: ids(&[1, 2]), blacklist_ids: ids(&[3]) };
+    assert!(old.diff(&old).is_empty(), "Unchanged set diffed");
+
+    let new = DefaultedIdSet::Inherit { whitelist_ids: ids(&[2, 4]), blacklist_ids: ids(&[3, 1]) };
+    assert_eq!(old.diff(&new), IdSetDiff {
+        base: None,
+        whitelisted: ids(&[4]),
+        unwhitelisted: ids(&[1]),
+        blacklisted: ids(&[1]),
+        unblacklisted: Vec::new(),
+    }, "Incorrect list changes");
+    // reordering isn't a change
+    let reordered = DefaultedIdSet::Inherit { whitelist_ids: ids(&[2, 1]), blacklist_ids: ids(&[3]) };
+    assert!(old.diff(&reordered).is_empty(), "Reordering diffed");
+
+    // a list the new base doesn't have is emptied
+    let white = DefaultedIdSet::White { blacklist_ids: ids(&[3]) };
+    assert_eq!(old.diff(&white), IdSetDiff {
+        base: Some((DefaultBase::Inherit, DefaultBase::White)),
+        unwhitelisted: ids(&[1, 2]),
+        ..Default::default()
+    }, "Dropped list not emptied");
+}
+
+#[test]
+fn id_byte_conversion() {
+    let entry_id = EntryId::from(0x0123456789ABCDEF);
+    assert_eq!(EntryId::from_le_bytes(entry_id.to_le_bytes()), entry_id, "Entry id changed through its bytes");
+    assert_eq!(entry_id.to_le_bytes(), 0x0123456789ABCDEFu64.to_le_bytes(), "Entry id bytes differ from the wire format");
+    let user_id = UserId::from(0x0123456789ABCDEF);
+    assert_eq!(UserId::from_le_bytes(user_id.to_le_bytes()), user_id, "User id changed through its bytes");
+    assert_eq!(format!("{}", entry_id), "0123456789ABCDEF", "Entry id not shown in hex");
+}
+
+#[test]
+fn read_receipt_dedup() {
+    let mut receipts = ReadReceipts::default();
+    assert!(receipts.insert(5u64.into()), "First read not recorded");
+    assert!(!receipts.insert(5u64.into()), "Second read recorded");
+    assert!(receipts.insert(2u64.into()), "Other user's read not recorded");
+    assert_eq!(receipts.len(), 2, "Reads counted more than once");
+    assert!(receipts.contains(2u64.into()) && receipts.contains(5u64.into()), "Reader missing");
+
+    let data = receipts.into_data().unwrap();
+    assert_eq!(data.len(), receipts.size_hint(), "Incorrect receipts size hint");
+    assert_eq!(ReadReceipts::from_data(&data).unwrap(), receipts, "Invalid ReadReceipts Conversion");
+    // repeats in a stored file are dropped on read
+    let mut repeated = data.clone();
+    repeated.extend_from_slice(&data);
+    assert_eq!(ReadReceipts::from_data(&repeated).unwrap().len(), 2, "Repeated readers counted");
+}
+
+#[test]
+fn mark_read_conversion() {
+    let request = BoardRequest::MarkRead { user_id: 1u64.into(), entry_id: 2u64.into() };
+    let data = request.into_data().unwrap();
+    assert_eq!(data[1], MARK_READ, "Wrong MarkRead discriminant");
+    assert_eq!(data.len(), request.size_hint(), "Incorrect request size hint");
+    assert_eq!(BoardRequest::from_data(&data).unwrap(), request, "Invalid MarkRead Conversion");
+
+    let response = BoardResponse::MarkRead(3);
+    let data = response.into_data().unwrap();
+    assert_eq!(data.len(), response.size_hint(), "Incorrect response size hint");
+    assert_eq!(BoardResponse::from_data(&data).unwrap(), response, "Invalid MarkRead response Conversion");
+
+    let mut old_version = data;
+    old_version[0] = 0x00;
+    assert_eq!(BoardResponse::from_data(&old_version), Err(DataError::UnsupportedVersion), "Response without view counts accepted");
+}
+
+#[test]
+fn children_validation() {
+    let entry_id = EntryId::from(7);
+    let mut header = HeaderData::new(ROOT_ENTRY_ID.into(), vec![1u64.into(), 2u64.into()], 1u64.into());
+    assert_eq!(header.validate(entry_id), Ok(()), "Valid children rejected");
+
+    header.add_child(2u64.into()).unwrap();
+    assert_eq!(header.children_ids, vec![EntryId::from(1), EntryId::from(2)], "Child added twice");
+
+    let duplicated = HeaderData::new(ROOT_ENTRY_ID.into(), vec![1u64.into(), 2u64.into(), 1u64.into()], 1u64.into());
+    assert_eq!(duplicated.validate(entry_id), Err(DataError::InvalidStructure), "Duplicate children accepted");
+    let self_referencing = HeaderData::new(ROOT_ENTRY_ID.into(), vec![1u64.into(), entry_id], 1u64.into());
+    assert_eq!(self_referencing.validate(entry_id), Err(DataError::InvalidStructure), "Self-referencing children accepted");
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn markdown_styling() {
+    use crate::markdown::*;
+    let plain = SpanStyle::default();
+    let bold = SpanStyle { bold: true, ..plain };
+    let code = SpanStyle { code: true, ..plain };
+    let span = |text: &str, style: SpanStyle| (String::from(text), style);
+
+    let lines = styled_lines("# Title\n\nSome **bold** and *it*.\n").unwrap();
+    assert_eq!(lines, vec![
+        vec![span("Title", SpanStyle { heading: true, ..plain })],
+        vec![],
+        vec![span("Some ", plain), span("bold", bold), span(" and ", plain), span("it", SpanStyle { italic: true, ..plain }), span(".", plain)],
+    ], "Heading / emphasis styled wrong");
+
+    let lines = styled_lines("- one\n- two\n\n1. a\n2. b\n").unwrap();
+    assert_eq!(lines, vec![
+        vec![span("- ", plain), span("one", plain)],
+        vec![span("- ", plain), span("two", plain)],
+        vec![],
+        vec![span("1. ", plain), span("a", plain)],
+        vec![span("2. ", plain), span("b", plain)],
+    ], "Lists styled wrong");
+
+    let lines = styled_lines("```\nlet x = 1;\n\nx\n```\nrun `x`").unwrap();
+    assert_eq!(lines, vec![
+        vec![span("let x = 1;", code)],
+        vec![],
+        vec![span("x", code)],
+        vec![],
+        vec![span("run ", plain), span("x", code)],
+    ], "Code styled wrong");
+
+    let plain_text = "no markup here";
+    assert_eq!(styled_lines(plain_text).unwrap(), vec![vec![span(plain_text, plain)]], "Plain text changed");
+
+    let rows = wrap_styled_line(&[span("hello ", plain), span("ab cd", bold)], 6);
+    assert_eq!(rows, vec![vec![span("hello ", plain)], vec![span("ab cd", bold)]], "Styled line wrapped wrong");
+    let rows = wrap_styled_line(&[span("ab cd", bold)], 3);
+    assert_eq!(rows, vec![vec![span("ab ", bold)], vec![span("cd", bold)]], "Span not split across rows");
+}
+
+#[test]
+fn tombstones() {
+    let mut entry = Entry::new_message(1u64.into(), 2u64.into(), 3, String::from("regrettable"));
+    entry.header_data.children_ids = vec![4u64.into(), 5u64.into()];
+    entry.header_data.pinned = true;
+    entry.tombstone();
+    assert!(entry.header_data.deleted && !entry.header_data.pinned, "Tombstone flags wrong");
+    assert_eq!(entry.entry_data, EntryData::Message { timestamp: 3, message: String::from(DELETED_PLACEHOLDER) }, "Message kept");
+    assert_eq!(entry.header_data.children_ids, vec![EntryId::from(4), EntryId::from(5)], "Children lost");
+    assert_eq!(entry.validate_new(2u64.into()), Err(DataError::InsufficientPerms), "New entry deleted itself");
+
+    let data = entry.into_data().unwrap();
+    assert_eq!(data.len(), entry.size_hint(), "Incorrect size hint");
+    assert_eq!(Entry::from_data(&data).unwrap(), entry, "Invalid Tombstone Conversion");
+
+    // a version 3 file has no flag and isn't deleted,
+    // the flag following the magic number, version, type, parent, 2 children, author, last activity, and pinned flag
+    let mut old_data = data;
+    old_data[2] = 3;
+    assert_eq!(old_data.remove(2 + 1 + 1 + 8 + 2 + 2 * 8 + 8 + 8 + 1), 1, "Removed something other than the deleted flag");
+    let old_entry = Entry::from_data(&old_data).unwrap();
+    assert!(!old_entry.header_data.deleted, "Old entry deleted");
+    assert!(!old_entry.header_data.pinned, "Old entry pinned");
+    assert_eq!(old_entry.header_data.children_ids, entry.header_data.children_ids, "Old entry misread");
+}
+
+#[test]
+fn chunked_frames() {
+    let mut rng = rand::rng();
+    let chunk_size = 1024;
+    let frame = rand_bytes(&mut rng, 4 * chunk_size + 1..8 * chunk_size);
+    let request_id = rng.next_u64();
+    let mut data = Vec::new();
+    write_chunked_frame(&mut data, request_id, &frame, chunk_size).unwrap();
+    let num_chunks = frame.len().div_ceil(chunk_size);
+    assert_eq!(data.len(), 16 + 8 * (num_chunks + 1) + frame.len(), "Incorrect chunked size");
+    // the chunks don't get in the way of the frames after them
+    let small_frame = rand_bytes(&mut rng, 0..chunk_size);
+    write_frame(&mut data, 1, &small_frame).unwrap();
+
+    let mut frame_reader = FrameReader::new();
+    let mut data_stream = &data[..];
+    assert_eq!(frame_reader.read_frame(&mut data_stream).unwrap(), Some((request_id, frame.clone())), "Chunks not reassembled");
+    assert_eq!(frame_reader.read_frame(&mut data_stream).unwrap(), Some((1, small_frame)), "Frame after the chunks misread");
+
+    let mut frame_reader = FrameReader::with_max_frame_size(frame.len());
+    assert_eq!(frame_reader.read_frame(&mut &data[..]).unwrap(), Some((request_id, frame.clone())), "Chunks at the size limit refused");
+    let mut frame_reader = FrameReader::with_max_frame_size(frame.len() - 1);
+    let error = frame_reader.read_frame(&mut &data[..]).expect_err("Chunks over the size limit accepted");
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "Wrong error for chunks over the size limit");
+
+    let mut empty = Vec::new();
+    write_chunked_frame(&mut empty, request_id, &[], chunk_size).unwrap();
+    assert_eq!(FrameReader::new().read_frame(&mut &empty[..]).unwrap(), Some((request_id, Vec::new())), "Empty chunked frame misread");
+}
+
+#[test]
+fn streamed_response_data_conversion() {
+    let mut rng = rand::rng();
+    let mut char_rng = get_char_rng(rng.clone());
+    let mut crypto_rng = get_crypto_rng();
+
+    let user_id = rng.random::<u64>().into();
+    let mut servers_user_aead_key = UserAeadKey::new_random(&mut crypto_rng);
+    let (_kem_dk, kem_ek) = get_kem_set(&mut crypto_rng);
+    let mut user_key = PublicKeySet::new(Some(kem_ek), Some(servers_user_aead_key.clone()));
+
+    for _ in 0..RANDOM_TEST_RETRIES / 10 {
+        // a few chunks worth of body, or one of the small responses which fit in a single chunk
+        let response = if rng.random_bool(0.5) {
+            let mut entry = rand_entry(&mut rng, &mut char_rng);
+            entry.entry_data = EntryData::Attachment {
+                filename: (&mut char_rng).take(rng.random_range(1..50)).collect(),
+                mime: (&mut char_rng).take(rng.random_range(1..50)).collect(),
+                data: rand_bytes(&mut rng, 2 * RESPONSE_CHUNK_SIZE..5 * RESPONSE_CHUNK_SIZE),
+            };
+            BoardResponse::GetEntry { entry, view_count: rng.next_u64() }
+        } else {
+            rand_response(&mut rng, &mut char_rng, &mut crypto_rng)
+        };
+        let re_encryptor = rand_re_encryptor(&mut rng, &mut crypto_rng, user_id);
+        if let ReEncryptionData::FullAnonymous(key) = &re_encryptor {
+            user_key.simple_aead.push_back(key.clone());
+        }
+        let request_id = rng.next_u64();
+        let (head, key) = BoardResponse::secure_stream_head(&mut crypto_rng, re_encryptor, |key_user_id| 
+            if key_user_id == user_id {Some(&mut servers_user_aead_key)} else {None}
+        ).unwrap();
+        let mut data = Vec::new();
+        response.write_streamed(&mut data, request_id, &head, key, rng.random_bool(0.5)).unwrap();
+        // the stream is left at the frame after
+        let small_frame = rand_bytes(&mut rng, 0..1024);
+        write_frame(&mut data, 1, &small_frame).unwrap();
+
+        let mut frame_reader = FrameReader::new();
+        let mut data_stream = &data[..];
+        let Some(FramePart::Chunk(head_id, head)) = frame_reader.read_part(&mut data_stream).unwrap() else {panic!("Streamed response didn't start with a chunk")};
+        assert_eq!(head_id, request_id, "Request id not kept with its chunks");
+        let decoded = BoardResponse::read_streamed(&mut frame_reader, &mut data_stream, &head, &mut user_key).unwrap();
+        if let BoardResponse::Error(_) = response {
+            let BoardResponse::Error(_) = decoded else {panic!("Invalid Streamed Conversion (Err)")};
+        } else {
+            assert_eq!(response, decoded, "Invalid Streamed Conversion");
+        }
+        assert_eq!(frame_reader.read_frame(&mut data_stream).unwrap(), Some((1, small_frame)), "Frame after the stream misread");
+    }
+}
+
+#[test]
+fn streamed_response_tampering() {
+    let mut rng = rand::rng();
+    let mut crypto_rng = get_crypto_rng();
+    let response = BoardResponse::Metrics("a".repeat(3 * RESPONSE_CHUNK_SIZE));
+    let (head, key) = BoardResponse::secure_stream_head(&mut crypto_rng, ReEncryptionData::Exposed, |_| None::<&mut UserAeadKey>).unwrap();
+    let mut data = Vec::new();
+    response.write_streamed(&mut data, 0, &head, key, false).unwrap();
+    let mut frame_reader = FrameReader::new();
+    let mut data_stream = &data[..];
+    let mut chunks = Vec::new();
+    while let Some(FramePart::Chunk(_, chunk)) = frame_reader.read_part(&mut data_stream).unwrap() {
+        chunks.push(chunk);
+    }
+    assert!(chunks.len() > 3, "Response not split into chunks");
+
+    let small_frame = rand_bytes(&mut rng, 0..1024);
+    let check_refused = |chunks: &[Vec<u8>], message: &str| {
+        let mut data = Vec::new();
+        write_all_blocking(&mut data, &u64::MAX.to_le_bytes()).unwrap();
+        write_all_blocking(&mut data, &0u64.to_le_bytes()).unwrap();
+        for chunk in chunks {
+            write_chunk(&mut data, chunk).unwrap();
+        }
+        end_chunked_frame(&mut data).unwrap();
+        write_frame(&mut data, 1, &small_frame).unwrap();
+
+        let mut frame_reader = FrameReader::new();
+        let mut data_stream = &data[..];
+        let Some(FramePart::Chunk(_, head)) = frame_reader.read_part(&mut data_stream).unwrap() else {panic!("Streamed response didn't start with a chunk")};
+        BoardResponse::read_streamed(&mut frame_reader, &mut data_stream, &head, &mut PublicKeySet::new(None, None)).expect_err(message);
+        assert_eq!(frame_reader.read_frame(&mut data_stream).unwrap(), Some((1, small_frame.clone())), "Frame after a refused stream misread");
+    };
+    check_refused(&chunks[..chunks.len() - 1], "Stream missing its last chunk accepted");
+    let mut reordered = chunks.clone();
+    reordered.swap(1, 2);
+    check_refused(&reordered, "Reordered chunks accepted");
+    let mut extended = chunks.clone();
+    extended.push(chunks[1].clone());
+    check_refused(&extended, "Chunk past the last accepted");
+}
+
+#[test]
+fn version_negotiation() {
+    assert_eq!(negotiate_version(MIN_PROTOCOL_VERSION, PROTOCOL_VERSION), Ok(PROTOCOL_VERSION), "Shared version not agreed on");
+    // a newer client is talked down to the newest version both know
+    assert_eq!(negotiate_version(MIN_PROTOCOL_VERSION, u8::MAX), Ok(PROTOCOL_VERSION), "Newer client not given the shared version");
+    assert_eq!(negotiate_version(PROTOCOL_VERSION + 1, u8::MAX), Err(DataError::VersionMismatch), "Too new a client accepted");
+    assert_eq!(negotiate_version(MIN_PROTOCOL_VERSION, MIN_PROTOCOL_VERSION), Ok(MIN_PROTOCOL_VERSION), "Older client not kept on its version");
+    assert_eq!(negotiate_version(0, MIN_PROTOCOL_VERSION - 1), Err(DataError::VersionMismatch), "Too old a client accepted");
+    assert_eq!(negotiate_version(PROTOCOL_VERSION, MIN_PROTOCOL_VERSION), Err(DataError::VersionMismatch), "Empty range accepted");
+
+    let request = BoardRequest::NegotiateVersion { min_version: 1, max_version: 3 };
+    assert_eq!(BoardRequest::from_data(&request.into_data().unwrap()).unwrap(), request, "Invalid NegotiateVersion Conversion");
+    let response = BoardResponse::NegotiateVersion(PROTOCOL_VERSION);
+    assert_eq!(BoardResponse::from_data(&response.into_data().unwrap()).unwrap(), response, "Invalid NegotiateVersion Response Conversion");
+    // the mismatch has its own code so the client can say why it was refused
+    let mismatch = BoardResponse::Error(DataError::VersionMismatch);
+    assert_eq!(BoardResponse::from_data(&mismatch.into_data().unwrap()).unwrap(), mismatch, "VersionMismatch not kept over the wire");
+
+    // the handshake is in the same frame whatever the version or board
+    let mut crypto_rng = get_crypto_rng();
+    let (kem_dk, kem_ek) = get_kem_set(&mut crypto_rng);
+    let mut keys = PublicKeySet::new(Some(kem_ek), None);
+    let request = BoardRequest::NegotiateVersion { min_version: MIN_PROTOCOL_VERSION, max_version: PROTOCOL_VERSION };
+    let expected = request.secure_into_data_to(MAIN_BOARD, PROTOCOL_VERSION, &mut crypto_rng, &mut keys).unwrap();
+    assert_eq!(&expected[..2], &[REQUEST_FORMAT_VERSION, EXPOSED], "Handshake not in the original frame");
+    for protocol_version in [0, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, u8::MAX] {
+        let encoded = request.secure_into_data_to("side_2", protocol_version, &mut crypto_rng, &mut keys).unwrap();
+        assert_eq!(encoded, expected, "Handshake frame changed for version {}", protocol_version);
+    }
+    let (_, decoded) = BoardRequest::secure_from_data(&kem_dk, |_| None::<&mut UserAeadKey>, &expected).unwrap();
+    assert_eq!(decoded, request, "Invalid handshake Conversion");
+    // anything else has to wait on an agreed version
+    assert_eq!(BoardRequest::Ping.secure_into_data_to(MAIN_BOARD, PROTOCOL_VERSION + 1, &mut crypto_rng, &mut keys), Err(DataError::VersionMismatch), "Sent a request in an unknown version");
+    assert_eq!(BoardRequest::Ping.secure_into_data_to(MAIN_BOARD, MIN_PROTOCOL_VERSION - 1, &mut crypto_rng, &mut keys), Err(DataError::VersionMismatch), "Sent a request in an unsupported version");
+}
+
+#[test]
+fn guest_requests() {
+    let mut crypto_rng = get_crypto_rng();
+    let (kem_dk, kem_ek) = get_kem_set(&mut crypto_rng);
+    // a guest has no key of its own
+    let mut guest_keys = PublicKeySet::new(Some(kem_ek), None);
+    let guest_id = UserId::from(ANONYMOUS_USER_ID);
+
+    let request = BoardRequest::GetEntry { user_id: guest_id, entry_id: ROOT_ENTRY_ID.into() };
+    assert_eq!(request.user_id(), Some(guest_id), "Guest id not the sender");
+    assert!(request.is_read_only(), "GetEntry not read only");
+    let encoded = request.secure_into_data(&mut crypto_rng, &mut guest_keys).unwrap();
+    let (re_encryptor, decoded) = BoardRequest::secure_from_data(&kem_dk, |_| None::<&mut UserAeadKey>, &encoded).unwrap();
+    assert_eq!(decoded, request, "Invalid guest Request Conversion");
+    let ReEncryptionData::FullAnonymous(_) = re_encryptor else {panic!("Guest request not sent anonymously")};
+
+    let response = BoardResponse::MarkRead(3);
+    let encoded = response.secure_into_data(&mut crypto_rng, re_encryptor, false, |_| None::<&mut UserAeadKey>).unwrap();
+    assert_eq!(BoardResponse::secure_from_data(&encoded, &mut guest_keys).unwrap(), response, "Invalid guest Response Conversion");
+
+    let write = BoardRequest::AddEntry { user_id: guest_id, entry: Entry::new_message(ROOT_ENTRY_ID.into(), guest_id, 1, String::from("hi")) };
+    assert!(!write.is_read_only(), "AddEntry read only");
+    assert!(!BoardRequest::MarkRead { user_id: guest_id, entry_id: ROOT_ENTRY_ID.into() }.is_read_only(), "MarkRead read only");
+    assert_eq!(BoardRequest::Ping.user_id(), None, "Ping made as a user");
+}
+
+#[test]
+fn attachments() {
+    let mut rng = rand::rng();
+    let empty = Entry::new_attachment(1u64.into(), 2u64.into(), String::from("empty.bin"), String::from("application/octet-stream"), Vec::new());
+    let large = Entry::new_attachment(1u64.into(), 2u64.into(), String::from("large.bin"), String::from("application/octet-stream"), rand_bytes(&mut rng, DEFAULT_MAX_MESSAGE_SIZE..=DEFAULT_MAX_MESSAGE_SIZE));
+    for entry in [&empty, &large] {
+        assert_eq!(entry.entry_data.get_discriminant(), ATTACHMENT, "Incorrect discriminant");
+        assert_eq!(entry.entry_data.check_size_limits(DEFAULT_MAX_MESSAGE_SIZE), Ok(()), "Bounded attachment rejected");
+        let data = entry.clone().into_data().unwrap();
+        assert_eq!(data.len(), entry.size_hint(), "Incorrect size hint");
+        assert_eq!(&Entry::from_data(&data).unwrap(), entry, "Invalid Attachment Conversion");
+        // the data is read byte for byte, so running out partway is caught
+        assert_eq!(Entry::from_data(&data[..data.len() - 1]).err(), Some(DataError::InsufficientBytes), "Truncated attachment accepted");
+    }
+
+    let EntryData::Attachment { filename, mime, data } = large.entry_data.clone() else {panic!("Entry type changed")};
+    let mut too_large = data.clone();
+    too_large.push(0);
+    let limits = [
+        (EntryData::Attachment { filename: filename.clone(), mime: mime.clone(), data: too_large }, DEFAULT_MAX_MESSAGE_SIZE),
+        (EntryData::Attachment { filename: "a".repeat(MAX_ATTACHMENT_NAME_SIZE + 1), mime: mime.clone(), data: Vec::new() }, MAX_ATTACHMENT_NAME_SIZE),
+        (EntryData::Attachment { filename, mime: "a".repeat(MAX_MIME_SIZE + 1), data: Vec::new() }, MAX_MIME_SIZE),
+    ];
+    for (entry_data, max) in limits {
+        assert_eq!(entry_data.check_size_limits(DEFAULT_MAX_MESSAGE_SIZE), Err(DataError::PayloadTooLarge { max }), "Oversized attachment accepted");
+    }
+
+    let mut deleted = large.clone();
+    deleted.tombstone();
+    assert_eq!(deleted.entry_data, EntryData::Attachment { filename: String::from(DELETED_PLACEHOLDER), mime: String::new(), data: Vec::new() }, "Attachment data kept");
+    assert!(large.diff(&deleted).data_changed, "Data change missed");
+}
+
+#[test]
+fn subtree_counting() {
+    let entry = |parent_id: u64, children_ids: &[u64]| Entry {
+        header_data: HeaderData::new(parent_id.into(), children_ids.iter().copied().map(EntryId::from).collect(), 1u64.into()),
+        entry_data: EntryData::Message { timestamp: 0, message: String::from("hi") },
+    };
+    // root -> 1 -> (2 -> 4, 3)
+    let mut index = EntryIndex::new();
+    index.insert(ROOT_ENTRY_ID.into(), &entry(ROOT_ENTRY_ID, &[1]));
+    index.insert(1u64.into(), &entry(ROOT_ENTRY_ID, &[2, 3]));
+    index.insert(2u64.into(), &entry(1, &[4]));
+    index.insert(3u64.into(), &entry(1, &[]));
+    index.insert(4u64.into(), &entry(2, &[]));
+
+    let counts = [(ROOT_ENTRY_ID, 4), (1, 3), (2, 1), (3, 0), (4, 0)];
+    for (entry_id, count) in counts {
+        assert_eq!(index.subtree_count(entry_id.into(), DEFAULT_MAX_TREE_DEPTH), Ok(count), "Incorrect count below {}", entry_id);
+    }
+    assert_eq!(index.subtree_count(5u64.into(), DEFAULT_MAX_TREE_DEPTH), Err(DataError::DoesNotExist), "Counted a missing entry");
+
+    // a cycle isn't walked forever, nor counted twice
+    index.insert(4u64.into(), &entry(2, &[1]));
+    assert_eq!(index.subtree_count(1u64.into(), DEFAULT_MAX_TREE_DEPTH), Ok(3), "Cycle miscounted");
+    assert_eq!(index.subtree_count(ROOT_ENTRY_ID.into(), DEFAULT_MAX_TREE_DEPTH), Ok(4), "Cycle miscounted from the root");
+
+    let request = BoardRequest::SubtreeCount { user_id: 1u64.into(), entry_id: 2u64.into() };
+    let data = request.into_data().unwrap();
+    assert_eq!(data[1], SUBTREE_COUNT, "Wrong SubtreeCount discriminant");
+    assert_eq!(BoardRequest::from_data(&data).unwrap(), request, "Invalid SubtreeCount Conversion");
+    let response = BoardResponse::SubtreeCount(4);
+    let data = response.into_data().unwrap();
+    assert_eq!(data.len(), response.size_hint(), "Incorrect size hint");
+    assert_eq!(BoardResponse::from_data(&data).unwrap(), response, "Invalid SubtreeCount response Conversion");
+}
+
+#[test]
+fn fair_queue_interleaving() {
+    let (chatty, quiet) = (1, 2);
+    let mut queue = FairQueue::new();
+    for request in 0..10 {
+        queue.push(chatty, request);
+    }
+    queue.push(quiet, 0);
+    queue.push(quiet, 1);
+    assert_eq!(queue.len(), 12, "Incorrect queued count");
+
+    // one handler, each request finished before the next, so the clients take turns
+    let mut served = Vec::new();
+    while let Some((client_id, request)) = queue.pop() {
+        served.push((client_id, request));
+        queue.finish(client_id);
+    }
+    assert_eq!(&served[..4], &[(chatty, 0), (quiet, 0), (chatty, 1), (quiet, 1)], "Clients not interleaved");
+    assert_eq!(served[4..].iter().map(|x| x.1).collect::<Vec<_>>(), (2..10).collect::<Vec<_>>(), "Requests reordered within a client");
+    assert!(queue.is_empty(), "Requests left queued");
+
+    // the client with the fewest requests being handled goes first, however many it has queued
+    for request in 0..3 {
+        queue.push(chatty, request);
+    }
+    assert_eq!(queue.pop(), Some((chatty, 0)), "Only client not served");
+    queue.push(quiet, 0);
+    assert_eq!(queue.pop(), Some((quiet, 0)), "Idle client starved");
+    assert_eq!(queue.pop(), Some((chatty, 1)), "Busy client starved");
+    assert_eq!((queue.in_flight(chatty), queue.in_flight(quiet)), (2, 1), "Incorrect in flight counts");
+
+    // a disconnected client's requests are dropped, and finishing one of them does nothing
+    queue.retain_clients(|client_id| client_id != chatty);
+    queue.finish(chatty);
+    assert!(queue.is_empty() && queue.in_flight(chatty) == 0, "Disconnected client kept");
+    assert_eq!(queue.in_flight(quiet), 1, "Connected client dropped");
+}
+
+#[test]
+fn display_names() {
+    let mut user = UserData::new_empty(UserAeadKey::new_random(get_crypto_rng()));
+    user.entry_ids = vec![1u64.into(), 2u64.into()];
+    user.display_name = Some(String::from("Pyramid"));
+    let data = user.into_data().unwrap();
+    assert_eq!(data.len(), user.size_hint(), "Incorrect size hint");
+    assert_eq!(UserData::from_data(&data).unwrap(), user, "Invalid Display Name Conversion");
+
+    // a version 0 user has no display name
+    user.display_name = None;
+    let mut old_data = user.into_data().unwrap();
+    old_data[2] = 0;
+    old_data.pop();
+    assert_eq!(UserData::from_data(&old_data).unwrap(), user, "Version 0 user misread");
+
+    // an empty name is the same as none
+    let request = BoardRequest::SetDisplayName { user_id: 3u64.into(), display_name: Some(String::new()) };
+    let data = request.into_data().unwrap();
+    assert_eq!(BoardRequest::from_data(&data).unwrap(), BoardRequest::SetDisplayName { user_id: 3u64.into(), display_name: None }, "Empty display name kept");
+
+    let user_id = UserId::from(0xABCDu64);
+    assert_eq!(user_label(user_id, Some("Pyramid")), "Pyramid", "Display name not used");
+    assert_eq!(user_label(user_id, None), user_id.to_string(), "No fallback to the id");
+    assert_eq!(user_label(user_id, Some(" \t")), user_id.to_string(), "Blank display name used");
+    assert_eq!(user_label(user_id, Some("Pyra\x1b[2Jmid")), "Pyra[2Jmid", "Control characters kept");
+    assert_eq!(user_label(ADMIN_USER_ID.into(), Some("Not the admin")), UserId::from(ADMIN_USER_ID).to_string(), "Reserved user renamed");
+}
+
+#[test]
+fn perms_draft_editing() {
+    use crate::perms_draft::*;
+    let mut draft = PermsDraft::new(DefaultedIdSet::empty_from_base(DefaultBase::Inherit));
+    assert!(!draft.blacklist_focused(), "Inherit didn't start on the whitelist");
+
+    // validation, the input is kept when rejected
+    assert_eq!(draft.add_input(), Err(DataError::NotHex), "Empty input added");
+    assert!(!draft.push_char('g'), "Non hex typed");
+    for c in "00000000000000af".chars() {
+        assert!(draft.push_char(c), "Hex digit rejected");
+    }
+    assert!(!draft.push_char('1'), "Typed past the longest id");
+    assert_eq!(draft.input(), "00000000000000AF", "Incorrect input");
+    assert_eq!(draft.add_input(), Ok(UserId::from(0xAF)), "Id not added");
+    assert_eq!(draft.input(), "", "Input not cleared");
+    assert_eq!(draft.selected(), Some(0), "Added id not selected");
+    "af".chars().for_each(|c| {draft.push_char(c);});
+    assert_eq!(draft.add_input(), Err(DataError::AlreadyExists), "Duplicate added");
+    assert_eq!(draft.input(), "AF", "Rejected input cleared");
+    assert_eq!(draft.pop_char(), Some('F'), "Incorrect char popped");
+
+    // an id in the other list moves over
+    assert!(draft.toggle_list(), "Inherit has both lists");
+    assert_eq!(draft.selected(), None, "Selection kept across lists");
+    draft.pop_char();
+    "af".chars().for_each(|c| {draft.push_char(c);});
+    assert_eq!(draft.add_input(), Ok(UserId::from(0xAF)), "Id not moved");
+    "b".chars().for_each(|c| {draft.push_char(c);});
+    draft.add_input().unwrap();
+    assert_eq!(draft.focused_ids(), &[UserId::from(0xAF), UserId::from(0xB)], "Incorrect blacklist");
+    assert_eq!(draft.perms().len_whitelist(), 0, "Moved id left in the whitelist");
+
+    // removal
+    draft.select_up();
+    assert_eq!(draft.remove_selected(), Some(UserId::from(0xAF)), "Incorrect id removed");
+    assert_eq!(draft.selected(), Some(0), "Selection not moved to the next id");
+    assert_eq!(draft.remove_selected(), Some(UserId::from(0xB)), "Incorrect id removed");
+    assert_eq!(draft.selected(), None, "Selection kept in an empty list");
+    assert_eq!(draft.remove_selected(), None, "Removed from an empty list");
+    draft.select_down();
+    assert_eq!(draft.selected(), None, "Selected in an empty list");
+
+    // a base with one list stays on it
+    let mut draft = PermsDraft::new(DefaultedIdSet::empty_from_base(DefaultBase::White));
+    assert!(draft.blacklist_focused(), "White didn't start on the blacklist");
+    assert!(!draft.toggle_list(), "Toggled to a missing list");
+    draft.push_char('1');
+    draft.add_input().unwrap();
+    draft.set_base(DefaultBase::Black);
+    assert!(!draft.blacklist_focused(), "Focus left on a dropped list");
+    assert_eq!(draft.into_perms(), DefaultedIdSet::Black { whitelist_ids: Vec::new() }, "Incorrect rebased perms");
+}
+
+/// takes at most `max_write` bytes per write, failing every write from the `fail_on`th (counting from 1)
+struct FlakyWriter {
+    data: Vec<u8>,
+    writes: usize,
+    fail_on: usize,
+    max_write: usize,
+    flushed: bool,
+}
+
+impl Write for FlakyWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writes += 1;
+        if self.writes >= self.fail_on {return Err(std::io::ErrorKind::BrokenPipe.into())}
+        let len = buf.len().min(self.max_write);
+        self.data.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> std::io::Result<usize> {
+        self.writes += 1;
+        if self.writes >= self.fail_on {return Err(std::io::ErrorKind::BrokenPipe.into())}
+        let mut len = 0;
+        for buf in bufs {
+            let taken = buf.len().min(self.max_write - len);
+            self.data.extend_from_slice(&buf[..taken]);
+            len += taken;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flushed = true;
+        Ok(())
+    }
+}
+
+#[test]
+fn frame_write_errors() {
+    let mut rng = rand::rng();
+    let frame = rand_bytes(&mut rng, 64..1024);
+    let request_id = rng.next_u64();
+    let mut expected = Vec::new();
+    write_frame(&mut expected, request_id, &frame).unwrap();
+
+    // the prefix and frame go out in one write, and are flushed
+    let mut writer = FlakyWriter { data: Vec::new(), writes: 0, fail_on: 2, max_write: usize::MAX, flushed: false };
+    write_frame(&mut writer, request_id, &frame).unwrap();
+    assert_eq!(writer.writes, 1, "Frame split across writes");
+    assert_eq!(writer.data, expected, "Incorrect frame written");
+    assert!(writer.flushed, "Frame not flushed");
+
+    // a stream taking part of the frame gets the rest on later writes
+    let mut writer = FlakyWriter { data: Vec::new(), writes: 0, fail_on: usize::MAX, max_write: 7, flushed: false };
+    write_frame(&mut writer, request_id, &frame).unwrap();
+    assert_eq!(writer.data, expected, "Incorrect frame written in parts");
+
+    // failing on the second write, the error is returned rather than the frame silently left half sent
+    let mut writer = FlakyWriter { data: Vec::new(), writes: 0, fail_on: 2, max_write: 7, flushed: false };
+    let result = write_frame(&mut writer, request_id, &frame);
+    assert_eq!(result.map_err(|e| e.kind()), Err(std::io::ErrorKind::BrokenPipe), "Failed write not returned");
+    assert_eq!(writer.data.len(), 7, "Wrote past the failure");
+    assert!(!writer.flushed, "Flushed after a failed write");
+    assert_eq!(DataError::from(std::io::Error::from(std::io::ErrorKind::BrokenPipe)), DataError::IoError(std::io::ErrorKind::BrokenPipe), "Incorrect error surfaced");
+}
+
+#[test]
+fn tree_depth_limits() {
+    // root -> 1 -> 2 -> ... -> 10, and 20 <-> 21 which never reaches the root
+    let mut parents: std::collections::HashMap<EntryId, EntryId> = (1..=10u64).map(|entry_id| (entry_id.into(), (entry_id - 1).into())).collect();
+    parents.insert(20u64.into(), 21u64.into());
+    parents.insert(21u64.into(), 20u64.into());
+    let get_parent_id = |entry_id: EntryId| parents.get(&entry_id).copied().ok_or(DataError::DoesNotExist);
+    // none are access groups, so everything falls through to the default
+    let get_entry = |entry_id: EntryId| -> Result<(EntryId, Option<EntryData>), DataError> {
+        if *entry_id == ROOT_ENTRY_ID {return Ok((ROOT_ENTRY_ID.into(), None))}
+        Ok((get_parent_id(entry_id)?, None))
+    };
+
+    assert_eq!(ancestor_path(10u64.into(), 10, get_parent_id).map(|path| path.len()), Ok(10), "Path at the max depth rejected");
+    assert_eq!(ancestor_path(10u64.into(), 9, get_parent_id), Err(DataError::DepthExceeded), "Over-deep path allowed");
+    assert_eq!(ancestor_path(20u64.into(), DEFAULT_MAX_TREE_DEPTH, get_parent_id), Err(DataError::MalformedRoot), "Cyclic path not detected");
+
+    let resolve = |entry_id: u64, max_depth| resolve_access(1u64.into(), entry_id.into(), PermKind::Read, true, max_depth, get_entry);
+    assert_eq!(resolve(10, 10), Ok(true), "Access at the max depth rejected");
+    assert_eq!(resolve(10, 9), Err(DataError::DepthExceeded), "Over-deep access allowed");
+    assert_eq!(resolve(20, DEFAULT_MAX_TREE_DEPTH), Err(DataError::MalformedRoot), "Cyclic access not detected");
+
+    let filter = |entry_ids: &[u64], max_depth| {
+        let entry_ids: Vec<EntryId> = entry_ids.iter().copied().map(EntryId::from).collect();
+        filter_accessible(1u64.into(), &entry_ids, PermKind::Read, true, max_depth, get_entry)
+    };
+    assert_eq!(filter(&[5, 10], 10).map(|accessible| accessible.len()), Ok(2), "Filtering at the max depth rejected");
+    assert_eq!(filter(&[10], 9), Err(DataError::DepthExceeded), "Over-deep filtering allowed");
+    // decided on the way to 5, but 10 still has to be walked down to it
+    assert_eq!(filter(&[5, 10], 9).map(|accessible| accessible.len()), Ok(2), "Cached decisions not reused");
+    assert_eq!(filter(&[20], DEFAULT_MAX_TREE_DEPTH), Err(DataError::MalformedRoot), "Cyclic filtering not detected");
+
+    let entry = |parent_id: u64, children_ids: &[u64]| Entry {
+        header_data: HeaderData::new(parent_id.into(), children_ids.iter().copied().map(EntryId::from).collect(), 1u64.into()),
+        entry_data: EntryData::Message { timestamp: 0, message: String::from("hi") },
+    };
+    let mut index = EntryIndex::new();
+    index.insert(ROOT_ENTRY_ID.into(), &entry(ROOT_ENTRY_ID, &[1]));
+    for entry_id in 1..10u64 {
+        index.insert(entry_id.into(), &entry(entry_id - 1, &[entry_id + 1]));
+    }
+    index.insert(10u64.into(), &entry(9, &[]));
+    assert_eq!(index.subtree_count(ROOT_ENTRY_ID.into(), 10), Ok(10), "Count at the max depth rejected");
+    assert_eq!(index.subtree_count(ROOT_ENTRY_ID.into(), 9), Err(DataError::DepthExceeded), "Over-deep count allowed");
+    assert_eq!(index.subtree_count(1u64.into(), 9), Ok(9), "Depth not counted from the entry");
+    assert_eq!(index.subtree_height(ROOT_ENTRY_ID.into()), 10, "Incorrect height");
+    assert_eq!(index.subtree_height(9u64.into()), 1, "Incorrect height");
+    assert_eq!(index.subtree_height(10u64.into()), 0, "Leaf has a height");
+    index.insert(10u64.into(), &entry(9, &[1]));
+    assert_eq!(index.subtree_count(1u64.into(), DEFAULT_MAX_TREE_DEPTH), Ok(9), "Cyclic count walked twice");
+    assert_eq!(index.subtree_height(1u64.into()), 9, "Cyclic height walked twice");
+}
+
+#[test]
+fn get_root_data_conversion() {
+    let mut crypto_rng = get_crypto_rng();
+    let (kem_dk, kem_ek) = get_kem_set(&mut crypto_rng);
+    let mut user_key = PublicKeySet::new(Some(kem_ek), None);
+    let root_id = EntryId::from(rand::rng().next_u64());
+
+    let data = BoardRequest::GetRoot.into_data().unwrap();
+    assert_eq!(data[1], GET_ROOT, "Wrong GetRoot discriminant");
+    assert_eq!(BoardRequest::from_data(&data).unwrap(), BoardRequest::GetRoot, "Invalid GetRoot Conversion");
+    let response = BoardResponse::GetRoot(root_id);
+    assert_eq!(BoardResponse::from_data(&response.into_data().unwrap()).unwrap(), response, "Invalid GetRoot response Conversion");
+
+    // like pings, asking for the root needs no user
+    let encoded = BoardRequest::GetRoot.secure_into_data(&mut crypto_rng, &mut user_key).unwrap();
+    let (re_encryptor, decoded) = BoardRequest::secure_from_data(&kem_dk, |_| None::<&mut UserAeadKey>, &encoded).unwrap();
+    assert_eq!(decoded, BoardRequest::GetRoot, "Invalid secure GetRoot Conversion");
+    let encoded = response.secure_into_data(&mut crypto_rng, re_encryptor, false, |_| None::<&mut UserAeadKey>).unwrap();
+    assert_eq!(BoardResponse::secure_from_data(&encoded, &mut user_key).unwrap(), response, "Invalid secure GetRoot response Conversion");
+}
+
+#[test]
+fn named_board_envelope() {
+    let mut crypto_rng = get_crypto_rng();
+    let (kem_dk, kem_ek) = get_kem_set(&mut crypto_rng);
+    let mut user_key = PublicKeySet::new(Some(kem_ek), None);
+
+    // the main board keeps the format from before there were named boards, until there were response flags
+    let encoded = BoardRequest::Status.secure_into_data_to(MAIN_BOARD, RESPONSE_FLAGS_PROTOCOL_VERSION - 1, &mut crypto_rng, &mut user_key).unwrap();
+    assert_eq!(encoded[0], REQUEST_FORMAT_VERSION, "Main board request changed format");
+    assert_eq!(BoardRequest::secure_board_name(&encoded).unwrap(), MAIN_BOARD, "Main board not found");
+    let encoded = BoardRequest::Status.secure_into_data(&mut crypto_rng, &mut user_key).unwrap();
+    assert_eq!(BoardRequest::secure_board_name(&encoded).unwrap(), MAIN_BOARD, "Requests not to the main board by default");
+
+    let encoded = BoardRequest::Status.secure_into_data_to("side_2", RESPONSE_FLAGS_PROTOCOL_VERSION - 1, &mut crypto_rng, &mut user_key).unwrap();
+    assert_eq!(encoded[0], NAMED_BOARD_REQUEST_VERSION, "Wrong named board version");
+    assert_eq!(BoardRequest::secure_board_name(&encoded).unwrap(), "side_2", "Wrong board name");
+    let (_, decoded) = BoardRequest::secure_from_data(&kem_dk, |_| None::<&mut UserAeadKey>, &encoded).unwrap();
+    assert_eq!(decoded, BoardRequest::Status, "Invalid named board request Conversion");
+    // a board from before named boards can only be sent to the main one
+    let encoded = BoardRequest::Status.secure_into_data_to("side_2", NAMED_BOARDS_PROTOCOL_VERSION - 1, &mut crypto_rng, &mut user_key);
+    assert_eq!(encoded, Err(DataError::VersionMismatch), "Named board sent in a version without them");
+    let encoded = BoardRequest::Status.secure_into_data_to(MAIN_BOARD, NAMED_BOARDS_PROTOCOL_VERSION - 1, &mut crypto_rng, &mut user_key).unwrap();
+    assert_eq!(encoded[0], REQUEST_FORMAT_VERSION, "Main board request changed format for an older version");
+
+    let too_long = "x".repeat(MAX_BOARD_NAME_SIZE + 1);
+    for name in ["", "has space", "dots.", "ünicode", too_long.as_str()] {
+        assert_eq!(validate_board_name(name), Err(DataError::InvalidStructure), "Accepted the board name {:?}", name);
+        assert!(BoardRequest::Status.secure_into_data_to(name, PROTOCOL_VERSION, &mut crypto_rng, &mut user_key).is_err(), "Sent to the board {:?}", name);
+    }
+    assert_eq!(validate_board_name(&"x".repeat(MAX_BOARD_NAME_SIZE)), Ok(()), "Rejected a full length name");
+    assert_eq!(BoardRequest::secure_board_name(&[0x7f]), Err(DataError::UnsupportedVersion), "Accepted an unknown version");
+
+    let response = BoardResponse::ListBoards(vec![String::from(MAIN_BOARD), String::from("side_2")]);
+    assert_eq!(BoardResponse::from_data(&response.into_data().unwrap()).unwrap(), response, "Invalid ListBoards response Conversion");
+    let data = BoardRequest::ListBoards.into_data().unwrap();
+    assert_eq!(data[1], LIST_BOARDS, "Wrong ListBoards discriminant");
+    assert!(BoardRequest::ListBoards.is_read_only(), "Guests can't list the boards");
+}
+
+#[test]
+fn flagged_request_envelope() {
+    let mut crypto_rng = get_crypto_rng();
+    let (kem_dk, kem_ek) = get_kem_set(&mut crypto_rng);
+    let mut user_key = PublicKeySet::new(Some(kem_ek), None);
+
+    // guests' requests are encrypted like adding a user
+    let request = BoardRequest::GetEntry { user_id: UserId::from(ANONYMOUS_USER_ID), entry_id: EntryId::from(3) };
+    for board in [MAIN_BOARD, "side_2"] {
+        let encoded = request.secure_into_data_with(board, RESPONSE_FLAGS_PROTOCOL_VERSION, CHUNKED_RESPONSE_FLAG | COMPRESSED_RESPONSE_FLAG | SUMMARIZED_PERMS_FLAG, &mut crypto_rng, &mut user_key).unwrap();
+        assert_eq!(encoded[0], FLAGGED_REQUEST_VERSION, "Wrong flagged request version");
+        assert_eq!(BoardRequest::secure_board_name(&encoded).unwrap(), board, "Wrong board name");
+        let (_, flags, decoded) = BoardRequest::secure_flagged_from_data(&kem_dk, |_| None::<&mut UserAeadKey>, &encoded).unwrap();
+        assert_eq!((flags, &decoded), (CHUNKED_RESPONSE_FLAG | COMPRESSED_RESPONSE_FLAG | SUMMARIZED_PERMS_FLAG, &request), "Invalid flagged request Conversion");
+        // the flags are in the encrypted body, so they can't be flipped without the request failing
+        let mut tampered = encoded.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0x01;
+        assert!(BoardRequest::secure_flagged_from_data(&kem_dk, |_| None::<&mut UserAeadKey>, &tampered).is_err(), "Tampered request accepted");
+    }
+    // a board from before them is never sent flags
+    let encoded = request.secure_into_data_with(MAIN_BOARD, RESPONSE_FLAGS_PROTOCOL_VERSION - 1, CHUNKED_RESPONSE_FLAG, &mut crypto_rng, &mut user_key).unwrap();
+    assert_eq!(encoded[0], REQUEST_FORMAT_VERSION, "Flags sent in a version without them");
+    let (_, flags, _) = BoardRequest::secure_flagged_from_data(&kem_dk, |_| None::<&mut UserAeadKey>, &encoded).unwrap();
+    assert_eq!(flags, 0, "Flags read from a request without them");
+    // nor is the handshake, which has to be read before the version is known
+    let negotiate = BoardRequest::NegotiateVersion { min_version: MIN_PROTOCOL_VERSION, max_version: PROTOCOL_VERSION };
+    let encoded = negotiate.secure_into_data_with(MAIN_BOARD, PROTOCOL_VERSION, CHUNKED_RESPONSE_FLAG, &mut crypto_rng, &mut user_key).unwrap();
+    assert_eq!(encoded[0], REQUEST_FORMAT_VERSION, "Flags sent with the handshake");
+}
+
+#[test]
+fn perms_summary_data_conversion() {
+    let whitelist_ids = (0..1024u64).map(UserId::from).collect();
+    let perms = DefaultedIdSet::Inherit { whitelist_ids, blacklist_ids: vec![2048u64.into()] };
+    let summary = PermsSummary::new(&perms).unwrap();
+    assert_eq!(summary, PermsSummary { base: DefaultBase::Inherit, whitelist_len: 1024, blacklist_len: 1 }, "Incorrect summary");
+    let white = PermsSummary::new(&DefaultedIdSet::White { blacklist_ids: Vec::new() }).unwrap();
+    assert_eq!(white, PermsSummary { base: DefaultBase::White, whitelist_len: 0, blacklist_len: 0 }, "Incorrect empty summary");
+
+    let request = BoardRequest::GetPermsSummary { user_id: 1u64.into(), entry_id: 2u64.into() };
+    let data = request.into_data().unwrap();
+    assert_eq!(data[1], GET_PERMS_SUMMARY, "Wrong GetPermsSummary discriminant");
+    assert_eq!(BoardRequest::from_data(&data).unwrap(), request, "Invalid GetPermsSummary Conversion");
+    assert!(request.is_read_only(), "Summarizing perms isn't read only");
+    let response = BoardResponse::GetPermsSummary { write: summary, read: white };
+    let data = response.into_data().unwrap();
+    assert!(data.len() < perms.into_data().unwrap().len() / 100, "Summary not smaller than the ids");
+    assert_eq!(BoardResponse::from_data(&data).unwrap(), response, "Invalid GetPermsSummary response Conversion");
+
+    // a White base has no whitelist to count
+    let mut bad = white.into_data().unwrap();
+    bad[1] = 1;
+    assert_eq!(PermsSummary::from_data(&bad), Err(DataError::InvalidStructure), "Accepted a whitelist count without a whitelist");
+
+    // entries sent for `SUMMARIZED_PERMS_FLAG` keep their bases but not their ids
+    let group = Entry::new_access_group(ROOT_ENTRY_ID.into(), 1u64.into(), String::from("big"), perms.clone(), DefaultedIdSet::White { blacklist_ids: vec![3u64.into()] });
+    let message = Entry::new_message(ROOT_ENTRY_ID.into(), 1u64.into(), 0, String::from("untouched"));
+    let mut response = BoardResponse::GetEntries(vec![Ok(group.clone()), Ok(message.clone()), Err(DataError::DoesNotExist)]);
+    response.summarize_perms();
+    let BoardResponse::GetEntries(entries) = response else {unreachable!()};
+    let Ok(Entry { entry_data: EntryData::AccessGroup { name, write_perms, read_perms }, .. }) = &entries[0] else {panic!("Group not kept")};
+    assert_eq!(name, "big", "Group name lost");
+    assert_eq!(*write_perms, DefaultedIdSet::empty_from_base(DefaultBase::Inherit), "Write ids kept");
+    assert_eq!(*read_perms, DefaultedIdSet::empty_from_base(DefaultBase::White), "Read ids kept");
+    assert_eq!(entries[1], Ok(message), "Message changed");
+    let mut response = BoardResponse::GetEntry { entry: group, view_count: 0 };
+    response.summarize_perms();
+    let BoardResponse::GetEntry { entry, .. } = response else {unreachable!()};
+    assert!(matches!(entry.entry_data, EntryData::AccessGroup { write_perms, .. } if write_perms.id_count() == 0), "Ids kept");
+}
+
+#[test]
+fn message_preparation() {
+    let prepare = |message: &str| prepare_message(String::from(message), false);
+    assert_eq!(prepare("hello\n"), Ok(String::from("hello")), "Trailing newline kept");
+    assert_eq!(prepare("hello\r\n"), Ok(String::from("hello")), "Trailing crlf kept");
+    assert_eq!(prepare("hello\n\n"), Ok(String::from("hello\n")), "More than one newline stripped");
+    assert_eq!(prepare("  indented\nlines  "), Ok(String::from("  indented\nlines  ")), "Message body changed");
+
+    for empty in ["", "\n", " \t\n", "\n\n\n"] {
+        assert_eq!(prepare(empty), Err(DataError::EmptyMessage), "Accepted the empty message {:?}", empty);
+    }
+    assert_eq!(prepare_message(String::from("\n"), true), Ok(String::new()), "Empty message rejected when allowed");
+    assert_eq!(prepare_message(String::from(" \n\n"), true), Ok(String::from(" \n")), "Allowed empty message not stripped");
+}
+
+#[test]
+fn metrics_data_conversion() {
+    let request = BoardRequest::Metrics { admin_id: 0x1234u64.into() };
+    let data = request.into_data().unwrap();
+    assert_eq!(data[1], METRICS, "Wrong Metrics discriminant");
+    assert_eq!(data.len(), request.size_hint(), "Incorrect Metrics request size hint");
+    assert_eq!(BoardRequest::from_data(&data).unwrap(), request, "Invalid Metrics Conversion");
+    assert!(request.is_read_only(), "Reading the metrics counted as a change");
+    assert_eq!(request.user_id(), Some(0x1234u64.into()), "Metrics not sent as the admin");
+    assert_eq!(request.type_name(), "Metrics", "Incorrect type name");
+
+    // longer than a u16 length could hold
+    let text = "message_board_requests_total{type=\"Ping\"} 1\n".repeat(2048);
+    let response = BoardResponse::Metrics(text);
+    let data = response.into_data().unwrap();
+    assert_eq!(data.len(), response.size_hint(), "Incorrect Metrics size hint");
+    assert_eq!(BoardResponse::from_data(&data).unwrap(), response, "Invalid Metrics response Conversion");
+}
+
+#[test]
+fn wire_error_codes() {
+    for error in [DataError::TooManyChildren, DataError::InvalidStructure, DataError::DepthExceeded, DataError::EmptyReaction, DataError::CyclicMove, DataError::EdittedLocation, DataError::MalformedRoot] {
+        assert_eq!(DataError::from_error_code(error.error_code()), error, "Error lost on the wire");
+    }
+    // the max is sent after the code, as a response and in a GetEntries list
+    let too_large = DataError::PayloadTooLarge { max: MAX_REQUESTED_ENTRIES };
+    let response = BoardResponse::Error(too_large);
+    let data = response.into_data().unwrap();
+    assert_eq!(data.len(), response.size_hint(), "Incorrect PayloadTooLarge size hint");
+    assert_eq!(BoardResponse::from_data(&data).unwrap(), response, "PayloadTooLarge lost its max on the wire");
+    let response = BoardResponse::GetEntries(vec![Err(DataError::PayloadTooLarge { max: 16 }), Err(DataError::DoesNotExist)]);
+    let data = response.into_data().unwrap();
+    assert_eq!(data.len(), response.size_hint(), "Incorrect GetEntries size hint");
+    assert_eq!(BoardResponse::from_data(&data).unwrap(), response, "PayloadTooLarge lost its max in GetEntries");
+}
@@ -0,0 +1,67 @@
+use std::collections::{HashMap, HashSet};
+use crate::*;
+
+/// how many levels below the current entry the preview shows, only the children can be expanded
+pub const MAX_PREVIEW_DEPTH: usize = 2;
+
+/// the current entry's children, each expandable to show its own children, 
+/// so the preview is never fetched deeper than `MAX_PREVIEW_DEPTH`
+#[derive(Debug, Default)]
+pub struct TreePreview {
+    children: Vec<EntryId>, // in the order shown
+    expanded: HashSet<EntryId>,
+    grandchildren: HashMap<EntryId, Vec<EntryId>>, // of each child fetched, kept when collapsed so expanding again doesn't refetch
+}
+
+impl TreePreview {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the current entry's children, ie. after navigating or reloading,
+    /// anything kept of those no longer among them is dropped
+    pub fn set_children(&mut self, children: Vec<EntryId>) {
+        self.expanded.retain(|child_id| children.contains(child_id));
+        self.grandchildren.retain(|child_id, _| children.contains(child_id));
+        self.children = children;
+    }
+
+    /// expands the child if collapsed and vice versa, giving false if it isn't a child (ie. is too deep to expand)
+    pub fn toggle(&mut self, child_id: EntryId) -> bool {
+        if !self.children.contains(&child_id) {return false}
+        if !self.expanded.remove(&child_id) {
+            self.expanded.insert(child_id);
+        }
+        true
+    }
+
+    pub fn is_expanded(&self, child_id: EntryId) -> bool {
+        self.expanded.contains(&child_id)
+    }
+
+    /// the expanded children whose own children haven't been set yet
+    pub fn to_fetch(&self) -> Vec<EntryId> {
+        self.children.iter().copied().filter(|child_id| self.expanded.contains(child_id) && !self.grandchildren.contains_key(child_id)).collect()
+    }
+
+    /// the children of one of the children, ignored if it isn't one
+    pub fn set_grandchildren(&mut self, child_id: EntryId, grandchildren: Vec<EntryId>) {
+        if self.children.contains(&child_id) {
+            self.grandchildren.insert(child_id, grandchildren);
+        }
+    }
+
+    /// each entry shown with its depth below the current entry, the children being 1, 
+    /// the expanded children followed by their own children
+    pub fn rows(&self) -> Vec<(usize, EntryId)> {
+        let mut rows = Vec::new();
+        for child_id in &self.children {
+            rows.push((1, *child_id));
+            if !self.expanded.contains(child_id) {continue}
+            if let Some(grandchildren) = self.grandchildren.get(child_id) {
+                rows.extend(grandchildren.iter().map(|grandchild_id| (MAX_PREVIEW_DEPTH, *grandchild_id)));
+            }
+        }
+        rows
+    }
+}
\ No newline at end of file